@@ -2,7 +2,7 @@ use std::sync::Arc;
 use tauri::{
     menu::{Menu, MenuItem},
     tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent},
-    Manager, WindowEvent,
+    Manager, RunEvent, WindowEvent,
 };
 
 #[cfg(desktop)]
@@ -11,7 +11,7 @@ use tauri_plugin_autostart::{init as autostart_init, MacosLauncher, ManagerExt};
 use crate::{
     command::{self, config::ConfigState},
     service,
-    state::AppState,
+    state::{AppState, SharedState},
 };
 
 fn theme_is_dark(app: &tauri::App) -> bool {
@@ -44,12 +44,11 @@ pub fn run() {
     let app_conf: crate::config::AppConfig = crate::config::AppConfig::load();
     let startup = app_conf.startup;
     let background = app_conf.background;
-    let _ = crate::log::init_logger(&app_conf);
+    let shared_app_state = Arc::new(AppState::default());
+    let _ = crate::log::init_logger(&app_conf, shared_app_state.run_logs.clone());
 
     let conf_state = ConfigState(tokio::sync::RwLock::new(app_conf));
 
-    let shared_app_state = Arc::new(AppState::default());
-
     tauri::Builder::default()
         // Plugins
         .plugin(tauri_plugin_opener::init())
@@ -68,6 +67,15 @@ pub fn run() {
                 }
             });
 
+            // Resume recurring scan jobs saved by a previous session
+            tauri::async_runtime::spawn({
+                let app_handle = app.handle().clone();
+                let shared = shared_app_state.clone();
+                async move {
+                    shared.scheduler.restore(&app_handle, &shared).await;
+                }
+            });
+
             if background {
                 let tray_icon_bytes = tray_icon_bytes(theme_is_dark(&app));
                 let tray_icon = tauri::image::Image::from_bytes(tray_icon_bytes)
@@ -180,28 +188,84 @@ pub fn run() {
             command::interfaces::reload_interfaces,
             command::interfaces::get_default_network_interface,
             command::interfaces::get_network_address_map,
+            command::interfaces::list_local_subnets,
             command::routes::get_routes,
             command::routes::get_neighbor_table,
             command::socket::get_sockets_all,
             command::internet::get_public_ip_info,
             command::system::get_sys_info,
+            command::system::capabilities_check,
             command::config::get_config,
             command::config::reload_config,
             command::config::save_config,
             command::config::logs_dir_path,
+            command::config::list_scan_templates,
+            command::config::load_scan_template,
+            command::config::save_scan_template,
             command::dns::lookup_host,
             command::dns::lookup_domain,
             command::dns::lookup_ip,
             command::dns::reverse_lookup,
+            command::dns::reverse_lookup_bulk,
+            command::dns::verify_fcrdns,
             command::dns::lookup_all,
+            command::dns::clear_dns_cache,
+            command::dns::get_dns_config,
+            command::dns::test_dns_server,
+            command::dns::lookup_ip_race,
             command::ping::ping,
+            command::ping::icmp_query,
+            command::ping::ipv6_ext_probe,
             command::scan::get_target_ports,
             command::scan::port_scan,
+            command::scan::check_port,
+            command::scan::port_scan_url,
+            command::scan::rescan_open_ports,
+            command::scan::estimate_scan_cost,
+            command::scan::scan_endpoint,
+            command::scan::lookup_service,
+            command::scan::ports_for_service,
             command::scan::host_scan,
+            command::scan::host_scan_all_interfaces,
+            command::scan::host_port_scan,
             command::scan::neighbor_scan,
+            command::scan::quick_lan_scan,
+            command::scan::ip_protocol_scan,
+            command::scan::pause_scan,
+            command::scan::resume_scan,
+            command::scan::resume_interrupted_scan,
+            command::scan::shutdown_all_scans,
+            command::scan::get_run_logs,
+            command::history::list_scan_history,
+            command::history::diff_scans,
+            command::history::list_interrupted_scans,
+            command::history::read_scan_results,
+            command::export::export_report_to_string,
+            command::export::export_port_scan_html,
+            command::export::export_findings,
+            command::policy::load_port_policy,
+            command::policy::check_against_policy,
+            command::schedule::add_scan_job,
+            command::schedule::remove_scan_job,
+            command::schedule::list_scan_jobs,
+            command::capture::start_capture,
+            command::capture::stop_capture,
+            command::lldp::listen_lldp,
+            command::lldp::stop_lldp,
             command::trace::traceroute,
+            command::wol::send_wol,
+            command::dhcp::discover_dhcp,
+            command::knock::port_knock,
             command::scan::init_probe_db,
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running netpulse application");
+        .build(tauri::generate_context!())
+        .expect("error while building netpulse application")
+        .run(|app, event| {
+            // Let running scans finalize instead of hanging on in-flight sockets
+            if let RunEvent::Exit = event {
+                let state = app.state::<SharedState>();
+                let config = app.state::<ConfigState>();
+                tauri::async_runtime::block_on(command::scan::shutdown_scans(&state, &config));
+            }
+        });
 }
@@ -0,0 +1,5 @@
+//! Headless scanner printing JSON lines; see `netpulse_lib::cli`.
+
+fn main() {
+    std::process::exit(netpulse_lib::cli::run());
+}
@@ -0,0 +1,291 @@
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crate::error::ScanError;
+use crate::model::endpoint::Host;
+use crate::model::scan::{HostScanSetting, PortScanSetting};
+
+/// Directory under the app dir holding the progress of unfinished scan runs
+pub const CHECKPOINT_DIR_NAME: &str = "checkpoints";
+
+/// Minimum time between two writes of a running scan's checkpoint
+const SAVE_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Scan settings and the work already completed.
+/// Only negative results are kept: open ports and alive hosts are probed
+/// again on resume, so service detection and RTTs cover them.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(tag = "kind")]
+pub enum CheckpointScan {
+    PortScan {
+        setting: PortScanSetting,
+        /// Ports probed and found not open
+        done_ports: Vec<u16>,
+    },
+    HostScan {
+        setting: HostScanSetting,
+        /// Hosts probed without a reply
+        unreachable: Vec<Host>,
+    },
+}
+
+/// Progress of an interrupted scan run
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ScanCheckpoint {
+    pub run_id: String,
+    /// Unix timestamp in milliseconds
+    pub saved_at_ms: u64,
+    pub scan: CheckpointScan,
+}
+
+/// Summary of an interrupted scan run for listing
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ScanCheckpointSummary {
+    pub run_id: String,
+    pub saved_at_ms: u64,
+    /// "PortScan" or "HostScan"
+    pub kind: String,
+    /// Ports or hosts already completed
+    pub completed: usize,
+}
+
+impl ScanCheckpoint {
+    pub fn new(run_id: &str, scan: CheckpointScan) -> Self {
+        Self {
+            run_id: run_id.to_string(),
+            saved_at_ms: 0,
+            scan,
+        }
+    }
+
+    pub fn summary(&self) -> ScanCheckpointSummary {
+        let (kind, completed) = match &self.scan {
+            CheckpointScan::PortScan { done_ports, .. } => ("PortScan", done_ports.len()),
+            CheckpointScan::HostScan { unreachable, .. } => ("HostScan", unreachable.len()),
+        };
+        ScanCheckpointSummary {
+            run_id: self.run_id.clone(),
+            saved_at_ms: self.saved_at_ms,
+            kind: kind.to_string(),
+            completed,
+        }
+    }
+}
+
+/// Records completed work of a running scan and writes it out periodically
+#[derive(Debug)]
+pub struct Checkpointer {
+    state: Mutex<CheckpointState>,
+    /// Sequence number of the snapshot last written. Also serializes the
+    /// writes, which run on the blocking pool.
+    written: Arc<Mutex<u64>>,
+    /// Hosts found unreachable before this run was resumed
+    prior_unreachable: Vec<Host>,
+}
+
+#[derive(Debug)]
+struct CheckpointState {
+    checkpoint: ScanCheckpoint,
+    last_saved: Option<Instant>,
+    /// Sequence number of the last snapshot taken
+    seq: u64,
+}
+
+/// Snapshot of a checkpoint to be written off the scan's tasks
+struct SaveJob {
+    seq: u64,
+    checkpoint: ScanCheckpoint,
+    written: Arc<Mutex<u64>>,
+}
+
+impl Checkpointer {
+    /// Start recording on top of `checkpoint`, fresh or loaded for a resume.
+    pub fn new(checkpoint: ScanCheckpoint) -> Self {
+        let prior_unreachable = match &checkpoint.scan {
+            CheckpointScan::HostScan { unreachable, .. } => unreachable.clone(),
+            CheckpointScan::PortScan { .. } => Vec::new(),
+        };
+        Self {
+            state: Mutex::new(CheckpointState {
+                checkpoint,
+                last_saved: None,
+                seq: 0,
+            }),
+            written: Arc::new(Mutex::new(0)),
+            prior_unreachable,
+        }
+    }
+
+    pub fn prior_unreachable(&self) -> &[Host] {
+        &self.prior_unreachable
+    }
+
+    /// Record a port found not open.
+    pub fn port_done(&self, port: u16) {
+        let job = {
+            let mut state = self.state.lock().expect("checkpoint poisoned");
+            if let CheckpointScan::PortScan { done_ports, .. } = &mut state.checkpoint.scan {
+                done_ports.push(port);
+            }
+            state.save_due(&self.written)
+        };
+        spawn_save(job);
+    }
+
+    /// Record a host that did not reply.
+    pub fn host_unreachable(&self, host: Host) {
+        let job = {
+            let mut state = self.state.lock().expect("checkpoint poisoned");
+            if let CheckpointScan::HostScan { unreachable, .. } = &mut state.checkpoint.scan {
+                unreachable.push(host);
+            }
+            state.save_due(&self.written)
+        };
+        spawn_save(job);
+    }
+
+    /// Write the checkpoint now. Failures are logged, not returned.
+    pub async fn flush(&self) {
+        let job = self
+            .state
+            .lock()
+            .expect("checkpoint poisoned")
+            .snapshot(&self.written);
+        let _ = tokio::task::spawn_blocking(move || job.run()).await;
+    }
+
+    /// Drop the checkpoint file of a finished run. Snapshots still queued
+    /// are not written afterwards. Failures are logged, not returned.
+    pub async fn discard(&self) {
+        let run_id = self
+            .state
+            .lock()
+            .expect("checkpoint poisoned")
+            .checkpoint
+            .run_id
+            .clone();
+        let written = self.written.clone();
+        let _ = tokio::task::spawn_blocking(move || {
+            *written.lock().expect("checkpoint poisoned") = u64::MAX;
+            if let Err(e) = remove(&run_id) {
+                tracing::warn!("Failed to remove scan checkpoint: {}", e);
+            }
+        })
+        .await;
+    }
+}
+
+impl CheckpointState {
+    fn save_due(&mut self, written: &Arc<Mutex<u64>>) -> Option<SaveJob> {
+        self.last_saved
+            .is_none_or(|at| at.elapsed() >= SAVE_INTERVAL)
+            .then(|| self.snapshot(written))
+    }
+
+    fn snapshot(&mut self, written: &Arc<Mutex<u64>>) -> SaveJob {
+        self.last_saved = Some(Instant::now());
+        self.seq += 1;
+        SaveJob {
+            seq: self.seq,
+            checkpoint: self.checkpoint.clone(),
+            written: written.clone(),
+        }
+    }
+}
+
+impl SaveJob {
+    fn run(mut self) {
+        let mut written = self.written.lock().expect("checkpoint poisoned");
+        // A newer snapshot is already on disk, or the run has finished
+        if *written >= self.seq {
+            return;
+        }
+        match save(&mut self.checkpoint) {
+            Ok(()) => *written = self.seq,
+            Err(e) => tracing::error!("Failed to save scan checkpoint: {}", e),
+        }
+    }
+}
+
+/// Write a due snapshot on the blocking pool, off the scan's workers
+fn spawn_save(job: Option<SaveJob>) {
+    if let Some(job) = job {
+        tokio::task::spawn_blocking(move || job.run());
+    }
+}
+
+fn checkpoint_dir() -> Result<PathBuf> {
+    let mut path = crate::fs::get_app_dir_path().context("Failed to resolve app dir")?;
+    path.push(CHECKPOINT_DIR_NAME);
+    if !path.exists() {
+        std::fs::create_dir_all(&path)?;
+    }
+    Ok(path)
+}
+
+fn checkpoint_path(run_id: &str) -> Result<PathBuf> {
+    // Run IDs are UUIDs; reject anything else so it can't escape the checkpoint dir
+    if uuid::Uuid::parse_str(run_id).is_err() {
+        bail!(ScanError::invalid_input(format!(
+            "Invalid run ID: {}",
+            run_id
+        )));
+    }
+    let mut path = checkpoint_dir()?;
+    path.push(format!("{}.json", run_id));
+    Ok(path)
+}
+
+/// Store the progress of a scan run, stamping `saved_at_ms`
+pub fn save(checkpoint: &mut ScanCheckpoint) -> Result<()> {
+    checkpoint.saved_at_ms = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0);
+    let path = checkpoint_path(&checkpoint.run_id)?;
+    // Write then rename so a crash mid-write keeps the previous checkpoint
+    let tmp = path.with_extension("json.tmp");
+    std::fs::write(&tmp, serde_json::to_string(checkpoint)?)?;
+    std::fs::rename(&tmp, &path)?;
+    Ok(())
+}
+
+/// Load the progress of an interrupted scan run
+pub fn load(run_id: &str) -> Result<ScanCheckpoint> {
+    let path = checkpoint_path(run_id)?;
+    let content = std::fs::read_to_string(&path)
+        .with_context(|| format!("No interrupted scan to resume: {}", run_id))?;
+    Ok(serde_json::from_str(&content)?)
+}
+
+/// Drop the checkpoint of a finished scan run. A missing file is not an error.
+pub fn remove(run_id: &str) -> Result<()> {
+    let path = checkpoint_path(run_id)?;
+    match std::fs::remove_file(&path) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+        _ => Ok(()),
+    }
+}
+
+/// List interrupted scan runs, newest first
+pub fn list() -> Result<Vec<ScanCheckpointSummary>> {
+    let mut summaries = Vec::new();
+    for dir_entry in std::fs::read_dir(checkpoint_dir()?)? {
+        let path = dir_entry?.path();
+        if path.extension().and_then(|e| e.to_str()) != Some("json") {
+            continue;
+        }
+        let parsed = std::fs::read_to_string(&path)
+            .map_err(anyhow::Error::from)
+            .and_then(|c| Ok(serde_json::from_str::<ScanCheckpoint>(&c)?));
+        match parsed {
+            Ok(checkpoint) => summaries.push(checkpoint.summary()),
+            Err(e) => tracing::warn!("Skipping checkpoint file {:?}: {}", path, e),
+        }
+    }
+    summaries.sort_by(|a, b| b.saved_at_ms.cmp(&a.saved_at_ms));
+    Ok(summaries)
+}
@@ -0,0 +1,121 @@
+//! Headless entry point for running scans without the GUI.
+//!
+//! `netpulse-cli <method> [FILE|-]` reads the settings of `method` as JSON from
+//! FILE or stdin, prints every scan event as a JSON line
+//! (`{"event":...,"payload":...}`) and ends with `{"result":...}` or `{"error":...}`.
+//! `port_scan_stream` prints only the samples (`{"sample":...}`), read from
+//! `probe::scan::stream`.
+
+use anyhow::{bail, Context, Result};
+use futures::StreamExt;
+use serde_json::json;
+use std::io::Read;
+use std::sync::Arc;
+
+use crate::model::scan::{HostScanRequest, HostScanSetting, PortScanSetting};
+use crate::probe::scan::control::ScanControl;
+use crate::probe::scan::runner::{scan_hosts, scan_ports};
+use crate::probe::scan::sink::StdoutSink;
+use crate::probe::scan::stream::port_scan_stream;
+
+const USAGE: &str = "usage: netpulse-cli <port_scan|port_scan_stream|host_scan> [SETTINGS.json|-]
+
+  port_scan          settings: PortScanSetting
+  port_scan_stream   settings: PortScanSetting; prints only the samples
+                     (open ports, every port when verbose) as they complete
+  host_scan          settings: HostScanRequest
+
+Settings are read from stdin when no file or '-' is given.";
+
+fn read_params(path: Option<&str>) -> Result<String> {
+    match path {
+        None | Some("-") => {
+            let mut buf = String::new();
+            std::io::stdin()
+                .read_to_string(&mut buf)
+                .context("Failed to read settings from stdin")?;
+            Ok(buf)
+        }
+        Some(path) => std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read settings from {}", path)),
+    }
+}
+
+async fn dispatch(method: &str, params: &str) -> Result<serde_json::Value> {
+    let sink = StdoutSink;
+    let run_id = uuid::Uuid::new_v4().to_string();
+    match method {
+        "port_scan" => {
+            let setting: PortScanSetting =
+                serde_json::from_str(params).context("Invalid PortScanSetting")?;
+            if setting.service_detection {
+                crate::command::scan::init_probe_db().await?;
+            }
+            let control = Arc::new(ScanControl::new(&run_id, "portscan"));
+            let report = scan_ports(&sink, &run_id, setting, control).await?;
+            Ok(serde_json::to_value(report)?)
+        }
+        "port_scan_stream" => {
+            let setting: PortScanSetting =
+                serde_json::from_str(params).context("Invalid PortScanSetting")?;
+            if setting.service_detection {
+                crate::command::scan::init_probe_db().await?;
+            }
+            let control = Arc::new(ScanControl::new(&run_id, "portscan"));
+            let mut samples = port_scan_stream(&run_id, setting, control);
+            while let Some(sample) = samples.next().await {
+                println!("{}", json!({ "sample": sample }));
+            }
+            let report = samples.finish().await?;
+            Ok(serde_json::to_value(report)?)
+        }
+        "host_scan" => {
+            let request: HostScanRequest =
+                serde_json::from_str(params).context("Invalid HostScanRequest")?;
+            let setting = HostScanSetting::from_request(request);
+            let control = Arc::new(ScanControl::new(&run_id, "hostscan"));
+            let report = scan_hosts(&sink, &run_id, setting, control).await?;
+            Ok(serde_json::to_value(report)?)
+        }
+        _ => bail!("Unknown method: {}\n{}", method, USAGE),
+    }
+}
+
+/// Run the CLI with the process arguments. Returns the exit code.
+pub fn run() -> i32 {
+    // Logs go to stderr so stdout stays machine-readable
+    tracing_subscriber::fmt()
+        .with_writer(std::io::stderr)
+        .with_max_level(tracing::Level::WARN)
+        .init();
+
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let Some(method) = args.first() else {
+        eprintln!("{}", USAGE);
+        return 2;
+    };
+    if method == "-h" || method == "--help" {
+        println!("{}", USAGE);
+        return 0;
+    }
+
+    let runtime = match tokio::runtime::Runtime::new() {
+        Ok(rt) => rt,
+        Err(e) => {
+            eprintln!("Failed to start runtime: {}", e);
+            return 1;
+        }
+    };
+    let outcome = read_params(args.get(1).map(String::as_str))
+        .and_then(|params| runtime.block_on(dispatch(method, &params)));
+    match outcome {
+        Ok(result) => {
+            println!("{}", json!({ "result": result }));
+            0
+        }
+        Err(e) => {
+            println!("{}", json!({ "error": format!("{:#}", e) }));
+            1
+        }
+    }
+}
@@ -0,0 +1,64 @@
+use tauri::{AppHandle, Emitter, State};
+use tracing::Instrument;
+
+use crate::error::ScanError;
+use crate::model::capture::CaptureReport;
+use crate::probe::capture::{CaptureFilter, DEFAULT_MAX_PACKETS, MAX_PACKETS_LIMIT};
+use crate::state::SharedState;
+
+/// Capture packets on `iface` and stream summaries as `capture:packet` events.
+/// Runs until `max_packets` matching packets are captured or `stop_capture` is called.
+#[tauri::command]
+pub async fn start_capture(
+    app: AppHandle,
+    state: State<'_, SharedState>,
+    iface: String,
+    bpf_filter: Option<String>,
+    max_packets: Option<usize>,
+) -> Result<CaptureReport, ScanError> {
+    let interface = netdev::get_interfaces()
+        .into_iter()
+        .find(|i| i.name == iface)
+        .ok_or_else(|| ScanError::no_interface(format!("Interface not found: {}", iface)))?;
+    let filter = match bpf_filter.as_deref().map(str::trim) {
+        Some(expr) if !expr.is_empty() => CaptureFilter::parse(expr).map_err(ScanError::from)?,
+        _ => CaptureFilter::default(),
+    };
+    let max_packets = max_packets
+        .unwrap_or(DEFAULT_MAX_PACKETS)
+        .clamp(1, MAX_PACKETS_LIMIT);
+
+    let capture_id = uuid::Uuid::new_v4().to_string();
+    let _ = app.emit("capture:start", capture_id.clone());
+
+    let span = tracing::info_span!("capture", run_id = %capture_id, iface = %iface);
+    let control = state.scans.register(&capture_id, "capture").await;
+    let result = crate::probe::capture::capture(
+        &app,
+        &capture_id,
+        &iface,
+        interface.index,
+        filter,
+        max_packets,
+        control,
+    )
+    .instrument(span)
+    .await;
+    state.scans.remove(&capture_id).await;
+    result.map_err(ScanError::from)
+}
+
+/// Stop a running capture.
+#[tauri::command]
+pub async fn stop_capture(
+    state: State<'_, SharedState>,
+    capture_id: String,
+) -> Result<(), ScanError> {
+    let control = state
+        .scans
+        .get(&capture_id)
+        .await
+        .ok_or_else(|| ScanError::not_found(format!("capture not running: {capture_id}")))?;
+    control.cancel();
+    Ok(())
+}
@@ -2,13 +2,14 @@ use std::path::{Path, PathBuf};
 use tauri::State;
 use tokio::sync::RwLock;
 
-use crate::config::AppConfig;
+use crate::config::{AppConfig, ScanTemplate, ScanTemplateSetting};
+use crate::error::ScanError;
 
 #[derive(Default)]
 pub struct ConfigState(pub RwLock<AppConfig>);
 
 #[tauri::command]
-pub async fn get_config(state: State<'_, ConfigState>) -> Result<AppConfig, String> {
+pub async fn get_config(state: State<'_, ConfigState>) -> Result<AppConfig, ScanError> {
     // Return in-memory if already loaded, else load from disk once.
     let cfg = {
         let read = state.0.read().await;
@@ -18,7 +19,7 @@ pub async fn get_config(state: State<'_, ConfigState>) -> Result<AppConfig, Stri
 }
 
 #[tauri::command]
-pub async fn reload_config(state: State<'_, ConfigState>) -> Result<AppConfig, String> {
+pub async fn reload_config(state: State<'_, ConfigState>) -> Result<AppConfig, ScanError> {
     let cfg = AppConfig::load();
     {
         let mut write = state.0.write().await;
@@ -28,7 +29,7 @@ pub async fn reload_config(state: State<'_, ConfigState>) -> Result<AppConfig, S
 }
 
 #[tauri::command]
-pub async fn save_config(state: State<'_, ConfigState>, cfg: AppConfig) -> Result<(), String> {
+pub async fn save_config(state: State<'_, ConfigState>, cfg: AppConfig) -> Result<(), ScanError> {
     // Persist to disk + update in-memory
     cfg.save();
     {
@@ -47,7 +48,7 @@ pub struct LogsPath {
 #[tauri::command]
 pub async fn logs_dir_path(
     state: tauri::State<'_, super::config::ConfigState>,
-) -> Result<LogsPath, String> {
+) -> Result<LogsPath, ScanError> {
     let cfg = state.0.read().await.clone();
 
     // parent folder of logging.file_path, or fallback to ~/.netpulse
@@ -72,3 +73,22 @@ pub async fn logs_dir_path(
         file: file_str,
     })
 }
+
+#[tauri::command]
+pub async fn list_scan_templates() -> Result<Vec<ScanTemplate>, ScanError> {
+    Ok(ScanTemplate::list())
+}
+
+#[tauri::command]
+pub async fn load_scan_template(name: String) -> Result<ScanTemplate, ScanError> {
+    ScanTemplate::find(&name)
+        .ok_or_else(|| ScanError::not_found(format!("Template not found: {}", name)))
+}
+
+#[tauri::command]
+pub async fn save_scan_template(
+    name: String,
+    setting: ScanTemplateSetting,
+) -> Result<ScanTemplate, ScanError> {
+    ScanTemplate::save(&name, setting).map_err(ScanError::from)
+}
@@ -0,0 +1,20 @@
+use std::time::Duration;
+
+use crate::error::ScanError;
+use crate::model::dhcp::DhcpDiscoveryReport;
+use crate::net::dhcp::{self, DEFAULT_DHCP_TIMEOUT_MS};
+
+/// Broadcast a DHCPDISCOVER on `iface` and report every server that offers
+/// an address within `timeout_ms`. More than one server points to a rogue one.
+#[tauri::command]
+pub async fn discover_dhcp(
+    iface: String,
+    timeout_ms: Option<u64>,
+) -> Result<DhcpDiscoveryReport, ScanError> {
+    dhcp::discover(
+        &iface,
+        Duration::from_millis(timeout_ms.unwrap_or(DEFAULT_DHCP_TIMEOUT_MS)),
+    )
+    .await
+    .map_err(ScanError::from)
+}
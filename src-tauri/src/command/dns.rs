@@ -1,49 +1,128 @@
-use std::net::IpAddr;
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
 
+use crate::error::ScanError;
 use crate::net::dns::resolver::DnsResolver;
 use crate::{
     model::{
-        dns::{Domain, DomainLookupInfo},
+        dns::{DnsConfig, DnsServerTest, Domain, DomainLookupInfo, RaceLookup},
         endpoint::Host,
     },
     net::dns,
 };
 
 #[tauri::command]
-pub async fn lookup_host(host: &str) -> Result<Host, String> {
+pub async fn lookup_host(host: &str) -> Result<Host, ScanError> {
     crate::net::dns::lookup_host(host, std::time::Duration::from_secs(5))
         .await
-        .map_err(|e| e.to_string())
+        .map_err(ScanError::from)
 }
 
 #[tauri::command]
-pub async fn lookup_domain(hostname: &str) -> Result<Domain, String> {
+pub async fn lookup_domain(hostname: &str) -> Result<Domain, ScanError> {
     let timeout = std::time::Duration::from_secs(5);
     Ok(dns::lookup_domain(hostname, timeout).await)
 }
 
 #[tauri::command]
-pub async fn lookup_ip(hostname: &str) -> Result<Vec<IpAddr>, String> {
+pub async fn lookup_ip(hostname: &str) -> Result<Vec<IpAddr>, ScanError> {
     let timeout = std::time::Duration::from_secs(5);
     dns::lookup_ip(hostname, timeout)
         .await
-        .ok_or_else(|| "failed to resolve IP addresses".to_string())
+        .ok_or_else(|| ScanError::resolution_failed(format!("failed to resolve {}", hostname)))
 }
 
 #[tauri::command]
-pub async fn reverse_lookup(ip: IpAddr) -> Result<String, String> {
+pub async fn reverse_lookup(ip: IpAddr) -> Result<String, ScanError> {
     let timeout = std::time::Duration::from_secs(5);
     dns::reverse_lookup(ip, timeout)
         .await
-        .ok_or_else(|| "failed to perform reverse lookup".to_string())
+        .ok_or_else(|| ScanError::not_found(format!("no PTR record for {}", ip)))
 }
 
+/// Check that the PTR name of `ip` resolves back to it (FCrDNS)
 #[tauri::command]
-pub async fn lookup_all(hostname: &str) -> Result<DomainLookupInfo, String> {
-    let resolver =
-        DnsResolver::new().map_err(|e| format!("failed to create DNS resolver: {}", e))?;
+pub async fn verify_fcrdns(ip: IpAddr) -> Result<Host, ScanError> {
+    let timeout = std::time::Duration::from_secs(5);
+    Ok(dns::verify_fcrdns(ip, timeout).await)
+}
+
+/// Reverse lookup of a list of addresses, e.g. to enrich a table on demand.
+/// Returns IP -> hostname, `None` where no name was found.
+#[tauri::command]
+pub async fn reverse_lookup_bulk(
+    ips: Vec<IpAddr>,
+    timeout_ms: Option<u64>,
+    concurrency: Option<usize>,
+) -> Result<HashMap<IpAddr, Option<String>>, ScanError> {
+    let timeout = std::time::Duration::from_millis(
+        timeout_ms.unwrap_or(crate::model::scan::DEFAULT_RESOLVE_TIMEOUT_MS),
+    );
+    let concurrency = concurrency.unwrap_or(crate::model::scan::DEFAULT_RESOLVE_CONCURRENCY);
+    Ok(dns::reverse_lookup_bulk(&ips, timeout, concurrency).await)
+}
+
+/// Nameservers the resolver is using
+#[tauri::command]
+pub fn get_dns_config() -> Result<DnsConfig, ScanError> {
+    dns::resolver::dns_config().map_err(ScanError::from)
+}
+
+/// Benchmark a nameserver (`1.1.1.1`, `[2606:4700::1111]:53`, ...) with a sample query
+#[tauri::command]
+pub async fn test_dns_server(
+    addr: String,
+    query: Option<String>,
+) -> Result<DnsServerTest, ScanError> {
+    let addr = addr.trim();
+    let server = match addr.parse::<SocketAddr>() {
+        Ok(sa) => sa,
+        Err(_) => addr
+            .parse::<IpAddr>()
+            .map(|ip| SocketAddr::new(ip, 53))
+            .map_err(|_| {
+                ScanError::invalid_input(format!("invalid DNS server address: {}", addr))
+            })?,
+    };
+    let query = query
+        .map(|q| q.trim().to_string())
+        .filter(|q| !q.is_empty())
+        .unwrap_or_else(|| "example.com".to_string());
+    let query = dns::idn::to_ascii(&query).map_err(ScanError::from)?;
+    Ok(dns::resolver::test_dns_server(server, &query, 3, std::time::Duration::from_secs(2)).await)
+}
+
+/// Resolve a name through all nameservers at once and report which answered first
+#[tauri::command]
+pub async fn lookup_ip_race(
+    hostname: &str,
+    check_consistency: Option<bool>,
+) -> Result<RaceLookup, ScanError> {
+    let race = dns::race::RaceResolver::from_system().map_err(ScanError::from)?;
+    let ascii = dns::idn::to_ascii(hostname).map_err(ScanError::from)?;
+    race.lookup_ip(
+        &ascii,
+        std::time::Duration::from_secs(5),
+        check_consistency.unwrap_or(false),
+    )
+    .await
+    .map(|(answer, _)| answer)
+    .map_err(ScanError::from)
+}
+
+/// Drop cached DNS answers. Returns the number of entries removed.
+#[tauri::command]
+pub fn clear_dns_cache() -> usize {
+    dns::cache::clear()
+}
+
+#[tauri::command]
+pub async fn lookup_all(hostname: &str) -> Result<DomainLookupInfo, ScanError> {
+    let resolver = DnsResolver::new().map_err(|e| {
+        ScanError::resolution_failed(format!("failed to create DNS resolver: {}", e))
+    })?;
     resolver
         .lookup_all(hostname)
         .await
-        .map_err(|e| format!("failed to lookup domain info: {}", e))
+        .map_err(|e| ScanError::resolution_failed(format!("failed to lookup domain info: {}", e)))
 }
@@ -0,0 +1,31 @@
+use crate::error::ScanError;
+use crate::export::{self, ExportFormat, FindingsFormat};
+use crate::history::ScanRecord;
+use crate::model::scan::PortScanReport;
+
+/// Render a scan report as text, e.g. for copying to the clipboard.
+/// Uses the same formats as the file exports.
+#[tauri::command]
+pub async fn export_report_to_string(
+    report: ScanRecord,
+    format: ExportFormat,
+) -> Result<String, ScanError> {
+    export::format_report(&report, format).map_err(ScanError::from)
+}
+
+/// Write a port scan as a standalone HTML report to `path`
+#[tauri::command]
+pub async fn export_port_scan_html(report: PortScanReport, path: String) -> Result<(), ScanError> {
+    let html = export::port_scan_html(&report).map_err(ScanError::from)?;
+    tokio::fs::write(&path, html).await.map_err(ScanError::from)
+}
+
+/// Derive security findings from a scan and render them for tooling
+/// pipelines, either as the versioned findings JSON or as SARIF.
+#[tauri::command]
+pub async fn export_findings(
+    report: ScanRecord,
+    format: FindingsFormat,
+) -> Result<String, ScanError> {
+    export::format_findings(&report, format).map_err(ScanError::from)
+}
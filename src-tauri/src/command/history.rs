@@ -0,0 +1,42 @@
+use crate::checkpoint::{self, ScanCheckpointSummary};
+use crate::error::ScanError;
+use crate::history::{self, ScanHistorySummary};
+use crate::model::diff::ScanDiff;
+use crate::model::scan::PortScanSample;
+use crate::results;
+
+#[tauri::command]
+pub async fn list_scan_history() -> Result<Vec<ScanHistorySummary>, ScanError> {
+    history::list().map_err(ScanError::from)
+}
+
+/// Compare two stored scan runs. `run_id_a` is treated as the earlier run.
+#[tauri::command]
+pub async fn diff_scans(run_id_a: String, run_id_b: String) -> Result<ScanDiff, ScanError> {
+    let a = history::load(&run_id_a).map_err(ScanError::from)?;
+    let b = history::load(&run_id_b).map_err(ScanError::from)?;
+    ScanDiff::between(&a.record, &b.record).map_err(ScanError::from)
+}
+
+/// Interrupted scan runs that `resume_interrupted_scan` can continue
+#[tauri::command]
+pub async fn list_interrupted_scans() -> Result<Vec<ScanCheckpointSummary>, ScanError> {
+    checkpoint::list().map_err(ScanError::from)
+}
+
+/// Samples of a scan that spilled its results to disk (`results_file` in
+/// the report), `limit` at a time starting at `offset`.
+#[tauri::command]
+pub async fn read_scan_results(
+    run_id: String,
+    offset: Option<usize>,
+    limit: Option<usize>,
+) -> Result<Vec<PortScanSample>, ScanError> {
+    let path = results::results_path(&run_id).map_err(ScanError::from)?;
+    results::read_page(
+        &path,
+        offset.unwrap_or(0),
+        limit.unwrap_or(results::RESULTS_BATCH),
+    )
+    .map_err(ScanError::from)
+}
@@ -1,4 +1,5 @@
-use crate::model::interface::{NetworkInterface, TrafficStats};
+use crate::error::ScanError;
+use crate::model::interface::{LocalSubnet, NetworkInterface, TrafficStats};
 use crate::state::SharedState;
 use anyhow::Result;
 use netdev::ipnet::Ipv4Net;
@@ -12,7 +13,7 @@ use tokio::sync::MutexGuard;
 #[tauri::command]
 pub async fn get_network_interfaces(
     state: State<'_, SharedState>,
-) -> Result<Vec<NetworkInterface>, String> {
+) -> Result<Vec<NetworkInterface>, ScanError> {
     // Acquire locks
     let ifaces_guard: MutexGuard<'_, HashMap<u32, Interface>> = state.interfaces.lock().await;
     let stats_guard: MutexGuard<'_, HashMap<u32, crate::state::IfStats>> = state.stats.lock().await;
@@ -75,24 +76,24 @@ pub async fn get_network_interfaces(
 pub async fn reload_interfaces(
     app: AppHandle,
     state: State<'_, SharedState>,
-) -> Result<(), String> {
+) -> Result<(), ScanError> {
     crate::service::task::reload_interfaces(&state)
         .await
-        .map_err(|e| e.to_string())?;
+        .map_err(ScanError::from)?;
     let _ = app.emit("interfaces_updated", ());
     Ok(())
 }
 
 #[tauri::command]
-pub async fn get_default_network_interface() -> Result<NetworkInterface, String> {
+pub async fn get_default_network_interface() -> Result<NetworkInterface, ScanError> {
     match netdev::get_default_interface() {
         Ok(iface) => Ok(NetworkInterface::from(iface)),
-        Err(e) => Err(e.to_string()),
+        Err(e) => Err(ScanError::no_interface(e)),
     }
 }
 
 #[tauri::command]
-pub async fn get_network_address_map() -> Result<HashMap<String, Ipv4Net>, String> {
+pub async fn get_network_address_map() -> Result<HashMap<String, Ipv4Net>, ScanError> {
     // Key: Interface name, Value: Network address
     let mut map: HashMap<String, Ipv4Net> = HashMap::new();
 
@@ -110,11 +111,22 @@ pub async fn get_network_address_map() -> Result<HashMap<String, Ipv4Net>, Strin
             iface.name.clone()
         };
         if let Some(ipv4) = iface.ipv4.first() {
-            let network =
-                Ipv4Net::new(ipv4.network(), ipv4.prefix_len()).map_err(|e| e.to_string())?;
+            let network = Ipv4Net::new(ipv4.network(), ipv4.prefix_len())
+                .map_err(|e| ScanError::invalid_input(e.to_string()))?;
             map.insert(name, network);
         }
     }
 
     Ok(map)
 }
+
+/// List local subnets of the given interface (default interface if not specified)
+#[tauri::command]
+pub async fn list_local_subnets(iface_name: Option<String>) -> Result<Vec<LocalSubnet>, ScanError> {
+    let iface = match iface_name {
+        Some(name) => crate::net::interface::find_interface(&name)
+            .ok_or_else(|| ScanError::no_interface(format!("interface not found: {name}")))?,
+        None => netdev::get_default_interface().map_err(ScanError::no_interface)?,
+    };
+    Ok(crate::net::interface::local_subnets(&iface))
+}
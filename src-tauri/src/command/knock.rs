@@ -0,0 +1,29 @@
+use std::net::IpAddr;
+use std::time::Duration;
+
+use crate::error::ScanError;
+use crate::model::knock::{KnockStep, PortKnockReport};
+use crate::probe::knock::{DEFAULT_KNOCK_DELAY_MS, DEFAULT_KNOCK_VERIFY_TIMEOUT_MS};
+
+/// Send a port-knock sequence (TCP SYN or UDP datagram per step) to `ip`,
+/// `delay_ms` apart, then optionally try a TCP connect to `verify_port`.
+#[tauri::command]
+pub async fn port_knock(
+    ip: IpAddr,
+    sequence: Vec<KnockStep>,
+    delay_ms: Option<u64>,
+    verify_port: Option<u16>,
+    timeout_ms: Option<u64>,
+) -> Result<PortKnockReport, ScanError> {
+    if sequence.is_empty() {
+        return Err(ScanError::invalid_input("Knock sequence is empty"));
+    }
+    Ok(crate::probe::knock::port_knock(
+        ip,
+        &sequence,
+        Duration::from_millis(delay_ms.unwrap_or(DEFAULT_KNOCK_DELAY_MS)),
+        verify_port,
+        Duration::from_millis(timeout_ms.unwrap_or(DEFAULT_KNOCK_VERIFY_TIMEOUT_MS)),
+    )
+    .await)
+}
@@ -0,0 +1,47 @@
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, State};
+
+use crate::error::ScanError;
+use crate::model::lldp::LinkDiscoveryReport;
+use crate::probe::lldp::DEFAULT_LLDP_TIMEOUT_MS;
+use crate::state::SharedState;
+
+/// Listen passively on `iface` for LLDP and CDP announcements for `timeout_ms`,
+/// reporting the switch name, port and VLAN the interface is connected to.
+/// The listen ID is sent as `lldp:start`; `stop_lldp` ends the listen early.
+#[tauri::command]
+pub async fn listen_lldp(
+    app: AppHandle,
+    state: State<'_, SharedState>,
+    iface: String,
+    timeout_ms: Option<u64>,
+) -> Result<LinkDiscoveryReport, ScanError> {
+    let interface = crate::net::interface::find_interface(&iface)
+        .ok_or_else(|| ScanError::no_interface(format!("Interface not found: {}", iface)))?;
+
+    let listen_id = uuid::Uuid::new_v4().to_string();
+    let _ = app.emit("lldp:start", listen_id.clone());
+    let control = state.scans.register(&listen_id, "lldp").await;
+    let result = crate::probe::lldp::listen(
+        &listen_id,
+        &interface.name,
+        interface.index,
+        Duration::from_millis(timeout_ms.unwrap_or(DEFAULT_LLDP_TIMEOUT_MS)),
+        control,
+    )
+    .await;
+    state.scans.remove(&listen_id).await;
+    result.map_err(ScanError::from)
+}
+
+/// Stop a running LLDP/CDP listen. It reports the neighbors seen so far.
+#[tauri::command]
+pub async fn stop_lldp(state: State<'_, SharedState>, listen_id: String) -> Result<(), ScanError> {
+    let control = state
+        .scans
+        .get(&listen_id)
+        .await
+        .ok_or_else(|| ScanError::not_found(format!("LLDP listen not running: {listen_id}")))?;
+    control.cancel();
+    Ok(())
+}
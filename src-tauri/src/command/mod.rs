@@ -1,13 +1,22 @@
+pub mod capture;
 pub mod config;
+pub mod dhcp;
 pub mod dns;
+pub mod export;
+pub mod history;
 pub mod interfaces;
 pub mod internet;
+pub mod knock;
+pub mod lldp;
 pub mod ping;
+pub mod policy;
 pub mod routes;
 pub mod scan;
+pub mod schedule;
 pub mod socket;
 pub mod system;
 pub mod trace;
+pub mod wol;
 
 use crate::model::AppInfo;
 
@@ -1,15 +1,22 @@
 use std::net::IpAddr;
+use std::time::Duration;
 
 use netdev::Interface;
 use tauri::{AppHandle, Emitter};
 
-use crate::model::ping::{PingProtocol, PingSetting, PingStartPayload, PingStat};
+use crate::error::ScanError;
+use crate::model::ping::{
+    IcmpQueryKind, IcmpQueryResult, Ipv6ExtHeader, Ipv6ExtProbeReport, PingProtocol, PingSetting,
+    PingStartPayload, PingStat,
+};
 use crate::probe::ping;
 
+const DEFAULT_ICMP_QUERY_TIMEOUT_MS: u64 = 2000;
+
 #[tauri::command]
-pub async fn ping(app: AppHandle, setting: PingSetting) -> Result<PingStat, String> {
+pub async fn ping(app: AppHandle, setting: PingSetting) -> Result<PingStat, ScanError> {
     let default_interface: Interface = netdev::get_default_interface()
-        .map_err(|e| format!("Failed to get default interface: {}", e))?;
+        .map_err(|e| ScanError::no_interface(format!("Failed to get default interface: {}", e)))?;
     let src_ip = match setting.ip_addr {
         std::net::IpAddr::V4(_) => {
             // Pick first IPv4 address of default interface
@@ -17,7 +24,9 @@ pub async fn ping(app: AppHandle, setting: PingSetting) -> Result<PingStat, Stri
                 .ipv4_addrs()
                 .into_iter()
                 .next()
-                .ok_or("No IPv4 address found on default interface")?;
+                .ok_or_else(|| {
+                    ScanError::no_interface("No IPv4 address found on default interface")
+                })?;
             IpAddr::V4(ipv4)
         }
         std::net::IpAddr::V6(_) => {
@@ -26,7 +35,9 @@ pub async fn ping(app: AppHandle, setting: PingSetting) -> Result<PingStat, Stri
                 .ipv6_addrs()
                 .into_iter()
                 .next()
-                .ok_or("No IPv6 address found on default interface")?;
+                .ok_or_else(|| {
+                    ScanError::no_interface("No IPv6 address found on default interface")
+                })?;
             IpAddr::V6(ipv6)
         }
     };
@@ -42,18 +53,68 @@ pub async fn ping(app: AppHandle, setting: PingSetting) -> Result<PingStat, Stri
     match setting.protocol {
         PingProtocol::Icmp => ping::icmp::icmp_ping(&app, &run_id, src_ip, setting)
             .await
-            .map_err(|e| e.to_string()),
+            .map_err(ScanError::from),
         PingProtocol::Tcp => ping::tcp::tcp_ping(&app, &run_id, src_ip, setting)
             .await
-            .map_err(|e| e.to_string()),
+            .map_err(ScanError::from),
         PingProtocol::Udp => ping::udp::udp_ping_icmp_unreach(&app, &run_id, src_ip, setting)
             .await
-            .map_err(|e| e.to_string()),
+            .map_err(ScanError::from),
         PingProtocol::Quic => ping::quic::quic_ping(&app, &run_id, src_ip, setting)
             .await
-            .map_err(|e| e.to_string()),
+            .map_err(ScanError::from),
         PingProtocol::Http => ping::http::http_ping(&app, &run_id, setting)
             .await
-            .map_err(|e| e.to_string()),
+            .map_err(ScanError::from),
+    }
+}
+
+/// Send an ICMP Timestamp or Address Mask request to an IPv4 host.
+#[tauri::command]
+pub async fn icmp_query(
+    ip_addr: IpAddr,
+    kind: IcmpQueryKind,
+    timeout_ms: Option<u64>,
+) -> Result<IcmpQueryResult, ScanError> {
+    let IpAddr::V4(ipv4) = ip_addr else {
+        return Err(ScanError::invalid_input(
+            "ICMP timestamp and address mask queries are IPv4 only",
+        ));
+    };
+    let timeout = Duration::from_millis(timeout_ms.unwrap_or(DEFAULT_ICMP_QUERY_TIMEOUT_MS));
+    ping::icmp::icmp_query(ipv4, kind, timeout)
+        .await
+        .map_err(ScanError::from)
+}
+
+/// Probe how an IPv6 host handles echoes carrying extension headers.
+/// Sends a plain echo as baseline, then one per header and, for more than one
+/// header, one with the whole chain. Defaults to all supported headers.
+#[tauri::command]
+pub async fn ipv6_ext_probe(
+    ip_addr: IpAddr,
+    ext_headers: Option<Vec<Ipv6ExtHeader>>,
+    timeout_ms: Option<u64>,
+) -> Result<Ipv6ExtProbeReport, ScanError> {
+    let IpAddr::V6(ipv6) = ip_addr else {
+        return Err(ScanError::invalid_input(
+            "Extension header probing requires an IPv6 address",
+        ));
+    };
+    let headers = ext_headers.unwrap_or_else(|| {
+        vec![
+            Ipv6ExtHeader::HopByHop,
+            Ipv6ExtHeader::DestinationOptions,
+            Ipv6ExtHeader::Fragment,
+        ]
+    });
+    let mut chains: Vec<Vec<Ipv6ExtHeader>> = vec![Vec::new()];
+    chains.extend(headers.iter().map(|h| vec![*h]));
+    if headers.len() > 1 {
+        chains.push(headers);
     }
+    let timeout = Duration::from_millis(timeout_ms.unwrap_or(DEFAULT_ICMP_QUERY_TIMEOUT_MS));
+    ping::icmp::ipv6_ext_probe(ipv6, chains, timeout)
+        .await
+        .map_err(ScanError::from)
 }
@@ -0,0 +1,20 @@
+use std::path::Path;
+
+use crate::error::ScanError;
+use crate::model::policy::{PolicyCheckResult, PortPolicy};
+use crate::model::scan::PortScanReport;
+
+/// Read and validate an expected-ports policy file
+#[tauri::command]
+pub async fn load_port_policy(path: String) -> Result<PortPolicy, ScanError> {
+    PortPolicy::load(Path::new(&path)).map_err(ScanError::from)
+}
+
+/// Flag open ports the policy does not allow and required ports that are not open
+#[tauri::command]
+pub async fn check_against_policy(
+    report: PortScanReport,
+    policy: PortPolicy,
+) -> Result<PolicyCheckResult, ScanError> {
+    policy.check(&report).map_err(ScanError::from)
+}
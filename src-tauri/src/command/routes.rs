@@ -4,17 +4,18 @@ use anyhow::Result;
 use netdev::MacAddr;
 use tauri::State;
 
+use crate::error::ScanError;
 use crate::{net::route::list_routes, state::SharedState};
 use netroute::RouteEntry;
 
 #[tauri::command]
-pub async fn get_routes(_state: State<'_, SharedState>) -> Result<Vec<RouteEntry>, String> {
-    list_routes().map_err(|e| e.to_string())
+pub async fn get_routes(_state: State<'_, SharedState>) -> Result<Vec<RouteEntry>, ScanError> {
+    list_routes().map_err(ScanError::from)
 }
 
 #[tauri::command]
 pub async fn get_neighbor_table(
     _state: State<'_, SharedState>,
-) -> Result<HashMap<IpAddr, MacAddr>, String> {
-    crate::net::neigh::get_neighbor_table().map_err(|e| e.to_string())
+) -> Result<HashMap<IpAddr, MacAddr>, ScanError> {
+    crate::net::neigh::get_neighbor_table().map_err(ScanError::from)
 }
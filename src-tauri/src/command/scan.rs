@@ -1,154 +1,734 @@
 use std::net::IpAddr;
+use std::sync::Arc;
 
 use netdev::Interface;
-use tauri::{AppHandle, Emitter};
+use tauri::ipc::Channel;
+use tauri::{AppHandle, Emitter, State};
+use tracing::Instrument;
 
+use crate::checkpoint::{CheckpointScan, Checkpointer, ScanCheckpoint};
+use crate::command::config::ConfigState;
+use crate::history::ScanRecord;
+use crate::log::RunLogLine;
+use crate::model::endpoint::{Endpoint, Port, TransportProtocol};
 use crate::model::scan::{
-    HostScanReport, HostScanRequest, HostScanSetting, NeighborScanReport, PortScanProtocol,
-    PortScanReport, PortScanSetting, TargetPortsPreset,
+    HostPortScanReport, HostScanReport, HostScanRequest, HostScanSetting, InterfaceHostScan,
+    IpProtocolScanReport, IpProtocolScanSetting, MultiInterfaceHostScanReport, NeighborScanOptions,
+    NeighborScanReport, PortScanProtocol, PortScanReport, PortScanSample, PortScanSetting,
+    RegisteredService, ScanCostEstimate, ScanCostSetting, TargetPortsPreset,
+    DEFAULT_RECHECK_TIMEOUT_MS,
 };
 
+use crate::error::ScanError;
+use crate::probe::scan::control::ScanControl;
+use crate::probe::scan::runner::{scan_hosts, scan_hosts_ports, scan_ports, DEFAULT_HOST_FANOUT};
+use crate::probe::scan::sink::{ChannelSink, ProgressEmitter, ScanEvent};
 use crate::probe::service::db::service::{
-    init_port_probe_db, init_response_signatures_db, init_service_probe_db, init_tcp_service_db,
-    init_udp_service_db, PORT_PROBE_DB, RESPONSE_SIGNATURES_DB, SERVICE_PROBE_DB, TCP_SERVICE_DB,
-    UDP_SERVICE_DB,
+    init_port_probe_db, init_response_signatures_db, init_service_port_index,
+    init_service_probe_db, init_tcp_service_db, init_udp_service_db, PORT_PROBE_DB,
+    RESPONSE_SIGNATURES_DB, SERVICE_PORT_INDEX, SERVICE_PROBE_DB, TCP_SERVICE_DB, UDP_SERVICE_DB,
 };
 use crate::probe::service::db::tls::{init_tls_oid_map, TLS_OID_MAP};
+use crate::state::SharedState;
 
 #[tauri::command]
-pub async fn init_probe_db() -> Result<(), String> {
+pub async fn init_probe_db() -> Result<(), ScanError> {
     // Initialize service databases if not already initialized
 
     if TCP_SERVICE_DB.get().is_none() {
-        init_tcp_service_db().map_err(|e| e.to_string())?;
+        init_tcp_service_db().map_err(ScanError::from)?;
     }
 
     if UDP_SERVICE_DB.get().is_none() {
-        init_udp_service_db().map_err(|e| e.to_string())?;
+        init_udp_service_db().map_err(ScanError::from)?;
     }
 
     if TLS_OID_MAP.get().is_none() {
-        init_tls_oid_map().map_err(|e| e.to_string())?;
+        init_tls_oid_map().map_err(ScanError::from)?;
     }
 
     if PORT_PROBE_DB.get().is_none() {
-        init_port_probe_db().map_err(|e| e.to_string())?;
+        init_port_probe_db().map_err(ScanError::from)?;
     }
 
     if SERVICE_PROBE_DB.get().is_none() {
-        init_service_probe_db().map_err(|e| e.to_string())?;
+        init_service_probe_db().map_err(ScanError::from)?;
     }
 
     if RESPONSE_SIGNATURES_DB.get().is_none() {
-        init_response_signatures_db().map_err(|e| e.to_string())?;
+        init_response_signatures_db().map_err(ScanError::from)?;
+    }
+
+    if SERVICE_PORT_INDEX.get().is_none() {
+        init_service_port_index().map_err(ScanError::from)?;
     }
 
     Ok(())
 }
 
+/// Scan the ports of one host. Events of the run go to `channel` when given,
+/// and app-wide only if `global_events` is set (always without a channel).
+/// Rust consumers get the same samples as a stream from
+/// `probe::scan::stream::port_scan_stream`, without Tauri (the CLI's
+/// `port_scan_stream`).
 #[tauri::command]
-pub async fn port_scan(app: AppHandle, setting: PortScanSetting) -> Result<PortScanReport, String> {
-    let default_interface: Interface = netdev::get_default_interface()
-        .map_err(|e| format!("Failed to get default interface: {}", e))?;
-    let src_ip = match setting.ip_addr {
-        std::net::IpAddr::V4(_) => {
-            // Pick first IPv4 address of default interface
-            let ipv4 = default_interface
-                .ipv4_addrs()
-                .into_iter()
-                .next()
-                .ok_or("No IPv4 address found on default interface")?;
-            IpAddr::V4(ipv4)
-        }
-        std::net::IpAddr::V6(_) => {
-            // Pick first IPv6 address of default interface
-            let ipv6 = default_interface
-                .ipv6_addrs()
-                .into_iter()
-                .next()
-                .ok_or("No IPv6 address found on default interface")?;
-            IpAddr::V6(ipv6)
-        }
-    };
+pub async fn port_scan(
+    app: AppHandle,
+    state: State<'_, SharedState>,
+    setting: PortScanSetting,
+    channel: Option<Channel<ScanEvent>>,
+    global_events: Option<bool>,
+) -> Result<PortScanReport, ScanError> {
+    let sink = ChannelSink::new(app, channel, global_events);
+    run_port_scan(&sink, &state, setting).await
+}
+
+/// Run a port scan and store the report in history.
+/// Shared by the `port_scan` command and scheduled jobs.
+pub async fn run_port_scan(
+    sink: &impl ProgressEmitter,
+    state: &SharedState,
+    setting: PortScanSetting,
+) -> Result<PortScanReport, ScanError> {
     let run_id = uuid::Uuid::new_v4().to_string();
-    // Start event
-    let _ = app.emit(
-        "portscan:start",
-        crate::model::scan::PortScanStartPayload {
-            run_id: run_id.clone(),
+    let checkpoint = ScanCheckpoint::new(
+        &run_id,
+        CheckpointScan::PortScan {
+            setting: setting.clone(),
+            done_ports: Vec::new(),
         },
     );
+    port_scan_run(sink, state, &run_id, setting, checkpoint).await
+}
 
-    match setting.protocol {
-        PortScanProtocol::Tcp => crate::probe::scan::tcp::port_scan(&app, &run_id, src_ip, setting)
-            .await
-            .map_err(|e| e.to_string()),
-        PortScanProtocol::Quic => {
-            crate::probe::scan::quic::port_scan(&app, &run_id, src_ip, setting)
-                .await
-                .map_err(|e| e.to_string())
+/// Run a port scan under `run_id`, recording its progress on top of `checkpoint`.
+async fn port_scan_run(
+    sink: &impl ProgressEmitter,
+    state: &SharedState,
+    run_id: &str,
+    setting: PortScanSetting,
+    checkpoint: ScanCheckpoint,
+) -> Result<PortScanReport, ScanError> {
+    let span = tracing::info_span!(
+        "portscan",
+        run_id = %run_id,
+        protocol = ?setting.protocol,
+        target = %setting.ip_addr
+    );
+    let control = state.scans.register(run_id, "portscan").await;
+    control.set_checkpoint(Checkpointer::new(checkpoint));
+    let result = scan_ports(sink, run_id, setting, control.clone())
+        .instrument(span)
+        .await;
+    state.scans.remove(run_id).await;
+    // Cancel and app shutdown end the run early too
+    let interrupted =
+        control.is_cancelled() || result.as_ref().is_ok_and(|report| report.deadline_reached);
+    finish_checkpoint(&control, interrupted).await;
+    if let Ok(report) = &result {
+        store_history(ScanRecord::PortScan(report.clone()), interrupted);
+    }
+    result.map_err(ScanError::from)
+}
+
+/// Scan the host and port of a URL (e.g. `https://example.com:8443/path`)
+/// with service detection, sending the URL host as SNI.
+#[tauri::command]
+pub async fn port_scan_url(
+    app: AppHandle,
+    state: State<'_, SharedState>,
+    url: String,
+    timeout_ms: Option<u64>,
+) -> Result<PortScanReport, ScanError> {
+    let setting = crate::probe::scan::urltarget::url_scan_setting(&url, timeout_ms.unwrap_or(3000))
+        .await
+        .map_err(ScanError::from)?;
+    run_port_scan(&app, &state, setting).await
+}
+
+/// Scan only the ports an earlier port scan found open, to confirm they are
+/// still up. Takes the run ID of a scan in history, or the report itself.
+#[tauri::command]
+pub async fn rescan_open_ports(
+    app: AppHandle,
+    state: State<'_, SharedState>,
+    run_id: Option<String>,
+    report: Option<PortScanReport>,
+    timeout_ms: Option<u64>,
+    service_detection: Option<bool>,
+) -> Result<PortScanReport, ScanError> {
+    let report = match (report, run_id) {
+        (Some(report), _) => report,
+        (None, Some(run_id)) => match crate::history::load(&run_id)?.record {
+            ScanRecord::PortScan(report) => report,
+            ScanRecord::HostScan(_) => {
+                return Err(ScanError::invalid_input(format!(
+                    "{run_id} is a host scan, not a port scan"
+                )))
+            }
+        },
+        (None, None) => {
+            return Err(ScanError::invalid_input(
+                "Either a run ID or a report is required",
+            ))
         }
+    };
+    let ports = report.open_ports()?;
+    if ports.is_empty() {
+        return Err(ScanError::invalid_input(format!(
+            "No open ports in scan {}",
+            report.run_id
+        )));
+    }
+    let service_detection = service_detection.unwrap_or(false);
+    if service_detection {
+        init_probe_db().await?;
+    }
+    let setting = PortScanSetting::recheck(
+        &report,
+        ports,
+        timeout_ms.unwrap_or(DEFAULT_RECHECK_TIMEOUT_MS),
+        service_detection,
+    );
+    run_port_scan(&app, &state, setting).await
+}
+
+/// Estimate the packets, time and bandwidth a scan would take, without
+/// sending anything. Lets the UI warn before an aggressive scan.
+#[tauri::command]
+pub async fn estimate_scan_cost(setting: ScanCostSetting) -> Result<ScanCostEstimate, ScanError> {
+    crate::probe::scan::estimate::estimate_scan_cost(&setting).map_err(ScanError::from)
+}
+
+/// Scan one host over each of `protocols` with the same settings and merge
+/// the open ports into a single endpoint. Each scan is kept in history.
+#[tauri::command]
+pub async fn scan_endpoint(
+    app: AppHandle,
+    state: State<'_, SharedState>,
+    ip: IpAddr,
+    protocols: Vec<PortScanProtocol>,
+    setting: PortScanSetting,
+) -> Result<Endpoint, ScanError> {
+    if protocols.is_empty() {
+        return Err(ScanError::invalid_input("No protocol selected"));
+    }
+    let mut endpoint = Endpoint::new(ip);
+    endpoint.hostname = setting.hostname.clone();
+    for protocol in protocols {
+        let mut setting = setting.clone();
+        setting.ip_addr = ip;
+        setting.protocol = protocol;
+        let report = run_port_scan(&app, &state, setting).await?;
+        endpoint.add_report(&report).map_err(ScanError::from)?;
     }
+    Ok(endpoint)
 }
 
+/// Sweep hosts for liveness. `channel` and `global_events` work as in `port_scan`.
 #[tauri::command]
-pub async fn host_scan(app: AppHandle, setting: HostScanRequest) -> Result<HostScanReport, String> {
+pub async fn host_scan(
+    app: AppHandle,
+    state: State<'_, SharedState>,
+    setting: HostScanRequest,
+    channel: Option<Channel<ScanEvent>>,
+    global_events: Option<bool>,
+) -> Result<HostScanReport, ScanError> {
     let scan_setting: HostScanSetting = HostScanSetting::from_request(setting);
+    let sink = ChannelSink::new(app, channel, global_events);
+    run_host_scan(&sink, &state, scan_setting, None).await
+}
+
+/// Run the host scan from every up interface in turn and group the results
+/// by interface, e.g. to see a host answering over the VPN but not the LAN.
+#[tauri::command]
+pub async fn host_scan_all_interfaces(
+    app: AppHandle,
+    state: State<'_, SharedState>,
+    setting: HostScanRequest,
+) -> Result<MultiInterfaceHostScanReport, ScanError> {
+    let interfaces = crate::probe::scan::runner::scan_interfaces();
+    if interfaces.is_empty() {
+        return Err(ScanError::no_interface("No usable network interface"));
+    }
+    let base = HostScanSetting::from_request(setting);
+    // Parent of the per-interface runs: cancelling it, or the interface
+    // being scanned, skips the interfaces not scanned yet
     let run_id = uuid::Uuid::new_v4().to_string();
+    let control = state.scans.register(&run_id, "hostscan").await;
+    let mut scans: Vec<InterfaceHostScan> = Vec::with_capacity(interfaces.len());
+    for iface in interfaces {
+        control.wait_if_paused().await;
+        if control.is_cancelled() {
+            scans.push(InterfaceHostScan {
+                iface_name: iface.name,
+                report: None,
+                error: Some("Cancelled before this interface was scanned".to_string()),
+            });
+            continue;
+        }
+        let mut setting = base.clone();
+        setting.iface_name = Some(iface.name.clone());
+        let (report, error) = match run_host_scan(&app, &state, setting, Some(&control)).await {
+            Ok(report) => (Some(report), None),
+            Err(e) => {
+                tracing::warn!("host scan from {} failed: {}", iface.name, e);
+                (None, Some(e.to_string()))
+            }
+        };
+        scans.push(InterfaceHostScan {
+            iface_name: iface.name,
+            report,
+            error,
+        });
+    }
+    state.scans.remove(&run_id).await;
+    Ok(MultiInterfaceHostScanReport::new(scans))
+}
 
-    let default_if = netdev::get_default_interface().map_err(|e| e.to_string())?;
+/// Run a host scan and store the report in history.
+/// Shared by the `host_scan` command and scheduled jobs. Under `parent`, the
+/// run follows the parent's pause and cancel, and cancelling it cancels the parent.
+pub async fn run_host_scan(
+    sink: &impl ProgressEmitter,
+    state: &SharedState,
+    scan_setting: HostScanSetting,
+    parent: Option<&Arc<ScanControl>>,
+) -> Result<HostScanReport, ScanError> {
+    let run_id = uuid::Uuid::new_v4().to_string();
+    let checkpoint = ScanCheckpoint::new(
+        &run_id,
+        CheckpointScan::HostScan {
+            setting: scan_setting.clone(),
+            unreachable: Vec::new(),
+        },
+    );
+    host_scan_run(sink, state, &run_id, scan_setting, checkpoint, parent).await
+}
 
-    let src_ipv4_opt = default_if
-        .ipv4_addrs()
-        .into_iter()
-        .next()
-        .map(std::net::IpAddr::V4);
-    let src_ipv6_opt = default_if
-        .ipv6_addrs()
-        .into_iter()
-        .next()
-        .map(std::net::IpAddr::V6);
+/// Run a host scan under `run_id`, recording its progress on top of `checkpoint`.
+async fn host_scan_run(
+    sink: &impl ProgressEmitter,
+    state: &SharedState,
+    run_id: &str,
+    scan_setting: HostScanSetting,
+    checkpoint: ScanCheckpoint,
+    parent: Option<&Arc<ScanControl>>,
+) -> Result<HostScanReport, ScanError> {
+    let span = tracing::info_span!(
+        "hostscan",
+        run_id = %run_id,
+        protocol = "icmp",
+        targets = scan_setting.targets.len()
+    );
+    let control = match parent {
+        Some(parent) => state.scans.register_child(parent, run_id, "hostscan").await,
+        None => state.scans.register(run_id, "hostscan").await,
+    };
+    control.set_checkpoint(Checkpointer::new(checkpoint));
+    let result = scan_hosts(sink, run_id, scan_setting, control.clone())
+        .instrument(span)
+        .await;
+    state.scans.remove(run_id).await;
+    // Cancel and app shutdown end the run early too
+    let interrupted =
+        control.is_cancelled() || result.as_ref().is_ok_and(|report| report.deadline_reached);
+    if let (Some(parent), true) = (parent, control.is_cancelled()) {
+        parent.cancel();
+    }
+    finish_checkpoint(&control, interrupted).await;
+    if let Ok(report) = &result {
+        store_history(ScanRecord::HostScan(report.clone()), interrupted);
+    }
+    result.map_err(ScanError::from)
+}
 
-    let _ = app.emit(
-        "hostscan:start",
-        crate::model::scan::HostScanStartPayload {
-            run_id: run_id.clone(),
-        },
+/// Sweep the targets, then port scan every alive host (`fanout` at a time,
+/// default 4). Progress of both phases is reported as one `scan:progress`
+/// stream. The host scan and each port scan are kept in history.
+/// `channel` and `global_events` work as in `port_scan`.
+#[tauri::command]
+pub async fn host_port_scan(
+    app: AppHandle,
+    state: State<'_, SharedState>,
+    hosts: HostScanRequest,
+    ports: PortScanSetting,
+    fanout: Option<usize>,
+    channel: Option<Channel<ScanEvent>>,
+    global_events: Option<bool>,
+) -> Result<HostPortScanReport, ScanError> {
+    let sink = ChannelSink::new(app, channel, global_events);
+    let run_id = uuid::Uuid::new_v4().to_string();
+    let host_setting = HostScanSetting::from_request(hosts);
+    let span = tracing::info_span!(
+        "hostportscan",
+        run_id = %run_id,
+        protocol = ?ports.protocol,
+        targets = host_setting.targets.len()
     );
-    crate::probe::scan::icmp::host_scan(&app, &run_id, src_ipv4_opt, src_ipv6_opt, scan_setting)
-        .await
-        .map_err(|e| e.to_string())
+    // Discovery runs under this run ID and emits hostscan:* events
+    let control = state.scans.register(&run_id, "hostscan").await;
+    let result = scan_hosts_ports(
+        &sink,
+        &run_id,
+        host_setting,
+        ports,
+        fanout.unwrap_or(DEFAULT_HOST_FANOUT),
+        control.clone(),
+    )
+    .instrument(span)
+    .await;
+    state.scans.remove(&run_id).await;
+    if let Ok(report) = &result {
+        let interrupted = control.is_cancelled();
+        store_history(
+            ScanRecord::HostScan(report.discovery.clone()),
+            interrupted || report.discovery.deadline_reached,
+        );
+        for scan in &report.scans {
+            if let Some(port_report) = &scan.report {
+                store_history(
+                    ScanRecord::PortScan(port_report.clone()),
+                    interrupted || port_report.deadline_reached,
+                );
+            }
+        }
+    }
+    result.map_err(ScanError::from)
 }
 
+/// Continue an interrupted port or host scan under its original run ID.
+/// Ports and hosts its checkpoint records as done are skipped; the final
+/// report replaces the partial one in history.
+/// (`resume_scan` un-pauses a scan that is still running.)
+#[tauri::command]
+pub async fn resume_interrupted_scan(
+    app: AppHandle,
+    state: State<'_, SharedState>,
+    run_id: String,
+) -> Result<ScanRecord, ScanError> {
+    if state.scans.get(&run_id).await.is_some() {
+        return Err(ScanError::invalid_input(format!(
+            "scan still running: {run_id}"
+        )));
+    }
+    let checkpoint = crate::checkpoint::load(&run_id).map_err(ScanError::from)?;
+    match checkpoint.scan.clone() {
+        CheckpointScan::PortScan {
+            mut setting,
+            done_ports,
+        } => {
+            tracing::info!(
+                "resuming port scan {}: {} ports done",
+                run_id,
+                done_ports.len()
+            );
+            setting.exclude_ports.extend(done_ports);
+            port_scan_run(&app, &state, &run_id, setting, checkpoint)
+                .await
+                .map(ScanRecord::PortScan)
+        }
+        CheckpointScan::HostScan {
+            mut setting,
+            unreachable,
+        } => {
+            tracing::info!(
+                "resuming host scan {}: {} hosts done",
+                run_id,
+                unreachable.len()
+            );
+            setting
+                .exclude_targets
+                .extend(unreachable.iter().map(|host| host.ip.to_string()));
+            host_scan_run(&app, &state, &run_id, setting, checkpoint, None)
+                .await
+                .map(ScanRecord::HostScan)
+        }
+    }
+}
+
+/// Keep the checkpoint of a run cut short so it can be resumed,
+/// drop it once the run has finished or failed.
+async fn finish_checkpoint(control: &ScanControl, interrupted: bool) {
+    let Some(checkpoint) = control.checkpoint() else {
+        return;
+    };
+    if interrupted {
+        checkpoint.flush().await;
+    } else {
+        checkpoint.discard().await;
+    }
+}
+
+/// Store a report in scan history, `partial` if the run did not finish.
+/// Failures are logged, not returned.
+fn store_history(record: ScanRecord, partial: bool) {
+    if let Err(e) = crate::history::save(record, partial) {
+        tracing::error!("Failed to save scan history: {}", e);
+    }
+}
+
+/// Sweep the subnet of `iface_name` (default interface if unset) and
+/// resolve MAC addresses. `channel` and `global_events` work as in `port_scan`.
 #[tauri::command]
 pub async fn neighbor_scan(
     app: AppHandle,
+    state: State<'_, SharedState>,
     iface_name: Option<String>,
-) -> Result<NeighborScanReport, String> {
+    prefix_len: Option<u8>,
+    all_subnets: Option<bool>,
+    channel: Option<Channel<ScanEvent>>,
+    global_events: Option<bool>,
+) -> Result<NeighborScanReport, ScanError> {
+    let sink = ChannelSink::new(app, channel, global_events);
     let run_id = uuid::Uuid::new_v4().to_string();
-    let _ = app.emit("neighborscan:start", run_id.clone());
+    sink.emit("neighborscan:start", run_id.clone());
     let iface = if let Some(name) = iface_name {
-        netdev::get_interfaces()
-            .into_iter()
-            .find(|i| i.name == name || i.friendly_name.as_deref() == Some(&name))
-            .ok_or_else(|| format!("interface not found: {name}"))?
+        crate::net::interface::find_interface(&name)
+            .ok_or_else(|| ScanError::no_interface(format!("interface not found: {name}")))?
     } else {
-        netdev::get_default_interface().map_err(|e| e.to_string())?
+        netdev::get_default_interface().map_err(ScanError::no_interface)?
+    };
+    let opts = NeighborScanOptions {
+        prefix_len,
+        all_subnets: all_subnets.unwrap_or(false),
     };
-    crate::probe::scan::neigh::neighbor_scan(&app, &run_id, iface)
+    let span = tracing::info_span!(
+        "neighborscan",
+        run_id = %run_id,
+        protocol = "icmp",
+        target = %iface.name
+    );
+    // Underlying host scan emits hostscan:* events
+    let control = state.scans.register(&run_id, "hostscan").await;
+    let result = crate::probe::scan::neigh::neighbor_scan(&sink, &run_id, iface, opts, control)
+        .instrument(span)
+        .await;
+    state.scans.remove(&run_id).await;
+    result.map_err(ScanError::from)
+}
+
+/// One-click "who is on my network": sweeps the default interface's own
+/// subnet with quick ICMP settings, or TCP connects without ICMP privileges.
+/// `channel` and `global_events` work as in `port_scan`.
+#[tauri::command]
+pub async fn quick_lan_scan(
+    app: AppHandle,
+    state: State<'_, SharedState>,
+    channel: Option<Channel<ScanEvent>>,
+    global_events: Option<bool>,
+) -> Result<NeighborScanReport, ScanError> {
+    let sink = ChannelSink::new(app, channel, global_events);
+    let run_id = uuid::Uuid::new_v4().to_string();
+    sink.emit("neighborscan:start", run_id.clone());
+    let iface = netdev::get_default_interface().map_err(ScanError::no_interface)?;
+    let span = tracing::info_span!(
+        "neighborscan",
+        run_id = %run_id,
+        protocol = "quick",
+        target = %iface.name
+    );
+    let control = state.scans.register(&run_id, "hostscan").await;
+    let result = crate::probe::scan::neigh::quick_lan_scan(&sink, &run_id, iface, control)
+        .instrument(span)
+        .await;
+    state.scans.remove(&run_id).await;
+    result.map_err(ScanError::from)
+}
+
+#[tauri::command]
+pub async fn ip_protocol_scan(
+    app: AppHandle,
+    state: State<'_, SharedState>,
+    setting: IpProtocolScanSetting,
+) -> Result<IpProtocolScanReport, ScanError> {
+    let default_interface: Interface = netdev::get_default_interface()
+        .map_err(|e| ScanError::no_interface(format!("Failed to get default interface: {}", e)))?;
+    let src_ip = default_interface
+        .ipv4_addrs()
+        .into_iter()
+        .next()
+        .ok_or_else(|| ScanError::no_interface("No IPv4 address found on default interface"))?;
+    let run_id = uuid::Uuid::new_v4().to_string();
+    let _ = app.emit("ipprotoscan:start", run_id.clone());
+
+    let span = tracing::info_span!(
+        "ipprotoscan",
+        run_id = %run_id,
+        protocol = "ip",
+        target = %setting.ip_addr
+    );
+    let control = state.scans.register(&run_id, "ipprotoscan").await;
+    let result =
+        crate::probe::scan::ipproto::ip_protocol_scan(&app, &run_id, src_ip, setting, control)
+            .instrument(span)
+            .await;
+    state.scans.remove(&run_id).await;
+    result.map_err(ScanError::from)
+}
+
+/// Pause a running scan. In-flight probes complete; no new probes are issued.
+#[tauri::command]
+pub async fn pause_scan(
+    app: AppHandle,
+    state: State<'_, SharedState>,
+    run_id: String,
+) -> Result<(), ScanError> {
+    let control = state
+        .scans
+        .get(&run_id)
         .await
-        .map_err(|e| e.to_string())
+        .ok_or_else(|| ScanError::not_found(format!("scan not running: {run_id}")))?;
+    if control.pause() {
+        let _ = app.emit(
+            &format!("{}:paused", control.event_prefix()),
+            control.run_id().to_string(),
+        );
+    }
+    Ok(())
+}
+
+/// Resume a paused scan.
+#[tauri::command]
+pub async fn resume_scan(
+    app: AppHandle,
+    state: State<'_, SharedState>,
+    run_id: String,
+) -> Result<(), ScanError> {
+    let control = state
+        .scans
+        .get(&run_id)
+        .await
+        .ok_or_else(|| ScanError::not_found(format!("scan not running: {run_id}")))?;
+    if control.resume() {
+        let _ = app.emit(
+            &format!("{}:resumed", control.event_prefix()),
+            control.run_id().to_string(),
+        );
+    }
+    Ok(())
+}
+
+/// Cancel every running scan and wait for them to finalize, up to the
+/// configured `scan_shutdown_timeout_ms`. Returns the run IDs that did not stop in time.
+#[tauri::command]
+pub async fn shutdown_all_scans(
+    state: State<'_, SharedState>,
+    config: State<'_, ConfigState>,
+) -> Result<Vec<String>, ScanError> {
+    Ok(shutdown_scans(&state, &config).await)
+}
+
+/// Shared by `shutdown_all_scans` and app exit.
+pub async fn shutdown_scans(state: &SharedState, config: &ConfigState) -> Vec<String> {
+    let timeout = config.0.read().await.scan_shutdown_timeout();
+    let remaining = state.scans.shutdown_all(timeout).await;
+    if !remaining.is_empty() {
+        tracing::warn!(
+            "{} scans still running after {:?}: {}",
+            remaining.len(),
+            timeout,
+            remaining.join(", ")
+        );
+    }
+    remaining
 }
 
+/// Get buffered log lines captured during a scan run
 #[tauri::command]
-pub async fn get_target_ports(preset: String, user_ports: Vec<u16>) -> Vec<u16> {
+pub async fn get_run_logs(
+    state: State<'_, SharedState>,
+    run_id: String,
+) -> Result<Vec<RunLogLine>, ScanError> {
+    Ok(state.run_logs.get(&run_id))
+}
+
+#[tauri::command]
+pub async fn get_target_ports(
+    preset: String,
+    user_ports: Vec<u16>,
+    exclude_ports: Option<Vec<u16>>,
+) -> Vec<u16> {
     let preset_enum = match preset.as_str() {
         "Custom" => TargetPortsPreset::Custom,
         "Common" => TargetPortsPreset::Common,
         "WellKnown" => TargetPortsPreset::WellKnown,
         "Top1000" => TargetPortsPreset::Top1000,
         "Full" => TargetPortsPreset::Full,
+        "Services" => TargetPortsPreset::Services,
         _ => TargetPortsPreset::Common,
     };
-    crate::probe::scan::expand_ports(&preset_enum, &user_ports)
+    crate::probe::scan::expand_ports(
+        &preset_enum,
+        &user_ports,
+        &exclude_ports.unwrap_or_default(),
+    )
+}
+
+/// Service usually found on `port`, from the bundled TCP / UDP service
+/// databases (QUIC uses the UDP one). `None` if the port is not registered.
+#[tauri::command]
+pub async fn lookup_service(
+    port: u16,
+    protocol: TransportProtocol,
+) -> Result<Option<RegisteredService>, ScanError> {
+    let entry = match protocol {
+        TransportProtocol::Tcp => ndb_tcp_service::TcpServiceDb::bundled()
+            .get(port)
+            .map(|e| (e.name.clone(), e.description.clone())),
+        TransportProtocol::Udp | TransportProtocol::Quic => {
+            ndb_udp_service::UdpServiceDb::bundled()
+                .get(port)
+                .map(|e| (e.name.clone(), e.description.clone()))
+        }
+    };
+    Ok(entry.map(|(name, description)| RegisteredService {
+        port,
+        protocol,
+        name,
+        description,
+    }))
+}
+
+/// Ports registered for a service name in the bundled service databases
+/// (e.g. `http` -> tcp/80, tcp/8008, tcp/8080, ...), optionally for one protocol.
+#[tauri::command]
+pub async fn ports_for_service(
+    name: String,
+    protocol: Option<TransportProtocol>,
+) -> Result<Vec<Port>, ScanError> {
+    init_probe_db().await?;
+    let mut ports = crate::probe::service::db::service::ports_for_service(&name);
+    if let Some(protocol) = protocol {
+        // QUIC runs over UDP ports
+        let transport = match protocol {
+            TransportProtocol::Quic => TransportProtocol::Udp,
+            other => other,
+        };
+        ports.retain(|p| p.transport == transport);
+    }
+    Ok(ports)
+}
+
+/// Probe a single port without running the full scan pipeline
+#[tauri::command]
+pub async fn check_port(
+    ip: IpAddr,
+    port: u16,
+    protocol: PortScanProtocol,
+    timeout_ms: u64,
+    service_detection: bool,
+) -> Result<PortScanSample, ScanError> {
+    if service_detection {
+        init_probe_db().await?;
+    }
+    crate::probe::scan::check::check_port(
+        ip,
+        port,
+        protocol,
+        std::time::Duration::from_millis(timeout_ms),
+        service_detection,
+    )
+    .await
+    .map_err(ScanError::from)
 }
@@ -0,0 +1,39 @@
+use tauri::{AppHandle, State};
+
+use crate::config::ScanTemplateSetting;
+use crate::error::ScanError;
+use crate::service::schedule::ScanJob;
+use crate::state::SharedState;
+
+/// Schedule a scan to run every `interval_secs`
+#[tauri::command]
+pub async fn add_scan_job(
+    app: AppHandle,
+    state: State<'_, SharedState>,
+    name: Option<String>,
+    setting: ScanTemplateSetting,
+    interval_secs: u64,
+) -> Result<ScanJob, ScanError> {
+    state
+        .scheduler
+        .add(&app, &state, name, setting, interval_secs)
+        .await
+        .map_err(ScanError::from)
+}
+
+#[tauri::command]
+pub async fn remove_scan_job(
+    state: State<'_, SharedState>,
+    job_id: String,
+) -> Result<bool, ScanError> {
+    state
+        .scheduler
+        .remove(&job_id)
+        .await
+        .map_err(ScanError::from)
+}
+
+#[tauri::command]
+pub async fn list_scan_jobs(state: State<'_, SharedState>) -> Result<Vec<ScanJob>, ScanError> {
+    Ok(state.scheduler.list().await)
+}
@@ -2,9 +2,11 @@ use netsock::{
     family::AddressFamilyFlags, get_sockets, protocol::ProtocolFlags, socket::SocketInfo,
 };
 
+use crate::error::ScanError;
+
 /// Returns all sockets (IPv4/IPv6 x TCP/UDP) available on the host.
 #[tauri::command]
-pub fn get_sockets_all() -> Result<Vec<SocketInfo>, String> {
+pub fn get_sockets_all() -> Result<Vec<SocketInfo>, ScanError> {
     // Combine IPv4 + IPv6 address families.
     let af = AddressFamilyFlags::IPV4 | AddressFamilyFlags::IPV6;
 
@@ -12,7 +14,8 @@ pub fn get_sockets_all() -> Result<Vec<SocketInfo>, String> {
     let pf = ProtocolFlags::TCP | ProtocolFlags::UDP;
 
     // Retrieve all sockets using netsock.
-    let sockets = get_sockets(af, pf).map_err(|e| e.to_string())?;
+    let sockets = get_sockets(af, pf)
+        .map_err(|e| ScanError::socket_error(format!("failed to list sockets: {}", e)))?;
 
     // Directly return the result as JSON since SocketInfo implements Serialize.
     Ok(sockets)
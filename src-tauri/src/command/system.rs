@@ -1,7 +1,14 @@
-use crate::model::SysInfo;
+use crate::model::{SocketCapability, SysInfo};
 use crate::net::sys::os::system_info;
 
 #[tauri::command]
 pub fn get_sys_info() -> SysInfo {
     system_info()
 }
+
+/// Which socket types (ICMP, raw, TCP, UDP, QUIC) can be opened here,
+/// so unsupported scan modes can be disabled up front.
+#[tauri::command]
+pub async fn capabilities_check() -> Vec<SocketCapability> {
+    crate::socket::caps::check_capabilities().await
+}
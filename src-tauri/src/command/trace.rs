@@ -4,12 +4,13 @@ use anyhow::Result;
 use netdev::Interface;
 use tauri::{AppHandle, Emitter};
 
+use crate::error::ScanError;
 use crate::probe::trace::{self, TracerouteSetting};
 
 #[tauri::command]
-pub async fn traceroute(app: AppHandle, setting: TracerouteSetting) -> Result<(), String> {
+pub async fn traceroute(app: AppHandle, setting: TracerouteSetting) -> Result<(), ScanError> {
     let default_interface: Interface = netdev::get_default_interface()
-        .map_err(|e| format!("Failed to get default interface: {}", e))?;
+        .map_err(|e| ScanError::no_interface(format!("Failed to get default interface: {}", e)))?;
     let src_ip = match setting.ip_addr {
         std::net::IpAddr::V4(_) => {
             // Pick first IPv4 address of default interface
@@ -17,7 +18,9 @@ pub async fn traceroute(app: AppHandle, setting: TracerouteSetting) -> Result<()
                 .ipv4_addrs()
                 .into_iter()
                 .next()
-                .ok_or("No IPv4 address found on default interface")?;
+                .ok_or_else(|| {
+                    ScanError::no_interface("No IPv4 address found on default interface")
+                })?;
             IpAddr::V4(ipv4)
         }
         std::net::IpAddr::V6(_) => {
@@ -26,7 +29,9 @@ pub async fn traceroute(app: AppHandle, setting: TracerouteSetting) -> Result<()
                 .ipv6_addrs()
                 .into_iter()
                 .next()
-                .ok_or("No IPv6 address found on default interface")?;
+                .ok_or_else(|| {
+                    ScanError::no_interface("No IPv6 address found on default interface")
+                })?;
             IpAddr::V6(ipv6)
         }
     };
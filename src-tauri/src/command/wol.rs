@@ -0,0 +1,34 @@
+use std::net::{IpAddr, Ipv4Addr};
+
+use crate::error::ScanError;
+use crate::net::wol::{self, DEFAULT_WOL_BROADCAST, DEFAULT_WOL_PORT};
+
+/// Send a Wake-on-LAN magic packet.
+/// The MAC is taken from `mac`, or resolved from the neighbor table by `ip_addr`.
+/// Returns the MAC address the packet was sent for.
+#[tauri::command]
+pub async fn send_wol(
+    mac: Option<String>,
+    ip_addr: Option<IpAddr>,
+    broadcast_addr: Option<Ipv4Addr>,
+    port: Option<u16>,
+) -> Result<String, ScanError> {
+    let mac = match (mac.as_deref().map(str::trim), ip_addr) {
+        (Some(mac), _) if !mac.is_empty() => wol::parse_mac(mac),
+        (_, Some(ip)) => wol::resolve_mac(ip),
+        _ => {
+            return Err(ScanError::invalid_input(
+                "Either a MAC address or an IP address is required",
+            ))
+        }
+    }
+    .map_err(ScanError::from)?;
+    wol::send_magic_packet(
+        &mac,
+        broadcast_addr.unwrap_or(DEFAULT_WOL_BROADCAST),
+        port.unwrap_or(DEFAULT_WOL_PORT),
+    )
+    .await
+    .map_err(ScanError::from)?;
+    Ok(wol::format_mac(&mac))
+}
@@ -1,8 +1,14 @@
+use anyhow::{bail, Result};
 use serde::{Deserialize, Serialize};
+use std::time::Duration;
 
+use crate::error::ScanError;
 use crate::log::DEFAULT_LOG_FILE_NAME;
+use crate::model::scan::{HostScanSetting, PortScanSetting, TargetPortsPreset};
+use crate::probe::scan::control::DEFAULT_SCAN_SHUTDOWN_TIMEOUT_MS;
 
 pub const DEFAULT_CONFIG_FILE_NAME: &str = "netpulse-config.json";
+pub const DEFAULT_TEMPLATE_FILE_NAME: &str = "netpulse-templates.json";
 
 pub mod bps_unit {
     pub const BITS: &str = "bits";
@@ -24,6 +30,12 @@ pub struct AppConfig {
     pub data_unit: String,
     /// Logging configuration.
     pub logging: LoggingConfig,
+    /// Webhook notification for scheduled scans.
+    #[serde(default)]
+    pub notify: NotifyConfig,
+    /// Time running scans get to finalize when the app exits (default 3000).
+    #[serde(default)]
+    pub scan_shutdown_timeout_ms: Option<u64>,
 }
 
 // Implement default
@@ -42,8 +54,16 @@ impl AppConfig {
             theme: "system".to_string(),
             data_unit: bps_unit::BITS.to_string(),
             logging: LoggingConfig::new(),
+            notify: NotifyConfig::new(),
+            scan_shutdown_timeout_ms: None,
         }
     }
+    pub fn scan_shutdown_timeout(&self) -> Duration {
+        Duration::from_millis(
+            self.scan_shutdown_timeout_ms
+                .unwrap_or(DEFAULT_SCAN_SHUTDOWN_TIMEOUT_MS),
+        )
+    }
     pub fn load() -> AppConfig {
         match crate::fs::get_user_file_path(DEFAULT_CONFIG_FILE_NAME) {
             Some(path) => {
@@ -89,6 +109,32 @@ impl AppConfig {
     }
 }
 
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct NotifyConfig {
+    /// URL receiving a JSON POST. Notifications are disabled when unset.
+    pub webhook_url: Option<String>,
+    /// Notify after every completed scheduled scan.
+    pub on_complete: bool,
+    /// Notify when a scheduled scan differs from its previous run.
+    pub on_change: bool,
+}
+
+impl Default for NotifyConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl NotifyConfig {
+    pub fn new() -> NotifyConfig {
+        NotifyConfig {
+            webhook_url: None,
+            on_complete: false,
+            on_change: true,
+        }
+    }
+}
+
 #[derive(Deserialize, Serialize, Debug, PartialEq, Eq, Clone)]
 pub enum LogLevel {
     DEBUG,
@@ -147,3 +193,124 @@ impl LoggingConfig {
         }
     }
 }
+
+/// Scan settings stored in a template.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(tag = "kind", content = "setting")]
+pub enum ScanTemplateSetting {
+    PortScan(PortScanSetting),
+    HostScan(HostScanSetting),
+}
+
+/// Named scan profile persisted in the app config dir.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct ScanTemplate {
+    /// Template name (unique).
+    pub name: String,
+    /// Bundled template (read-only).
+    #[serde(default)]
+    pub builtin: bool,
+    /// Scan settings.
+    pub setting: ScanTemplateSetting,
+}
+
+impl ScanTemplate {
+    /// Built-in templates shipped with the app.
+    /// Targets are left unspecified and filled in by the caller.
+    pub fn builtin_templates() -> Vec<ScanTemplate> {
+        vec![
+            ScanTemplate {
+                name: "Web audit".to_string(),
+                builtin: true,
+                setting: ScanTemplateSetting::PortScan(PortScanSetting {
+                    user_ports: vec![80, 443, 8000, 8008, 8080, 8443, 8888],
+                    service_detection: true,
+                    ..Default::default()
+                }),
+            },
+            ScanTemplate {
+                name: "Service discovery".to_string(),
+                builtin: true,
+                setting: ScanTemplateSetting::PortScan(PortScanSetting {
+                    target_ports_preset: TargetPortsPreset::Services,
+                    service_detection: true,
+                    ..Default::default()
+                }),
+            },
+            ScanTemplate {
+                name: "LAN sweep".to_string(),
+                builtin: true,
+                setting: ScanTemplateSetting::HostScan(HostScanSetting {
+                    concurrency: Some(100),
+                    ..Default::default()
+                }),
+            },
+        ]
+    }
+    /// List built-in templates followed by user templates.
+    pub fn list() -> Vec<ScanTemplate> {
+        let mut templates = ScanTemplate::builtin_templates();
+        templates.extend(ScanTemplate::load_user_templates());
+        templates
+    }
+    pub fn find(name: &str) -> Option<ScanTemplate> {
+        ScanTemplate::list().into_iter().find(|t| t.name == name)
+    }
+    /// Save (insert or replace) a user template by name.
+    pub fn save(name: &str, setting: ScanTemplateSetting) -> Result<ScanTemplate> {
+        let name = name.trim();
+        if name.is_empty() {
+            bail!(ScanError::invalid_input("Template name must not be empty"));
+        }
+        if ScanTemplate::builtin_templates()
+            .iter()
+            .any(|t| t.name == name)
+        {
+            bail!(ScanError::invalid_input(format!(
+                "Cannot overwrite built-in template: {}",
+                name
+            )));
+        }
+        let template = ScanTemplate {
+            name: name.to_string(),
+            builtin: false,
+            setting,
+        };
+        let mut templates = ScanTemplate::load_user_templates();
+        match templates.iter_mut().find(|t| t.name == template.name) {
+            Some(t) => *t = template.clone(),
+            None => templates.push(template.clone()),
+        }
+        ScanTemplate::save_user_templates(&templates)?;
+        Ok(template)
+    }
+    fn load_user_templates() -> Vec<ScanTemplate> {
+        let Some(path) = crate::fs::get_user_file_path(DEFAULT_TEMPLATE_FILE_NAME) else {
+            return Vec::new();
+        };
+        if !path.exists() {
+            return Vec::new();
+        }
+        match std::fs::read_to_string(&path) {
+            Ok(content) => match serde_json::from_str::<Vec<ScanTemplate>>(&content) {
+                Ok(templates) => templates,
+                Err(e) => {
+                    tracing::error!("{:?}", e);
+                    Vec::new()
+                }
+            },
+            Err(e) => {
+                tracing::error!("{:?}", e);
+                Vec::new()
+            }
+        }
+    }
+    fn save_user_templates(templates: &[ScanTemplate]) -> Result<()> {
+        let Some(path) = crate::fs::get_user_file_path(DEFAULT_TEMPLATE_FILE_NAME) else {
+            bail!("Failed to resolve template file path");
+        };
+        let content = serde_json::to_string_pretty(templates)?;
+        std::fs::write(&path, content)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,227 @@
+//! Error type returned by every command.
+//!
+//! Serializes as `{"kind": "PermissionDenied", "message": "..."}` so the
+//! frontend can branch on `kind` and localize, while `message` keeps the
+//! text commands used to return as a plain string.
+
+use serde::Serialize;
+use std::io;
+
+use crate::net::dns::LookupFailure;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, thiserror::Error)]
+#[serde(tag = "kind")]
+pub enum ScanError {
+    /// Raw sockets, privileged ports or capture need elevated rights
+    #[error("{message}")]
+    PermissionDenied { message: String },
+    /// A host name did not resolve
+    #[error("{message}")]
+    ResolutionFailed { message: String },
+    /// The named or default interface does not exist or has no usable address
+    #[error("{message}")]
+    NoInterface { message: String },
+    /// Opening, binding or using a socket failed
+    #[error("{message}")]
+    SocketError { message: String },
+    #[error("{message}")]
+    Timeout { message: String },
+    #[error("{message}")]
+    Cancelled { message: String },
+    /// Not available on this platform
+    #[error("{message}")]
+    Unsupported { message: String },
+    /// A setting or argument was rejected
+    #[error("{message}")]
+    InvalidInput { message: String },
+    /// A scan, run, job or file does not exist
+    #[error("{message}")]
+    NotFound { message: String },
+    /// Reading or writing local files
+    #[error("{message}")]
+    Io { message: String },
+    #[error("{message}")]
+    Internal { message: String },
+}
+
+impl ScanError {
+    pub fn invalid_input(message: impl Into<String>) -> Self {
+        ScanError::InvalidInput {
+            message: message.into(),
+        }
+    }
+
+    pub fn not_found(message: impl Into<String>) -> Self {
+        ScanError::NotFound {
+            message: message.into(),
+        }
+    }
+
+    pub fn no_interface(message: impl Into<String>) -> Self {
+        ScanError::NoInterface {
+            message: message.into(),
+        }
+    }
+
+    pub fn permission_denied(message: impl Into<String>) -> Self {
+        ScanError::PermissionDenied {
+            message: message.into(),
+        }
+    }
+
+    pub fn resolution_failed(message: impl Into<String>) -> Self {
+        ScanError::ResolutionFailed {
+            message: message.into(),
+        }
+    }
+
+    pub fn unsupported(message: impl Into<String>) -> Self {
+        ScanError::Unsupported {
+            message: message.into(),
+        }
+    }
+
+    pub fn socket_error(message: impl Into<String>) -> Self {
+        ScanError::SocketError {
+            message: message.into(),
+        }
+    }
+
+    /// Same kind of error with another message
+    fn with_message(&self, message: String) -> Self {
+        match self {
+            ScanError::PermissionDenied { .. } => ScanError::PermissionDenied { message },
+            ScanError::ResolutionFailed { .. } => ScanError::ResolutionFailed { message },
+            ScanError::NoInterface { .. } => ScanError::NoInterface { message },
+            ScanError::SocketError { .. } => ScanError::SocketError { message },
+            ScanError::Timeout { .. } => ScanError::Timeout { message },
+            ScanError::Cancelled { .. } => ScanError::Cancelled { message },
+            ScanError::Unsupported { .. } => ScanError::Unsupported { message },
+            ScanError::InvalidInput { .. } => ScanError::InvalidInput { message },
+            ScanError::NotFound { .. } => ScanError::NotFound { message },
+            ScanError::Io { .. } => ScanError::Io { message },
+            ScanError::Internal { .. } => ScanError::Internal { message },
+        }
+    }
+
+    /// Category of an I/O error
+    fn from_io_kind(kind: io::ErrorKind, message: String) -> Self {
+        match kind {
+            io::ErrorKind::PermissionDenied => ScanError::PermissionDenied { message },
+            io::ErrorKind::TimedOut => ScanError::Timeout { message },
+            io::ErrorKind::Unsupported => ScanError::Unsupported { message },
+            io::ErrorKind::Interrupted => ScanError::Cancelled { message },
+            io::ErrorKind::AddrInUse
+            | io::ErrorKind::AddrNotAvailable
+            | io::ErrorKind::ConnectionRefused
+            | io::ErrorKind::ConnectionReset
+            | io::ErrorKind::ConnectionAborted
+            | io::ErrorKind::NotConnected
+            | io::ErrorKind::HostUnreachable
+            | io::ErrorKind::NetworkUnreachable
+            | io::ErrorKind::NetworkDown => ScanError::SocketError { message },
+            io::ErrorKind::InvalidInput => ScanError::InvalidInput { message },
+            io::ErrorKind::NotFound => ScanError::NotFound { message },
+            _ => ScanError::Io { message },
+        }
+    }
+}
+
+impl From<anyhow::Error> for ScanError {
+    /// The kind comes from the first typed error in the chain (a `ScanError`
+    /// raised by a scanner, an I/O, lookup or JSON error); the message is the
+    /// outermost context. Anything else is `Internal`.
+    fn from(e: anyhow::Error) -> Self {
+        let message = e.to_string();
+        for cause in e.chain() {
+            if let Some(error) = cause.downcast_ref::<ScanError>() {
+                return error.with_message(message);
+            }
+            if let Some(io) = cause.downcast_ref::<io::Error>() {
+                return Self::from_io_kind(io.kind(), message);
+            }
+            if cause.is::<LookupFailure>() {
+                return ScanError::ResolutionFailed { message };
+            }
+            if cause.is::<serde_json::Error>() {
+                return ScanError::InvalidInput { message };
+            }
+        }
+        ScanError::Internal { message }
+    }
+}
+
+impl From<io::Error> for ScanError {
+    fn from(e: io::Error) -> Self {
+        Self::from_io_kind(e.kind(), e.to_string())
+    }
+}
+
+/// Untyped messages, e.g. from a dependency's error
+impl From<String> for ScanError {
+    fn from(message: String) -> Self {
+        ScanError::Internal { message }
+    }
+}
+
+impl From<&str> for ScanError {
+    fn from(message: &str) -> Self {
+        ScanError::Internal {
+            message: message.to_string(),
+        }
+    }
+}
+
+impl From<serde_json::Error> for ScanError {
+    fn from(e: serde_json::Error) -> Self {
+        ScanError::InvalidInput {
+            message: e.to_string(),
+        }
+    }
+}
+
+impl From<tauri::Error> for ScanError {
+    fn from(e: tauri::Error) -> Self {
+        ScanError::Internal {
+            message: e.to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn serializes_tagged() {
+        let e = ScanError::invalid_input("Knock sequence is empty");
+        assert_eq!(
+            serde_json::to_value(&e).unwrap(),
+            serde_json::json!({"kind": "InvalidInput", "message": "Knock sequence is empty"})
+        );
+    }
+
+    #[test]
+    fn categorizes_errors() {
+        let denied = io::Error::from(io::ErrorKind::PermissionDenied);
+        let e = ScanError::from(anyhow::Error::new(denied).context("Failed to open capture"));
+        assert!(matches!(e, ScanError::PermissionDenied { .. }));
+        assert_eq!(e.to_string(), "Failed to open capture");
+
+        // A scanner's typed error keeps its kind under added context
+        let typed = anyhow::Error::new(ScanError::no_interface("Interface not found: eth9"))
+            .context("Host scan from eth9 failed");
+        let e = ScanError::from(typed);
+        assert_eq!(e, ScanError::no_interface("Host scan from eth9 failed"));
+
+        // The wording of an untyped error does not decide its kind
+        assert!(matches!(
+            ScanError::from(anyhow::anyhow!("Failed to resolve app dir")),
+            ScanError::Internal { .. }
+        ));
+        assert!(matches!(
+            ScanError::from(anyhow::Error::new(LookupFailure::NotFound)),
+            ScanError::ResolutionFailed { .. }
+        ));
+    }
+}
@@ -0,0 +1,579 @@
+//! Text renderings of scan reports, shared by every export command.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::history::ScanRecord;
+use crate::model::endpoint::Host;
+use crate::model::findings::{Finding, FindingRule, FindingSeverity, FindingsReport};
+use crate::model::scan::{HostScanReport, PortScanProtocol, PortScanReport, PortScanSample};
+use crate::probe::service::models::TlsInfo;
+
+/// Output format of an exported report
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// The report as pretty-printed JSON
+    Json,
+    /// One row per port or host, with a header row
+    Csv,
+    /// One line per host in nmap's grepable (`-oG`) layout
+    Grepable,
+    /// Aligned columns for reading or pasting into a ticket
+    Table,
+}
+
+/// Render a scan report in `format`.
+pub fn format_report(record: &ScanRecord, format: ExportFormat) -> Result<String> {
+    let text = match record {
+        ScanRecord::PortScan(r) => {
+            // Spilled scans keep their samples in the results file
+            let r = r.with_samples()?;
+            match format {
+                ExportFormat::Json => serde_json::to_string_pretty(&*r)?,
+                ExportFormat::Csv => to_csv(&PORT_HEADER, &port_rows(&r)),
+                ExportFormat::Grepable => port_grepable(&r),
+                ExportFormat::Table => to_table(&PORT_HEADER, &port_rows(&r)),
+            }
+        }
+        ScanRecord::HostScan(r) => match format {
+            ExportFormat::Json => serde_json::to_string_pretty(r)?,
+            ExportFormat::Csv => to_csv(&HOST_HEADER, &host_rows(r)),
+            ExportFormat::Grepable => host_grepable(r),
+            ExportFormat::Table => to_table(&HOST_HEADER, &host_rows(r)),
+        },
+    };
+    Ok(text)
+}
+
+const PORT_HEADER: [&str; 9] = [
+    "ip_addr", "hostname", "port", "protocol", "state", "service", "product", "version", "rtt_ms",
+];
+const HOST_HEADER: [&str; 4] = ["ip_addr", "hostname", "status", "rtt_ms"];
+
+fn transport(protocol: &PortScanProtocol) -> &'static str {
+    match protocol {
+        PortScanProtocol::Tcp => "tcp",
+        PortScanProtocol::Quic => "udp",
+    }
+}
+
+/// Service name of a sample, preferring the detected one
+fn service_name(sample: &PortScanSample) -> String {
+    sample
+        .service_info
+        .as_ref()
+        .and_then(|info| info.name.clone())
+        .or_else(|| sample.service_name.clone())
+        .unwrap_or_default()
+}
+
+fn port_rows(report: &PortScanReport) -> Vec<Vec<String>> {
+    report
+        .samples
+        .iter()
+        .map(|s| {
+            let info = s.service_info.as_ref();
+            vec![
+                s.ip_addr.to_string(),
+                report.hostname.clone().unwrap_or_default(),
+                s.port.to_string(),
+                transport(&report.protocol).to_string(),
+                s.state.as_str().to_string(),
+                service_name(s),
+                info.and_then(|i| i.product.clone()).unwrap_or_default(),
+                info.and_then(|i| i.version.clone()).unwrap_or_default(),
+                s.rtt_ms.map(|rtt| rtt.to_string()).unwrap_or_default(),
+            ]
+        })
+        .collect()
+}
+
+fn host_row(host: &Host, status: &str, rtt_ms: Option<u64>) -> Vec<String> {
+    vec![
+        host.ip.to_string(),
+        host.hostname.clone().unwrap_or_default(),
+        status.to_string(),
+        rtt_ms.map(|rtt| rtt.to_string()).unwrap_or_default(),
+    ]
+}
+
+fn host_rows(report: &HostScanReport) -> Vec<Vec<String>> {
+    let alive = report
+        .alive
+        .iter()
+        .map(|(host, rtt)| host_row(host, "up", Some(*rtt)));
+    let down = report
+        .unreachable
+        .iter()
+        .map(|host| host_row(host, "down", None));
+    alive.chain(down).collect()
+}
+
+/// Quote a CSV field when it holds a separator, quote or line break (RFC 4180)
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+fn to_csv(header: &[&str], rows: &[Vec<String>]) -> String {
+    let mut out = header.join(",");
+    out.push('\n');
+    for row in rows {
+        let fields: Vec<String> = row.iter().map(|f| csv_field(f)).collect();
+        out.push_str(&fields.join(","));
+        out.push('\n');
+    }
+    out
+}
+
+/// One table row, each field left-aligned to its column width
+fn table_line(fields: &[impl AsRef<str>], widths: &[usize]) -> String {
+    let cells: Vec<String> = fields
+        .iter()
+        .zip(widths)
+        .map(|(f, w)| format!("{:<w$}", f.as_ref(), w = w))
+        .collect();
+    format!("{}\n", cells.join("  ").trim_end())
+}
+
+fn to_table(header: &[&str], rows: &[Vec<String>]) -> String {
+    let mut widths: Vec<usize> = header.iter().map(|h| h.chars().count()).collect();
+    for row in rows {
+        for (width, field) in widths.iter_mut().zip(row) {
+            *width = (*width).max(field.chars().count());
+        }
+    }
+    let rule: Vec<String> = widths.iter().map(|w| "-".repeat(*w)).collect();
+    let mut out = table_line(header, &widths);
+    out.push_str(&table_line(&rule, &widths));
+    for row in rows {
+        out.push_str(&table_line(row, &widths));
+    }
+    out
+}
+
+/// `Host: <ip> (<hostname>)`
+fn grepable_host(ip: &str, hostname: Option<&str>) -> String {
+    format!("Host: {} ({})", ip, hostname.unwrap_or(""))
+}
+
+/// Field of a grepable port entry; `/` and `,` delimit entries
+fn grepable_field(field: &str) -> String {
+    field.replace(['/', ','], "|")
+}
+
+fn port_grepable(report: &PortScanReport) -> String {
+    let ports: Vec<String> = report
+        .samples
+        .iter()
+        .map(|s| {
+            let info = s.service_info.as_ref();
+            let version = [
+                info.and_then(|i| i.product.as_deref()),
+                info.and_then(|i| i.version.as_deref()),
+            ]
+            .into_iter()
+            .flatten()
+            .collect::<Vec<_>>()
+            .join(" ");
+            format!(
+                "{}/{}/{}//{}//{}/",
+                s.port,
+                s.state.as_str(),
+                transport(&report.protocol),
+                grepable_field(&service_name(s)),
+                grepable_field(&version)
+            )
+        })
+        .collect();
+    format!(
+        "{}\tPorts: {}\n",
+        grepable_host(&report.ip_addr.to_string(), report.hostname.as_deref()),
+        ports.join(", ")
+    )
+}
+
+fn host_grepable(report: &HostScanReport) -> String {
+    let mut out = String::new();
+    for (host, rtt) in &report.alive {
+        out.push_str(&format!(
+            "{}\tStatus: Up\tRTT: {}ms\n",
+            grepable_host(&host.ip.to_string(), host.hostname.as_deref()),
+            rtt
+        ));
+    }
+    for host in &report.unreachable {
+        out.push_str(&format!(
+            "{}\tStatus: Down\n",
+            grepable_host(&host.ip.to_string(), host.hostname.as_deref())
+        ));
+    }
+    out
+}
+
+/// Escape text for HTML element content and attribute values
+fn escape_html(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// `<tr><th>label</th><td>value</td></tr>` with `value` already escaped
+fn html_meta_row(label: &str, value_html: &str) -> String {
+    format!(
+        "<tr><th>{}</th><td>{}</td></tr>",
+        escape_html(label),
+        value_html
+    )
+}
+
+fn format_duration(ms: u64) -> String {
+    if ms < 1000 {
+        format!("{} ms", ms)
+    } else {
+        format!("{:.1} s", ms as f64 / 1000.0)
+    }
+}
+
+fn html_target(report: &PortScanReport) -> String {
+    match &report.hostname {
+        Some(hostname) => format!("{} ({})", hostname, report.ip_addr),
+        None => report.ip_addr.to_string(),
+    }
+}
+
+fn html_meta_rows(report: &PortScanReport) -> String {
+    let mut rows = vec![
+        html_meta_row("Target", &escape_html(&html_target(report))),
+        html_meta_row("Protocol", transport(&report.protocol)),
+    ];
+    if let Some(preset) = &report.ports_preset {
+        rows.push(html_meta_row(
+            "Port profile",
+            &escape_html(&format!("{:?}", preset)),
+        ));
+    }
+    if let Some(scanned) = report.ports_scanned {
+        rows.push(html_meta_row("Ports scanned", &scanned.to_string()));
+    }
+    if let Some(ms) = report.elapsed_ms {
+        rows.push(html_meta_row("Duration", &format_duration(ms)));
+    }
+    if report.deadline_reached {
+        rows.push(html_meta_row(
+            "Completed",
+            "<span class=\"warning\">No, stopped at the time limit</span>",
+        ));
+    }
+    if report.suspicious {
+        let reason = report.suspicious_reason.as_deref().unwrap_or("flagged");
+        rows.push(html_meta_row(
+            "Suspicious target",
+            &format!("<span class=\"warning\">{}</span>", escape_html(reason)),
+        ));
+    }
+    rows.join("\n")
+}
+
+fn html_summary_rows(report: &PortScanReport) -> String {
+    let summary = &report.summary;
+    let mut rows = vec![html_meta_row("Open ports", &summary.open.to_string())];
+    if !summary.by_service.is_empty() {
+        let services: Vec<String> = summary
+            .by_service
+            .iter()
+            .map(|s| format!("{} ({})", escape_html(&s.name), s.count))
+            .collect();
+        rows.push(html_meta_row("Services", &services.join(", ")));
+    }
+    if let Some(rtt) = &summary.rtt {
+        rows.push(html_meta_row(
+            "RTT min / avg / max",
+            &format!("{} / {:.1} / {} ms", rtt.min_ms, rtt.avg_ms, rtt.max_ms),
+        ));
+    }
+    rows.join("\n")
+}
+
+fn html_port_rows(report: &PortScanReport) -> String {
+    if report.samples.is_empty() {
+        return "<tr><td colspan=\"7\" class=\"empty\">No ports reported.</td></tr>".to_string();
+    }
+    report
+        .samples
+        .iter()
+        .map(|s| {
+            let info = s.service_info.as_ref();
+            let details = info
+                .and_then(|i| i.banner.clone())
+                .or_else(|| s.message.clone())
+                .unwrap_or_default();
+            let cell = |v: Option<String>| escape_html(&v.unwrap_or_default());
+            format!(
+                "<tr><td class=\"num\">{}</td><td class=\"state-{}\">{}</td><td>{}</td><td>{}</td><td>{}</td><td class=\"num\">{}</td><td class=\"mono\">{}</td></tr>",
+                s.port,
+                s.state.as_str().replace('|', "-"),
+                escape_html(s.state.as_str()),
+                escape_html(&service_name(s)),
+                cell(info.and_then(|i| i.product.clone())),
+                cell(info.and_then(|i| i.version.clone())),
+                s.rtt_ms.map(|rtt| rtt.to_string()).unwrap_or_default(),
+                escape_html(&details),
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn html_tls_section(port: u16, tls: &TlsInfo) -> String {
+    let fields = [
+        ("Subject", tls.subject.clone()),
+        ("Issuer", tls.issuer.clone()),
+        (
+            "Subject alternative names",
+            (!tls.san_list.is_empty()).then(|| tls.san_list.join(", ")),
+        ),
+        ("Valid from", tls.not_before.clone()),
+        ("Valid until", tls.not_after.clone()),
+        ("Protocol", tls.version.clone()),
+        ("Cipher suite", tls.cipher_suite.clone()),
+        ("ALPN", tls.alpn.clone()),
+        ("Signature algorithm", tls.sig_algorithm.clone()),
+        ("Public key algorithm", tls.pubkey_algorithm.clone()),
+        ("Serial", tls.serial_hex.clone()),
+    ];
+    let rows: Vec<String> = fields
+        .into_iter()
+        .filter_map(|(label, value)| Some(html_meta_row(label, &escape_html(&value?))))
+        .collect();
+    format!(
+        "<h3>Port {}</h3>\n<table class=\"meta\">\n{}\n</table>",
+        port,
+        rows.join("\n")
+    )
+}
+
+fn html_tls_sections(report: &PortScanReport) -> String {
+    let sections: Vec<String> = report
+        .samples
+        .iter()
+        .filter_map(|s| {
+            let tls = s.service_info.as_ref()?.tls_info.as_ref()?;
+            Some(html_tls_section(s.port, tls))
+        })
+        .collect();
+    if sections.is_empty() {
+        "<p class=\"empty\">No TLS services detected.</p>".to_string()
+    } else {
+        sections.join("\n")
+    }
+}
+
+/// Replace `{{name}}` placeholders in one pass, so values that happen to
+/// contain a placeholder are left alone. Unknown placeholders are kept.
+fn fill_template(template: &str, values: &[(&str, String)]) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let value = after.find("}}").and_then(|end| {
+            let name = &after[..end];
+            let (_, value) = values.iter().find(|(key, _)| *key == name)?;
+            Some((value, end))
+        });
+        match value {
+            Some((value, end)) => {
+                out.push_str(value);
+                rest = &after[end + 2..];
+            }
+            None => {
+                out.push_str("{{");
+                rest = after;
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Render a port scan as a standalone HTML page from the bundled template.
+pub fn port_scan_html(report: &PortScanReport) -> Result<String> {
+    let report = report.with_samples()?;
+    let title = format!("NetPulse report: {}", html_target(&report));
+    Ok(fill_template(
+        crate::resources::REPORT_TEMPLATE_HTML,
+        &[
+            ("title", escape_html(&title)),
+            ("meta_rows", html_meta_rows(&report)),
+            ("summary_rows", html_summary_rows(&report)),
+            ("port_rows", html_port_rows(&report)),
+            ("tls_sections", html_tls_sections(&report)),
+            ("run_id", escape_html(&report.run_id)),
+        ],
+    ))
+}
+
+/// Output format of an exported findings document
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FindingsFormat {
+    /// The versioned netpulse findings schema as JSON
+    Json,
+    /// SARIF 2.1.0, for code scanning and security dashboards
+    Sarif,
+}
+
+/// Derive the findings of a scan and render them in `format`.
+pub fn format_findings(record: &ScanRecord, format: FindingsFormat) -> Result<String> {
+    let findings = FindingsReport::from_record(record)?;
+    let text = match format {
+        FindingsFormat::Json => serde_json::to_string_pretty(&findings)?,
+        FindingsFormat::Sarif => serde_json::to_string_pretty(&to_sarif(&findings))?,
+    };
+    Ok(text)
+}
+
+const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+
+fn sarif_level(severity: FindingSeverity) -> &'static str {
+    match severity {
+        FindingSeverity::Info => "note",
+        FindingSeverity::Low | FindingSeverity::Medium => "warning",
+        FindingSeverity::High | FindingSeverity::Critical => "error",
+    }
+}
+
+/// Address of a finding, e.g. `tcp://192.0.2.1:443` or `ip://192.0.2.1`
+fn finding_uri(finding: &Finding) -> String {
+    let ip = match finding.target.ip_addr {
+        std::net::IpAddr::V4(ip) => ip.to_string(),
+        std::net::IpAddr::V6(ip) => format!("[{}]", ip),
+    };
+    match (&finding.target.protocol, finding.target.port) {
+        (Some(protocol), Some(port)) => format!("{}://{}:{}", protocol, ip, port),
+        _ => format!("ip://{}", ip),
+    }
+}
+
+fn to_sarif(report: &FindingsReport) -> serde_json::Value {
+    let rules: Vec<serde_json::Value> = FindingRule::ALL
+        .iter()
+        .map(|rule| {
+            serde_json::json!({
+                "id": rule.id(),
+                "shortDescription": { "text": rule.description() },
+            })
+        })
+        .collect();
+    let results: Vec<serde_json::Value> = report
+        .findings
+        .iter()
+        .map(|finding| {
+            serde_json::json!({
+                "ruleId": finding.rule.id(),
+                "ruleIndex": FindingRule::ALL.iter().position(|r| *r == finding.rule),
+                "level": sarif_level(finding.severity),
+                "message": { "text": format!("{}: {}", finding.title, finding.evidence) },
+                "locations": [{
+                    "physicalLocation": {
+                        "artifactLocation": { "uri": finding_uri(finding) }
+                    }
+                }],
+                "properties": {
+                    "severity": finding.severity,
+                    "hostname": finding.target.hostname,
+                },
+            })
+        })
+        .collect();
+    serde_json::json!({
+        "$schema": SARIF_SCHEMA,
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "NetPulse",
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "informationUri": env!("CARGO_PKG_REPOSITORY"),
+                    "rules": rules,
+                }
+            },
+            "automationDetails": { "id": report.run_id },
+            "properties": { "findingsSchemaVersion": report.schema_version },
+            "results": results,
+        }]
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn csv_quotes_fields_with_separators() {
+        let rows = vec![vec!["a,b".to_string(), "say \"hi\"".to_string()]];
+        assert_eq!(
+            to_csv(&["x", "y"], &rows),
+            "x,y\n\"a,b\",\"say \"\"hi\"\"\"\n"
+        );
+    }
+
+    #[test]
+    fn table_aligns_columns() {
+        let rows = vec![vec!["10.0.0.1".to_string(), "up".to_string()]];
+        assert_eq!(
+            to_table(&["ip", "status"], &rows),
+            "ip        status\n--------  ------\n10.0.0.1  up\n"
+        );
+    }
+
+    #[test]
+    fn html_report_fills_template() {
+        let report = PortScanReport {
+            hostname: Some("<host>".into()),
+            elapsed_ms: Some(1500),
+            ports_scanned: Some(1000),
+            scanned_ports: vec![(1, 1000)],
+            ..PortScanReport::fixture("192.0.2.1".parse().unwrap(), Vec::new())
+        };
+        let html = port_scan_html(&report).unwrap();
+        assert!(!html.contains("{{"));
+        assert!(html.contains("&lt;host&gt; (192.0.2.1)"));
+        assert!(html.contains("1.5 s"));
+    }
+
+    #[test]
+    fn sarif_reports_open_ports() {
+        let ip = "192.0.2.1".parse().unwrap();
+        let sample = PortScanSample {
+            rtt_ms: Some(3),
+            service_name: Some("telnet".into()),
+            ..PortScanSample::fixture(ip, 23, crate::model::scan::PortState::Open)
+        };
+        let report = PortScanReport::fixture(ip, vec![sample]);
+        let findings = FindingsReport::from_record(&ScanRecord::PortScan(report)).unwrap();
+        assert_eq!(findings.findings.len(), 2);
+        // Most severe first
+        assert_eq!(findings.findings[0].rule, FindingRule::CleartextLogin);
+        let sarif = to_sarif(&findings);
+        let result = &sarif["runs"][0]["results"][1];
+        assert_eq!(result["ruleId"], "NP-OPEN-PORT");
+        assert_eq!(result["level"], "note");
+        assert_eq!(
+            result["locations"][0]["physicalLocation"]["artifactLocation"]["uri"],
+            "tcp://192.0.2.1:23"
+        );
+    }
+}
@@ -0,0 +1,230 @@
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::error::ScanError;
+use crate::model::scan::{HostScanReport, PortScanReport};
+
+/// Directory under the app dir holding one JSON file per scan run
+pub const HISTORY_DIR_NAME: &str = "history";
+/// Stored scan runs kept at most; the oldest are evicted first
+pub const MAX_HISTORY_ENTRIES: usize = 500;
+/// Stored scan runs older than this are evicted
+pub const MAX_HISTORY_AGE: Duration = Duration::from_secs(90 * 24 * 60 * 60);
+
+/// Report of a completed scan
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(tag = "kind", content = "report")]
+pub enum ScanRecord {
+    PortScan(PortScanReport),
+    HostScan(HostScanReport),
+}
+
+impl ScanRecord {
+    pub fn run_id(&self) -> &str {
+        match self {
+            ScanRecord::PortScan(r) => &r.run_id,
+            ScanRecord::HostScan(r) => &r.run_id,
+        }
+    }
+
+    /// Short description of what was scanned
+    pub fn target(&self) -> String {
+        match self {
+            ScanRecord::PortScan(r) => r.hostname.clone().unwrap_or_else(|| r.ip_addr.to_string()),
+            ScanRecord::HostScan(r) => format!("{} hosts", r.total),
+        }
+    }
+}
+
+/// Stored scan run
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ScanHistoryEntry {
+    pub run_id: String,
+    /// Unix timestamp in milliseconds
+    pub saved_at_ms: u64,
+    /// Cancelled, shut down or cut off at its deadline before finishing
+    #[serde(default)]
+    pub partial: bool,
+    pub record: ScanRecord,
+}
+
+/// Summary of a stored scan run for listing
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ScanHistorySummary {
+    pub run_id: String,
+    pub saved_at_ms: u64,
+    /// "PortScan" or "HostScan"
+    pub kind: String,
+    pub target: String,
+    #[serde(default)]
+    pub partial: bool,
+}
+
+impl ScanHistoryEntry {
+    pub fn summary(&self) -> ScanHistorySummary {
+        let kind = match &self.record {
+            ScanRecord::PortScan(_) => "PortScan",
+            ScanRecord::HostScan(_) => "HostScan",
+        };
+        ScanHistorySummary {
+            run_id: self.run_id.clone(),
+            saved_at_ms: self.saved_at_ms,
+            kind: kind.to_string(),
+            target: self.record.target(),
+            partial: self.partial,
+        }
+    }
+}
+
+fn history_dir() -> Result<PathBuf> {
+    let mut path = crate::fs::get_app_dir_path().context("Failed to resolve app dir")?;
+    path.push(HISTORY_DIR_NAME);
+    if !path.exists() {
+        std::fs::create_dir_all(&path)?;
+    }
+    Ok(path)
+}
+
+fn entry_path(run_id: &str) -> Result<PathBuf> {
+    // Run IDs are UUIDs; reject anything else so it can't escape the history dir
+    if uuid::Uuid::parse_str(run_id).is_err() {
+        bail!(ScanError::invalid_input(format!(
+            "Invalid run ID: {}",
+            run_id
+        )));
+    }
+    let mut path = history_dir()?;
+    path.push(format!("{}.json", run_id));
+    Ok(path)
+}
+
+/// Store a scan report, `partial` if the run did not finish
+pub fn save(record: ScanRecord, partial: bool) -> Result<ScanHistoryEntry> {
+    let saved_at_ms = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0);
+    let entry = ScanHistoryEntry {
+        run_id: record.run_id().to_string(),
+        saved_at_ms,
+        partial,
+        record,
+    };
+    let path = entry_path(&entry.run_id)?;
+    std::fs::write(&path, serde_json::to_string(&entry)?)?;
+    if let Err(e) = prune() {
+        tracing::warn!("Failed to prune scan history: {}", e);
+    }
+    Ok(entry)
+}
+
+/// Run IDs to evict from `(run_id, saved_at)` pairs: everything older than
+/// `MAX_HISTORY_AGE`, then the oldest beyond `MAX_HISTORY_ENTRIES`.
+fn evictions(mut entries: Vec<(String, SystemTime)>, now: SystemTime) -> Vec<String> {
+    entries.sort_by(|a, b| b.1.cmp(&a.1));
+    entries
+        .into_iter()
+        .enumerate()
+        .filter(|(i, (_, saved_at))| {
+            *i >= MAX_HISTORY_ENTRIES
+                || now
+                    .duration_since(*saved_at)
+                    .is_ok_and(|age| age > MAX_HISTORY_AGE)
+        })
+        .map(|(_, (run_id, _))| run_id)
+        .collect()
+}
+
+/// Evict old scan runs together with their spilled results. Entries are
+/// aged by file modification time, so the reports are not parsed.
+fn prune() -> Result<()> {
+    let mut entries = Vec::new();
+    for dir_entry in std::fs::read_dir(history_dir()?)? {
+        let dir_entry = dir_entry?;
+        let path = dir_entry.path();
+        if path.extension().and_then(|e| e.to_str()) != Some("json") {
+            continue;
+        }
+        let Some(run_id) = path.file_stem().and_then(|s| s.to_str()) else {
+            continue;
+        };
+        entries.push((run_id.to_string(), dir_entry.metadata()?.modified()?));
+    }
+    for run_id in evictions(entries, SystemTime::now()) {
+        if let Err(e) = remove(&run_id) {
+            tracing::warn!("Failed to evict scan run {}: {}", run_id, e);
+        }
+    }
+    Ok(())
+}
+
+/// Delete a stored scan run and its results files
+fn remove(run_id: &str) -> Result<()> {
+    let paths = [
+        entry_path(run_id)?,
+        crate::results::results_path(run_id)?,
+        crate::results::partial_path(run_id)?,
+    ];
+    for path in paths {
+        match std::fs::remove_file(&path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                return Err(e).with_context(|| format!("Failed to remove {}", path.display()))
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Load a stored scan run
+pub fn load(run_id: &str) -> Result<ScanHistoryEntry> {
+    let path = entry_path(run_id)?;
+    let content = std::fs::read_to_string(&path)
+        .with_context(|| format!("Scan run not found in history: {}", run_id))?;
+    Ok(serde_json::from_str(&content)?)
+}
+
+/// List stored scan runs, newest first
+pub fn list() -> Result<Vec<ScanHistorySummary>> {
+    let mut summaries = Vec::new();
+    for dir_entry in std::fs::read_dir(history_dir()?)? {
+        let path = dir_entry?.path();
+        if path.extension().and_then(|e| e.to_str()) != Some("json") {
+            continue;
+        }
+        let parsed = std::fs::read_to_string(&path)
+            .map_err(anyhow::Error::from)
+            .and_then(|c| Ok(serde_json::from_str::<ScanHistoryEntry>(&c)?));
+        match parsed {
+            Ok(entry) => summaries.push(entry.summary()),
+            Err(e) => tracing::warn!("Skipping history file {:?}: {}", path, e),
+        }
+    }
+    summaries.sort_by(|a, b| b.saved_at_ms.cmp(&a.saved_at_ms));
+    Ok(summaries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn evicts_by_age_then_count() {
+        let now = SystemTime::now();
+        let day = Duration::from_secs(24 * 60 * 60);
+        let mut entries: Vec<(String, SystemTime)> = (0..MAX_HISTORY_ENTRIES + 2)
+            .map(|i| (format!("run-{}", i), now - Duration::from_secs(i as u64)))
+            .collect();
+        entries.push(("stale".to_string(), now - MAX_HISTORY_AGE - day));
+        let mut evicted = evictions(entries, now);
+        evicted.sort();
+        let expected = [
+            format!("run-{}", MAX_HISTORY_ENTRIES),
+            format!("run-{}", MAX_HISTORY_ENTRIES + 1),
+            "stale".to_string(),
+        ];
+        assert_eq!(evicted, expected);
+    }
+}
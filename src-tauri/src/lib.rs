@@ -1,12 +1,18 @@
 mod app;
+mod checkpoint;
+pub mod cli;
 mod command;
 mod config;
+mod error;
+mod export;
 mod fs;
+mod history;
 mod log;
 mod model;
 mod net;
 mod probe;
 mod resources;
+mod results;
 mod service;
 mod socket;
 mod state;
@@ -1,15 +1,27 @@
 use anyhow::Result;
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::fmt::Write as _;
 use std::fs::File;
 use std::path::Path;
-use tracing::Level;
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id};
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::filter::LevelFilter;
 use tracing_subscriber::fmt::time::ChronoLocal;
 use tracing_subscriber::fmt::writer::BoxMakeWriter;
 use tracing_subscriber::fmt::writer::MakeWriterExt;
-use tracing_subscriber::FmtSubscriber;
+use tracing_subscriber::layer::{Context, SubscriberExt};
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::{Layer, Registry};
 
 pub const DEFAULT_LOG_FILE_NAME: &str = "netpulse.log";
+/// Maximum number of log lines kept in memory across all runs.
+pub const RUN_LOG_CAPACITY: usize = 5000;
 
-pub fn init_logger(config: &crate::config::AppConfig) -> Result<()> {
+pub fn init_logger(config: &crate::config::AppConfig, run_logs: Arc<RunLogBuffer>) -> Result<()> {
     // Init logger
     let log_file_path = if let Some(file_path) = &config.logging.file_path {
         // Convert to PathBuf
@@ -28,28 +40,167 @@ pub fn init_logger(config: &crate::config::AppConfig) -> Result<()> {
         let error_writer = error_log.with_max_level(Level::ERROR);
         let else_writer = BoxMakeWriter::new(std::io::stdout);
         let writer = error_writer.and(else_writer);
-        let subscriber = FmtSubscriber::builder()
-            .with_max_level(config.logging.level.to_level_filter())
+        let fmt_layer = tracing_subscriber::fmt::layer()
             .with_ansi(false)
             .with_target(false)
             .with_timer(ChronoLocal::rfc_3339())
             .with_writer(writer)
-            .finish();
+            .with_filter(LevelFilter::from_level(
+                config.logging.level.to_level_filter(),
+            ));
+        // Scan runs are always captured at DEBUG level into the in-memory buffer
+        let run_log_layer = RunLogLayer::new(run_logs).with_filter(LevelFilter::DEBUG);
+        let subscriber = Registry::default().with(fmt_layer).with(run_log_layer);
         tracing::subscriber::set_global_default(subscriber)
             .expect("setting default subscriber failed");
     } else {
         // In release mode, log only to the error log file
         let error_writer = error_log.with_max_level(Level::ERROR);
-        let subscriber = FmtSubscriber::builder()
-            .with_max_level(Level::ERROR)
+        let fmt_layer = tracing_subscriber::fmt::layer()
             .with_ansi(false)
             .with_target(false)
             .with_timer(ChronoLocal::rfc_3339())
             .with_writer(error_writer)
-            .finish();
+            .with_filter(LevelFilter::ERROR);
+        // Scan runs are always captured at DEBUG level into the in-memory buffer
+        let run_log_layer = RunLogLayer::new(run_logs).with_filter(LevelFilter::DEBUG);
+        let subscriber = Registry::default().with(fmt_layer).with(run_log_layer);
         tracing::subscriber::set_global_default(subscriber)
             .expect("setting default subscriber failed");
     }
 
     Ok(())
 }
+
+/// Log line captured within a scan run span.
+#[derive(Serialize, Clone, Debug)]
+pub struct RunLogLine {
+    pub run_id: String,
+    /// Unix timestamp in milliseconds
+    pub timestamp_ms: u64,
+    pub level: String,
+    pub message: String,
+}
+
+/// Ring buffer of log lines correlated by run ID.
+#[derive(Debug)]
+pub struct RunLogBuffer {
+    lines: Mutex<VecDeque<RunLogLine>>,
+    capacity: usize,
+}
+
+impl Default for RunLogBuffer {
+    fn default() -> Self {
+        Self::new(RUN_LOG_CAPACITY)
+    }
+}
+
+impl RunLogBuffer {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            lines: Mutex::new(VecDeque::with_capacity(capacity)),
+            capacity,
+        }
+    }
+
+    pub fn push(&self, line: RunLogLine) {
+        let mut lines = self.lines.lock().expect("RunLogBuffer::lines poisoned");
+        if lines.len() >= self.capacity {
+            lines.pop_front();
+        }
+        lines.push_back(line);
+    }
+
+    /// Get buffered log lines for the run, oldest first.
+    pub fn get(&self, run_id: &str) -> Vec<RunLogLine> {
+        let lines = self.lines.lock().expect("RunLogBuffer::lines poisoned");
+        lines
+            .iter()
+            .filter(|l| l.run_id == run_id)
+            .cloned()
+            .collect()
+    }
+}
+
+/// Run ID stored in the extensions of a span that declares a `run_id` field.
+struct RunId(String);
+
+/// Layer that copies events inside a `run_id` span into a [`RunLogBuffer`].
+pub struct RunLogLayer {
+    buffer: Arc<RunLogBuffer>,
+}
+
+impl RunLogLayer {
+    pub fn new(buffer: Arc<RunLogBuffer>) -> Self {
+        Self { buffer }
+    }
+}
+
+impl<S> Layer<S> for RunLogLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        let mut visitor = RunIdVisitor(None);
+        attrs.record(&mut visitor);
+        if let (Some(run_id), Some(span)) = (visitor.0, ctx.span(id)) {
+            span.extensions_mut().insert(RunId(run_id));
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        let Some(scope) = ctx.event_scope(event) else {
+            return;
+        };
+        let Some(run_id) = scope
+            .into_iter()
+            .find_map(|span| span.extensions().get::<RunId>().map(|r| r.0.clone()))
+        else {
+            return;
+        };
+        let mut visitor = MessageVisitor::default();
+        event.record(&mut visitor);
+        let timestamp_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0);
+        self.buffer.push(RunLogLine {
+            run_id,
+            timestamp_ms,
+            level: event.metadata().level().to_string(),
+            message: visitor.message,
+        });
+    }
+}
+
+struct RunIdVisitor(Option<String>);
+
+impl Visit for RunIdVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "run_id" {
+            self.0 = Some(value.to_string());
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        if field.name() == "run_id" {
+            self.0 = Some(format!("{:?}", value));
+        }
+    }
+}
+
+/// Formats the `message` field followed by any other fields as `key=value`.
+#[derive(Default)]
+struct MessageVisitor {
+    message: String,
+}
+
+impl Visit for MessageVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        if field.name() == "message" {
+            let _ = write!(self.message, "{:?}", value);
+        } else {
+            let _ = write!(self.message, " {}={:?}", field.name(), value);
+        }
+    }
+}
@@ -0,0 +1,31 @@
+use serde::{Deserialize, Serialize};
+use std::net::IpAddr;
+
+/// Summary of one captured IP packet
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CapturedPacket {
+    /// Sequence number within the capture, starting at 1
+    pub index: u64,
+    /// Unix timestamp in milliseconds
+    pub timestamp_ms: u64,
+    pub src: IpAddr,
+    pub dst: IpAddr,
+    /// "TCP", "UDP", "ICMP", "ICMPv6" or the protocol name from the IP header
+    pub protocol: String,
+    pub src_port: Option<u16>,
+    pub dst_port: Option<u16>,
+    /// Length of the IP packet in bytes
+    pub length: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CaptureReport {
+    pub capture_id: String,
+    pub iface: String,
+    /// Packets matching the filter
+    pub captured: u64,
+    /// All IP packets seen on the interface
+    pub seen: u64,
+    /// Whether the capture was stopped before reaching `max_packets`
+    pub stopped: bool,
+}
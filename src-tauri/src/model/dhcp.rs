@@ -0,0 +1,41 @@
+use serde::{Deserialize, Serialize};
+use std::net::{IpAddr, Ipv4Addr};
+
+/// One DHCPOFFER received in answer to our DHCPDISCOVER
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DhcpOffer {
+    /// Server identifier (option 54), or the source of the datagram
+    pub server_ip: Ipv4Addr,
+    /// Source address of the datagram; differs from `server_ip` behind a relay
+    pub source_ip: IpAddr,
+    /// Relay agent address (giaddr), if the offer was relayed
+    pub relay_ip: Option<Ipv4Addr>,
+    /// Address offered to us (yiaddr)
+    pub offered_ip: Ipv4Addr,
+    pub subnet_mask: Option<Ipv4Addr>,
+    pub routers: Vec<Ipv4Addr>,
+    pub dns_servers: Vec<Ipv4Addr>,
+    pub domain_name: Option<String>,
+    pub lease_time_secs: Option<u32>,
+    pub renewal_time_secs: Option<u32>,
+    pub rebinding_time_secs: Option<u32>,
+    pub broadcast_addr: Option<Ipv4Addr>,
+    pub ntp_servers: Vec<Ipv4Addr>,
+    /// Codes of every option present in the offer
+    pub option_codes: Vec<u8>,
+    /// Time from the DISCOVER to this offer
+    pub rtt_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DhcpDiscoveryReport {
+    pub iface: String,
+    /// Hardware address sent as the client address
+    pub mac_addr: String,
+    /// Transaction id of the DISCOVER
+    pub xid: u32,
+    pub offers: Vec<DhcpOffer>,
+    /// More than one distinct server answered, a sign of a rogue server
+    pub multiple_servers: bool,
+    pub elapsed_ms: u64,
+}
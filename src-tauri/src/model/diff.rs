@@ -0,0 +1,159 @@
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::net::IpAddr;
+
+use crate::error::ScanError;
+use crate::history::ScanRecord;
+use crate::model::endpoint::Host;
+use crate::model::scan::{HostScanReport, PortScanReport, PortScanSample};
+use crate::probe::service::models::ServiceInfo;
+
+/// Service detected on a port in both runs, with differing details
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ServiceChange {
+    pub port: u16,
+    pub before: Option<ServiceInfo>,
+    pub after: Option<ServiceInfo>,
+}
+
+/// Difference between two port scans of the same target
+#[derive(Serialize, Deserialize, Clone)]
+pub struct PortScanDiff {
+    pub ip_addr: IpAddr,
+    /// Open in the second run only
+    pub opened: Vec<PortScanSample>,
+    /// Open in the first run only
+    pub closed: Vec<PortScanSample>,
+    /// Open in both runs, with a changed service name, product, version or banner
+    pub changed: Vec<ServiceChange>,
+}
+
+/// Difference between two host scans
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct HostScanDiff {
+    /// Alive in the second run only
+    pub up: Vec<Host>,
+    /// Alive in the first run only
+    pub down: Vec<Host>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(tag = "kind", content = "diff")]
+pub enum ScanDiff {
+    PortScan(PortScanDiff),
+    HostScan(HostScanDiff),
+}
+
+impl ScanDiff {
+    /// Compare two scan records of the same kind. `a` is the earlier run.
+    pub fn between(a: &ScanRecord, b: &ScanRecord) -> Result<Self> {
+        match (a, b) {
+            (ScanRecord::PortScan(a), ScanRecord::PortScan(b)) => {
+                PortScanDiff::between(a, b).map(ScanDiff::PortScan)
+            }
+            (ScanRecord::HostScan(a), ScanRecord::HostScan(b)) => {
+                Ok(ScanDiff::HostScan(HostScanDiff::between(a, b)))
+            }
+            _ => bail!(ScanError::invalid_input(
+                "Cannot compare a port scan with a host scan"
+            )),
+        }
+    }
+
+    /// Whether the two runs had the same result
+    pub fn is_empty(&self) -> bool {
+        match self {
+            ScanDiff::PortScan(d) => {
+                d.opened.is_empty() && d.closed.is_empty() && d.changed.is_empty()
+            }
+            ScanDiff::HostScan(d) => d.up.is_empty() && d.down.is_empty(),
+        }
+    }
+}
+
+/// Whether the fields users compare between runs differ
+fn service_changed(a: &Option<ServiceInfo>, b: &Option<ServiceInfo>) -> bool {
+    let key = |s: &Option<ServiceInfo>| {
+        s.as_ref().map(|s| {
+            (
+                s.name.clone(),
+                s.product.clone(),
+                s.version.clone(),
+                s.banner.clone(),
+            )
+        })
+    };
+    key(a) != key(b)
+}
+
+impl PortScanDiff {
+    /// Compare two reports of the same target. `a` is the earlier run.
+    pub fn between(a: &PortScanReport, b: &PortScanReport) -> Result<Self> {
+        if a.ip_addr != b.ip_addr {
+            bail!(
+                "Reports target different hosts: {} and {}",
+                a.ip_addr,
+                b.ip_addr
+            );
+        }
+        // Spilled scans keep their samples in the results file
+        let (a_samples, b_samples) = (a.load_samples()?, b.load_samples()?);
+        let before: BTreeMap<u16, &PortScanSample> =
+            a_samples.iter().map(|s| (s.port, s)).collect();
+        let after: BTreeMap<u16, &PortScanSample> = b_samples.iter().map(|s| (s.port, s)).collect();
+
+        let opened = after
+            .iter()
+            .filter(|(port, _)| !before.contains_key(port))
+            .map(|(_, s)| (*s).clone())
+            .collect();
+        let closed = before
+            .iter()
+            .filter(|(port, _)| !after.contains_key(port))
+            .map(|(_, s)| (*s).clone())
+            .collect();
+        let changed = before
+            .iter()
+            .filter_map(|(port, prev)| {
+                let next = after.get(port)?;
+                service_changed(&prev.service_info, &next.service_info).then(|| ServiceChange {
+                    port: *port,
+                    before: prev.service_info.clone(),
+                    after: next.service_info.clone(),
+                })
+            })
+            .collect();
+
+        Ok(Self {
+            ip_addr: a.ip_addr,
+            opened,
+            closed,
+            changed,
+        })
+    }
+}
+
+impl HostScanDiff {
+    /// Compare two host scan reports. `a` is the earlier run.
+    pub fn between(a: &HostScanReport, b: &HostScanReport) -> Self {
+        let before: HashSet<IpAddr> = a.alive.iter().map(|(h, _)| h.ip).collect();
+        let after: HashSet<IpAddr> = b.alive.iter().map(|(h, _)| h.ip).collect();
+
+        let mut up: Vec<Host> = b
+            .alive
+            .iter()
+            .filter(|(h, _)| !before.contains(&h.ip))
+            .map(|(h, _)| h.clone())
+            .collect();
+        let mut down: Vec<Host> = a
+            .alive
+            .iter()
+            .filter(|(h, _)| !after.contains(&h.ip))
+            .map(|(h, _)| h.clone())
+            .collect();
+        up.sort_by_key(|h| h.ip);
+        down.sort_by_key(|h| h.ip);
+        Self { up, down }
+    }
+}
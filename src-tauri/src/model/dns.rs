@@ -1,17 +1,90 @@
 use serde::{Deserialize, Serialize};
-use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
 
 /// A domain with its associated IP addresses
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Domain {
+    /// Domain name, with IDN labels in Unicode
     pub name: String,
+    /// ASCII (punycode) form of the name as sent to DNS
+    #[serde(default)]
+    pub ascii_name: String,
     pub ips: Vec<IpAddr>,
 }
 
+/// Nameserver used by the resolver
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DnsServer {
+    pub addr: SocketAddr,
+    /// Transport, e.g. "udp" or "tcp"
+    pub protocol: String,
+}
+
+/// Resolver configuration (system or configured)
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DnsConfig {
+    pub nameservers: Vec<DnsServer>,
+    /// Local domain appended to single-label names
+    pub domain: Option<String>,
+    pub search: Vec<String>,
+    pub timeout_ms: u64,
+    pub attempts: usize,
+}
+
+/// Response time benchmark of one nameserver
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DnsServerTest {
+    pub server: SocketAddr,
+    pub query: String,
+    /// Addresses returned by the last successful query
+    pub ips: Vec<IpAddr>,
+    /// Response time of each successful query
+    pub rtts_ms: Vec<u64>,
+    pub avg_rtt_ms: Option<u64>,
+    pub attempts: u32,
+    /// Last error, if any query failed
+    pub error: Option<String>,
+}
+
+/// Hostname target that could not be resolved, even after retries
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct UnresolvedName {
+    pub name: String,
+    /// The name does not exist (NXDOMAIN / no records), as opposed to a resolver failure
+    pub not_found: bool,
+    pub error: String,
+}
+
+/// How hostname targets are resolved
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ResolveStrategy {
+    /// System resolver, nameservers tried in turn
+    #[default]
+    System,
+    /// Query all nameservers in parallel, take the first answer
+    Race,
+    /// Query all nameservers and flag names they disagree on
+    RaceVerified,
+}
+
+/// Answer of a multi-resolver race
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RaceLookup {
+    pub name: String,
+    pub ips: Vec<IpAddr>,
+    /// Nameserver whose answer was taken
+    pub resolver: SocketAddr,
+    pub rtt_ms: u64,
+    /// Whether all answering nameservers agreed (verified race only)
+    pub consistent: Option<bool>,
+}
+
 /// Detailed DNS lookup information for a domain
 #[derive(Serialize, Deserialize)]
 pub struct DomainLookupInfo {
     pub name: String,
+    #[serde(default)]
+    pub ascii_name: String,
     pub a: Vec<Ipv4Addr>,
     pub aaaa: Vec<Ipv6Addr>,
     pub mx: Vec<MxRecord>,
@@ -2,6 +2,9 @@ use netdev::MacAddr;
 use serde::{Deserialize, Serialize};
 use std::net::{IpAddr, SocketAddr};
 
+use crate::model::scan::{PortScanProtocol, PortScanReport, PortScanSample};
+use crate::probe::service::models::ServiceInfo;
+
 /// Transport protocol type
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Ord, PartialOrd)]
 #[serde(rename_all = "lowercase")]
@@ -77,7 +80,15 @@ impl std::fmt::Display for Port {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Host {
     pub ip: IpAddr,
+    /// Host name for display, with IDN labels in Unicode
     pub hostname: Option<String>,
+    /// ASCII (punycode) form of the host name as sent to DNS
+    #[serde(default)]
+    pub ascii_hostname: Option<String>,
+    /// Forward-confirmed reverse DNS: the PTR name resolves back to `ip`.
+    /// `None` when not checked.
+    #[serde(default)]
+    pub fcrdns: Option<bool>,
 }
 
 impl Default for Host {
@@ -85,6 +96,8 @@ impl Default for Host {
         Self {
             ip: IpAddr::V4(std::net::Ipv4Addr::UNSPECIFIED),
             hostname: None,
+            ascii_hostname: None,
+            fcrdns: None,
         }
     }
 }
@@ -114,6 +127,30 @@ pub struct MaybeHost {
     pub hostname: Option<String>,
 }
 
+/// Scan result of one open port of an endpoint
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PortService {
+    pub port: Port,
+    pub rtt_ms: Option<u64>,
+    pub service_name: Option<String>,
+    pub service_info: Option<ServiceInfo>,
+}
+
+impl PortService {
+    /// Fill fields this result is missing from another result for the same port.
+    fn merge(&mut self, other: PortService) {
+        if self.rtt_ms.is_none() {
+            self.rtt_ms = other.rtt_ms;
+        }
+        if self.service_name.is_none() {
+            self.service_name = other.service_name;
+        }
+        if self.service_info.is_none() {
+            self.service_info = other.service_info;
+        }
+    }
+}
+
 /// Representation of an endpoint with IP, hostname, MAC address, tags, and ports
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Endpoint {
@@ -122,6 +159,9 @@ pub struct Endpoint {
     pub mac_addr: Option<MacAddr>,
     pub tags: Vec<String>,
     pub ports: Vec<Port>,
+    /// Per-port scan results, one per (port, transport)
+    #[serde(default)]
+    pub services: Vec<PortService>,
 }
 
 impl Default for Endpoint {
@@ -132,6 +172,7 @@ impl Default for Endpoint {
             mac_addr: None,
             tags: Vec::new(),
             ports: Vec::new(),
+            services: Vec::new(),
         }
     }
 }
@@ -178,6 +219,44 @@ impl Endpoint {
                 self.ports.push(p);
             }
         }
+
+        for svc in other.services {
+            self.upsert_service(svc);
+        }
+    }
+    /// Add a port result, merging it into an existing one for the same port.
+    pub fn upsert_service(&mut self, svc: PortService) {
+        self.upsert_port(svc.port);
+        match self.services.iter_mut().find(|s| s.port == svc.port) {
+            Some(existing) => existing.merge(svc),
+            None => self.services.push(svc),
+        }
+    }
+    /// Add an open port found by a port scan.
+    pub fn add_sample(&mut self, sample: &PortScanSample, transport: TransportProtocol) {
+        self.upsert_service(PortService {
+            port: Port::new(sample.port, transport),
+            rtt_ms: sample.rtt_ms,
+            service_name: sample.service_name.clone(),
+            service_info: sample.service_info.clone(),
+        });
+    }
+    /// Add every open port of a port scan report of this endpoint.
+    /// Fails only if the samples of a spilled scan cannot be read back.
+    pub fn add_report(&mut self, report: &PortScanReport) -> anyhow::Result<()> {
+        if self.hostname.is_none() {
+            self.hostname = report.hostname.clone();
+        }
+        let transport = match report.protocol {
+            PortScanProtocol::Tcp => TransportProtocol::Tcp,
+            PortScanProtocol::Quic => TransportProtocol::Quic,
+        };
+        for sample in report.load_samples()?.iter() {
+            self.add_sample(sample, transport);
+        }
+        self.ports.sort();
+        self.services.sort_by_key(|s| s.port);
+        Ok(())
     }
     /// Get the SocketAddr instances for the specified transport protocol.
     pub fn socket_addrs(&self, transport: TransportProtocol) -> Vec<SocketAddr> {
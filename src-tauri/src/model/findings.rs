@@ -0,0 +1,374 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::net::IpAddr;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::history::ScanRecord;
+use crate::model::scan::{PortScanProtocol, PortScanReport, PortState};
+use crate::probe::service::models::ServiceInfo;
+
+/// Name of the findings document schema
+pub const FINDINGS_SCHEMA: &str = "netpulse-findings";
+/// Version of the findings schema. Bump the major version when a field is
+/// removed or changes meaning; adding fields or rules is a minor change.
+pub const FINDINGS_SCHEMA_VERSION: &str = "1.0";
+
+/// Certificates expiring within this many days are reported
+const CERT_EXPIRY_WARNING_DAYS: i64 = 30;
+
+/// Severity of a finding
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum FindingSeverity {
+    Info,
+    Low,
+    Medium,
+    High,
+    Critical,
+}
+
+/// Stable identifiers of the checks producing findings
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FindingRule {
+    #[serde(rename = "NP-HOST-UP")]
+    HostUp,
+    #[serde(rename = "NP-OPEN-PORT")]
+    OpenPort,
+    #[serde(rename = "NP-CLEARTEXT-LOGIN")]
+    CleartextLogin,
+    #[serde(rename = "NP-KNOWN-CVE")]
+    KnownCve,
+    #[serde(rename = "NP-TLS-EXPIRED")]
+    TlsExpired,
+    #[serde(rename = "NP-TLS-NOT-YET-VALID")]
+    TlsNotYetValid,
+    #[serde(rename = "NP-TLS-EXPIRING")]
+    TlsExpiring,
+    #[serde(rename = "NP-TLS-SELF-SIGNED")]
+    TlsSelfSigned,
+    #[serde(rename = "NP-TLS-WEAK-SIGNATURE")]
+    TlsWeakSignature,
+    #[serde(rename = "NP-TLS-LEGACY-PROTOCOL")]
+    TlsLegacyProtocol,
+    #[serde(rename = "NP-RDP-NO-NLA")]
+    RdpNoNla,
+    #[serde(rename = "NP-RDP-STANDARD-SECURITY")]
+    RdpStandardSecurity,
+    #[serde(rename = "NP-SMB-SIGNING-NOT-REQUIRED")]
+    SmbSigningNotRequired,
+    #[serde(rename = "NP-SSH-WEAK-ALGORITHMS")]
+    SshWeakAlgorithms,
+}
+
+impl FindingRule {
+    pub const ALL: [FindingRule; 14] = [
+        FindingRule::HostUp,
+        FindingRule::OpenPort,
+        FindingRule::CleartextLogin,
+        FindingRule::KnownCve,
+        FindingRule::TlsExpired,
+        FindingRule::TlsNotYetValid,
+        FindingRule::TlsExpiring,
+        FindingRule::TlsSelfSigned,
+        FindingRule::TlsWeakSignature,
+        FindingRule::TlsLegacyProtocol,
+        FindingRule::RdpNoNla,
+        FindingRule::RdpStandardSecurity,
+        FindingRule::SmbSigningNotRequired,
+        FindingRule::SshWeakAlgorithms,
+    ];
+
+    /// Identifier as serialized, e.g. `NP-OPEN-PORT`
+    pub fn id(&self) -> &'static str {
+        match self {
+            FindingRule::HostUp => "NP-HOST-UP",
+            FindingRule::OpenPort => "NP-OPEN-PORT",
+            FindingRule::CleartextLogin => "NP-CLEARTEXT-LOGIN",
+            FindingRule::KnownCve => "NP-KNOWN-CVE",
+            FindingRule::TlsExpired => "NP-TLS-EXPIRED",
+            FindingRule::TlsNotYetValid => "NP-TLS-NOT-YET-VALID",
+            FindingRule::TlsExpiring => "NP-TLS-EXPIRING",
+            FindingRule::TlsSelfSigned => "NP-TLS-SELF-SIGNED",
+            FindingRule::TlsWeakSignature => "NP-TLS-WEAK-SIGNATURE",
+            FindingRule::TlsLegacyProtocol => "NP-TLS-LEGACY-PROTOCOL",
+            FindingRule::RdpNoNla => "NP-RDP-NO-NLA",
+            FindingRule::RdpStandardSecurity => "NP-RDP-STANDARD-SECURITY",
+            FindingRule::SmbSigningNotRequired => "NP-SMB-SIGNING-NOT-REQUIRED",
+            FindingRule::SshWeakAlgorithms => "NP-SSH-WEAK-ALGORITHMS",
+        }
+    }
+
+    /// One-line description of what the rule reports
+    pub fn description(&self) -> &'static str {
+        match self {
+            FindingRule::HostUp => "Host answered the discovery probes",
+            FindingRule::OpenPort => "Port accepts connections",
+            FindingRule::CleartextLogin => "Remote login service without encryption",
+            FindingRule::KnownCve => "Detected software has known CVEs",
+            FindingRule::TlsExpired => "TLS certificate has expired",
+            FindingRule::TlsNotYetValid => "TLS certificate is not valid yet",
+            FindingRule::TlsExpiring => "TLS certificate expires soon",
+            FindingRule::TlsSelfSigned => "TLS certificate is self-signed",
+            FindingRule::TlsWeakSignature => "TLS certificate is signed with MD5 or SHA-1",
+            FindingRule::TlsLegacyProtocol => "Server negotiated SSL 3.0, TLS 1.0 or TLS 1.1",
+            FindingRule::RdpNoNla => "RDP does not require Network Level Authentication",
+            FindingRule::RdpStandardSecurity => "RDP accepts standard RDP security without TLS",
+            FindingRule::SmbSigningNotRequired => "SMB server does not require message signing",
+            FindingRule::SshWeakAlgorithms => "SSH server offers weak algorithms",
+        }
+    }
+}
+
+/// Where a finding was observed
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct FindingTarget {
+    pub ip_addr: IpAddr,
+    pub hostname: Option<String>,
+    /// `None` for host-level findings
+    pub port: Option<u16>,
+    /// "tcp" or "udp"
+    pub protocol: Option<String>,
+}
+
+/// One issue on one target
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Finding {
+    pub rule: FindingRule,
+    pub severity: FindingSeverity,
+    pub title: String,
+    pub target: FindingTarget,
+    /// Observed data supporting the finding
+    pub evidence: String,
+}
+
+/// Versioned findings document of one scan run
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct FindingsReport {
+    pub schema: String,
+    pub schema_version: String,
+    pub run_id: String,
+    /// Unix timestamp in milliseconds
+    pub generated_at_ms: u64,
+    /// Most severe first
+    pub findings: Vec<Finding>,
+}
+
+impl FindingsReport {
+    /// Findings of a stored or fresh scan. Fails only if the samples of a
+    /// spilled scan cannot be read back.
+    pub fn from_record(record: &ScanRecord) -> Result<Self> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        let mut findings = match record {
+            ScanRecord::PortScan(report) => {
+                port_findings(&report.with_samples()?, now.as_secs() as i64)
+            }
+            ScanRecord::HostScan(report) => report
+                .alive
+                .iter()
+                .map(|(host, rtt)| Finding {
+                    rule: FindingRule::HostUp,
+                    severity: FindingSeverity::Info,
+                    title: format!("Host {} is up", host.ip),
+                    target: FindingTarget {
+                        ip_addr: host.ip,
+                        hostname: host.hostname.clone(),
+                        port: None,
+                        protocol: None,
+                    },
+                    evidence: format!("RTT {} ms", rtt),
+                })
+                .collect(),
+        };
+        // Stable sort keeps port order within the same severity
+        findings.sort_by(|a, b| b.severity.cmp(&a.severity));
+        Ok(FindingsReport {
+            schema: FINDINGS_SCHEMA.to_string(),
+            schema_version: FINDINGS_SCHEMA_VERSION.to_string(),
+            run_id: record.run_id().to_string(),
+            generated_at_ms: now.as_millis() as u64,
+            findings,
+        })
+    }
+}
+
+fn port_findings(report: &PortScanReport, now_unix: i64) -> Vec<Finding> {
+    let protocol = match report.protocol {
+        PortScanProtocol::Tcp => "tcp",
+        PortScanProtocol::Quic => "udp",
+    };
+    let mut findings = Vec::new();
+    for sample in report.samples.iter().filter(|s| s.state == PortState::Open) {
+        let mut push = |rule: FindingRule, severity: FindingSeverity, evidence: String| {
+            findings.push(Finding {
+                rule,
+                severity,
+                title: format!("{} on {}/{}", rule.description(), sample.port, protocol),
+                target: FindingTarget {
+                    ip_addr: sample.ip_addr,
+                    hostname: report.hostname.clone(),
+                    port: Some(sample.port),
+                    protocol: Some(protocol.to_string()),
+                },
+                evidence,
+            })
+        };
+        let info = sample.service_info.as_ref();
+        let service = info
+            .and_then(|i| i.name.clone())
+            .or_else(|| sample.service_name.clone())
+            .unwrap_or_else(|| "unknown".to_string());
+        push(
+            FindingRule::OpenPort,
+            FindingSeverity::Info,
+            format!("service: {}", service),
+        );
+        if matches!(service.as_str(), "telnet" | "login" | "shell" | "exec") {
+            push(
+                FindingRule::CleartextLogin,
+                FindingSeverity::High,
+                format!("service: {}", service),
+            );
+        }
+        if let Some(info) = info {
+            service_findings(info, now_unix, &mut push);
+        }
+    }
+    findings
+}
+
+fn service_findings(
+    info: &ServiceInfo,
+    now_unix: i64,
+    push: &mut impl FnMut(FindingRule, FindingSeverity, String),
+) {
+    if let Some(vulns) = info.vuln_summary.as_ref().filter(|v| v.total > 0) {
+        let severity = if vulns.critical > 0 {
+            FindingSeverity::Critical
+        } else if vulns.high > 0 {
+            FindingSeverity::High
+        } else if vulns.medium > 0 {
+            FindingSeverity::Medium
+        } else {
+            FindingSeverity::Low
+        };
+        push(
+            FindingRule::KnownCve,
+            severity,
+            format!(
+                "{} CVEs ({} critical, {} high) for {}: {}",
+                vulns.total,
+                vulns.critical,
+                vulns.high,
+                info.cpes.join(" "),
+                vulns.cve_ids.join(", ")
+            ),
+        );
+    }
+
+    if let Some(tls) = &info.tls_info {
+        let subject = tls.subject.as_deref().unwrap_or("-");
+        if let Some(not_after) = tls.not_after_unix {
+            let valid_until = tls.not_after.as_deref().unwrap_or("-");
+            if not_after < now_unix {
+                push(
+                    FindingRule::TlsExpired,
+                    FindingSeverity::High,
+                    format!("subject {}, not after {}", subject, valid_until),
+                );
+            } else if not_after - now_unix < CERT_EXPIRY_WARNING_DAYS * 86400 {
+                push(
+                    FindingRule::TlsExpiring,
+                    FindingSeverity::Low,
+                    format!("subject {}, not after {}", subject, valid_until),
+                );
+            }
+        }
+        if tls.not_before_unix.is_some_and(|t| t > now_unix) {
+            push(
+                FindingRule::TlsNotYetValid,
+                FindingSeverity::Medium,
+                format!(
+                    "subject {}, not before {}",
+                    subject,
+                    tls.not_before.as_deref().unwrap_or("-")
+                ),
+            );
+        }
+        if tls.self_signed == Some(true) {
+            push(
+                FindingRule::TlsSelfSigned,
+                FindingSeverity::Low,
+                format!("subject and issuer {}", subject),
+            );
+        }
+        if let Some(sig) = tls.sig_algorithm.as_deref().filter(|sig| {
+            let sig = sig.to_ascii_lowercase();
+            sig.contains("md5") || sig.contains("sha1")
+        }) {
+            push(
+                FindingRule::TlsWeakSignature,
+                FindingSeverity::Medium,
+                format!("signature algorithm {}", sig),
+            );
+        }
+        if let Some(version) = tls
+            .version
+            .as_deref()
+            .filter(|v| matches!(*v, "SSLv3" | "TLSv1_0" | "TLSv1_1"))
+        {
+            push(
+                FindingRule::TlsLegacyProtocol,
+                FindingSeverity::Medium,
+                format!("negotiated {}", version),
+            );
+        }
+    }
+
+    if let Some(rdp) = &info.rdp_info {
+        if rdp.nla_required == Some(false) {
+            push(
+                FindingRule::RdpNoNla,
+                FindingSeverity::High,
+                format!(
+                    "standard RDP security accepted: {:?}, TLS without CredSSP accepted: {:?}",
+                    rdp.rdp_security_allowed, rdp.tls_allowed
+                ),
+            );
+        }
+        if rdp.rdp_security_allowed == Some(true) {
+            push(
+                FindingRule::RdpStandardSecurity,
+                FindingSeverity::Medium,
+                "server selected PROTOCOL_RDP when offered alone".to_string(),
+            );
+        }
+    }
+
+    if let Some(smb) = &info.smb_info {
+        if smb.signing_required == Some(false) {
+            push(
+                FindingRule::SmbSigningNotRequired,
+                FindingSeverity::Medium,
+                format!(
+                    "dialect {}, host {}",
+                    smb.dialect.as_deref().unwrap_or("-"),
+                    smb.computer_name.as_deref().unwrap_or("-")
+                ),
+            );
+        }
+    }
+
+    if let Some(ssh) = info
+        .ssh_info
+        .as_ref()
+        .filter(|s| !s.weak_algorithms.is_empty())
+    {
+        push(
+            FindingRule::SshWeakAlgorithms,
+            FindingSeverity::Medium,
+            ssh.weak_algorithms.join(", "),
+        );
+    }
+}
@@ -1,6 +1,6 @@
 use netdev::interface::state::OperState;
 use netdev::interface::types::InterfaceType;
-use netdev::ipnet::{Ipv4Net, Ipv6Net};
+use netdev::ipnet::{IpNet, Ipv4Net, Ipv6Net};
 use netdev::{MacAddr, NetworkDevice};
 use serde::{Deserialize, Serialize};
 use std::net::IpAddr;
@@ -176,3 +176,16 @@ pub struct TrafficStats {
     /// The system timestamp when this snapshot was taken.
     pub timestamp: SystemTime,
 }
+
+/// Local subnet that an interface participates in
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct LocalSubnet {
+    /// Name of the interface
+    pub iface_name: String,
+    /// Address assigned to the interface on this subnet
+    pub addr: IpAddr,
+    /// Network address + prefix
+    pub network: IpNet,
+    /// Number of usable host addresses. None if too large to count (e.g. IPv6 /64).
+    pub host_count: Option<u64>,
+}
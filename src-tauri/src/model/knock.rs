@@ -0,0 +1,38 @@
+use serde::{Deserialize, Serialize};
+use std::net::IpAddr;
+
+use crate::model::endpoint::TransportProtocol;
+
+/// One port of a knock sequence. QUIC knocks are sent as UDP.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct KnockStep {
+    pub port: u16,
+    pub protocol: TransportProtocol,
+}
+
+/// Outcome of one knock
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KnockResult {
+    pub port: u16,
+    pub protocol: TransportProtocol,
+    /// The SYN or datagram left this host
+    pub sent: bool,
+    pub error: Option<String>,
+}
+
+/// TCP connect attempt made after the sequence
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KnockVerify {
+    pub port: u16,
+    pub open: bool,
+    pub rtt_ms: Option<u64>,
+    pub message: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PortKnockReport {
+    pub ip_addr: IpAddr,
+    pub knocks: Vec<KnockResult>,
+    /// `None` when no verify port was given
+    pub verify: Option<KnockVerify>,
+}
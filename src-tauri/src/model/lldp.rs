@@ -0,0 +1,46 @@
+use serde::{Deserialize, Serialize};
+use std::net::IpAddr;
+
+/// Link-layer discovery protocol a neighbor announced itself with
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DiscoveryProtocol {
+    Lldp,
+    Cdp,
+}
+
+/// Switch (or other device) announcing itself on the link
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LinkNeighbor {
+    pub protocol: DiscoveryProtocol,
+    /// Source MAC of the frame, i.e. the switch port
+    pub source_mac: String,
+    /// LLDP chassis id, or CDP device id
+    pub chassis_id: Option<String>,
+    /// Port we are connected to, e.g. `GigabitEthernet1/0/12`
+    pub port_id: Option<String>,
+    pub port_description: Option<String>,
+    pub system_name: Option<String>,
+    /// LLDP system description, or CDP software version
+    pub system_description: Option<String>,
+    /// CDP platform, e.g. `cisco WS-C2960X-48FPD-L`
+    pub platform: Option<String>,
+    /// Port VLAN id (LLDP 802.1) or native VLAN (CDP)
+    pub vlan_id: Option<u16>,
+    /// Enabled capabilities, e.g. `Bridge`, `Router`
+    pub capabilities: Vec<String>,
+    pub management_addrs: Vec<IpAddr>,
+    /// How long the information stays valid
+    pub ttl_secs: Option<u16>,
+    /// Unix timestamp in milliseconds of the last frame
+    pub last_seen_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LinkDiscoveryReport {
+    /// ID to stop the listen with (`stop_lldp`)
+    pub listen_id: String,
+    pub iface: String,
+    /// One entry per announcing device and port
+    pub neighbors: Vec<LinkNeighbor>,
+    pub elapsed_ms: u64,
+}
@@ -1,11 +1,19 @@
 #![allow(dead_code)]
 
+pub mod capture;
+pub mod dhcp;
+pub mod diff;
 pub mod dns;
 pub mod endpoint;
+pub mod findings;
 pub mod interface;
+pub mod knock;
+pub mod lldp;
 pub mod ping;
+pub mod policy;
 pub mod probe;
 pub mod scan;
+pub mod stats;
 
 use serde::{Deserialize, Serialize};
 
@@ -86,3 +94,29 @@ pub struct ProxyEnv {
     pub all: Option<String>,
     pub no_proxy: Option<String>,
 }
+
+/// Socket type checked by `capabilities_check`
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum SocketKind {
+    IcmpV4,
+    IcmpV6,
+    RawV4,
+    RawV6,
+    TcpV4,
+    TcpV6,
+    UdpV4,
+    UdpV6,
+    Quic,
+}
+
+/// Whether a socket type can be opened on this system
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SocketCapability {
+    pub kind: SocketKind,
+    pub supported: bool,
+    /// Variant that was opened, e.g. `DGRAM` or `RAW` for ICMP
+    pub detail: Option<String>,
+    pub error: Option<String>,
+    /// How to make a failed socket type work
+    pub hint: Option<String>,
+}
@@ -166,3 +166,75 @@ pub struct PingErrorPayload {
     pub run_id: String,
     pub message: String,
 }
+
+/// ICMP query message other than echo (IPv4 only)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum IcmpQueryKind {
+    /// Timestamp request (type 13)
+    Timestamp,
+    /// Address Mask request (type 17)
+    AddressMask,
+}
+
+/// Result of an ICMP Timestamp or Address Mask query
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct IcmpQueryResult {
+    pub ip_addr: IpAddr,
+    pub kind: IcmpQueryKind,
+    /// Whether the host answered before the timeout
+    pub responded: bool,
+    pub rtt_ms: Option<u64>,
+    /// Remote receive/transmit timestamps in milliseconds since midnight UT
+    pub remote_receive_ms: Option<u32>,
+    pub remote_transmit_ms: Option<u32>,
+    /// Estimated remote clock offset from the local clock (remote - local)
+    pub clock_offset_ms: Option<i64>,
+    /// Subnet mask reported by the host
+    pub netmask: Option<std::net::Ipv4Addr>,
+    pub message: Option<String>,
+}
+
+/// IPv6 extension header inserted in front of an ICMPv6 echo request
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Ipv6ExtHeader {
+    /// Hop-by-Hop Options header with a PadN option
+    HopByHop,
+    /// Destination Options header with a PadN option
+    DestinationOptions,
+    /// Atomic Fragment header (offset 0, no more fragments)
+    Fragment,
+}
+
+/// How the target answered an echo carrying extension headers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Ipv6ExtProbeOutcome {
+    EchoReply,
+    /// ICMPv6 Parameter Problem, usually an unrecognized header or option
+    ParameterProblem,
+    /// Any other ICMPv6 error (e.g. Destination Unreachable from a middlebox)
+    IcmpError,
+    NoResponse,
+}
+
+/// Result of one echo probe with a given extension header chain
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Ipv6ExtProbeResult {
+    /// Extension headers in the probe. Empty for the baseline echo.
+    pub ext_headers: Vec<Ipv6ExtHeader>,
+    pub outcome: Ipv6ExtProbeOutcome,
+    pub rtt_ms: Option<u64>,
+    /// Sender of the reply, which may be a router on the path
+    pub responder: Option<IpAddr>,
+    /// ICMPv6 type and code of an error reply
+    pub icmp_type: Option<u8>,
+    pub icmp_code: Option<u8>,
+    /// Offset of the offending octet reported by a Parameter Problem
+    pub pointer: Option<u32>,
+}
+
+/// Results of an IPv6 extension header probe run
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Ipv6ExtProbeReport {
+    pub ip_addr: std::net::Ipv6Addr,
+    pub results: Vec<Ipv6ExtProbeResult>,
+}
@@ -0,0 +1,250 @@
+use anyhow::{bail, Context, Result};
+use netdev::ipnet::IpNet;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::net::IpAddr;
+use std::path::Path;
+
+use crate::error::ScanError;
+use crate::model::scan::{PortScanReport, PortState};
+
+/// Ports expected on one host or subnet
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct PolicyRule {
+    /// IP address or CIDR, e.g. `10.0.0.5` or `10.0.0.0/24`
+    pub target: String,
+    /// Ports that must be open
+    #[serde(default)]
+    pub required_ports: Vec<u16>,
+    /// Ports that may be open but are not required
+    #[serde(default)]
+    pub allowed_ports: Vec<u16>,
+    /// Shown in violations to identify the rule
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
+/// Expected open ports, checked against port scan reports.
+/// For each host the most specific matching rule applies.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct PortPolicy {
+    #[serde(default)]
+    pub rules: Vec<PolicyRule>,
+    /// Treat every open port on hosts without a matching rule as a violation.
+    /// Otherwise such hosts are not checked.
+    #[serde(default)]
+    pub default_deny: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PolicyViolationKind {
+    /// Open, but neither required nor allowed
+    UnexpectedOpen,
+    /// Required, probed, and not found open
+    ExpectedClosed,
+    /// Required, but the scan did not probe it
+    NotChecked,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct PolicyViolation {
+    pub ip_addr: IpAddr,
+    pub port: u16,
+    pub kind: PolicyViolationKind,
+    /// Target of the rule that applied; `None` under default deny
+    pub rule: Option<String>,
+    pub rule_description: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct PolicyCheckResult {
+    pub run_id: String,
+    pub compliant: bool,
+    /// Hosts without a matching rule, not checked unless `default_deny` is set
+    pub unmatched_hosts: Vec<IpAddr>,
+    pub violations: Vec<PolicyViolation>,
+    /// Required ports the scan did not probe. Not violations, but the
+    /// policy is not fully verified while any are left.
+    #[serde(default)]
+    pub not_checked: Vec<PolicyViolation>,
+}
+
+impl PortPolicy {
+    /// Read a policy from a JSON file
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read policy {}", path.display()))?;
+        let policy: PortPolicy = serde_json::from_str(&text)
+            .with_context(|| format!("Invalid policy {}", path.display()))?;
+        policy.networks()?;
+        Ok(policy)
+    }
+
+    fn networks(&self) -> Result<Vec<(IpNet, &PolicyRule)>> {
+        self.rules
+            .iter()
+            .map(|rule| Ok((parse_target(&rule.target)?, rule)))
+            .collect()
+    }
+
+    /// Check the open ports of a scan against the policy.
+    /// A required port counts as closed only if the scan probed it; reports
+    /// that do not record the probed ports leave every required port unchecked.
+    pub fn check(&self, report: &PortScanReport) -> Result<PolicyCheckResult> {
+        let networks = self.networks()?;
+
+        let mut open: BTreeMap<IpAddr, BTreeSet<u16>> = BTreeMap::new();
+        // The scanned host has no samples when nothing is open
+        open.entry(report.ip_addr).or_default();
+        for sample in report.load_samples()?.iter() {
+            if sample.state == PortState::Open {
+                open.entry(sample.ip_addr).or_default().insert(sample.port);
+            }
+        }
+
+        let mut violations = Vec::new();
+        let mut not_checked = Vec::new();
+        let mut unmatched_hosts = Vec::new();
+        for (ip, ports) in &open {
+            let rule = networks
+                .iter()
+                .filter(|(net, _)| net.contains(ip))
+                .max_by_key(|(net, _)| net.prefix_len())
+                .map(|(_, rule)| *rule);
+            let Some(rule) = rule else {
+                unmatched_hosts.push(*ip);
+                if self.default_deny {
+                    violations.extend(ports.iter().map(|&port| PolicyViolation {
+                        ip_addr: *ip,
+                        port,
+                        kind: PolicyViolationKind::UnexpectedOpen,
+                        rule: None,
+                        rule_description: None,
+                    }));
+                }
+                continue;
+            };
+            let violation = |port: u16, kind: PolicyViolationKind| PolicyViolation {
+                ip_addr: *ip,
+                port,
+                kind,
+                rule: Some(rule.target.clone()),
+                rule_description: rule.description.clone(),
+            };
+            for &port in ports {
+                if !rule.required_ports.contains(&port) && !rule.allowed_ports.contains(&port) {
+                    violations.push(violation(port, PolicyViolationKind::UnexpectedOpen));
+                }
+            }
+            for &port in &rule.required_ports {
+                if ports.contains(&port) {
+                    continue;
+                }
+                match report.was_scanned(port) {
+                    Some(true) => {
+                        violations.push(violation(port, PolicyViolationKind::ExpectedClosed))
+                    }
+                    _ => not_checked.push(violation(port, PolicyViolationKind::NotChecked)),
+                }
+            }
+        }
+        violations.sort_by_key(|v| (v.ip_addr, v.port));
+        not_checked.sort_by_key(|v| (v.ip_addr, v.port));
+
+        Ok(PolicyCheckResult {
+            run_id: report.run_id.clone(),
+            compliant: violations.is_empty(),
+            unmatched_hosts,
+            violations,
+            not_checked,
+        })
+    }
+}
+
+fn parse_target(target: &str) -> Result<IpNet> {
+    let t = target.trim();
+    if let Ok(ip) = t.parse::<IpAddr>() {
+        return Ok(IpNet::from(ip));
+    }
+    match t.parse::<IpNet>() {
+        Ok(net) => Ok(net.trunc()),
+        Err(_) => bail!(ScanError::invalid_input(format!(
+            "Invalid policy target: {}",
+            target
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::scan::PortScanSample;
+
+    fn report(ip: &str, open: &[u16]) -> PortScanReport {
+        let ip_addr: IpAddr = ip.parse().unwrap();
+        let samples = open
+            .iter()
+            .map(|&port| PortScanSample::fixture(ip_addr, port, PortState::Open))
+            .collect();
+        PortScanReport::fixture(ip_addr, samples)
+    }
+
+    #[test]
+    fn most_specific_rule_applies() {
+        let policy: PortPolicy = serde_json::from_str(
+            r#"{"rules": [
+                {"target": "10.0.0.0/24", "required_ports": [22]},
+                {"target": "10.0.0.5", "required_ports": [443], "allowed_ports": [80]}
+            ]}"#,
+        )
+        .unwrap();
+        let result = policy.check(&report("10.0.0.5", &[80, 22])).unwrap();
+        let found: Vec<_> = result.violations.iter().map(|v| (v.port, v.kind)).collect();
+        assert_eq!(
+            found,
+            [
+                (22, PolicyViolationKind::UnexpectedOpen),
+                (443, PolicyViolationKind::ExpectedClosed)
+            ]
+        );
+        assert!(policy.check(&report("10.0.0.9", &[22])).unwrap().compliant);
+    }
+
+    #[test]
+    fn unscanned_and_non_open_ports_are_not_counted() {
+        let policy: PortPolicy = serde_json::from_str(
+            r#"{"rules": [{"target": "10.0.0.5", "required_ports": [22, 8443]}]}"#,
+        )
+        .unwrap();
+        let mut report = report("10.0.0.5", &[22, 80]);
+        report.samples[1].state = PortState::Closed;
+        let result = policy.check(&report).unwrap();
+        assert!(result.compliant);
+        let unchecked: Vec<_> = result.not_checked.iter().map(|v| v.port).collect();
+        assert_eq!(unchecked, [8443]);
+
+        report.scanned_ports.clear();
+        report.samples.clear();
+        let result = policy.check(&report).unwrap();
+        assert!(result.compliant);
+        assert_eq!(result.not_checked.len(), 2);
+    }
+
+    #[test]
+    fn default_deny_flags_unmatched_hosts() {
+        let mut policy = PortPolicy {
+            rules: Vec::new(),
+            default_deny: false,
+        };
+        let result = policy.check(&report("192.0.2.1", &[8080])).unwrap();
+        assert!(result.compliant);
+        assert_eq!(result.unmatched_hosts.len(), 1);
+        policy.default_deny = true;
+        assert!(
+            !policy
+                .check(&report("192.0.2.1", &[8080]))
+                .unwrap()
+                .compliant
+        );
+    }
+}
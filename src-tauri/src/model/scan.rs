@@ -1,10 +1,19 @@
 use netdev::MacAddr;
 use serde::{Deserialize, Serialize};
-use std::{net::IpAddr, time::Duration};
+use std::{
+    borrow::Cow,
+    collections::BTreeMap,
+    net::{IpAddr, Ipv4Addr},
+    time::Duration,
+};
 
+use crate::error::ScanError;
 use crate::{
-    model::endpoint::{Host, MaybeHost},
-    probe::service::models::ServiceInfo,
+    model::dns::{ResolveStrategy, UnresolvedName},
+    model::endpoint::{Host, MaybeHost, TransportProtocol},
+    model::stats::{HostScanSummary, PortScanSummary},
+    probe::scan::exclude::ExcludeSet,
+    probe::service::{models::ServiceInfo, DetectionIntensity},
 };
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
@@ -20,6 +29,60 @@ pub enum TargetPortsPreset {
     Full,
     Top1000,
     Custom,
+    /// Curated high-value service ports; service detection is always on
+    Services,
+}
+
+/// Order in which scan targets (ports or hosts) are probed
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(from = "ScanOrderRepr")]
+pub enum ScanOrder {
+    /// Ascending port number, or hosts in the given order
+    Sequential,
+    /// Shuffled, optionally with a fixed seed for reproducible runs
+    Random(Option<u64>),
+    /// Most commonly open ports first. Hosts keep the given order.
+    ByFrequency,
+}
+
+impl Default for ScanOrder {
+    fn default() -> Self {
+        ScanOrder::Random(None)
+    }
+}
+
+/// Accepted encodings of [`ScanOrder`], including the legacy `ordered: bool`.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ScanOrderRepr {
+    Legacy(bool),
+    Plain(PlainScanOrder),
+    Seeded(SeededScanOrder),
+}
+
+#[derive(Deserialize)]
+enum PlainScanOrder {
+    Sequential,
+    Random,
+    ByFrequency,
+}
+
+#[derive(Deserialize)]
+enum SeededScanOrder {
+    Random(Option<u64>),
+}
+
+impl From<ScanOrderRepr> for ScanOrder {
+    fn from(repr: ScanOrderRepr) -> Self {
+        match repr {
+            ScanOrderRepr::Legacy(true) => ScanOrder::Sequential,
+            ScanOrderRepr::Legacy(false) => ScanOrder::Random(None),
+            ScanOrderRepr::Plain(PlainScanOrder::Sequential) => ScanOrder::Sequential,
+            ScanOrderRepr::Plain(PlainScanOrder::Random) => ScanOrder::Random(None),
+            ScanOrderRepr::Seeded(SeededScanOrder::Random(seed)) => ScanOrder::Random(seed),
+            ScanOrderRepr::Plain(PlainScanOrder::ByFrequency) => ScanOrder::ByFrequency,
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone, PartialEq, Eq)]
@@ -27,6 +90,27 @@ pub enum PortState {
     Open,
     Closed,
     Filtered,
+    /// No response; the port may be open or filtered (e.g. UDP / QUIC timeout)
+    OpenFiltered,
+    /// The probe failed locally, so nothing is known about the port
+    Unknown,
+    /// Explicitly rejected by a firewall (ICMP administratively prohibited
+    /// or a local filter), as opposed to no route to the host
+    Blocked,
+}
+
+impl PortState {
+    /// Lowercase name as used in text exports, e.g. `open|filtered`
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PortState::Open => "open",
+            PortState::Closed => "closed",
+            PortState::Filtered => "filtered",
+            PortState::OpenFiltered => "open|filtered",
+            PortState::Unknown => "unknown",
+            PortState::Blocked => "blocked",
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -43,10 +127,30 @@ pub struct PortScanSample {
     pub message: Option<String>,
     pub service_name: Option<String>,
     pub service_info: Option<ServiceInfo>,
+    /// TTL / hop limit of the reply. Not exposed for connection-based scans.
+    #[serde(default)]
+    pub reply_ttl: Option<u8>,
+    /// QUIC handshake diagnostics. Only set for open ports in QUIC scans.
+    #[serde(default)]
+    pub quic_handshake: Option<QuicHandshakeInfo>,
     pub done: u32,
     pub total: u32,
 }
 
+/// Observations from the QUIC handshake with an open port
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct QuicHandshakeInfo {
+    /// Server answered the Initial with a Retry packet
+    pub retry: bool,
+    /// Server validated the client address (Retry or PATH_CHALLENGE)
+    pub address_validation: bool,
+    /// Server issued a NEW_TOKEN for future connections
+    pub new_token: bool,
+    /// Whether 0-RTT was accepted on a resumed connection.
+    /// `None` if the server issued no session ticket.
+    pub zero_rtt_accepted: Option<bool>,
+}
+
 #[derive(Serialize, Deserialize, Clone)]
 pub struct PortScanReport {
     pub run_id: String,
@@ -54,6 +158,152 @@ pub struct PortScanReport {
     pub hostname: Option<String>,
     pub protocol: PortScanProtocol,
     pub samples: Vec<PortScanSample>,
+    /// Target looks like a tarpit or honeypot
+    #[serde(default)]
+    pub suspicious: bool,
+    /// Why the target was flagged
+    #[serde(default)]
+    pub suspicious_reason: Option<String>,
+    #[serde(default)]
+    pub summary: PortScanSummary,
+    /// Stopped early because `max_duration_ms` elapsed
+    #[serde(default)]
+    pub deadline_reached: bool,
+    /// Wall time of the scan, service detection included
+    #[serde(default)]
+    pub elapsed_ms: Option<u64>,
+    /// Ports preset the scan ran with
+    #[serde(default)]
+    pub ports_preset: Option<TargetPortsPreset>,
+    /// Number of ports probed
+    #[serde(default)]
+    pub ports_scanned: Option<u32>,
+    /// Ports probed, as ascending inclusive ranges. Empty in reports saved
+    /// before it was recorded.
+    #[serde(default)]
+    pub scanned_ports: Vec<(u16, u16)>,
+    /// JSONL file holding the samples when the scan spilled them to disk;
+    /// `samples` is empty then. Read with `read_scan_results`.
+    #[serde(default)]
+    pub results_file: Option<String>,
+}
+
+/// Collapse ports into ascending inclusive ranges.
+pub fn port_ranges(mut ports: Vec<u16>) -> Vec<(u16, u16)> {
+    ports.sort_unstable();
+    ports.dedup();
+    let mut ranges: Vec<(u16, u16)> = Vec::new();
+    for port in ports {
+        match ranges.last_mut() {
+            Some((_, last)) if *last + 1 == port => *last = port,
+            _ => ranges.push((port, port)),
+        }
+    }
+    ranges
+}
+
+impl PortScanReport {
+    /// Whether `port` was probed; `None` if the report does not record it.
+    pub fn was_scanned(&self, port: u16) -> Option<bool> {
+        if self.scanned_ports.is_empty() {
+            return None;
+        }
+        Some(
+            self.scanned_ports
+                .iter()
+                .any(|&(first, last)| (first..=last).contains(&port)),
+        )
+    }
+
+    /// Samples of the scan, read back from `results_file` when the scan
+    /// spilled them to disk.
+    pub fn load_samples(&self) -> anyhow::Result<Cow<'_, [PortScanSample]>> {
+        match &self.results_file {
+            Some(path) => {
+                let samples = crate::results::SampleReader::open(std::path::Path::new(path))?
+                    .collect::<anyhow::Result<Vec<_>>>()?;
+                Ok(Cow::Owned(samples))
+            }
+            None => Ok(Cow::Borrowed(&self.samples)),
+        }
+    }
+
+    /// The report with `samples` filled in, for consumers that need every
+    /// sample at once (exports, findings, diffs, policy checks).
+    pub fn with_samples(&self) -> anyhow::Result<Cow<'_, PortScanReport>> {
+        if self.results_file.is_none() {
+            return Ok(Cow::Borrowed(self));
+        }
+        let mut report = self.clone();
+        report.samples = self.load_samples()?.into_owned();
+        Ok(Cow::Owned(report))
+    }
+
+    /// Ports found open, sorted. Read from `results_file` when the scan
+    /// spilled its samples to disk.
+    pub fn open_ports(&self) -> anyhow::Result<Vec<u16>> {
+        let mut ports: Vec<u16> = self
+            .load_samples()?
+            .iter()
+            .filter(|s| s.state == PortState::Open)
+            .map(|s| s.port)
+            .collect();
+        ports.sort_unstable();
+        ports.dedup();
+        Ok(ports)
+    }
+}
+
+#[cfg(test)]
+impl PortScanSample {
+    /// Sample of `port` with a 1 ms RTT and nothing else recorded
+    pub(crate) fn fixture(ip_addr: IpAddr, port: u16, state: PortState) -> Self {
+        PortScanSample {
+            ip_addr,
+            port,
+            state,
+            rtt_ms: Some(1),
+            message: None,
+            service_name: None,
+            service_info: None,
+            reply_ttl: None,
+            quic_handshake: None,
+            done: 0,
+            total: 0,
+        }
+    }
+}
+
+#[cfg(test)]
+impl PortScanReport {
+    /// TCP report of `samples` that scanned ports 1-1024
+    pub(crate) fn fixture(ip_addr: IpAddr, samples: Vec<PortScanSample>) -> Self {
+        PortScanReport {
+            run_id: "r1".into(),
+            ip_addr,
+            hostname: None,
+            protocol: PortScanProtocol::Tcp,
+            samples,
+            suspicious: false,
+            suspicious_reason: None,
+            summary: Default::default(),
+            deadline_reached: false,
+            elapsed_ms: None,
+            ports_preset: None,
+            ports_scanned: None,
+            scanned_ports: vec![(1, 1024)],
+            results_file: None,
+        }
+    }
+}
+
+/// Service registered for a port in the bundled service databases
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct RegisteredService {
+    pub port: u16,
+    pub protocol: TransportProtocol,
+    pub name: String,
+    pub description: Option<String>,
 }
 
 /// Settings for a port scan operation
@@ -65,8 +315,223 @@ pub struct PortScanSetting {
     pub user_ports: Vec<u16>,
     pub protocol: PortScanProtocol,
     pub timeout_ms: u64,
-    pub ordered: bool,
+    /// Port ordering. Also accepts the legacy `ordered: bool`.
+    #[serde(default, alias = "ordered")]
+    pub order: ScanOrder,
     pub service_detection: bool,
+    /// Service detection intensity
+    #[serde(default)]
+    pub detection_intensity: DetectionIntensity,
+    /// Emit `portscan:sample` for every completed port, not only open ones.
+    #[serde(default)]
+    pub verbose: bool,
+    /// Fixed local port to connect from (TCP only), for testing
+    /// source-port based firewall rules. Ports below 1024 need privileges on most systems.
+    #[serde(default)]
+    pub source_port: Option<u16>,
+    /// Connect from a random local port in this inclusive range (TCP only)
+    /// instead of the OS ephemeral range, e.g. `[1024, 65535]` to widen it
+    /// for aggressive scans. Cannot be combined with `source_port`.
+    #[serde(default)]
+    pub source_port_range: Option<(u16, u16)>,
+    /// Close open-port connections with an RST (SO_LINGER 0) instead of a FIN.
+    /// Avoids TIME_WAIT buildup on large scans and the target logging a completed session.
+    #[serde(default)]
+    pub reset_on_close: bool,
+    /// Routing table / VRF to scan through (Linux only)
+    #[serde(default)]
+    pub routing: ScanRouting,
+    /// Drop open ports that sent nothing to any service probe (open|no-response)
+    /// from the report. Requires service detection.
+    #[serde(default)]
+    pub responsive_only: bool,
+    /// Maximum simultaneous probes to the target, shared with other running
+    /// scans of the same host. Keeps fragile devices from being flooded.
+    #[serde(default)]
+    pub max_per_host: Option<usize>,
+    /// Skip ports known to crash fragile devices (bundled list, overridable
+    /// in the app dir) and run only read-only service probes.
+    #[serde(default)]
+    pub safe_mode: bool,
+    /// Ports removed after preset expansion
+    #[serde(default)]
+    pub exclude_ports: Vec<u16>,
+    /// Report firewall rejections as `Blocked` instead of `Filtered` (TCP only):
+    /// local policy errors, and ICMP administratively prohibited with raw socket rights
+    #[serde(default)]
+    pub detect_blocked: bool,
+    /// Keep service probe responses as a hex dump in `ServiceInfo.raw`
+    #[serde(default)]
+    pub raw_hex_dump: bool,
+    /// Bytes kept in the hex dump (default `DEFAULT_RAW_HEX_BYTES`)
+    #[serde(default)]
+    pub raw_hex_max_bytes: Option<usize>,
+    /// Annotate detected services with known CVEs from the offline index
+    #[serde(default)]
+    pub vuln_lookup: bool,
+    /// Skip service probes marked intrusive (free-form lines, HELP, OPTIONS)
+    #[serde(default)]
+    pub skip_intrusive: bool,
+    /// Scan over IPv4 or IPv6 only. `hostname` is re-resolved when
+    /// `ip_addr` is of the other family.
+    #[serde(default)]
+    pub family: Option<AddressFamily>,
+    /// Stop issuing probes after this long and report what was collected
+    #[serde(default)]
+    pub max_duration_ms: Option<u64>,
+    /// Append open samples to a results file as they complete instead of
+    /// keeping them in memory, for huge scans (TCP only). The report then
+    /// holds the summary and `results_file`, not the samples.
+    #[serde(default)]
+    pub spill_results: bool,
+    /// Keep the connection of each open port and hand it to the first
+    /// service probe instead of reconnecting (TCP only). Stateful services
+    /// then see one connection. Requires service detection.
+    #[serde(default)]
+    pub reuse_connection: bool,
+    /// Make a second, resuming handshake on TLS ports and report the
+    /// resumption time and whether session tickets are offered
+    #[serde(default)]
+    pub tls_resumption: bool,
+    /// Enumerate the TLS versions and cipher suites accepted on TLS ports,
+    /// with a bounded number of extra handshakes per port
+    #[serde(default)]
+    pub tls_enum: bool,
+}
+
+/// Sequential TCP scan of no ports with every option off; set the target
+/// and ports on top.
+impl Default for PortScanSetting {
+    fn default() -> Self {
+        PortScanSetting {
+            ip_addr: IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+            hostname: None,
+            target_ports_preset: TargetPortsPreset::Custom,
+            user_ports: Vec::new(),
+            protocol: PortScanProtocol::Tcp,
+            timeout_ms: 3000,
+            order: ScanOrder::Sequential,
+            service_detection: false,
+            detection_intensity: DetectionIntensity::default(),
+            verbose: false,
+            source_port: None,
+            source_port_range: None,
+            reset_on_close: false,
+            routing: ScanRouting::default(),
+            responsive_only: false,
+            max_per_host: None,
+            safe_mode: false,
+            exclude_ports: Vec::new(),
+            detect_blocked: false,
+            raw_hex_dump: false,
+            raw_hex_max_bytes: None,
+            vuln_lookup: false,
+            skip_intrusive: false,
+            family: None,
+            max_duration_ms: None,
+            spill_results: false,
+            reuse_connection: false,
+            tls_resumption: false,
+            tls_enum: false,
+        }
+    }
+}
+
+/// Default size of the raw response hex dump
+pub const DEFAULT_RAW_HEX_BYTES: usize = 4096;
+/// Connect timeout when re-checking the open ports of an earlier scan
+pub const DEFAULT_RECHECK_TIMEOUT_MS: u64 = 2000;
+
+impl PortScanSetting {
+    /// Scan only `ports` of the target of `report`, e.g. the ports it found
+    /// open, to confirm they are still up.
+    pub fn recheck(
+        report: &PortScanReport,
+        ports: Vec<u16>,
+        timeout_ms: u64,
+        service_detection: bool,
+    ) -> Self {
+        PortScanSetting {
+            ip_addr: report.ip_addr,
+            hostname: report.hostname.clone(),
+            user_ports: ports,
+            protocol: report.protocol,
+            timeout_ms,
+            service_detection,
+            ..Default::default()
+        }
+    }
+
+    /// Hex dump limit for service detection, `None` when capture is off.
+    pub fn raw_hex_limit(&self) -> Option<usize> {
+        self.raw_hex_dump
+            .then(|| self.raw_hex_max_bytes.unwrap_or(DEFAULT_RAW_HEX_BYTES))
+    }
+
+    /// Make `ip_addr` match `family`, resolving `hostname` again if needed.
+    /// Fails when the target has no address in that family.
+    pub async fn apply_family(&mut self) -> anyhow::Result<()> {
+        let family = self.family.unwrap_or_default();
+        if family.matches(&self.ip_addr) {
+            return Ok(());
+        }
+        let Some(hostname) = self.hostname.as_deref() else {
+            anyhow::bail!("{} is not an {:?} address", self.ip_addr, family);
+        };
+        let timeout = Duration::from_millis(DEFAULT_RESOLVE_TIMEOUT_MS);
+        let ips =
+            crate::net::dns::lookup_ip_retry(hostname, timeout, crate::net::dns::RESOLVE_ATTEMPTS)
+                .await
+                .map_err(|e| {
+                    ScanError::resolution_failed(format!(
+                        "{} could not be resolved: {}",
+                        hostname, e
+                    ))
+                })?;
+        match ips.into_iter().find(|ip| family.matches(ip)) {
+            Some(ip) => {
+                tracing::debug!("{}: scanning {} instead of {}", hostname, ip, self.ip_addr);
+                self.ip_addr = ip;
+                Ok(())
+            }
+            None => anyhow::bail!("{} has no {:?} address", hostname, family),
+        }
+    }
+}
+
+/// IP family a scan is restricted to
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub enum AddressFamily {
+    V4,
+    V6,
+    #[default]
+    Both,
+}
+
+impl AddressFamily {
+    pub fn matches(&self, ip: &IpAddr) -> bool {
+        match self {
+            AddressFamily::V4 => ip.is_ipv4(),
+            AddressFamily::V6 => ip.is_ipv6(),
+            AddressFamily::Both => true,
+        }
+    }
+}
+
+/// Network context the scan sockets are bound to (Linux only).
+/// Lets a scan use a specific VRF or policy routing table.
+#[derive(Deserialize, Serialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct ScanRouting {
+    /// Bind sockets to this device (SO_BINDTODEVICE), e.g. a VRF master interface
+    pub bind_device: Option<String>,
+    /// Firewall mark (SO_MARK) matched by `ip rule fwmark` policy routing
+    pub fwmark: Option<u32>,
+}
+
+impl ScanRouting {
+    pub fn is_empty(&self) -> bool {
+        self.bind_device.is_none() && self.fwmark.is_none()
+    }
 }
 
 #[derive(Debug, Clone, Copy, serde::Serialize, serde::Deserialize)]
@@ -75,6 +540,11 @@ pub enum HostState {
     Unreachable,
 }
 
+/// Default per-name timeout when resolving hostname targets
+pub const DEFAULT_RESOLVE_TIMEOUT_MS: u64 = 1000;
+/// Default number of hostname targets resolved in parallel
+pub const DEFAULT_RESOLVE_CONCURRENCY: usize = 64;
+
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct HostScanSetting {
     pub targets: Vec<MaybeHost>,
@@ -82,8 +552,75 @@ pub struct HostScanSetting {
     pub timeout_ms: u64,
     pub count: u32,
     pub payload: Option<String>,
-    pub ordered: bool,
+    /// Host ordering. Also accepts the legacy `ordered: bool`.
+    #[serde(default, alias = "ordered")]
+    pub order: ScanOrder,
+    /// Probes in flight to IPv4 targets, and to IPv6 targets unless `concurrency_v6` is set
     pub concurrency: Option<usize>,
+    /// Probes in flight to IPv6 targets. Each family has its own limit,
+    /// so a dual-stack sweep cannot starve either of them.
+    #[serde(default)]
+    pub concurrency_v6: Option<usize>,
+    /// Stop issuing new probes once this many alive hosts are found.
+    #[serde(default)]
+    pub max_alive: Option<usize>,
+    /// Routing table / VRF to scan through (Linux only)
+    #[serde(default)]
+    pub routing: ScanRouting,
+    /// IPs, CIDRs or ranges removed from the resolved targets
+    #[serde(default)]
+    pub exclude_targets: Vec<String>,
+    /// Per-name DNS timeout for hostname targets (default 1000 ms)
+    #[serde(default)]
+    pub resolve_timeout_ms: Option<u64>,
+    /// Hostnames resolved in parallel (default 64)
+    #[serde(default)]
+    pub resolve_concurrency: Option<usize>,
+    /// Resolve through the system resolver or race all nameservers
+    #[serde(default)]
+    pub resolve_strategy: ResolveStrategy,
+    /// Scan only IPv4 or IPv6 targets
+    #[serde(default)]
+    pub family: Option<AddressFamily>,
+    /// Interface to send from and receive on (default interface when unset)
+    #[serde(default)]
+    pub iface_name: Option<String>,
+    /// Stop issuing probes after this long and report what was collected
+    #[serde(default)]
+    pub max_duration_ms: Option<u64>,
+    /// Send all `count` probes per host and emit `hostscan:sample` for each
+    #[serde(default)]
+    pub verbose: bool,
+    /// Time between consecutive probes to the same host (back-to-back when unset)
+    #[serde(default)]
+    pub interval_ms: Option<u64>,
+}
+
+/// Sequential single-ping sweep of no targets; set the targets on top.
+impl Default for HostScanSetting {
+    fn default() -> Self {
+        HostScanSetting {
+            targets: Vec::new(),
+            hop_limit: 64,
+            timeout_ms: 1000,
+            count: 1,
+            payload: None,
+            order: ScanOrder::Sequential,
+            concurrency: None,
+            concurrency_v6: None,
+            max_alive: None,
+            routing: ScanRouting::default(),
+            exclude_targets: Vec::new(),
+            resolve_timeout_ms: None,
+            resolve_concurrency: None,
+            resolve_strategy: ResolveStrategy::default(),
+            family: None,
+            iface_name: None,
+            max_duration_ms: None,
+            verbose: false,
+            interval_ms: None,
+        }
+    }
 }
 
 impl HostScanSetting {
@@ -116,35 +653,39 @@ impl HostScanSetting {
             timeout_ms: req.timeout_ms,
             count: req.count,
             payload: req.payload,
-            ordered: req.ordered,
+            order: req.order,
             concurrency: req.concurrency,
+            concurrency_v6: req.concurrency_v6,
+            max_alive: req.max_alive,
+            routing: req.routing,
+            exclude_targets: req.exclude_targets,
+            resolve_timeout_ms: req.resolve_timeout_ms,
+            resolve_concurrency: req.resolve_concurrency,
+            resolve_strategy: req.resolve_strategy,
+            family: req.family,
+            iface_name: req.iface_name,
+            max_duration_ms: req.max_duration_ms,
+            verbose: req.verbose,
+            interval_ms: req.interval_ms,
         }
     }
-    pub fn neighbor_scan_default(iface: &netdev::Interface) -> Self {
+    pub fn neighbor_scan_default(iface: &netdev::Interface, opts: &NeighborScanOptions) -> Self {
         let mut targets: Vec<MaybeHost> = Vec::new();
-        if let Some(gw) = &iface.gateway {
-            if let Some(ipv4) = gw.ipv4.first() {
-                match netdev::ipnet::Ipv4Net::new(*ipv4, 24) {
-                    Ok(ipv4net) => {
-                        for ipv4 in ipv4net.hosts() {
-                            targets.push(MaybeHost {
-                                ip: Some(IpAddr::V4(ipv4)),
-                                hostname: None,
-                            });
-                        }
-                    }
-                    Err(_) => {}
-                }
+        for ipv4net in neighbor_subnets_v4(iface, opts) {
+            for ipv4 in ipv4net.hosts() {
+                targets.push(MaybeHost {
+                    ip: Some(IpAddr::V4(ipv4)),
+                    hostname: None,
+                });
             }
         }
         HostScanSetting {
             targets,
-            hop_limit: 64,
-            timeout_ms: 1000,
-            count: 1,
             payload: Some("np:neigh".to_string()),
-            ordered: true,
             concurrency: Some(100),
+            family: Some(AddressFamily::V4),
+            iface_name: Some(iface.name.clone()),
+            ..Default::default()
         }
     }
 
@@ -162,12 +703,39 @@ impl HostScanSetting {
             .collect()
     }
 
-    pub async fn resolve_targets(&self) -> Vec<crate::model::endpoint::Host> {
-        let timeout = Duration::from_millis(1000);
-        let concurrency = 64usize;
+    /// Resolve the targets to hosts, minus the excluded addresses.
+    /// Also returns the hostnames that did not resolve.
+    /// Fails on a malformed exclusion rather than scanning a host meant to be avoided.
+    pub async fn resolve_targets(&self) -> anyhow::Result<(Vec<Host>, Vec<UnresolvedName>)> {
+        let timeout = Duration::from_millis(
+            self.resolve_timeout_ms
+                .unwrap_or(DEFAULT_RESOLVE_TIMEOUT_MS),
+        );
+        let concurrency = self
+            .resolve_concurrency
+            .unwrap_or(DEFAULT_RESOLVE_CONCURRENCY);
 
+        let exclude = ExcludeSet::parse(&self.exclude_targets)?;
         let inputs = self.target_strings();
-        crate::net::dns::resolve_hosts(&inputs, timeout, concurrency).await
+        let (mut hosts, unresolved) =
+            crate::net::dns::resolve_hosts(&inputs, timeout, concurrency, self.resolve_strategy)
+                .await;
+        if let Some(family) = self.family {
+            let before = hosts.len();
+            hosts.retain(|h| family.matches(&h.ip));
+            tracing::debug!(
+                "kept {} of {} targets in family {:?}",
+                hosts.len(),
+                before,
+                family
+            );
+        }
+        if !exclude.is_empty() {
+            let before = hosts.len();
+            hosts.retain(|h| !exclude.contains(&h.ip));
+            tracing::debug!("excluded {} of {} targets", before - hosts.len(), before);
+        }
+        Ok((hosts, unresolved))
     }
 
     pub fn target_ips(&self) -> Vec<IpAddr> {
@@ -175,6 +743,63 @@ impl HostScanSetting {
     }
 }
 
+/// Smallest IPv4 prefix length used when derived from the interface (at most 4094 hosts).
+pub const NEIGHBOR_SCAN_MIN_PREFIX_V4: u8 = 20;
+/// Smallest IPv4 prefix length accepted as an explicit override (at most 65534 hosts).
+pub const NEIGHBOR_SCAN_MIN_OVERRIDE_PREFIX_V4: u8 = 16;
+
+/// Options for a neighbor scan
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NeighborScanOptions {
+    /// Override the prefix length derived from the interface address
+    pub prefix_len: Option<u8>,
+    /// Sweep every local IPv4 subnet of the interface instead of the gateway's one
+    pub all_subnets: bool,
+}
+
+/// Resolve the IPv4 subnets to sweep for a neighbor scan.
+///
+/// By default uses the interface address on the gateway's network (or the first
+/// IPv4 address) together with its assigned prefix length. Large subnets are
+/// narrowed around the interface address unless `prefix_len` overrides it.
+pub fn neighbor_subnets_v4(
+    iface: &netdev::Interface,
+    opts: &NeighborScanOptions,
+) -> Vec<netdev::ipnet::Ipv4Net> {
+    let ifnets: Vec<&netdev::ipnet::Ipv4Net> = if opts.all_subnets {
+        iface.ipv4.iter().collect()
+    } else {
+        let gw_ipv4 = iface.gateway.as_ref().and_then(|gw| gw.ipv4.first());
+        gw_ipv4
+            .and_then(|gw| iface.ipv4.iter().find(|net| net.contains(gw)))
+            .or_else(|| iface.ipv4.first())
+            .into_iter()
+            .collect()
+    };
+    let mut subnets: Vec<netdev::ipnet::Ipv4Net> = Vec::new();
+    for ifnet in ifnets {
+        let prefix = match opts.prefix_len {
+            Some(p) => p.clamp(NEIGHBOR_SCAN_MIN_OVERRIDE_PREFIX_V4, 32),
+            None => ifnet.prefix_len().max(NEIGHBOR_SCAN_MIN_PREFIX_V4),
+        };
+        if prefix != ifnet.prefix_len() {
+            tracing::debug!(
+                "neighbor scan: using /{} instead of assigned /{} on {}",
+                prefix,
+                ifnet.prefix_len(),
+                iface.name
+            );
+        }
+        if let Ok(net) = netdev::ipnet::Ipv4Net::new(ifnet.addr(), prefix) {
+            let net = net.trunc();
+            if !subnets.contains(&net) {
+                subnets.push(net);
+            }
+        }
+    }
+    subnets
+}
+
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct HostScanRequest {
     pub targets: Vec<String>,
@@ -182,8 +807,34 @@ pub struct HostScanRequest {
     pub timeout_ms: u64,
     pub count: u32,
     pub payload: Option<String>,
-    pub ordered: bool,
+    /// Host ordering. Also accepts the legacy `ordered: bool`.
+    #[serde(default, alias = "ordered")]
+    pub order: ScanOrder,
     pub concurrency: Option<usize>,
+    #[serde(default)]
+    pub concurrency_v6: Option<usize>,
+    #[serde(default)]
+    pub max_alive: Option<usize>,
+    #[serde(default)]
+    pub routing: ScanRouting,
+    #[serde(default)]
+    pub exclude_targets: Vec<String>,
+    #[serde(default)]
+    pub resolve_timeout_ms: Option<u64>,
+    #[serde(default)]
+    pub resolve_concurrency: Option<usize>,
+    #[serde(default)]
+    pub resolve_strategy: ResolveStrategy,
+    #[serde(default)]
+    pub family: Option<AddressFamily>,
+    #[serde(default)]
+    pub iface_name: Option<String>,
+    #[serde(default)]
+    pub max_duration_ms: Option<u64>,
+    #[serde(default)]
+    pub verbose: bool,
+    #[serde(default)]
+    pub interval_ms: Option<u64>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -191,22 +842,173 @@ pub struct HostScanStartPayload {
     pub run_id: String,
 }
 
+/// Scan stopped issuing probes at `max_duration_ms` (`*:deadline_reached`)
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct DeadlineReachedPayload {
+    pub run_id: String,
+    pub max_duration_ms: u64,
+}
+
+/// Hostname targets dropped before probing (`hostscan:unresolved`)
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct HostScanUnresolvedPayload {
+    pub run_id: String,
+    pub unresolved: Vec<UnresolvedName>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct HostScanLimitPayload {
+    pub run_id: String,
+    pub max_alive: usize,
+}
+
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct HostScanProgress {
     pub ip_addr: IpAddr,
     pub state: HostState,
     pub rtt_ms: Option<u64>,
+    /// Why an unreachable host failed: the ICMP unreachable reason and the
+    /// router that sent it, or a timeout
     pub message: Option<String>,
+    /// TTL / hop limit of the echo reply
+    #[serde(default)]
+    pub reply_ttl: Option<u8>,
     pub done: u32,
     pub total: u32,
 }
 
+/// One echo request of a verbose host scan (`hostscan:sample`)
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct EchoSample {
+    pub ip_addr: IpAddr,
+    pub seq: u32,
+    /// `None` if the probe was lost
+    pub rtt_ms: Option<u64>,
+    pub reply_ttl: Option<u8>,
+    /// Why the probe got no reply
+    pub message: Option<String>,
+}
+
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct HostScanReport {
     pub run_id: String,
     pub alive: Vec<(Host, u64)>, // (IP, RTT)
     pub unreachable: Vec<Host>,
     pub total: u32,
+    #[serde(default)]
+    pub summary: HostScanSummary,
+    /// Hostname targets that could not be resolved and were not scanned
+    #[serde(default)]
+    pub unresolved: Vec<UnresolvedName>,
+    /// Stopped early because `max_duration_ms` elapsed
+    #[serde(default)]
+    pub deadline_reached: bool,
+    /// Every probe per host, in `seq` order; verbose scans only
+    #[serde(default)]
+    pub samples: BTreeMap<IpAddr, Vec<EchoSample>>,
+}
+
+/// Host scan from one interface of a multi-interface run
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct InterfaceHostScan {
+    pub iface_name: String,
+    pub report: Option<HostScanReport>,
+    /// Why the scan from this interface failed
+    pub error: Option<String>,
+}
+
+/// The same host scan run once per up interface
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct MultiInterfaceHostScanReport {
+    pub scans: Vec<InterfaceHostScan>,
+    /// Interfaces each alive host answered through
+    pub reachable_via: BTreeMap<IpAddr, Vec<String>>,
+}
+
+impl MultiInterfaceHostScanReport {
+    pub fn new(scans: Vec<InterfaceHostScan>) -> Self {
+        let mut reachable_via: BTreeMap<IpAddr, Vec<String>> = BTreeMap::new();
+        for scan in &scans {
+            let Some(report) = &scan.report else {
+                continue;
+            };
+            for (host, _) in &report.alive {
+                reachable_via
+                    .entry(host.ip)
+                    .or_default()
+                    .push(scan.iface_name.clone());
+            }
+        }
+        Self {
+            scans,
+            reachable_via,
+        }
+    }
+}
+
+/// Phase of a host discovery + port scan run
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ScanPhase {
+    /// ICMP sweep of the targets
+    Discovery,
+    /// Port scans of the alive hosts
+    Ports,
+}
+
+/// Overall progress of a multi-phase scan (`scan:progress`)
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ScanProgressPayload {
+    pub run_id: String,
+    pub phase: ScanPhase,
+    pub done: u32,
+    pub total: u32,
+}
+
+/// Port scan of one alive host of a discovery run
+#[derive(Serialize, Deserialize, Clone)]
+pub struct HostPortScan {
+    pub host: Host,
+    pub report: Option<PortScanReport>,
+    /// Why the port scan of this host failed
+    pub error: Option<String>,
+}
+
+/// Host discovery followed by a port scan of every alive host
+#[derive(Serialize, Deserialize, Clone)]
+pub struct HostPortScanReport {
+    pub run_id: String,
+    pub discovery: HostScanReport,
+    pub scans: Vec<HostPortScan>,
+}
+
+/// Scan a cost estimate is asked for (`estimate_scan_cost`)
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(tag = "kind", content = "setting")]
+pub enum ScanCostSetting {
+    PortScan(PortScanSetting),
+    HostScan(HostScanRequest),
+}
+
+/// Packets, time and bandwidth a scan is expected to take, worked out
+/// from its setting before it runs
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct ScanCostEstimate {
+    /// Target addresses; a hostname counts as one
+    pub targets: u64,
+    /// Ports probed, or echo requests of a host scan
+    pub probes: u64,
+    /// Packets sent, SYN retransmissions included
+    pub packets: u64,
+    /// Bytes sent, IP headers included
+    pub bytes: u64,
+    /// Probes in flight at once
+    pub concurrency: usize,
+    /// Run time when no probe is answered; responsive targets finish sooner
+    pub duration_ms: u64,
+    /// Average upstream bandwidth over `duration_ms`, in bits per second
+    pub bandwidth_bps: u64,
+    /// `max_duration_ms` stops the scan before every probe is sent
+    pub truncated: bool,
 }
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
@@ -218,9 +1020,81 @@ pub struct NeighborHost {
     pub tags: Vec<String>,
 }
 
+/// How the hosts of a neighbor scan were found
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum NeighborDiscoveryMethod {
+    /// ICMP echo sweep
+    #[default]
+    Icmp,
+    /// TCP connects to common ports plus the neighbor table, used when
+    /// ICMP sockets cannot be opened without privileges
+    Tcp,
+}
+
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct NeighborScanReport {
     pub run_id: String,
     pub neighbors: Vec<NeighborHost>,
     pub total: u32,
+    #[serde(default)]
+    pub method: NeighborDiscoveryMethod,
+    /// Hosts the TCP sweep could not check because local connects failed
+    /// (e.g. out of file descriptors). Neither up nor down.
+    #[serde(default)]
+    pub unknown: Vec<IpAddr>,
+}
+
+/// State of an IP protocol on the target
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum IpProtocolState {
+    /// Target answered in the protocol (or with ICMP port unreachable)
+    Open,
+    /// Target sent ICMP protocol unreachable
+    Closed,
+    /// Another ICMP unreachable (administratively prohibited etc.)
+    Filtered,
+    /// No response
+    OpenFiltered,
+}
+
+/// Settings for an IP protocol scan (IPv4 only)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IpProtocolScanSetting {
+    pub ip_addr: IpAddr,
+    pub hostname: Option<String>,
+    /// IP protocol numbers to probe. Empty means the built-in set.
+    #[serde(default)]
+    pub protocols: Vec<u8>,
+    pub timeout_ms: u64,
+    /// Send each probe as IPv4 fragments carrying this many payload bytes
+    /// (rounded down to a multiple of 8), to test whether filters reassemble.
+    /// Builds the IP header on a raw socket, so it needs administrator privileges.
+    #[serde(default)]
+    pub fragment_size: Option<u16>,
+    /// IPv4 source addresses to spoof a copy of each probe from, so the
+    /// target sees several apparent scanners. Same privilege requirement as fragments.
+    #[serde(default)]
+    pub decoys: Vec<IpAddr>,
+    /// Source port written into the TCP and UDP probes instead of a random one
+    #[serde(default)]
+    pub source_port: Option<u16>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IpProtocolScanSample {
+    pub protocol: u8,
+    pub name: String,
+    pub state: IpProtocolState,
+    pub rtt_ms: Option<u64>,
+    pub message: Option<String>,
+    pub done: u32,
+    pub total: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IpProtocolScanReport {
+    pub run_id: String,
+    pub ip_addr: IpAddr,
+    pub hostname: Option<String>,
+    pub samples: Vec<IpProtocolScanSample>,
 }
@@ -0,0 +1,202 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::model::endpoint::Host;
+use crate::model::scan::{PortScanSample, PortState};
+
+/// Upper bounds (exclusive, ms) of the RTT histogram buckets.
+/// A final open-ended bucket holds everything slower.
+const RTT_BUCKET_BOUNDS_MS: [u64; 7] = [1, 5, 10, 50, 100, 500, 1000];
+
+/// Number of RTTs in `[lower_ms, upper_ms)`; `upper_ms` is `None` for the last bucket
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct RttBucket {
+    pub lower_ms: u64,
+    pub upper_ms: Option<u64>,
+    pub count: usize,
+}
+
+/// RTT distribution of the responding ports or hosts
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct RttStats {
+    pub min_ms: u64,
+    pub avg_ms: f64,
+    pub max_ms: u64,
+    /// Every bucket is listed, empty ones included, so charts keep their axis
+    pub histogram: Vec<RttBucket>,
+}
+
+impl RttStats {
+    /// `None` when there is no RTT to summarize.
+    pub fn from_rtts(rtts: impl IntoIterator<Item = u64>) -> Option<Self> {
+        let rtts: Vec<u64> = rtts.into_iter().collect();
+        let min_ms = *rtts.iter().min()?;
+        let max_ms = *rtts.iter().max()?;
+        let avg_ms = rtts.iter().sum::<u64>() as f64 / rtts.len() as f64;
+
+        let mut histogram: Vec<RttBucket> = Vec::with_capacity(RTT_BUCKET_BOUNDS_MS.len() + 1);
+        let mut lower_ms = 0;
+        for upper in RTT_BUCKET_BOUNDS_MS {
+            histogram.push(RttBucket {
+                lower_ms,
+                upper_ms: Some(upper),
+                count: 0,
+            });
+            lower_ms = upper;
+        }
+        histogram.push(RttBucket {
+            lower_ms,
+            upper_ms: None,
+            count: 0,
+        });
+        for rtt in &rtts {
+            let i = RTT_BUCKET_BOUNDS_MS
+                .iter()
+                .position(|upper| rtt < upper)
+                .unwrap_or(RTT_BUCKET_BOUNDS_MS.len());
+            histogram[i].count += 1;
+        }
+
+        Some(RttStats {
+            min_ms,
+            avg_ms,
+            max_ms,
+            histogram,
+        })
+    }
+}
+
+/// Open ports of one service
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ServiceCount {
+    pub name: String,
+    pub count: usize,
+}
+
+/// Aggregates of a port scan, computed once when the report is built
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct PortScanSummary {
+    pub open: usize,
+    /// Open ports per service name, most common first.
+    /// Ports without a known service count as `unknown`.
+    pub by_service: Vec<ServiceCount>,
+    /// RTTs of the open ports; `None` if no open port has one
+    pub rtt: Option<RttStats>,
+}
+
+impl PortScanSummary {
+    pub fn from_samples(samples: &[PortScanSample]) -> Self {
+        Self::from_open_ports(
+            samples
+                .iter()
+                .filter(|s| s.state == PortState::Open)
+                .map(|s| {
+                    let name = s
+                        .service_info
+                        .as_ref()
+                        .and_then(|info| info.name.as_deref())
+                        .or(s.service_name.as_deref());
+                    (name, s.rtt_ms)
+                }),
+        )
+    }
+
+    /// Summary of open ports given as service name and RTT, e.g. while
+    /// reading them back from a results file.
+    pub fn from_open_ports<S: AsRef<str>>(
+        ports: impl IntoIterator<Item = (Option<S>, Option<u64>)>,
+    ) -> Self {
+        let mut open = 0;
+        let mut rtts = Vec::new();
+        let mut services: BTreeMap<String, usize> = BTreeMap::new();
+        for (name, rtt_ms) in ports {
+            open += 1;
+            rtts.extend(rtt_ms);
+            let name = name
+                .as_ref()
+                .map(AsRef::as_ref)
+                .filter(|name| !name.is_empty())
+                .unwrap_or("unknown");
+            match services.get_mut(name) {
+                Some(count) => *count += 1,
+                None => {
+                    services.insert(name.to_string(), 1);
+                }
+            }
+        }
+        let mut by_service: Vec<ServiceCount> = services
+            .into_iter()
+            .map(|(name, count)| ServiceCount { name, count })
+            .collect();
+        // Stable sort keeps names alphabetical within the same count
+        by_service.sort_by(|a, b| b.count.cmp(&a.count));
+
+        PortScanSummary {
+            open,
+            by_service,
+            rtt: RttStats::from_rtts(rtts),
+        }
+    }
+}
+
+/// Aggregates of a host scan, computed once when the report is built
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct HostScanSummary {
+    pub alive: usize,
+    pub total: u32,
+    /// RTTs of the alive hosts; `None` if none answered
+    pub rtt: Option<RttStats>,
+}
+
+impl HostScanSummary {
+    pub fn from_alive(alive: &[(Host, u64)], total: u32) -> Self {
+        HostScanSummary {
+            alive: alive.len(),
+            total,
+            rtt: RttStats::from_rtts(alive.iter().map(|(_, rtt)| *rtt)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn histogram_buckets_are_half_open() {
+        let stats = RttStats::from_rtts([0, 1, 4, 50, 999, 1000, 5000]).unwrap();
+        assert_eq!((stats.min_ms, stats.max_ms), (0, 5000));
+        assert_eq!(stats.avg_ms, 7054.0 / 7.0);
+        let counts: Vec<usize> = stats.histogram.iter().map(|b| b.count).collect();
+        assert_eq!(counts, [1, 2, 0, 0, 1, 0, 1, 2]);
+        assert_eq!(
+            stats.histogram.last(),
+            Some(&RttBucket {
+                lower_ms: 1000,
+                upper_ms: None,
+                count: 2
+            })
+        );
+        assert!(RttStats::from_rtts([]).is_none());
+    }
+
+    #[test]
+    fn counts_open_ports_by_service() {
+        let summary = PortScanSummary::from_open_ports([
+            (Some("http"), Some(3)),
+            (Some("ssh"), None),
+            (Some("http"), Some(5)),
+            (None, None),
+            (Some(""), None),
+        ]);
+        assert_eq!(summary.open, 5);
+        let by_service: Vec<(&str, usize)> = summary
+            .by_service
+            .iter()
+            .map(|s| (s.name.as_str(), s.count))
+            .collect();
+        assert_eq!(by_service, [("http", 2), ("unknown", 2), ("ssh", 1)]);
+        let rtt = summary.rtt.unwrap();
+        assert_eq!((rtt.min_ms, rtt.max_ms, rtt.avg_ms), (3, 5, 4.0));
+    }
+}
@@ -0,0 +1,294 @@
+use anyhow::{bail, Context, Result};
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::time::{Duration, Instant};
+
+use crate::error::ScanError;
+use crate::model::dhcp::{DhcpDiscoveryReport, DhcpOffer};
+use crate::socket::udp::{AsyncUdpSocket, UdpConfig};
+
+pub const DHCP_SERVER_PORT: u16 = 67;
+pub const DHCP_CLIENT_PORT: u16 = 68;
+/// How long to collect offers (default)
+pub const DEFAULT_DHCP_TIMEOUT_MS: u64 = 3000;
+
+const BOOTREQUEST: u8 = 1;
+const BOOTREPLY: u8 = 2;
+const HTYPE_ETHERNET: u8 = 1;
+/// Ask servers to broadcast the reply, as we have no address yet
+const FLAG_BROADCAST: u16 = 0x8000;
+const MAGIC_COOKIE: [u8; 4] = [99, 130, 83, 99];
+/// Fixed BOOTP header before the magic cookie
+const HEADER_LEN: usize = 236;
+/// Minimum BOOTP message size accepted by old relays and servers
+const MIN_PACKET_LEN: usize = 300;
+
+const DHCPDISCOVER: u8 = 1;
+const DHCPOFFER: u8 = 2;
+
+const OPT_PAD: u8 = 0;
+const OPT_SUBNET_MASK: u8 = 1;
+const OPT_ROUTER: u8 = 3;
+const OPT_DNS: u8 = 6;
+const OPT_DOMAIN_NAME: u8 = 15;
+const OPT_BROADCAST: u8 = 28;
+const OPT_NTP: u8 = 42;
+const OPT_LEASE_TIME: u8 = 51;
+const OPT_MESSAGE_TYPE: u8 = 53;
+const OPT_SERVER_ID: u8 = 54;
+const OPT_PARAMETER_LIST: u8 = 55;
+const OPT_RENEWAL_TIME: u8 = 58;
+const OPT_REBINDING_TIME: u8 = 59;
+const OPT_CLIENT_ID: u8 = 61;
+const OPT_END: u8 = 255;
+
+/// Build a DHCPDISCOVER for the hardware address `mac`
+fn build_discover(xid: u32, mac: &[u8; 6]) -> Vec<u8> {
+    let mut packet = vec![0u8; HEADER_LEN];
+    packet[0] = BOOTREQUEST;
+    packet[1] = HTYPE_ETHERNET;
+    packet[2] = mac.len() as u8;
+    packet[4..8].copy_from_slice(&xid.to_be_bytes());
+    packet[10..12].copy_from_slice(&FLAG_BROADCAST.to_be_bytes());
+    // chaddr
+    packet[28..34].copy_from_slice(mac);
+
+    packet.extend_from_slice(&MAGIC_COOKIE);
+    packet.extend_from_slice(&[OPT_MESSAGE_TYPE, 1, DHCPDISCOVER]);
+    packet.extend_from_slice(&[OPT_CLIENT_ID, 7, HTYPE_ETHERNET]);
+    packet.extend_from_slice(mac);
+    let requested = [
+        OPT_SUBNET_MASK,
+        OPT_ROUTER,
+        OPT_DNS,
+        OPT_DOMAIN_NAME,
+        OPT_BROADCAST,
+        OPT_NTP,
+        OPT_LEASE_TIME,
+        OPT_SERVER_ID,
+        OPT_RENEWAL_TIME,
+        OPT_REBINDING_TIME,
+    ];
+    packet.extend_from_slice(&[OPT_PARAMETER_LIST, requested.len() as u8]);
+    packet.extend_from_slice(&requested);
+    packet.push(OPT_END);
+    if packet.len() < MIN_PACKET_LEN {
+        packet.resize(MIN_PACKET_LEN, OPT_PAD);
+    }
+    packet
+}
+
+fn ipv4_at(buf: &[u8], offset: usize) -> Ipv4Addr {
+    Ipv4Addr::new(
+        buf[offset],
+        buf[offset + 1],
+        buf[offset + 2],
+        buf[offset + 3],
+    )
+}
+
+fn ipv4_list(value: &[u8]) -> Vec<Ipv4Addr> {
+    value.chunks_exact(4).map(|c| ipv4_at(c, 0)).collect()
+}
+
+fn ipv4_value(value: &[u8]) -> Option<Ipv4Addr> {
+    (value.len() == 4).then(|| ipv4_at(value, 0))
+}
+
+fn u32_value(value: &[u8]) -> Option<u32> {
+    Some(u32::from_be_bytes(value.try_into().ok()?))
+}
+
+/// Options as (code, value) pairs, in packet order
+fn parse_options(mut buf: &[u8]) -> Vec<(u8, &[u8])> {
+    let mut options = Vec::new();
+    while let Some((&code, rest)) = buf.split_first() {
+        match code {
+            OPT_PAD => buf = rest,
+            OPT_END => break,
+            _ => {
+                let Some((&len, rest)) = rest.split_first() else {
+                    break;
+                };
+                let Some(value) = rest.get(..len as usize) else {
+                    break;
+                };
+                options.push((code, value));
+                buf = &rest[len as usize..];
+            }
+        }
+    }
+    options
+}
+
+/// Parse a DHCPOFFER answering `xid`. Any other datagram yields `None`.
+fn parse_offer(buf: &[u8], xid: u32, source: IpAddr, rtt: Duration) -> Option<DhcpOffer> {
+    if buf.len() < HEADER_LEN + MAGIC_COOKIE.len()
+        || buf[0] != BOOTREPLY
+        || buf[4..8] != xid.to_be_bytes()
+        || buf[HEADER_LEN..HEADER_LEN + 4] != MAGIC_COOKIE
+    {
+        return None;
+    }
+    let options = parse_options(&buf[HEADER_LEN + 4..]);
+    let option = |code: u8| {
+        options
+            .iter()
+            .find(|(c, _)| *c == code)
+            .map(|(_, value)| *value)
+    };
+    if option(OPT_MESSAGE_TYPE) != Some(&[DHCPOFFER][..]) {
+        return None;
+    }
+
+    let relay_ip = Some(ipv4_at(buf, 24)).filter(|ip| !ip.is_unspecified());
+    let server_ip = option(OPT_SERVER_ID)
+        .and_then(ipv4_value)
+        .or(match source {
+            IpAddr::V4(ip) => Some(ip),
+            IpAddr::V6(_) => None,
+        })
+        .unwrap_or(Ipv4Addr::UNSPECIFIED);
+    Some(DhcpOffer {
+        server_ip,
+        source_ip: source,
+        relay_ip,
+        offered_ip: ipv4_at(buf, 16),
+        subnet_mask: option(OPT_SUBNET_MASK).and_then(ipv4_value),
+        routers: option(OPT_ROUTER).map(ipv4_list).unwrap_or_default(),
+        dns_servers: option(OPT_DNS).map(ipv4_list).unwrap_or_default(),
+        domain_name: option(OPT_DOMAIN_NAME).map(|v| {
+            String::from_utf8_lossy(v)
+                .trim_end_matches('\0')
+                .to_string()
+        }),
+        lease_time_secs: option(OPT_LEASE_TIME).and_then(u32_value),
+        renewal_time_secs: option(OPT_RENEWAL_TIME).and_then(u32_value),
+        rebinding_time_secs: option(OPT_REBINDING_TIME).and_then(u32_value),
+        broadcast_addr: option(OPT_BROADCAST).and_then(ipv4_value),
+        ntp_servers: option(OPT_NTP).map(ipv4_list).unwrap_or_default(),
+        option_codes: options.iter().map(|(code, _)| *code).collect(),
+        rtt_ms: rtt.as_millis() as u64,
+    })
+}
+
+/// Broadcast a DHCPDISCOVER on `iface_name` and collect every DHCPOFFER
+/// received within `wait`. Nothing is requested, so no lease is taken.
+///
+/// Listening on the DHCP client port needs administrator privileges and
+/// fails while a local DHCP client holds the port exclusively. The socket
+/// is bound to the interface on Linux only; elsewhere the DISCOVER leaves
+/// through the interface of the broadcast route.
+pub async fn discover(iface_name: &str, wait: Duration) -> Result<DhcpDiscoveryReport> {
+    let iface = crate::net::interface::find_interface(iface_name)
+        .ok_or_else(|| ScanError::no_interface(format!("Interface not found: {}", iface_name)))?;
+    let Some(mac) = iface.mac_addr else {
+        bail!(ScanError::no_interface(format!(
+            "Interface {} has no hardware address",
+            iface_name
+        )));
+    };
+    let mac = crate::net::wol::parse_mac(&mac.to_string())?;
+
+    let mut config = UdpConfig::new()
+        .with_bind_addr(SocketAddr::from((Ipv4Addr::UNSPECIFIED, DHCP_CLIENT_PORT)))
+        .with_reuseaddr(true)
+        .with_broadcast(true);
+    if cfg!(any(target_os = "linux", target_os = "android")) {
+        config = config.with_bind_device(iface.name.clone());
+    }
+    let socket = AsyncUdpSocket::from_config(&config).with_context(|| {
+        format!(
+            "Failed to listen on UDP port {}. Administrator privileges are required, \
+             and no other DHCP client may hold the port.",
+            DHCP_CLIENT_PORT
+        )
+    })?;
+
+    let xid: u32 = rand::random();
+    let started = Instant::now();
+    socket
+        .send_to(
+            &build_discover(xid, &mac),
+            SocketAddr::from((Ipv4Addr::BROADCAST, DHCP_SERVER_PORT)),
+        )
+        .await
+        .with_context(|| format!("Failed to broadcast DHCPDISCOVER on {}", iface_name))?;
+
+    let mut offers: Vec<DhcpOffer> = Vec::new();
+    let mut buf = [0u8; 1500];
+    let deadline = tokio::time::Instant::now() + wait;
+    loop {
+        let received = tokio::time::timeout_at(deadline, socket.recv_from(&mut buf)).await;
+        let Ok(received) = received else {
+            break;
+        };
+        let (len, source) = received?;
+        if let Some(offer) = parse_offer(&buf[..len], xid, source.ip(), started.elapsed()) {
+            tracing::debug!("DHCPOFFER from {}: {}", offer.server_ip, offer.offered_ip);
+            // Servers may answer twice, e.g. directly and through a relay
+            if !offers
+                .iter()
+                .any(|o| o.server_ip == offer.server_ip && o.source_ip == offer.source_ip)
+            {
+                offers.push(offer);
+            }
+        }
+    }
+
+    let mut servers: Vec<Ipv4Addr> = offers.iter().map(|o| o.server_ip).collect();
+    servers.sort();
+    servers.dedup();
+    Ok(DhcpDiscoveryReport {
+        iface: iface.name.clone(),
+        mac_addr: crate::net::wol::format_mac(&mac),
+        xid,
+        multiple_servers: servers.len() > 1,
+        offers,
+        elapsed_ms: started.elapsed().as_millis() as u64,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builds_discover() {
+        let mac = [0x02, 0, 0, 0, 0, 0x01];
+        let packet = build_discover(0x1234_5678, &mac);
+        assert_eq!(packet.len(), MIN_PACKET_LEN);
+        assert_eq!(&packet[..4], &[BOOTREQUEST, HTYPE_ETHERNET, 6, 0]);
+        assert_eq!(&packet[4..8], &[0x12, 0x34, 0x56, 0x78]);
+        assert_eq!(&packet[28..34], &mac);
+        assert_eq!(
+            &packet[HEADER_LEN..HEADER_LEN + 7],
+            &[99, 130, 83, 99, OPT_MESSAGE_TYPE, 1, DHCPDISCOVER]
+        );
+    }
+
+    #[test]
+    fn parses_offer() {
+        let mut reply = vec![0u8; HEADER_LEN];
+        reply[0] = BOOTREPLY;
+        reply[4..8].copy_from_slice(&7u32.to_be_bytes());
+        reply[16..20].copy_from_slice(&[192, 168, 1, 50]);
+        reply.extend_from_slice(&MAGIC_COOKIE);
+        reply.extend_from_slice(&[OPT_MESSAGE_TYPE, 1, DHCPOFFER]);
+        reply.extend_from_slice(&[OPT_SERVER_ID, 4, 192, 168, 1, 1]);
+        reply.extend_from_slice(&[OPT_LEASE_TIME, 4, 0, 0, 0x0e, 0x10]);
+        reply.extend_from_slice(&[OPT_DNS, 8, 192, 168, 1, 1, 1, 1, 1, 1]);
+        reply.extend_from_slice(&[OPT_DOMAIN_NAME, 3, b'l', b'a', b'n']);
+        reply.push(OPT_END);
+
+        let source = IpAddr::from([192, 168, 1, 1]);
+        let offer = parse_offer(&reply, 7, source, Duration::from_millis(4)).unwrap();
+        assert_eq!(offer.server_ip, Ipv4Addr::new(192, 168, 1, 1));
+        assert_eq!(offer.offered_ip, Ipv4Addr::new(192, 168, 1, 50));
+        assert_eq!(offer.lease_time_secs, Some(3600));
+        assert_eq!(offer.dns_servers.len(), 2);
+        assert_eq!(offer.domain_name.as_deref(), Some("lan"));
+        assert_eq!(offer.relay_ip, None);
+        // Other transactions are ignored
+        assert!(parse_offer(&reply, 8, source, Duration::ZERO).is_none());
+    }
+}
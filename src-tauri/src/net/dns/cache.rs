@@ -0,0 +1,134 @@
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+/// Upper bound on how long an answer is kept, whatever its TTL
+const MAX_CACHE_TTL: Duration = Duration::from_secs(3600);
+/// How long a failed address lookup is remembered
+const NEGATIVE_TTL: Duration = Duration::from_secs(30);
+/// Entries kept before new answers are dropped instead of cached
+const MAX_ENTRIES: usize = 4096;
+
+/// Kind of query an answer was cached for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RecordType {
+    /// Address lookup (A / AAAA)
+    Ip,
+    /// Reverse lookup (PTR)
+    Ptr,
+}
+
+#[derive(Debug, Clone)]
+enum Answer {
+    Ips(Vec<IpAddr>),
+    Name(String),
+    /// The lookup failed or returned no records
+    NotFound,
+}
+
+/// Result of a cache lookup
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Cached<T> {
+    Found(T),
+    /// A recent lookup of the name failed
+    NotFound,
+}
+
+#[derive(Debug)]
+struct Entry {
+    answer: Answer,
+    expires: Instant,
+}
+
+type CacheMap = HashMap<(String, RecordType), Entry>;
+
+static DNS_CACHE: OnceLock<Mutex<CacheMap>> = OnceLock::new();
+
+fn cache() -> &'static Mutex<CacheMap> {
+    DNS_CACHE.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Names are case-insensitive and may or may not end with the root dot.
+fn key(name: &str, rtype: RecordType) -> (String, RecordType) {
+    (name.trim_end_matches('.').to_ascii_lowercase(), rtype)
+}
+
+fn get(name: &str, rtype: RecordType) -> Option<Answer> {
+    let mut map = cache().lock().expect("DNS_CACHE poisoned");
+    let k = key(name, rtype);
+    match map.get(&k) {
+        Some(entry) if entry.expires > Instant::now() => Some(entry.answer.clone()),
+        Some(_) => {
+            map.remove(&k);
+            None
+        }
+        None => None,
+    }
+}
+
+/// Store an answer until `valid_until`, the expiry derived from the record TTLs.
+fn put(name: &str, rtype: RecordType, answer: Answer, valid_until: Instant) {
+    let now = Instant::now();
+    let expires = valid_until.min(now + MAX_CACHE_TTL);
+    if expires <= now {
+        return;
+    }
+    let mut map = cache().lock().expect("DNS_CACHE poisoned");
+    if map.len() >= MAX_ENTRIES {
+        map.retain(|_, e| e.expires > now);
+        if map.len() >= MAX_ENTRIES {
+            return;
+        }
+    }
+    map.insert(key(name, rtype), Entry { answer, expires });
+}
+
+/// Cached addresses of `name`, if still valid.
+pub fn get_ips(name: &str) -> Option<Cached<Vec<IpAddr>>> {
+    match get(name, RecordType::Ip)? {
+        Answer::Ips(ips) => Some(Cached::Found(ips)),
+        Answer::NotFound => Some(Cached::NotFound),
+        Answer::Name(_) => None,
+    }
+}
+
+pub fn put_ips(name: &str, ips: Vec<IpAddr>, valid_until: Instant) {
+    put(name, RecordType::Ip, Answer::Ips(ips), valid_until);
+}
+
+/// Remember a failed address lookup briefly, so a list of dead names
+/// does not wait out the timeout on every scan.
+pub fn put_ips_not_found(name: &str) {
+    put(
+        name,
+        RecordType::Ip,
+        Answer::NotFound,
+        Instant::now() + NEGATIVE_TTL,
+    );
+}
+
+/// Cached reverse lookup of `ip`, if still valid.
+pub fn get_ptr(ip: IpAddr) -> Option<String> {
+    match get(&ip.to_string(), RecordType::Ptr)? {
+        Answer::Name(name) => Some(name),
+        Answer::Ips(_) | Answer::NotFound => None,
+    }
+}
+
+pub fn put_ptr(ip: IpAddr, name: String, valid_until: Instant) {
+    put(
+        &ip.to_string(),
+        RecordType::Ptr,
+        Answer::Name(name),
+        valid_until,
+    );
+}
+
+/// Drop every cached answer. Returns the number of entries removed.
+pub fn clear() -> usize {
+    let mut map = cache().lock().expect("DNS_CACHE poisoned");
+    let n = map.len();
+    map.clear();
+    n
+}
@@ -0,0 +1,125 @@
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::path::PathBuf;
+use std::sync::{Mutex, OnceLock};
+use std::time::SystemTime;
+
+/// Names of the hosts file keyed by lowercase name, with the file's mtime
+struct HostsFile {
+    modified: Option<SystemTime>,
+    names: HashMap<String, Vec<IpAddr>>,
+}
+
+static HOSTS_FILE: OnceLock<Mutex<Option<HostsFile>>> = OnceLock::new();
+
+/// Location of the OS hosts file.
+pub fn hosts_file_path() -> PathBuf {
+    #[cfg(windows)]
+    {
+        let root = std::env::var_os("SystemRoot").unwrap_or_else(|| "C:\\Windows".into());
+        PathBuf::from(root)
+            .join("System32")
+            .join("drivers")
+            .join("etc")
+            .join("hosts")
+    }
+    #[cfg(not(windows))]
+    {
+        PathBuf::from("/etc/hosts")
+    }
+}
+
+fn normalize(name: &str) -> String {
+    name.trim_end_matches('.').to_ascii_lowercase()
+}
+
+/// Parse hosts file content: `IP name [aliases...]` per line, `#` starts a comment.
+/// Addresses keep the file order; a name listed twice gets both addresses.
+fn parse(content: &str) -> HashMap<String, Vec<IpAddr>> {
+    let mut names: HashMap<String, Vec<IpAddr>> = HashMap::new();
+    for line in content.lines() {
+        let line = line.split('#').next().unwrap_or("");
+        let mut fields = line.split_whitespace();
+        let Some(addr) = fields.next() else {
+            continue;
+        };
+        // Drop the zone of link-local IPv6 entries (fe80::1%lo0)
+        let Ok(ip) = addr.split('%').next().unwrap_or(addr).parse::<IpAddr>() else {
+            continue;
+        };
+        for name in fields {
+            let ips = names.entry(normalize(name)).or_default();
+            if !ips.contains(&ip) {
+                ips.push(ip);
+            }
+        }
+    }
+    names
+}
+
+/// Addresses of `name` in the OS hosts file.
+/// The file is re-read when it changes; a missing file yields `None`.
+pub fn lookup(name: &str) -> Option<Vec<IpAddr>> {
+    let path = hosts_file_path();
+    let modified = std::fs::metadata(&path).and_then(|m| m.modified()).ok();
+    let mut cached = HOSTS_FILE
+        .get_or_init(|| Mutex::new(None))
+        .lock()
+        .expect("HOSTS_FILE poisoned");
+    let stale = match cached.as_ref() {
+        Some(file) => modified.is_none() || file.modified != modified,
+        None => true,
+    };
+    if stale {
+        *cached = std::fs::read_to_string(&path)
+            .map(|content| HostsFile {
+                modified,
+                names: parse(&content),
+            })
+            .map_err(|e| tracing::debug!("cannot read {}: {}", path.display(), e))
+            .ok();
+    }
+    cached
+        .as_ref()?
+        .names
+        .get(&normalize(name))
+        .filter(|ips| !ips.is_empty())
+        .cloned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_names_aliases_and_comments() {
+        let names = parse(
+            "# comment line\n\
+             127.0.0.1\tlocalhost  Localhost.localdomain\n\
+             ::1 localhost ip6-localhost # loopback\n\
+             fe80::1%lo0 linklocal\n\
+             10.0.0.5 nas\n\
+             10.0.0.5 nas.lan NAS\n\
+             not-an-ip ignored\n\
+             192.0.2.9\n",
+        );
+        let ips = |name: &str| names.get(name).cloned().unwrap_or_default();
+        assert_eq!(
+            ips("localhost"),
+            [
+                "127.0.0.1".parse::<IpAddr>().unwrap(),
+                "::1".parse().unwrap()
+            ]
+        );
+        assert_eq!(ips("localhost.localdomain").len(), 1);
+        assert_eq!(ips("linklocal"), ["fe80::1".parse::<IpAddr>().unwrap()]);
+        // Listed twice with the same address: one entry
+        assert_eq!(ips("nas"), ["10.0.0.5".parse::<IpAddr>().unwrap()]);
+        assert!(!names.contains_key("ignored") && !names.contains_key("loopback"));
+    }
+
+    #[test]
+    fn names_are_case_and_root_dot_insensitive() {
+        assert_eq!(normalize("NAS.lan."), "nas.lan");
+    }
+}
@@ -0,0 +1,26 @@
+use crate::error::ScanError;
+use anyhow::Result;
+
+/// Convert a host name to its ASCII (punycode) form for the resolver.
+/// ASCII names are passed through unchanged, so names the IDNA rules reject
+/// but DNS accepts (e.g. with underscores) still resolve.
+pub fn to_ascii(name: &str) -> Result<String> {
+    if name.is_ascii() {
+        return Ok(name.to_string());
+    }
+    idna::domain_to_ascii(name).map_err(|_| {
+        ScanError::invalid_input(format!("Invalid internationalized domain name: {}", name)).into()
+    })
+}
+
+/// Decode punycode labels of a host name for display.
+/// Returns the name unchanged if it has none or fails to decode.
+pub fn to_unicode(name: &str) -> String {
+    if !name.to_ascii_lowercase().contains("xn--") {
+        return name.to_string();
+    }
+    match idna::domain_to_unicode(name) {
+        (unicode, Ok(())) => unicode,
+        (_, Err(_)) => name.to_string(),
+    }
+}
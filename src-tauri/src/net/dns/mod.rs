@@ -1,57 +1,286 @@
+pub mod cache;
+pub mod hosts;
+pub mod idn;
+pub mod race;
 pub mod resolver;
 use anyhow::Result;
 use futures::{stream, StreamExt};
-use std::{collections::HashSet, net::IpAddr, time::Duration};
+use hickory_resolver::proto::{op::ResponseCode, ProtoErrorKind};
+use hickory_resolver::ResolveError;
+use std::{
+    collections::{HashMap, HashSet},
+    net::IpAddr,
+    time::{Duration, Instant},
+};
 
-use crate::model::{dns::Domain, endpoint::Host};
+use crate::error::ScanError;
+use crate::model::{
+    dns::{Domain, ResolveStrategy, UnresolvedName},
+    endpoint::Host,
+};
+
+/// Queries per name while failures are transient (timeout, SERVFAIL)
+pub const RESOLVE_ATTEMPTS: u32 = 3;
+/// Wait before the first retry, doubled for each further one
+const RESOLVE_BACKOFF: Duration = Duration::from_millis(200);
+
+/// Why a name did not resolve
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LookupFailure {
+    /// NXDOMAIN or no address records; asking again will not help
+    NotFound,
+    /// Timeout, SERVFAIL, refused or network error
+    Transient(String),
+}
+
+impl LookupFailure {
+    fn from_error(e: &ResolveError) -> Self {
+        let kind = e.proto().map(|p| p.kind());
+        match kind {
+            Some(ProtoErrorKind::NoRecordsFound {
+                response_code: ResponseCode::NXDomain | ResponseCode::NoError,
+                ..
+            }) => LookupFailure::NotFound,
+            _ => LookupFailure::Transient(e.to_string()),
+        }
+    }
+}
+
+impl std::fmt::Display for LookupFailure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LookupFailure::NotFound => write!(f, "name not found"),
+            LookupFailure::Transient(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for LookupFailure {}
 
 /// Lookup a host by name or IP address string.
 pub async fn lookup_host(host: &str, timeout: Duration) -> Result<Host> {
     if let Ok(ip) = host.parse::<IpAddr>() {
         // Reverse lookup for IP address
-        let hostname = reverse_lookup(ip, timeout)
+        let ascii = reverse_lookup_ascii(ip, timeout)
             .await
             .unwrap_or_else(|| ip.to_string());
         Ok(Host {
-            hostname: Some(hostname),
+            hostname: Some(idn::to_unicode(&ascii)),
+            ascii_hostname: Some(ascii),
             ip: ip,
+            fcrdns: None,
         })
     } else {
         // Resolve hostname to IP address
-        let ips = lookup_ip(host, timeout).await.unwrap_or_default();
+        let ascii = idn::to_ascii(host)?;
+        let ips = lookup_ip(&ascii, timeout).await.unwrap_or_default();
         match ips.first() {
             Some(ip) => Ok(Host {
-                hostname: Some(host.to_string()),
+                hostname: Some(idn::to_unicode(&ascii)),
+                ascii_hostname: Some(ascii),
                 ip: *ip,
+                fcrdns: None,
             }),
-            None => Err(anyhow::anyhow!("failed to resolve host")),
+            None => Err(ScanError::resolution_failed(format!("failed to resolve {}", host)).into()),
         }
     }
 }
 
 /// Lookup a domain and return its associated IP addresses.
 pub async fn lookup_domain(hostname: &str, timeout: Duration) -> Domain {
-    let ips = lookup_ip(hostname, timeout).await.unwrap_or_default();
+    let ascii = idn::to_ascii(hostname).unwrap_or_else(|_| hostname.to_string());
+    let ips = lookup_ip(&ascii, timeout).await.unwrap_or_default();
     Domain {
-        name: hostname.to_string(),
+        name: idn::to_unicode(&ascii),
+        ascii_name: ascii,
         ips,
     }
 }
 
 /// Perform a DNS lookup for the given hostname with a timeout.
+/// Internationalized names are converted to punycode first.
+/// Answers are cached for their TTL, failures for a short while.
+/// Names in the OS hosts file take precedence over DNS, whatever the resolver config.
 pub async fn lookup_ip(hostname: &str, timeout: Duration) -> Option<Vec<IpAddr>> {
-    let resolver = resolver::get_resolver().ok()?;
-    match tokio::time::timeout(timeout, async move { resolver.lookup_ip(hostname).await }).await {
-        Ok(Ok(ips)) => Some(ips.iter().collect()),
-        _ => None,
+    lookup_ip_retry(hostname, timeout, 1).await.ok()
+}
+
+/// Like `lookup_ip`, but makes up to `attempts` queries with exponential
+/// backoff while the failure is transient (timeout, SERVFAIL). A name that
+/// does not exist fails at once.
+pub async fn lookup_ip_retry(
+    hostname: &str,
+    timeout: Duration,
+    attempts: u32,
+) -> Result<Vec<IpAddr>, LookupFailure> {
+    let hostname = idn::to_ascii(hostname).map_err(|e| LookupFailure::Transient(e.to_string()))?;
+    if let Some(ips) = hosts::lookup(&hostname) {
+        return Ok(ips);
+    }
+    match cache::get_ips(&hostname) {
+        Some(cache::Cached::Found(ips)) => return Ok(ips),
+        // Failed recently; retrying now would likely fail again
+        Some(cache::Cached::NotFound) => return Err(LookupFailure::NotFound),
+        None => {}
+    }
+    let mut backoff = RESOLVE_BACKOFF;
+    let mut attempt = 1;
+    loop {
+        match query_ip(&hostname, timeout).await {
+            Ok((ips, valid_until)) => {
+                cache::put_ips(&hostname, ips.clone(), valid_until);
+                return Ok(ips);
+            }
+            Err(LookupFailure::Transient(e)) if attempt < attempts => {
+                tracing::debug!(
+                    "lookup of {} failed ({}), retrying in {:?}",
+                    hostname,
+                    e,
+                    backoff
+                );
+                tokio::time::sleep(backoff).await;
+                backoff *= 2;
+                attempt += 1;
+            }
+            Err(failure) => {
+                // Only a definite answer is cached; a transient failure may
+                // hide a name that exists
+                if failure == LookupFailure::NotFound {
+                    cache::put_ips_not_found(&hostname);
+                }
+                return Err(failure);
+            }
+        }
+    }
+}
+
+/// One uncached query through the system resolver.
+async fn query_ip(
+    hostname: &str,
+    timeout: Duration,
+) -> Result<(Vec<IpAddr>, Instant), LookupFailure> {
+    let resolver = resolver::get_resolver().map_err(|e| LookupFailure::Transient(e.to_string()))?;
+    let name = hostname.to_string();
+    match tokio::time::timeout(timeout, async move { resolver.lookup_ip(name).await }).await {
+        Ok(Ok(lookup)) => {
+            let ips: Vec<IpAddr> = lookup.iter().collect();
+            if ips.is_empty() {
+                return Err(LookupFailure::NotFound);
+            }
+            Ok((ips, lookup.valid_until()))
+        }
+        Ok(Err(e)) => Err(LookupFailure::from_error(&e)),
+        Err(_) => Err(LookupFailure::Transient(format!(
+            "timed out after {} ms",
+            timeout.as_millis()
+        ))),
+    }
+}
+
+/// Race lookup through the shared cache.
+/// Not retried: every nameserver is already asked at once.
+async fn lookup_ip_race(
+    race: &race::RaceResolver,
+    hostname: &str,
+    timeout: Duration,
+    verify: bool,
+) -> Result<Vec<IpAddr>, LookupFailure> {
+    let hostname = idn::to_ascii(hostname).map_err(|e| LookupFailure::Transient(e.to_string()))?;
+    if let Some(ips) = hosts::lookup(&hostname) {
+        return Ok(ips);
+    }
+    // A verified race must ask every server, so skip cached answers
+    if !verify {
+        match cache::get_ips(&hostname) {
+            Some(cache::Cached::Found(ips)) => return Ok(ips),
+            Some(cache::Cached::NotFound) => return Err(LookupFailure::NotFound),
+            None => {}
+        }
+    }
+    match race.lookup_ip(&hostname, timeout, verify).await {
+        Ok((answer, valid_until)) => {
+            tracing::debug!(
+                "resolved {} via {} in {} ms",
+                hostname,
+                answer.resolver,
+                answer.rtt_ms
+            );
+            cache::put_ips(&hostname, answer.ips.clone(), valid_until);
+            Ok(answer.ips)
+        }
+        Err(e) => {
+            tracing::debug!("race lookup of {} failed: {}", hostname, e);
+            match e.downcast::<LookupFailure>() {
+                Ok(LookupFailure::NotFound) => {
+                    cache::put_ips_not_found(&hostname);
+                    Err(LookupFailure::NotFound)
+                }
+                Ok(failure) => Err(failure),
+                Err(e) => Err(LookupFailure::Transient(e.to_string())),
+            }
+        }
     }
 }
 
 /// Perform a reverse DNS lookup for the given IP address with a timeout.
+/// Punycode labels are decoded for display.
 pub async fn reverse_lookup(ip: IpAddr, timeout: Duration) -> Option<String> {
+    reverse_lookup_ascii(ip, timeout)
+        .await
+        .map(|name| idn::to_unicode(&name))
+}
+
+/// Reverse lookup of many addresses with bounded concurrency.
+/// Duplicates are looked up once; addresses without a PTR record map to `None`.
+pub async fn reverse_lookup_bulk(
+    ips: &[IpAddr],
+    timeout: Duration,
+    concurrency: usize,
+) -> HashMap<IpAddr, Option<String>> {
+    let unique: HashSet<IpAddr> = ips.iter().copied().collect();
+    stream::iter(unique)
+        .map(|ip| async move { (ip, reverse_lookup(ip, timeout).await) })
+        .buffer_unordered(concurrency.max(1))
+        .collect()
+        .await
+}
+
+/// Forward-confirmed reverse DNS check of `ip`: look up its PTR name, resolve
+/// that name and pass if the answer contains `ip`. An address without a PTR
+/// record fails the check.
+pub async fn verify_fcrdns(ip: IpAddr, timeout: Duration) -> Host {
+    let Some(ascii) = reverse_lookup_ascii(ip, timeout).await else {
+        return Host {
+            fcrdns: Some(false),
+            ..Host::new(ip)
+        };
+    };
+    let confirmed = lookup_ip(&ascii, timeout)
+        .await
+        .is_some_and(|ips| ips.contains(&ip));
+    if !confirmed {
+        tracing::debug!("FCrDNS failed for {} ({})", ip, ascii);
+    }
+    Host {
+        ip,
+        hostname: Some(idn::to_unicode(&ascii)),
+        ascii_hostname: Some(ascii),
+        fcrdns: Some(confirmed),
+    }
+}
+
+/// Reverse lookup returning the name as received (ASCII / punycode).
+async fn reverse_lookup_ascii(ip: IpAddr, timeout: Duration) -> Option<String> {
+    if let Some(name) = cache::get_ptr(ip) {
+        return Some(name);
+    }
     let resolver = resolver::get_resolver().ok()?;
     match tokio::time::timeout(timeout, async move { resolver.reverse_lookup(ip).await }).await {
-        Ok(Ok(names)) => names.iter().next().map(|n| n.to_string()),
+        Ok(Ok(names)) => {
+            let name = names.iter().next().map(|n| n.to_string())?;
+            cache::put_ptr(ip, name.clone(), names.as_lookup().valid_until());
+            Some(name)
+        }
         _ => None,
     }
 }
@@ -62,8 +291,30 @@ pub async fn reverse_lookup(ip: IpAddr, timeout: Duration) -> Option<String> {
 /// - Hostnames may resolve to multiple IPs; all are returned.
 /// - Duplicate IPs are removed while preserving input order as much as possible.
 /// - Resolution runs concurrently with a bounded concurrency limit.
-pub async fn resolve_hosts(inputs: &[String], timeout: Duration, concurrency: usize) -> Vec<Host> {
+/// - With a race strategy each name is sent to all nameservers at once.
+/// - Transient failures are retried with backoff; names that still fail
+///   are returned alongside the hosts.
+pub async fn resolve_hosts(
+    inputs: &[String],
+    timeout: Duration,
+    concurrency: usize,
+    strategy: ResolveStrategy,
+) -> (Vec<Host>, Vec<UnresolvedName>) {
     let concurrency = concurrency.max(1);
+    let race = match strategy {
+        ResolveStrategy::System => None,
+        ResolveStrategy::Race | ResolveStrategy::RaceVerified => {
+            match race::RaceResolver::from_system() {
+                Ok(r) => Some(r),
+                Err(e) => {
+                    tracing::warn!("resolver race unavailable, using system resolver: {}", e);
+                    None
+                }
+            }
+        }
+    };
+    let verify = strategy == ResolveStrategy::RaceVerified;
+    let race = race.as_ref();
 
     let mut out: Vec<Host> = Vec::new();
     let mut seen: HashSet<IpAddr> = HashSet::new();
@@ -78,7 +329,12 @@ pub async fn resolve_hosts(inputs: &[String], timeout: Duration, concurrency: us
         if let Ok(ip) = t.parse::<IpAddr>() {
             if seen.insert(ip) {
                 // Keep hostname None here (reverse lookup can be expensive and noisy...)
-                out.push(Host { ip, hostname: None });
+                out.push(Host {
+                    ip,
+                    hostname: None,
+                    ascii_hostname: None,
+                    fcrdns: None,
+                });
             }
         } else {
             hostnames.push(t.to_string());
@@ -88,21 +344,39 @@ pub async fn resolve_hosts(inputs: &[String], timeout: Duration, concurrency: us
     // Resolve hostnames concurrently
     let mut st = stream::iter(hostnames.into_iter())
         .map(|hn| async move {
-            let ips = lookup_ip(&hn, timeout).await.unwrap_or_default();
+            let ips = match race {
+                Some(r) => lookup_ip_race(r, &hn, timeout, verify).await,
+                None => lookup_ip_retry(&hn, timeout, RESOLVE_ATTEMPTS).await,
+            };
             (hn, ips)
         })
         .buffer_unordered(concurrency);
 
+    let mut unresolved: Vec<UnresolvedName> = Vec::new();
     while let Some((hn, ips)) = st.next().await {
+        let ips = match ips {
+            Ok(ips) => ips,
+            Err(failure) => {
+                tracing::warn!("could not resolve {}: {}", hn, failure);
+                unresolved.push(UnresolvedName {
+                    not_found: failure == LookupFailure::NotFound,
+                    error: failure.to_string(),
+                    name: hn,
+                });
+                continue;
+            }
+        };
         for ip in ips {
             if seen.insert(ip) {
                 out.push(Host {
                     ip,
-                    hostname: Some(hn.clone()),
+                    hostname: Some(idn::to_unicode(&hn)),
+                    ascii_hostname: idn::to_ascii(&hn).ok(),
+                    fcrdns: None,
                 });
             }
         }
     }
 
-    out
+    (out, unresolved)
 }
@@ -0,0 +1,183 @@
+use anyhow::{bail, Result};
+use futures::stream::{FuturesUnordered, StreamExt};
+use hickory_resolver::{
+    config::{NameServerConfigGroup, ResolverConfig},
+    name_server::TokioConnectionProvider,
+    TokioResolver,
+};
+use std::collections::BTreeSet;
+use std::net::{IpAddr, SocketAddr};
+use std::time::{Duration, Instant};
+
+use super::LookupFailure;
+use crate::model::dns::RaceLookup;
+
+/// One resolver per configured nameserver, queried in parallel.
+pub struct RaceResolver {
+    resolvers: Vec<(SocketAddr, TokioResolver)>,
+}
+
+impl RaceResolver {
+    /// Build from the system nameservers. UDP and TCP entries of the same
+    /// server are merged, so each server is asked once.
+    pub fn from_system() -> Result<Self> {
+        let system = super::resolver::get_resolver()?;
+        let mut servers: Vec<SocketAddr> = Vec::new();
+        for ns in system.config().name_servers() {
+            if !servers.contains(&ns.socket_addr) {
+                servers.push(ns.socket_addr);
+            }
+        }
+        if servers.is_empty() {
+            bail!("No nameservers configured");
+        }
+        let resolvers = servers
+            .into_iter()
+            .map(|server| {
+                // Keep the search list so single-label names resolve as before
+                let config = ResolverConfig::from_parts(
+                    system.config().domain().cloned(),
+                    system.config().search().to_vec(),
+                    NameServerConfigGroup::from_ips_clear(&[server.ip()], server.port(), true),
+                );
+                let mut builder =
+                    TokioResolver::builder_with_config(config, TokioConnectionProvider::default());
+                *builder.options_mut() = system.options().clone();
+                // Each resolver talks to a single server; no retries on others
+                builder.options_mut().attempts = 1;
+                (server, builder.build())
+            })
+            .collect();
+        Ok(RaceResolver { resolvers })
+    }
+
+    /// Query every nameserver and return the first successful answer,
+    /// with the time it stays valid for caching.
+    /// With `check_consistency`, waits for all servers (up to `timeout`)
+    /// and reports whether their address sets agree.
+    /// If no server answers, the error is a `LookupFailure`: `NotFound`
+    /// only when every server said the name does not exist.
+    pub async fn lookup_ip(
+        &self,
+        hostname: &str,
+        timeout: Duration,
+        check_consistency: bool,
+    ) -> Result<(RaceLookup, Instant)> {
+        let start = Instant::now();
+        let mut queries: FuturesUnordered<_> = self
+            .resolvers
+            .iter()
+            .map(|(server, resolver)| async move {
+                let result = match tokio::time::timeout(timeout, resolver.lookup_ip(hostname)).await
+                {
+                    Ok(Ok(lookup)) => {
+                        let ips: Vec<IpAddr> = lookup.iter().collect();
+                        if ips.is_empty() {
+                            Err(LookupFailure::NotFound)
+                        } else {
+                            Ok((ips, start.elapsed(), lookup.valid_until()))
+                        }
+                    }
+                    Ok(Err(e)) => Err(LookupFailure::from_error(&e)),
+                    Err(_) => Err(LookupFailure::Transient("timed out".to_string())),
+                };
+                (*server, result)
+            })
+            .collect();
+
+        let mut answers = Vec::new();
+        let mut failures = Vec::new();
+        while let Some((server, result)) = queries.next().await {
+            match result {
+                Ok((ips, elapsed, valid_until)) if !check_consistency => {
+                    let answer = RaceLookup {
+                        name: hostname.to_string(),
+                        ips,
+                        resolver: server,
+                        rtt_ms: elapsed.as_millis() as u64,
+                        consistent: None,
+                    };
+                    return Ok((answer, valid_until));
+                }
+                Ok((ips, elapsed, valid_until)) => {
+                    answers.push((server, ips, elapsed, valid_until))
+                }
+                Err(failure) => failures.push((server, failure)),
+            }
+        }
+
+        let Some((server, ips, elapsed, valid_until)) =
+            answers.iter().min_by_key(|(_, _, t, _)| *t).cloned()
+        else {
+            return Err(all_failed(hostname, failures).into());
+        };
+        let first: BTreeSet<IpAddr> = ips.iter().copied().collect();
+        let consistent = answers
+            .iter()
+            .all(|(_, other, _, _)| other.iter().copied().collect::<BTreeSet<_>>() == first);
+        if !consistent {
+            tracing::warn!("nameservers disagree on {}", hostname);
+        }
+        let answer = RaceLookup {
+            name: hostname.to_string(),
+            ips,
+            resolver: server,
+            rtt_ms: elapsed.as_millis() as u64,
+            consistent: Some(consistent),
+        };
+        Ok((answer, valid_until))
+    }
+}
+
+/// Failure of a name no nameserver answered. Only a unanimous NXDOMAIN or
+/// empty answer is `NotFound`; a timeout or SERVFAIL of any server may hide
+/// a name that exists.
+fn all_failed(hostname: &str, failures: Vec<(SocketAddr, LookupFailure)>) -> LookupFailure {
+    if !failures.is_empty()
+        && failures
+            .iter()
+            .all(|(_, failure)| *failure == LookupFailure::NotFound)
+    {
+        return LookupFailure::NotFound;
+    }
+    let reasons: Vec<String> = failures
+        .iter()
+        .map(|(server, failure)| format!("{}: {}", server, failure))
+        .collect();
+    LookupFailure::Transient(format!(
+        "No nameserver answered for {}: {}",
+        hostname,
+        reasons.join(", ")
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn server(last: u8) -> SocketAddr {
+        SocketAddr::from(([192, 0, 2, last], 53))
+    }
+
+    #[test]
+    fn not_found_only_when_every_server_says_so() {
+        let nx = vec![
+            (server(1), LookupFailure::NotFound),
+            (server(2), LookupFailure::NotFound),
+        ];
+        assert_eq!(all_failed("a.test", nx), LookupFailure::NotFound);
+
+        let mixed = vec![
+            (server(1), LookupFailure::NotFound),
+            (server(2), LookupFailure::Transient("timed out".into())),
+        ];
+        assert!(matches!(
+            all_failed("a.test", mixed),
+            LookupFailure::Transient(_)
+        ));
+        assert!(matches!(
+            all_failed("a.test", Vec::new()),
+            LookupFailure::Transient(_)
+        ));
+    }
+}
@@ -1,15 +1,19 @@
 use anyhow::Result;
 use hickory_resolver::{
+    config::{NameServerConfigGroup, ResolverConfig},
+    name_server::TokioConnectionProvider,
     proto::rr::rdata::{CERT, MX, NS, SOA, SRV, TLSA, TXT},
     TokioResolver,
 };
 use std::{
-    net::{Ipv4Addr, Ipv6Addr},
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
     sync::Arc,
+    time::{Duration, Instant},
 };
 
 use crate::model::dns::{
-    CertRecord, DomainLookupInfo, MxRecord, SoaRecord, SrvRecord, TlsaRecord, TxtRecord,
+    CertRecord, DnsConfig, DnsServer, DnsServerTest, DomainLookupInfo, MxRecord, SoaRecord,
+    SrvRecord, TlsaRecord, TxtRecord,
 };
 
 pub fn get_resolver() -> Result<TokioResolver> {
@@ -23,6 +27,73 @@ pub fn get_resolver() -> Result<TokioResolver> {
     }
 }
 
+/// Nameservers and options the system resolver uses.
+pub fn dns_config() -> Result<DnsConfig> {
+    let resolver = get_resolver()?;
+    let config = resolver.config();
+    let opts = resolver.options();
+    Ok(DnsConfig {
+        nameservers: config
+            .name_servers()
+            .iter()
+            .map(|ns| DnsServer {
+                addr: ns.socket_addr,
+                protocol: ns.protocol.to_string(),
+            })
+            .collect(),
+        domain: config.domain().map(|d| d.to_utf8()),
+        search: config.search().iter().map(|n| n.to_utf8()).collect(),
+        timeout_ms: opts.timeout.as_millis() as u64,
+        attempts: opts.attempts,
+    })
+}
+
+/// Send `query` to one nameserver `count` times, uncached, and time the answers.
+pub async fn test_dns_server(
+    server: SocketAddr,
+    query: &str,
+    count: u32,
+    timeout: Duration,
+) -> DnsServerTest {
+    let config = ResolverConfig::from_parts(
+        None,
+        vec![],
+        NameServerConfigGroup::from_ips_clear(&[server.ip()], server.port(), true),
+    );
+    let mut builder =
+        TokioResolver::builder_with_config(config, TokioConnectionProvider::default());
+    let opts = builder.options_mut();
+    opts.cache_size = 0;
+    opts.attempts = 1;
+    opts.timeout = timeout;
+    let resolver = builder.build();
+
+    let fqdn = DnsResolver::fqdn(query);
+    let mut result = DnsServerTest {
+        server,
+        query: query.to_string(),
+        ips: Vec::new(),
+        rtts_ms: Vec::new(),
+        avg_rtt_ms: None,
+        attempts: count,
+        error: None,
+    };
+    for _ in 0..count {
+        let start = Instant::now();
+        match resolver.lookup_ip(fqdn.as_str()).await {
+            Ok(lookup) => {
+                result.rtts_ms.push(start.elapsed().as_millis() as u64);
+                result.ips = lookup.iter().collect::<Vec<IpAddr>>();
+            }
+            Err(e) => result.error = Some(e.to_string()),
+        }
+    }
+    if !result.rtts_ms.is_empty() {
+        result.avg_rtt_ms = Some(result.rtts_ms.iter().sum::<u64>() / result.rtts_ms.len() as u64);
+    }
+    result
+}
+
 #[derive(Clone)]
 pub struct DnsResolver {
     inner: Arc<TokioResolver>,
@@ -195,6 +266,8 @@ impl DnsResolver {
     }
 
     pub async fn lookup_all(&self, name: &str) -> Result<DomainLookupInfo> {
+        let ascii = super::idn::to_ascii(name)?;
+        let name = ascii.as_str();
         let (a, aaaa, mx, ns, soa, srv, tlsa, txt, cert) = tokio::join!(
             self.a(name),
             self.aaaa(name),
@@ -208,7 +281,8 @@ impl DnsResolver {
         );
 
         Ok(DomainLookupInfo {
-            name: name.to_string(),
+            name: super::idn::to_unicode(name),
+            ascii_name: ascii.clone(),
             a,
             aaaa,
             mx,
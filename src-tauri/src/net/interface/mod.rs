@@ -1,9 +1,52 @@
+use netdev::ipnet::IpNet;
 use netdev::Interface;
 
+use crate::model::interface::LocalSubnet;
+
 pub fn list_interfaces() -> Vec<Interface> {
     netdev::get_interfaces()
 }
 
+/// Find an interface by name (or friendly name on Windows/macOS).
+pub fn find_interface(name: &str) -> Option<Interface> {
+    netdev::get_interfaces()
+        .into_iter()
+        .find(|i| i.name == name || i.friendly_name.as_deref() == Some(name))
+}
+
+/// Enumerate all IPv4/IPv6 networks the interface participates in.
+pub fn local_subnets(iface: &Interface) -> Vec<LocalSubnet> {
+    let v4 = iface.ipv4.iter().map(|net| IpNet::V4(*net));
+    let v6 = iface.ipv6.iter().map(|net| IpNet::V6(*net));
+    let mut subnets: Vec<LocalSubnet> = Vec::new();
+    for ipnet in v4.chain(v6) {
+        let network = ipnet.trunc();
+        if subnets.iter().any(|s| s.network == network) {
+            continue;
+        }
+        subnets.push(LocalSubnet {
+            iface_name: iface.name.clone(),
+            addr: ipnet.addr(),
+            network,
+            host_count: host_count(&network),
+        });
+    }
+    subnets
+}
+
+fn host_count(net: &IpNet) -> Option<u64> {
+    let host_bits = (net.max_prefix_len() - net.prefix_len()) as u32;
+    if host_bits >= 64 {
+        return None;
+    }
+    let size = 1u64 << host_bits;
+    match net {
+        // Exclude network and broadcast addresses, except for /31 and /32
+        IpNet::V4(_) if host_bits >= 2 => Some(size - 2),
+        _ => Some(size),
+    }
+}
+
 pub fn get_display_name(iface: &Interface) -> String {
     // On Windows, use the friendly name if available
     #[cfg(target_os = "windows")]
@@ -1,6 +1,8 @@
+pub mod dhcp;
 pub mod dns;
 pub mod interface;
 pub mod internet;
 pub mod neigh;
 pub mod route;
 pub mod sys;
+pub mod wol;
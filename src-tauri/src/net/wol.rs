@@ -0,0 +1,123 @@
+use crate::error::ScanError;
+use anyhow::{bail, Context, Result};
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use tokio::net::UdpSocket;
+
+/// Port conventionally used for Wake-on-LAN (discard)
+pub const DEFAULT_WOL_PORT: u16 = 9;
+pub const DEFAULT_WOL_BROADCAST: Ipv4Addr = Ipv4Addr::BROADCAST;
+const MAGIC_PACKET_LEN: usize = 6 + 16 * 6;
+
+/// Parse a MAC address written as `aa:bb:cc:dd:ee:ff`, `aa-bb-cc-dd-ee-ff` or `aabbccddeeff`.
+pub fn parse_mac(s: &str) -> Result<[u8; 6]> {
+    let s = s.trim();
+    let hex: String = if s.contains(':') || s.contains('-') {
+        let parts: Vec<&str> = s.split(|c| c == ':' || c == '-').collect();
+        if parts.len() != 6 || parts.iter().any(|p| p.len() != 2) {
+            bail!(ScanError::invalid_input(format!(
+                "Invalid MAC address: {}",
+                s
+            )));
+        }
+        parts.concat()
+    } else {
+        s.to_string()
+    };
+    if hex.len() != 12 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        bail!(ScanError::invalid_input(format!(
+            "Invalid MAC address: {}",
+            s
+        )));
+    }
+    let mut mac = [0u8; 6];
+    for (i, octet) in mac.iter_mut().enumerate() {
+        *octet = u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16)?;
+    }
+    Ok(mac)
+}
+
+pub fn format_mac(mac: &[u8; 6]) -> String {
+    mac.iter()
+        .map(|b| format!("{:02x}", b))
+        .collect::<Vec<_>>()
+        .join(":")
+}
+
+/// 6 bytes of 0xFF followed by the MAC repeated 16 times
+pub fn build_magic_packet(mac: &[u8; 6]) -> Vec<u8> {
+    let mut packet = Vec::with_capacity(MAGIC_PACKET_LEN);
+    packet.extend_from_slice(&[0xff; 6]);
+    for _ in 0..16 {
+        packet.extend_from_slice(mac);
+    }
+    packet
+}
+
+/// Look up the MAC of a host in the OS neighbor (ARP/NDP) table,
+/// which a prior neighbor scan populates.
+pub fn resolve_mac(ip: IpAddr) -> Result<[u8; 6]> {
+    let table = crate::net::neigh::get_neighbor_table()?;
+    let mac = table.get(&ip).with_context(|| {
+        format!(
+            "No MAC address known for {}. Run a neighbor scan first.",
+            ip
+        )
+    })?;
+    parse_mac(&mac.to_string())
+}
+
+/// Broadcast a Wake-on-LAN magic packet over UDP.
+pub async fn send_magic_packet(mac: &[u8; 6], broadcast_addr: Ipv4Addr, port: u16) -> Result<()> {
+    let socket = UdpSocket::bind(SocketAddr::from((Ipv4Addr::UNSPECIFIED, 0))).await?;
+    socket.set_broadcast(true)?;
+    let packet = build_magic_packet(mac);
+    socket
+        .send_to(&packet, SocketAddr::from((broadcast_addr, port)))
+        .await
+        .with_context(|| format!("Failed to send magic packet to {}:{}", broadcast_addr, port))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAC: [u8; 6] = [0x00, 0x1a, 0x2b, 0x3c, 0x4d, 0x5e];
+
+    #[test]
+    fn parses_mac_notations() {
+        for s in ["00:1a:2b:3c:4d:5e", "00-1A-2B-3C-4D-5E", " 001a2b3c4d5e "] {
+            assert_eq!(parse_mac(s).unwrap(), MAC, "{}", s);
+        }
+        for s in [
+            "00:1a:2b:3c:4d",
+            "00:1a:2b:3c:4d:5",
+            "001a2b3c4d5g",
+            "0:1a:2b:3c:4d:5e:f",
+        ] {
+            let err = parse_mac(s).unwrap_err();
+            assert!(err.downcast_ref::<ScanError>().is_some(), "{}", s);
+        }
+        assert_eq!(format_mac(&MAC), "00:1a:2b:3c:4d:5e");
+    }
+
+    #[test]
+    fn magic_packet_layout() {
+        let packet = build_magic_packet(&MAC);
+        assert_eq!(packet.len(), MAGIC_PACKET_LEN);
+        assert_eq!(packet[..6], [0xff; 6]);
+        assert!(packet[6..].chunks(6).all(|chunk| chunk == MAC));
+    }
+
+    #[tokio::test]
+    async fn sends_magic_packet() {
+        let receiver = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+        let port = receiver.local_addr().unwrap().port();
+        send_magic_packet(&MAC, Ipv4Addr::LOCALHOST, port)
+            .await
+            .unwrap();
+        let mut buf = [0u8; 256];
+        let (n, _) = receiver.recv_from(&mut buf).await.unwrap();
+        assert_eq!(buf[..n], build_magic_packet(&MAC));
+    }
+}
@@ -0,0 +1,256 @@
+use anyhow::{bail, Context, Result};
+use nex_packet::ip::IpNextProtocol;
+use nex_packet::ipv4::Ipv4Packet;
+use nex_packet::ipv6::Ipv6Packet;
+use nex_packet::packet::Packet;
+use std::net::IpAddr;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Emitter};
+
+use crate::error::ScanError;
+use crate::model::capture::{CaptureReport, CapturedPacket};
+use crate::probe::scan::control::ScanControl;
+use crate::socket::packet::AsyncPacketSocket;
+
+/// Packets captured when no limit is given
+pub const DEFAULT_MAX_PACKETS: usize = 1000;
+/// Upper bound on `max_packets`
+pub const MAX_PACKETS_LIMIT: usize = 100_000;
+/// How often the receive loop checks for a stop request
+const CAPTURE_POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Direction {
+    Src,
+    Dst,
+    Any,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum FilterTerm {
+    /// "ip" or "ip6"
+    Family(bool),
+    /// Protocol name as reported in `CapturedPacket::protocol`, upper case
+    Protocol(String),
+    Host(Direction, IpAddr),
+    Port(Direction, u16),
+    Not(Box<FilterTerm>),
+}
+
+impl FilterTerm {
+    fn matches(&self, packet: &CapturedPacket) -> bool {
+        match self {
+            FilterTerm::Family(v6) => packet.src.is_ipv6() == *v6,
+            FilterTerm::Protocol(name) => packet.protocol == *name,
+            FilterTerm::Host(dir, ip) => match dir {
+                Direction::Src => packet.src == *ip,
+                Direction::Dst => packet.dst == *ip,
+                Direction::Any => packet.src == *ip || packet.dst == *ip,
+            },
+            FilterTerm::Port(dir, port) => match dir {
+                Direction::Src => packet.src_port == Some(*port),
+                Direction::Dst => packet.dst_port == Some(*port),
+                Direction::Any => packet.src_port == Some(*port) || packet.dst_port == Some(*port),
+            },
+            FilterTerm::Not(term) => !term.matches(packet),
+        }
+    }
+}
+
+/// Subset of BPF filter syntax evaluated on parsed packets.
+/// Supports `ip`, `ip6`, `tcp`, `udp`, `icmp`, `icmp6`,
+/// `[src|dst] host <addr>`, `[src|dst] port <port>` and `not`/`!`,
+/// joined with `and`/`&&`.
+#[derive(Debug, Clone, Default)]
+pub struct CaptureFilter {
+    terms: Vec<FilterTerm>,
+}
+
+impl CaptureFilter {
+    pub fn parse(expr: &str) -> Result<Self> {
+        let mut tokens = expr.split_whitespace();
+        let mut terms = Vec::new();
+        let mut negate = false;
+        let mut direction = Direction::Any;
+        while let Some(token) = tokens.next() {
+            let term = match token.to_ascii_lowercase().as_str() {
+                "and" | "&&" => continue,
+                "not" | "!" => {
+                    negate = !negate;
+                    continue;
+                }
+                "src" => {
+                    direction = Direction::Src;
+                    continue;
+                }
+                "dst" => {
+                    direction = Direction::Dst;
+                    continue;
+                }
+                "ip" => FilterTerm::Family(false),
+                "ip6" => FilterTerm::Family(true),
+                "tcp" => FilterTerm::Protocol("TCP".to_string()),
+                "udp" => FilterTerm::Protocol("UDP".to_string()),
+                "icmp" => FilterTerm::Protocol("ICMP".to_string()),
+                "icmp6" => FilterTerm::Protocol("ICMPv6".to_string()),
+                "host" => {
+                    let value = tokens
+                        .next()
+                        .ok_or_else(|| ScanError::invalid_input("Missing address after 'host'"))?;
+                    let ip: IpAddr = value.parse().map_err(|_| {
+                        ScanError::invalid_input(format!("Invalid host address: {}", value))
+                    })?;
+                    FilterTerm::Host(direction, ip)
+                }
+                "port" => {
+                    let value = tokens
+                        .next()
+                        .ok_or_else(|| ScanError::invalid_input("Missing number after 'port'"))?;
+                    let port: u16 = value.parse().map_err(|_| {
+                        ScanError::invalid_input(format!("Invalid port: {}", value))
+                    })?;
+                    FilterTerm::Port(direction, port)
+                }
+                other => bail!(ScanError::invalid_input(format!(
+                    "Unsupported filter term: {}",
+                    other
+                ))),
+            };
+            if direction != Direction::Any
+                && !matches!(term, FilterTerm::Host(..) | FilterTerm::Port(..))
+            {
+                bail!(ScanError::invalid_input(
+                    "'src'/'dst' must be followed by 'host' or 'port'"
+                ));
+            }
+            terms.push(if negate {
+                FilterTerm::Not(Box::new(term))
+            } else {
+                term
+            });
+            negate = false;
+            direction = Direction::Any;
+        }
+        if negate || direction != Direction::Any {
+            bail!(ScanError::invalid_input(format!(
+                "Incomplete filter expression: {}",
+                expr
+            )));
+        }
+        Ok(Self { terms })
+    }
+
+    pub fn matches(&self, packet: &CapturedPacket) -> bool {
+        self.terms.iter().all(|t| t.matches(packet))
+    }
+}
+
+fn protocol_name(protocol: IpNextProtocol) -> String {
+    match protocol {
+        IpNextProtocol::Tcp => "TCP".to_string(),
+        IpNextProtocol::Udp => "UDP".to_string(),
+        IpNextProtocol::Icmp => "ICMP".to_string(),
+        IpNextProtocol::Icmpv6 => "ICMPv6".to_string(),
+        other => format!("{:?}", other),
+    }
+}
+
+/// Source and destination ports of a TCP or UDP segment
+fn transport_ports(protocol: IpNextProtocol, payload: &[u8]) -> (Option<u16>, Option<u16>) {
+    if !matches!(protocol, IpNextProtocol::Tcp | IpNextProtocol::Udp) || payload.len() < 4 {
+        return (None, None);
+    }
+    (
+        Some(u16::from_be_bytes([payload[0], payload[1]])),
+        Some(u16::from_be_bytes([payload[2], payload[3]])),
+    )
+}
+
+/// Parse an IP packet (without link-layer header) into a summary.
+pub fn parse_ip_packet(buf: &[u8]) -> Option<CapturedPacket> {
+    let (src, dst, protocol, payload) = match buf.first()? >> 4 {
+        4 => {
+            let ip = Ipv4Packet::from_buf(buf)?;
+            let protocol = ip.header.next_level_protocol;
+            (
+                IpAddr::V4(ip.header.source),
+                IpAddr::V4(ip.header.destination),
+                protocol,
+                ip.payload(),
+            )
+        }
+        6 => {
+            let ip = Ipv6Packet::from_buf(buf)?;
+            let protocol = ip.header.next_header;
+            (
+                IpAddr::V6(ip.header.source),
+                IpAddr::V6(ip.header.destination),
+                protocol,
+                ip.payload(),
+            )
+        }
+        _ => return None,
+    };
+    let (src_port, dst_port) = transport_ports(protocol, &payload);
+    Some(CapturedPacket {
+        index: 0,
+        timestamp_ms: 0,
+        src,
+        dst,
+        protocol: protocol_name(protocol),
+        src_port,
+        dst_port,
+        length: buf.len(),
+    })
+}
+
+/// Capture packets on an interface until `max_packets` matching packets
+/// have been seen or the capture is cancelled.
+/// Emits `capture:packet` for each match and `capture:done` at the end.
+pub async fn capture(
+    app: &AppHandle,
+    capture_id: &str,
+    iface: &str,
+    if_index: u32,
+    filter: CaptureFilter,
+    max_packets: usize,
+    control: Arc<ScanControl>,
+) -> Result<CaptureReport> {
+    let socket = AsyncPacketSocket::bind(if_index)
+        .with_context(|| format!("Failed to open capture on {}", iface))?;
+    let mut buf = vec![0u8; 65535];
+    let mut captured: u64 = 0;
+    let mut seen: u64 = 0;
+
+    while (captured as usize) < max_packets && !control.is_cancelled() {
+        let n = match tokio::time::timeout(CAPTURE_POLL_INTERVAL, socket.recv(&mut buf)).await {
+            Err(_) => continue,
+            Ok(res) => res?,
+        };
+        let Some(mut packet) = parse_ip_packet(&buf[..n]) else {
+            continue;
+        };
+        seen += 1;
+        if !filter.matches(&packet) {
+            continue;
+        }
+        captured += 1;
+        packet.index = captured;
+        packet.timestamp_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0);
+        let _ = app.emit("capture:packet", packet);
+    }
+
+    let report = CaptureReport {
+        capture_id: capture_id.to_string(),
+        iface: iface.to_string(),
+        captured,
+        seen,
+        stopped: (captured as usize) < max_packets,
+    };
+    let _ = app.emit("capture:done", report.clone());
+    Ok(report)
+}
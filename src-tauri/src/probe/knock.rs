@@ -0,0 +1,223 @@
+use std::io;
+use std::net::{IpAddr, SocketAddr};
+use std::time::{Duration, Instant};
+
+use crate::model::endpoint::TransportProtocol;
+use crate::model::knock::{KnockResult, KnockStep, KnockVerify, PortKnockReport};
+use crate::socket::tcp::{AsyncTcpSocket, TcpConfig};
+use crate::socket::udp::{AsyncUdpSocket, UdpConfig};
+use crate::socket::SocketFamily;
+
+/// Pause between two knocks (default)
+pub const DEFAULT_KNOCK_DELAY_MS: u64 = 200;
+/// Timeout of the verify connect (default)
+pub const DEFAULT_KNOCK_VERIFY_TIMEOUT_MS: u64 = 3000;
+
+/// How long a TCP knock waits on its connect. The SYN is what counts,
+/// so there is no point waiting for the answer.
+const SYN_WAIT: Duration = Duration::from_millis(100);
+
+fn tcp_config(ip: IpAddr) -> TcpConfig {
+    if ip.is_ipv4() {
+        TcpConfig::v4_stream()
+    } else {
+        TcpConfig::v6_stream()
+    }
+}
+
+/// Send a SYN by starting a connect. A refusal, reset or timeout still means
+/// the SYN went out; only local errors count as failed.
+async fn knock_tcp(addr: SocketAddr) -> io::Result<()> {
+    let socket = AsyncTcpSocket::from_config(&tcp_config(addr.ip()))?;
+    match socket.connect_timeout(addr, SYN_WAIT).await {
+        Ok(_) => Ok(()),
+        Err(e)
+            if matches!(
+                e.kind(),
+                io::ErrorKind::TimedOut
+                    | io::ErrorKind::ConnectionRefused
+                    | io::ErrorKind::ConnectionReset
+            ) =>
+        {
+            Ok(())
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// Send an empty datagram.
+async fn knock_udp(addr: SocketAddr) -> io::Result<()> {
+    let config = UdpConfig {
+        socket_family: SocketFamily::from_ip(&addr.ip()),
+        ..UdpConfig::default()
+    };
+    let socket = AsyncUdpSocket::from_config(&config)?;
+    socket.send_to(&[], addr).await?;
+    Ok(())
+}
+
+async fn verify(ip: IpAddr, port: u16, timeout: Duration) -> KnockVerify {
+    let start = Instant::now();
+    let result = match AsyncTcpSocket::from_config(&tcp_config(ip)) {
+        Ok(socket) => socket
+            .connect_timeout(SocketAddr::new(ip, port), timeout)
+            .await
+            .map(drop),
+        Err(e) => Err(e),
+    };
+    match result {
+        Ok(()) => KnockVerify {
+            port,
+            open: true,
+            rtt_ms: Some(start.elapsed().as_millis() as u64),
+            message: None,
+        },
+        Err(e) => KnockVerify {
+            port,
+            open: false,
+            rtt_ms: None,
+            message: Some(e.to_string()),
+        },
+    }
+}
+
+/// Knock on `sequence` in order, `delay` apart, then optionally check that
+/// `verify_port` accepts a TCP connection.
+pub async fn port_knock(
+    ip: IpAddr,
+    sequence: &[KnockStep],
+    delay: Duration,
+    verify_port: Option<u16>,
+    verify_timeout: Duration,
+) -> PortKnockReport {
+    let mut knocks = Vec::with_capacity(sequence.len());
+    for (i, step) in sequence.iter().enumerate() {
+        if i > 0 {
+            tokio::time::sleep(delay).await;
+        }
+        let addr = SocketAddr::new(ip, step.port);
+        let result = match step.protocol {
+            TransportProtocol::Tcp => knock_tcp(addr).await,
+            TransportProtocol::Udp | TransportProtocol::Quic => knock_udp(addr).await,
+        };
+        if let Err(e) = &result {
+            tracing::warn!("knock on {} ({:?}) failed: {}", addr, step.protocol, e);
+        }
+        knocks.push(KnockResult {
+            port: step.port,
+            protocol: step.protocol,
+            sent: result.is_ok(),
+            error: result.err().map(|e| e.to_string()),
+        });
+    }
+
+    let verify = match verify_port {
+        Some(port) => {
+            // Give the knock daemon the same delay to open the port
+            tokio::time::sleep(delay).await;
+            Some(verify(ip, port, verify_timeout).await)
+        }
+        None => None,
+    };
+
+    PortKnockReport {
+        ip_addr: ip,
+        knocks,
+        verify,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::Ipv4Addr;
+    use tokio::sync::mpsc;
+
+    #[tokio::test]
+    async fn knocks_in_order_delay_apart_then_verifies() {
+        let ip = IpAddr::V4(Ipv4Addr::LOCALHOST);
+        let (tx, mut rx) = mpsc::unbounded_channel();
+
+        // Record when each knock arrives
+        let mut sequence = Vec::new();
+        for protocol in [
+            TransportProtocol::Tcp,
+            TransportProtocol::Udp,
+            TransportProtocol::Tcp,
+        ] {
+            let tx = tx.clone();
+            let port = match protocol {
+                TransportProtocol::Tcp => {
+                    let listener = tokio::net::TcpListener::bind((ip, 0)).await.unwrap();
+                    let port = listener.local_addr().unwrap().port();
+                    tokio::spawn(async move {
+                        let _conn = listener.accept().await.unwrap();
+                        let _ = tx.send((port, Instant::now()));
+                    });
+                    port
+                }
+                _ => {
+                    let socket = tokio::net::UdpSocket::bind((ip, 0)).await.unwrap();
+                    let port = socket.local_addr().unwrap().port();
+                    tokio::spawn(async move {
+                        let mut buf = [0u8; 16];
+                        socket.recv_from(&mut buf).await.unwrap();
+                        let _ = tx.send((port, Instant::now()));
+                    });
+                    port
+                }
+            };
+            sequence.push(KnockStep { port, protocol });
+        }
+        drop(tx);
+        let target = tokio::net::TcpListener::bind((ip, 0)).await.unwrap();
+        let verify_port = target.local_addr().unwrap().port();
+
+        let delay = Duration::from_millis(50);
+        let report = port_knock(
+            ip,
+            &sequence,
+            delay,
+            Some(verify_port),
+            Duration::from_secs(1),
+        )
+        .await;
+
+        assert!(report.knocks.iter().all(|k| k.sent && k.error.is_none()));
+        let verify = report.verify.expect("verify result");
+        assert!(verify.open && verify.port == verify_port);
+
+        let mut arrivals = Vec::new();
+        while let Some(arrival) = rx.recv().await {
+            arrivals.push(arrival);
+        }
+        arrivals.sort_by_key(|(_, at)| *at);
+        let order: Vec<u16> = arrivals.iter().map(|(port, _)| *port).collect();
+        let expected: Vec<u16> = sequence.iter().map(|s| s.port).collect();
+        assert_eq!(order, expected);
+        for pair in arrivals.windows(2) {
+            // Allow for timer granularity between send and receive
+            assert!(pair[1].1 - pair[0].1 >= delay - Duration::from_millis(10));
+        }
+    }
+
+    #[tokio::test]
+    async fn verify_reports_closed_port() {
+        let ip = IpAddr::V4(Ipv4Addr::LOCALHOST);
+        let closed = std::net::TcpListener::bind((ip, 0))
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let report = port_knock(
+            ip,
+            &[],
+            Duration::ZERO,
+            Some(closed),
+            Duration::from_secs(1),
+        )
+        .await;
+        let verify = report.verify.expect("verify result");
+        assert!(!verify.open && verify.message.is_some());
+    }
+}
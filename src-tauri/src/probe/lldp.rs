@@ -0,0 +1,398 @@
+use anyhow::{Context, Result};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crate::model::lldp::{DiscoveryProtocol, LinkDiscoveryReport, LinkNeighbor};
+use crate::net::wol::format_mac;
+use crate::probe::scan::control::ScanControl;
+use crate::socket::packet::AsyncPacketSocket;
+
+/// Listen time (default). LLDP is sent every 30 s; CDP every 60 s.
+pub const DEFAULT_LLDP_TIMEOUT_MS: u64 = 35_000;
+
+/// Nearest-bridge LLDP group address
+const LLDP_MULTICAST: [u8; 6] = [0x01, 0x80, 0xc2, 0x00, 0x00, 0x0e];
+const CDP_MULTICAST: [u8; 6] = [0x01, 0x00, 0x0c, 0xcc, 0xcc, 0xcc];
+const ETHERTYPE_LLDP: u16 = 0x88cc;
+const ETHERTYPE_VLAN: u16 = 0x8100;
+/// LLC/SNAP header of CDP: DSAP, SSAP, UI, Cisco OUI, protocol id 0x2000
+const CDP_SNAP: [u8; 8] = [0xaa, 0xaa, 0x03, 0x00, 0x00, 0x0c, 0x20, 0x00];
+
+const LLDP_END: u8 = 0;
+const LLDP_CHASSIS_ID: u8 = 1;
+const LLDP_PORT_ID: u8 = 2;
+const LLDP_TTL: u8 = 3;
+const LLDP_PORT_DESCRIPTION: u8 = 4;
+const LLDP_SYSTEM_NAME: u8 = 5;
+const LLDP_SYSTEM_DESCRIPTION: u8 = 6;
+const LLDP_CAPABILITIES: u8 = 7;
+const LLDP_MANAGEMENT_ADDRESS: u8 = 8;
+const LLDP_ORG_SPECIFIC: u8 = 127;
+const OUI_IEEE_8021: [u8; 3] = [0x00, 0x80, 0xc2];
+const IEEE_8021_PORT_VLAN_ID: u8 = 1;
+
+const CDP_DEVICE_ID: u16 = 0x0001;
+const CDP_ADDRESSES: u16 = 0x0002;
+const CDP_PORT_ID: u16 = 0x0003;
+const CDP_CAPABILITIES: u16 = 0x0004;
+const CDP_SOFTWARE_VERSION: u16 = 0x0005;
+const CDP_PLATFORM: u16 = 0x0006;
+const CDP_NATIVE_VLAN: u16 = 0x000a;
+const CDP_MANAGEMENT_ADDRESSES: u16 = 0x0016;
+
+const LLDP_CAPABILITY_NAMES: [&str; 11] = [
+    "Other",
+    "Repeater",
+    "Bridge",
+    "WLAN AP",
+    "Router",
+    "Telephone",
+    "DOCSIS",
+    "Station",
+    "C-VLAN",
+    "S-VLAN",
+    "TPMR",
+];
+const CDP_CAPABILITY_NAMES: [&str; 7] = [
+    "Router",
+    "Transparent bridge",
+    "Source route bridge",
+    "Switch",
+    "Host",
+    "IGMP",
+    "Repeater",
+];
+
+fn text(value: &[u8]) -> String {
+    String::from_utf8_lossy(value)
+        .trim_end_matches('\0')
+        .trim()
+        .to_string()
+}
+
+fn mac_text(value: &[u8]) -> Option<String> {
+    let mac: [u8; 6] = value.try_into().ok()?;
+    Some(format_mac(&mac))
+}
+
+/// Address with an IANA address family number (1 = IPv4, 2 = IPv6)
+fn iana_address(value: &[u8]) -> Option<IpAddr> {
+    let (&family, addr) = value.split_first()?;
+    match family {
+        1 => Some(IpAddr::V4(Ipv4Addr::from(<[u8; 4]>::try_from(addr).ok()?))),
+        2 => Some(IpAddr::V6(Ipv6Addr::from(<[u8; 16]>::try_from(addr).ok()?))),
+        _ => None,
+    }
+}
+
+fn capability_names(bits: u32, names: &[&str]) -> Vec<String> {
+    names
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| bits & (1 << i) != 0)
+        .map(|(_, name)| name.to_string())
+        .collect()
+}
+
+/// Chassis and port id TLVs: a subtype byte, then the id.
+/// `mac_subtype` and `addr_subtype` differ between the two TLVs.
+fn lldp_id(value: &[u8], mac_subtype: u8, addr_subtype: u8) -> Option<String> {
+    let (&subtype, id) = value.split_first()?;
+    if subtype == mac_subtype {
+        mac_text(id)
+    } else if subtype == addr_subtype {
+        iana_address(id).map(|ip| ip.to_string())
+    } else {
+        Some(text(id))
+    }
+}
+
+fn new_neighbor(protocol: DiscoveryProtocol, source: &[u8]) -> LinkNeighbor {
+    LinkNeighbor {
+        protocol,
+        source_mac: mac_text(source).unwrap_or_default(),
+        chassis_id: None,
+        port_id: None,
+        port_description: None,
+        system_name: None,
+        system_description: None,
+        platform: None,
+        vlan_id: None,
+        capabilities: Vec::new(),
+        management_addrs: Vec::new(),
+        ttl_secs: None,
+        last_seen_ms: 0,
+    }
+}
+
+/// Parse the LLDPDU following the Ethernet header.
+fn parse_lldp(source: &[u8], mut pdu: &[u8]) -> Option<LinkNeighbor> {
+    let mut neighbor = new_neighbor(DiscoveryProtocol::Lldp, source);
+    while pdu.len() >= 2 {
+        let header = u16::from_be_bytes([pdu[0], pdu[1]]);
+        let tlv_type = (header >> 9) as u8;
+        let len = (header & 0x01ff) as usize;
+        let value = pdu.get(2..2 + len)?;
+        pdu = &pdu[2 + len..];
+        match tlv_type {
+            LLDP_END => break,
+            LLDP_CHASSIS_ID => neighbor.chassis_id = lldp_id(value, 4, 5),
+            LLDP_PORT_ID => neighbor.port_id = lldp_id(value, 3, 4),
+            LLDP_TTL if len == 2 => {
+                neighbor.ttl_secs = Some(u16::from_be_bytes([value[0], value[1]]))
+            }
+            LLDP_PORT_DESCRIPTION => neighbor.port_description = Some(text(value)),
+            LLDP_SYSTEM_NAME => neighbor.system_name = Some(text(value)),
+            LLDP_SYSTEM_DESCRIPTION => neighbor.system_description = Some(text(value)),
+            LLDP_CAPABILITIES if len == 4 => {
+                let enabled = u16::from_be_bytes([value[2], value[3]]);
+                neighbor.capabilities = capability_names(enabled as u32, &LLDP_CAPABILITY_NAMES);
+            }
+            LLDP_MANAGEMENT_ADDRESS => {
+                // Address string length (subtype included), then the address
+                let addr_len = *value.first()? as usize;
+                if let Some(ip) = value.get(1..1 + addr_len).and_then(iana_address) {
+                    neighbor.management_addrs.push(ip);
+                }
+            }
+            LLDP_ORG_SPECIFIC
+                if len >= 6
+                    && value[..3] == OUI_IEEE_8021
+                    && value[3] == IEEE_8021_PORT_VLAN_ID =>
+            {
+                neighbor.vlan_id = Some(u16::from_be_bytes([value[4], value[5]]));
+            }
+            _ => {}
+        }
+    }
+    // Every LLDPDU starts with the mandatory chassis id
+    neighbor.chassis_id.as_ref()?;
+    Some(neighbor)
+}
+
+/// Addresses TLV of CDP: a count, then protocol-tagged addresses.
+/// Only IPv4 (NLPID 0xcc) and IPv6 (802.2 SNAP) are decoded.
+fn cdp_addresses(value: &[u8]) -> Vec<IpAddr> {
+    let mut addrs = Vec::new();
+    let Some(mut rest) = value.get(4..) else {
+        return addrs;
+    };
+    while rest.len() >= 2 {
+        let proto_len = rest[1] as usize;
+        let Some(proto) = rest.get(2..2 + proto_len) else {
+            break;
+        };
+        let Some(len) = rest.get(2 + proto_len..4 + proto_len) else {
+            break;
+        };
+        let addr_len = u16::from_be_bytes([len[0], len[1]]) as usize;
+        let start = 4 + proto_len;
+        let Some(addr) = rest.get(start..start + addr_len) else {
+            break;
+        };
+        match (proto, addr_len) {
+            ([0xcc], 4) => addrs.push(IpAddr::from(<[u8; 4]>::try_from(addr).unwrap())),
+            (_, 16) => addrs.push(IpAddr::from(<[u8; 16]>::try_from(addr).unwrap())),
+            _ => {}
+        }
+        rest = &rest[start + addr_len..];
+    }
+    addrs
+}
+
+/// Parse the CDP packet following the LLC/SNAP header.
+fn parse_cdp(source: &[u8], pdu: &[u8]) -> Option<LinkNeighbor> {
+    let mut neighbor = new_neighbor(DiscoveryProtocol::Cdp, source);
+    // Version, TTL, checksum
+    neighbor.ttl_secs = Some(*pdu.get(1)? as u16);
+    let mut tlvs = pdu.get(4..)?;
+    while tlvs.len() >= 4 {
+        let tlv_type = u16::from_be_bytes([tlvs[0], tlvs[1]]);
+        let len = u16::from_be_bytes([tlvs[2], tlvs[3]]) as usize;
+        if len < 4 {
+            break;
+        }
+        let value = tlvs.get(4..len)?;
+        tlvs = &tlvs[len..];
+        match tlv_type {
+            CDP_DEVICE_ID => {
+                let id = text(value);
+                neighbor.system_name = Some(id.clone());
+                neighbor.chassis_id = Some(id);
+            }
+            CDP_ADDRESSES | CDP_MANAGEMENT_ADDRESSES => {
+                for ip in cdp_addresses(value) {
+                    if !neighbor.management_addrs.contains(&ip) {
+                        neighbor.management_addrs.push(ip);
+                    }
+                }
+            }
+            CDP_PORT_ID => neighbor.port_id = Some(text(value)),
+            CDP_CAPABILITIES if value.len() == 4 => {
+                let bits = u32::from_be_bytes([value[0], value[1], value[2], value[3]]);
+                neighbor.capabilities = capability_names(bits, &CDP_CAPABILITY_NAMES);
+            }
+            CDP_SOFTWARE_VERSION => neighbor.system_description = Some(text(value)),
+            CDP_PLATFORM => neighbor.platform = Some(text(value)),
+            CDP_NATIVE_VLAN if value.len() == 2 => {
+                neighbor.vlan_id = Some(u16::from_be_bytes([value[0], value[1]]))
+            }
+            _ => {}
+        }
+    }
+    neighbor.chassis_id.as_ref()?;
+    Some(neighbor)
+}
+
+/// Parse an Ethernet frame carrying LLDP or CDP. Other frames yield `None`.
+pub fn parse_frame(frame: &[u8]) -> Option<LinkNeighbor> {
+    let source = frame.get(6..12)?;
+    let mut offset = 12;
+    let mut ethertype = u16::from_be_bytes([*frame.get(offset)?, *frame.get(offset + 1)?]);
+    if ethertype == ETHERTYPE_VLAN {
+        offset += 4;
+        ethertype = u16::from_be_bytes([*frame.get(offset)?, *frame.get(offset + 1)?]);
+    }
+    let payload = frame.get(offset + 2..)?;
+    if ethertype == ETHERTYPE_LLDP {
+        parse_lldp(source, payload)
+    } else if ethertype <= 1500 && frame[..6] == CDP_MULTICAST && payload.starts_with(&CDP_SNAP) {
+        parse_cdp(source, &payload[CDP_SNAP.len()..])
+    } else {
+        None
+    }
+}
+
+/// Listen on an interface for LLDP and CDP announcements until `wait` elapses
+/// or `control` is cancelled. Requires administrator privileges; only
+/// supported on Linux.
+pub async fn listen(
+    listen_id: &str,
+    iface: &str,
+    if_index: u32,
+    wait: Duration,
+    control: Arc<ScanControl>,
+) -> Result<LinkDiscoveryReport> {
+    let socket = AsyncPacketSocket::bind_link(if_index, &[LLDP_MULTICAST, CDP_MULTICAST])
+        .with_context(|| format!("Failed to open capture on {}", iface))?;
+    let started = Instant::now();
+    let deadline = tokio::time::Instant::now() + wait;
+    let mut neighbors: Vec<LinkNeighbor> = Vec::new();
+    let mut buf = vec![0u8; 65535];
+
+    loop {
+        let received = tokio::select! {
+            received = tokio::time::timeout_at(deadline, socket.recv(&mut buf)) => received,
+            _ = control.cancelled() => break,
+        };
+        let Ok(received) = received else {
+            break;
+        };
+        let n = received?;
+        let Some(mut neighbor) = parse_frame(&buf[..n]) else {
+            continue;
+        };
+        neighbor.last_seen_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0);
+        tracing::debug!(
+            "{:?} neighbor {:?} port {:?}",
+            neighbor.protocol,
+            neighbor.system_name,
+            neighbor.port_id
+        );
+        // Switches repeat their announcement; keep the latest one
+        match neighbors.iter_mut().find(|n| {
+            n.protocol == neighbor.protocol
+                && n.chassis_id == neighbor.chassis_id
+                && n.port_id == neighbor.port_id
+        }) {
+            Some(existing) => *existing = neighbor,
+            None => neighbors.push(neighbor),
+        }
+    }
+
+    Ok(LinkDiscoveryReport {
+        listen_id: listen_id.to_string(),
+        iface: iface.to_string(),
+        neighbors,
+        elapsed_ms: started.elapsed().as_millis() as u64,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tlv(tlv_type: u8, value: &[u8]) -> Vec<u8> {
+        let header = ((tlv_type as u16) << 9) | value.len() as u16;
+        let mut out = header.to_be_bytes().to_vec();
+        out.extend_from_slice(value);
+        out
+    }
+
+    #[test]
+    fn parses_lldp_frame() {
+        let mut frame = LLDP_MULTICAST.to_vec();
+        frame.extend_from_slice(&[0x00, 0x11, 0x22, 0x33, 0x44, 0x55]);
+        frame.extend_from_slice(&ETHERTYPE_LLDP.to_be_bytes());
+        frame.extend(tlv(
+            LLDP_CHASSIS_ID,
+            &[4, 0x00, 0x11, 0x22, 0x33, 0x44, 0x00],
+        ));
+        frame.extend(tlv(LLDP_PORT_ID, b"\x05Gi1/0/12"));
+        frame.extend(tlv(LLDP_TTL, &[0, 120]));
+        frame.extend(tlv(LLDP_SYSTEM_NAME, b"core-sw1"));
+        frame.extend(tlv(LLDP_CAPABILITIES, &[0, 0x14, 0, 0x04]));
+        frame.extend(tlv(
+            LLDP_MANAGEMENT_ADDRESS,
+            &[5, 1, 10, 0, 0, 2, 2, 0, 0, 0, 0, 0],
+        ));
+        frame.extend(tlv(LLDP_ORG_SPECIFIC, &[0x00, 0x80, 0xc2, 1, 0x00, 0x0a]));
+        frame.extend(tlv(LLDP_END, &[]));
+
+        let neighbor = parse_frame(&frame).unwrap();
+        assert_eq!(neighbor.protocol, DiscoveryProtocol::Lldp);
+        assert_eq!(neighbor.source_mac, "00:11:22:33:44:55");
+        assert_eq!(neighbor.chassis_id.as_deref(), Some("00:11:22:33:44:00"));
+        assert_eq!(neighbor.port_id.as_deref(), Some("Gi1/0/12"));
+        assert_eq!(neighbor.system_name.as_deref(), Some("core-sw1"));
+        assert_eq!(neighbor.ttl_secs, Some(120));
+        assert_eq!(neighbor.capabilities, ["Bridge"]);
+        assert_eq!(neighbor.management_addrs, [IpAddr::from([10, 0, 0, 2])]);
+        assert_eq!(neighbor.vlan_id, Some(10));
+    }
+
+    #[test]
+    fn parses_cdp_frame() {
+        let cdp_tlv = |tlv_type: u16, value: &[u8]| {
+            let mut out = tlv_type.to_be_bytes().to_vec();
+            out.extend_from_slice(&((value.len() + 4) as u16).to_be_bytes());
+            out.extend_from_slice(value);
+            out
+        };
+        let mut pdu = vec![2, 180, 0, 0];
+        pdu.extend(cdp_tlv(CDP_DEVICE_ID, b"access-sw2"));
+        pdu.extend(cdp_tlv(CDP_PORT_ID, b"FastEthernet0/3"));
+        pdu.extend(cdp_tlv(CDP_NATIVE_VLAN, &[0, 20]));
+        pdu.extend(cdp_tlv(
+            CDP_ADDRESSES,
+            &[0, 0, 0, 1, 1, 1, 0xcc, 0, 4, 192, 0, 2, 7],
+        ));
+        let mut frame = CDP_MULTICAST.to_vec();
+        frame.extend_from_slice(&[0x00, 0x0c, 0x29, 0x00, 0x00, 0x01]);
+        frame.extend_from_slice(&((CDP_SNAP.len() + pdu.len()) as u16).to_be_bytes());
+        frame.extend_from_slice(&CDP_SNAP);
+        frame.extend(pdu);
+
+        let neighbor = parse_frame(&frame).unwrap();
+        assert_eq!(neighbor.protocol, DiscoveryProtocol::Cdp);
+        assert_eq!(neighbor.system_name.as_deref(), Some("access-sw2"));
+        assert_eq!(neighbor.port_id.as_deref(), Some("FastEthernet0/3"));
+        assert_eq!(neighbor.vlan_id, Some(20));
+        assert_eq!(neighbor.ttl_secs, Some(180));
+        assert_eq!(neighbor.management_addrs, [IpAddr::from([192, 0, 2, 7])]);
+    }
+}
@@ -1,3 +1,6 @@
+pub mod capture;
+pub mod knock;
+pub mod lldp;
 pub mod packet;
 pub mod ping;
 pub mod scan;
@@ -8,7 +8,9 @@ use nex_packet::{
     ipv4::Ipv4Packet,
     packet::Packet,
 };
-use std::net::IpAddr;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+use crate::model::ping::Ipv6ExtHeader;
 
 pub fn build_icmp_echo_bytes(src: IpAddr, dst: IpAddr, id: u16, seq: u16, payload: &[u8]) -> Bytes {
     match (src, dst) {
@@ -30,52 +32,623 @@ pub fn build_icmp_echo_bytes(src: IpAddr, dst: IpAddr, id: u16, seq: u16, payloa
     }
 }
 
-/// Extract id/seq of ICMP Echo Reply (IPv4)
-pub fn parse_icmp_echo_v4(buf: &[u8]) -> Option<IcmpEchoReplyPacket> {
-    if let Some(ipv4_packet) = Ipv4Packet::from_buf(&buf) {
-        if ipv4_packet.header.next_level_protocol == nex_packet::ip::IpNextProtocol::Icmp {
-            if let Some(icmp_packet) = IcmpPacket::from_bytes(ipv4_packet.payload()) {
-                match icmp::echo_reply::EchoReplyPacket::try_from(icmp_packet) {
-                    Ok(reply) => {
-                        return Some(reply);
-                    }
-                    Err(_) => {
-                        return None;
-                    }
-                }
-            }
+/// Parse an ICMP Echo Reply (IPv4) carrying the identifier `id`.
+/// Accepts packets with the IPv4 header (RAW sockets) or without it (Linux DGRAM sockets).
+/// Replies with another identifier belong to an unrelated flow and are ignored.
+pub fn parse_icmp_echo_v4(buf: &[u8], id: u16) -> Option<IcmpEchoReplyPacket> {
+    let icmp_packet = if buf.first()? >> 4 == 4 {
+        let ipv4_packet = Ipv4Packet::from_buf(buf)?;
+        if ipv4_packet.header.next_level_protocol != nex_packet::ip::IpNextProtocol::Icmp {
+            return None;
         }
-    }
-    None
+        IcmpPacket::from_bytes(ipv4_packet.payload())?
+    } else {
+        IcmpPacket::from_buf(buf)?
+    };
+    let reply = icmp::echo_reply::EchoReplyPacket::try_from(icmp_packet).ok()?;
+    (reply.identifier == id).then_some(reply)
 }
 
-/// Extract id/seq of ICMPv6 Echo Reply. (ICMPv6 Header only)
+/// Parse an ICMPv6 Echo Reply carrying the identifier `id`. (ICMPv6 Header only)
 /// The IPv6 header is automatically cropped off when recvfrom() is used.
-pub fn parse_icmp_echo_v6(buf: &[u8]) -> Option<Icmpv6EchoReplyPacket> {
-    if let Some(icmpv6_packet) = Icmpv6Packet::from_buf(&buf) {
-        match icmpv6_packet.header.icmpv6_type {
-            Icmpv6Type::EchoReply => match Icmpv6EchoReplyPacket::from_buf(&buf) {
-                Some(reply) => {
-                    return Some(reply);
-                }
-                None => {
-                    return Some(Icmpv6EchoReplyPacket {
-                        header: icmpv6_packet.header,
-                        identifier: 0,
-                        sequence_number: 0,
-                        payload: icmpv6_packet.payload,
-                    });
-                }
-            },
-            _ => {
-                return Some(Icmpv6EchoReplyPacket {
-                    header: icmpv6_packet.header,
-                    identifier: 0,
-                    sequence_number: 0,
-                    payload: icmpv6_packet.payload,
-                });
-            }
+/// Other ICMPv6 messages and replies with another identifier are ignored.
+pub fn parse_icmp_echo_v6(buf: &[u8], id: u16) -> Option<Icmpv6EchoReplyPacket> {
+    let icmpv6_packet = Icmpv6Packet::from_buf(buf)?;
+    if !matches!(icmpv6_packet.header.icmpv6_type, Icmpv6Type::EchoReply) {
+        return None;
+    }
+    let reply = Icmpv6EchoReplyPacket::from_buf(buf)?;
+    (reply.identifier == id).then_some(reply)
+}
+
+const ICMP_DEST_UNREACHABLE: u8 = 3;
+const ICMP_ECHO_REQUEST: u8 = 8;
+const ICMPV6_DEST_UNREACHABLE: u8 = 1;
+const ICMPV6_ECHO_REQUEST: u8 = 128;
+
+/// Destination unreachable quoting one of our echo requests
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EchoUnreachable {
+    /// Destination of the quoted request
+    pub dst: IpAddr,
+    /// Identifier of the quoted request
+    pub id: u16,
+    pub code: u8,
+}
+
+/// Parse an ICMP Destination Unreachable (type 3) quoting an echo request.
+/// Accepts packets with or without the IPv4 header.
+pub fn parse_icmp_unreachable_v4(buf: &[u8]) -> Option<EchoUnreachable> {
+    let icmp = match buf.first()? >> 4 {
+        4 => buf.get(((buf[0] & 0x0f) as usize * 4)..)?,
+        _ => buf,
+    };
+    if icmp.len() < 8 || icmp[0] != ICMP_DEST_UNREACHABLE {
+        return None;
+    }
+    let quoted = &icmp[8..];
+    if quoted.len() < IPV4_HEADER_LEN || quoted[9] != 1 {
+        return None;
+    }
+    let echo = quoted.get(((quoted[0] & 0x0f) as usize * 4)..)?;
+    if echo.len() < 8 || echo[0] != ICMP_ECHO_REQUEST {
+        return None;
+    }
+    Some(EchoUnreachable {
+        dst: IpAddr::V4(Ipv4Addr::new(
+            quoted[16], quoted[17], quoted[18], quoted[19],
+        )),
+        id: u16::from_be_bytes([echo[4], echo[5]]),
+        code: icmp[1],
+    })
+}
+
+/// Parse an ICMPv6 Destination Unreachable (type 1) quoting an echo request.
+/// Quoted packets with extension headers are ignored.
+pub fn parse_icmpv6_unreachable(buf: &[u8]) -> Option<EchoUnreachable> {
+    if buf.len() < 8 || buf[0] != ICMPV6_DEST_UNREACHABLE {
+        return None;
+    }
+    let quoted = buf.get(8..8 + IPV6_HEADER_LEN)?;
+    if quoted[6] != IPV6_NEXT_ICMPV6 {
+        return None;
+    }
+    let echo = buf.get(8 + IPV6_HEADER_LEN..8 + IPV6_HEADER_LEN + 8)?;
+    if echo[0] != ICMPV6_ECHO_REQUEST {
+        return None;
+    }
+    let dst: [u8; 16] = quoted[24..40].try_into().ok()?;
+    Some(EchoUnreachable {
+        dst: IpAddr::V6(Ipv6Addr::from(dst)),
+        id: u16::from_be_bytes([echo[4], echo[5]]),
+        code: buf[1],
+    })
+}
+
+/// Reason of an ICMP Destination Unreachable code (RFC 792, 1812)
+pub fn icmp_unreachable_reason(code: u8) -> &'static str {
+    match code {
+        0 => "network unreachable",
+        1 => "host unreachable",
+        2 => "protocol unreachable",
+        3 => "port unreachable",
+        4 => "fragmentation needed",
+        5 => "source route failed",
+        6 => "destination network unknown",
+        7 => "destination host unknown",
+        8 => "source host isolated",
+        9 => "network administratively prohibited",
+        10 => "host administratively prohibited",
+        11 => "network unreachable for TOS",
+        12 => "host unreachable for TOS",
+        13 => "communication administratively prohibited",
+        14 => "host precedence violation",
+        15 => "precedence cutoff in effect",
+        _ => "destination unreachable",
+    }
+}
+
+/// Reason of an ICMPv6 Destination Unreachable code (RFC 4443)
+pub fn icmpv6_unreachable_reason(code: u8) -> &'static str {
+    match code {
+        0 => "no route to destination",
+        1 => "administratively prohibited",
+        2 => "beyond scope of source address",
+        3 => "address unreachable",
+        4 => "port unreachable",
+        5 => "source address failed ingress/egress policy",
+        6 => "reject route to destination",
+        _ => "destination unreachable",
+    }
+}
+
+/// Reserved QUIC version that forces a Version Negotiation reply (RFC 9000, 15)
+const QUIC_FORCE_VN_VERSION: u32 = 0x1a2a_3a4a;
+/// Minimum size of a datagram carrying a client Initial
+const QUIC_MIN_INITIAL_SIZE: usize = 1200;
+
+/// Build a long-header datagram with a reserved version.
+/// Any QUIC server answers it with a Version Negotiation packet.
+pub fn build_quic_version_probe() -> Vec<u8> {
+    let mut buf = Vec::with_capacity(QUIC_MIN_INITIAL_SIZE);
+    // Long header, fixed bit set
+    buf.push(0xc0);
+    buf.extend_from_slice(&QUIC_FORCE_VN_VERSION.to_be_bytes());
+    // Destination and source connection IDs
+    for _ in 0..2 {
+        buf.push(8);
+        buf.extend_from_slice(&rand::random::<[u8; 8]>());
+    }
+    buf.resize(QUIC_MIN_INITIAL_SIZE, 0);
+    buf
+}
+
+/// Check whether a datagram is a QUIC Version Negotiation packet.
+pub fn is_quic_version_negotiation(buf: &[u8]) -> bool {
+    buf.len() >= 5 && buf[0] & 0x80 != 0 && buf[1..5] == [0, 0, 0, 0]
+}
+
+/// Internet checksum (RFC 1071)
+pub fn internet_checksum(data: &[u8]) -> u16 {
+    let mut sum: u32 = 0;
+    for chunk in data.chunks(2) {
+        let word = if chunk.len() == 2 {
+            u16::from_be_bytes([chunk[0], chunk[1]])
+        } else {
+            u16::from_be_bytes([chunk[0], 0])
+        };
+        sum += word as u32;
+    }
+    while sum >> 16 != 0 {
+        sum = (sum & 0xffff) + (sum >> 16);
+    }
+    !(sum as u16)
+}
+
+const ICMP_TIMESTAMP_REQUEST: u8 = 13;
+const ICMP_TIMESTAMP_REPLY: u8 = 14;
+const ICMP_ADDRESS_MASK_REQUEST: u8 = 17;
+const ICMP_ADDRESS_MASK_REPLY: u8 = 18;
+
+/// Reply to an ICMP Timestamp or Address Mask request (IPv4)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IcmpQueryReply {
+    /// Milliseconds since midnight UT (RFC 792)
+    Timestamp {
+        originate: u32,
+        receive: u32,
+        transmit: u32,
+    },
+    AddressMask(Ipv4Addr),
+}
+
+fn build_icmp_query(icmp_type: u8, id: u16, seq: u16, body: &[u8]) -> Vec<u8> {
+    let mut buf = vec![icmp_type, 0, 0, 0];
+    buf.extend_from_slice(&id.to_be_bytes());
+    buf.extend_from_slice(&seq.to_be_bytes());
+    buf.extend_from_slice(body);
+    let sum = internet_checksum(&buf);
+    buf[2..4].copy_from_slice(&sum.to_be_bytes());
+    buf
+}
+
+/// Build an ICMP Timestamp request (type 13).
+/// `originate_ms` is the local time in milliseconds since midnight UT.
+pub fn build_icmp_timestamp_request(id: u16, seq: u16, originate_ms: u32) -> Vec<u8> {
+    let mut body = [0u8; 12];
+    body[0..4].copy_from_slice(&originate_ms.to_be_bytes());
+    build_icmp_query(ICMP_TIMESTAMP_REQUEST, id, seq, &body)
+}
+
+/// Build an ICMP Address Mask request (type 17).
+pub fn build_icmp_address_mask_request(id: u16, seq: u16) -> Vec<u8> {
+    build_icmp_query(ICMP_ADDRESS_MASK_REQUEST, id, seq, &[0u8; 4])
+}
+
+/// Parse a Timestamp or Address Mask reply with the given identifier.
+/// Accepts packets with or without the IPv4 header (RAW vs DGRAM sockets).
+pub fn parse_icmp_query_reply(buf: &[u8], id: u16) -> Option<IcmpQueryReply> {
+    let icmp = match buf.first()? >> 4 {
+        4 => buf.get(((buf[0] & 0x0f) as usize * 4)..)?,
+        _ => buf,
+    };
+    if icmp.len() < 8 || icmp[1] != 0 || u16::from_be_bytes([icmp[4], icmp[5]]) != id {
+        return None;
+    }
+    let word = |offset: usize| -> Option<u32> {
+        Some(u32::from_be_bytes(
+            icmp.get(offset..offset + 4)?.try_into().ok()?,
+        ))
+    };
+    match icmp[0] {
+        ICMP_TIMESTAMP_REPLY => Some(IcmpQueryReply::Timestamp {
+            originate: word(8)?,
+            receive: word(12)?,
+            transmit: word(16)?,
+        }),
+        ICMP_ADDRESS_MASK_REPLY => Some(IcmpQueryReply::AddressMask(Ipv4Addr::from(word(8)?))),
+        _ => None,
+    }
+}
+
+/// Length of an IPv4 header without options
+pub const IPV4_HEADER_LEN: usize = 20;
+/// "More fragments" flag in the IPv4 flags/fragment offset field
+const IPV4_MORE_FRAGMENTS: u16 = 0x2000;
+const IPV4_DEFAULT_TTL: u8 = 64;
+
+/// Store a 16-bit length/offset field of a header-included IPv4 packet.
+/// macOS expects these two fields in host byte order on raw sockets.
+fn put_hdrincl_u16(buf: &mut [u8], value: u16) {
+    #[cfg(any(target_os = "macos", target_os = "ios"))]
+    buf.copy_from_slice(&value.to_ne_bytes());
+    #[cfg(not(any(target_os = "macos", target_os = "ios")))]
+    buf.copy_from_slice(&value.to_be_bytes());
+}
+
+/// Build an IPv4 packet for a header-included raw socket.
+/// `fragment_offset` is in bytes and must be a multiple of 8.
+fn build_ipv4_packet(
+    src: Ipv4Addr,
+    dst: Ipv4Addr,
+    protocol: u8,
+    id: u16,
+    fragment_offset: usize,
+    more_fragments: bool,
+    payload: &[u8],
+) -> Vec<u8> {
+    let mut buf = vec![0u8; IPV4_HEADER_LEN];
+    // Version 4, IHL 5
+    buf[0] = 0x45;
+    put_hdrincl_u16(&mut buf[2..4], (IPV4_HEADER_LEN + payload.len()) as u16);
+    buf[4..6].copy_from_slice(&id.to_be_bytes());
+    let mut frag = (fragment_offset / 8) as u16;
+    if more_fragments {
+        frag |= IPV4_MORE_FRAGMENTS;
+    }
+    put_hdrincl_u16(&mut buf[6..8], frag);
+    buf[8] = IPV4_DEFAULT_TTL;
+    buf[9] = protocol;
+    buf[12..16].copy_from_slice(&src.octets());
+    buf[16..20].copy_from_slice(&dst.octets());
+    let sum = internet_checksum(&buf);
+    buf[10..12].copy_from_slice(&sum.to_be_bytes());
+    buf.extend_from_slice(payload);
+    buf
+}
+
+/// Split a transport payload into IPv4 fragments, each carrying its own header.
+/// `fragment_size` is the payload bytes per fragment, rounded down to a
+/// multiple of 8 (minimum 8). All fragments share one IP identification.
+pub fn build_ipv4_fragments(
+    src: Ipv4Addr,
+    dst: Ipv4Addr,
+    protocol: u8,
+    payload: &[u8],
+    fragment_size: usize,
+) -> Vec<Vec<u8>> {
+    let id: u16 = rand::random();
+    if payload.is_empty() {
+        return vec![build_ipv4_packet(src, dst, protocol, id, 0, false, payload)];
+    }
+    let size = (fragment_size / 8 * 8).max(8);
+    payload
+        .chunks(size)
+        .enumerate()
+        .map(|(i, chunk)| {
+            let offset = i * size;
+            let more = offset + chunk.len() < payload.len();
+            build_ipv4_packet(src, dst, protocol, id, offset, more, chunk)
+        })
+        .collect()
+}
+
+/// Length of the fixed IPv6 header
+pub const IPV6_HEADER_LEN: usize = 40;
+const IPV6_DEFAULT_HOP_LIMIT: u8 = 64;
+const IPV6_NEXT_HOP_BY_HOP: u8 = 0;
+const IPV6_NEXT_FRAGMENT: u8 = 44;
+const IPV6_NEXT_DESTINATION_OPTIONS: u8 = 60;
+pub const IPV6_NEXT_ICMPV6: u8 = 58;
+const ICMPV6_PARAMETER_PROBLEM: u8 = 4;
+const ICMPV6_ECHO_REPLY: u8 = 129;
+
+fn ext_header_type(ext: Ipv6ExtHeader) -> u8 {
+    match ext {
+        Ipv6ExtHeader::HopByHop => IPV6_NEXT_HOP_BY_HOP,
+        Ipv6ExtHeader::DestinationOptions => IPV6_NEXT_DESTINATION_OPTIONS,
+        Ipv6ExtHeader::Fragment => IPV6_NEXT_FRAGMENT,
+    }
+}
+
+/// Encode one extension header. Options headers carry a single PadN option,
+/// the Fragment header is an atomic fragment (RFC 6946).
+fn build_ipv6_ext_header(ext: Ipv6ExtHeader, next_header: u8) -> [u8; 8] {
+    match ext {
+        Ipv6ExtHeader::HopByHop | Ipv6ExtHeader::DestinationOptions => {
+            // Hdr Ext Len 0 (8 bytes), PadN with 4 bytes of padding
+            [next_header, 0, 1, 4, 0, 0, 0, 0]
+        }
+        Ipv6ExtHeader::Fragment => {
+            let id: u32 = rand::random();
+            let id = id.to_be_bytes();
+            // Offset 0, M flag clear
+            [next_header, 0, 0, 0, id[0], id[1], id[2], id[3]]
         }
     }
-    None
+}
+
+/// Build an IPv6 packet for a header-included raw socket, with the given
+/// extension header chain in front of the upper-layer payload.
+pub fn build_ipv6_packet(
+    src: Ipv6Addr,
+    dst: Ipv6Addr,
+    flow_label: u32,
+    ext_headers: &[Ipv6ExtHeader],
+    next_header: u8,
+    payload: &[u8],
+) -> Vec<u8> {
+    let mut body = Vec::with_capacity(ext_headers.len() * 8 + payload.len());
+    for (i, ext) in ext_headers.iter().enumerate() {
+        let next = ext_headers
+            .get(i + 1)
+            .map(|e| ext_header_type(*e))
+            .unwrap_or(next_header);
+        body.extend_from_slice(&build_ipv6_ext_header(*ext, next));
+    }
+    body.extend_from_slice(payload);
+
+    let first = ext_headers
+        .first()
+        .map(|e| ext_header_type(*e))
+        .unwrap_or(next_header);
+    let mut buf = Vec::with_capacity(IPV6_HEADER_LEN + body.len());
+    // Version 6, traffic class 0
+    buf.extend_from_slice(&((6u32 << 28) | (flow_label & 0x000f_ffff)).to_be_bytes());
+    buf.extend_from_slice(&(body.len() as u16).to_be_bytes());
+    buf.push(first);
+    buf.push(IPV6_DEFAULT_HOP_LIMIT);
+    buf.extend_from_slice(&src.octets());
+    buf.extend_from_slice(&dst.octets());
+    buf.extend_from_slice(&body);
+    buf
+}
+
+/// Build an ICMPv6 Echo Request with its checksum over the IPv6 pseudo-header,
+/// for sending on a header-included socket where the kernel does not fill it in.
+pub fn build_icmpv6_echo_request(
+    src: Ipv6Addr,
+    dst: Ipv6Addr,
+    id: u16,
+    seq: u16,
+    payload: &[u8],
+) -> Vec<u8> {
+    let mut msg = vec![128, 0, 0, 0];
+    msg.extend_from_slice(&id.to_be_bytes());
+    msg.extend_from_slice(&seq.to_be_bytes());
+    msg.extend_from_slice(payload);
+
+    let mut pseudo = Vec::with_capacity(40 + msg.len());
+    pseudo.extend_from_slice(&src.octets());
+    pseudo.extend_from_slice(&dst.octets());
+    pseudo.extend_from_slice(&(msg.len() as u32).to_be_bytes());
+    pseudo.extend_from_slice(&[0, 0, 0, IPV6_NEXT_ICMPV6]);
+    pseudo.extend_from_slice(&msg);
+    let sum = internet_checksum(&pseudo);
+    msg[2..4].copy_from_slice(&sum.to_be_bytes());
+    msg
+}
+
+/// ICMPv6 message answering a probe built with `build_ipv6_packet`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Icmpv6ProbeReply {
+    EchoReply,
+    /// Error message quoting the probe. `pointer` is set for Parameter Problem.
+    Error {
+        icmp_type: u8,
+        code: u8,
+        pointer: Option<u32>,
+    },
+}
+
+/// Parse an ICMPv6 message (without IPv6 header) answering a probe.
+/// Echo replies are matched by identifier, error messages by the flow label
+/// and destination of the quoted packet.
+pub fn parse_icmpv6_probe_reply(
+    buf: &[u8],
+    id: u16,
+    dst: Ipv6Addr,
+    flow_label: u32,
+) -> Option<Icmpv6ProbeReply> {
+    if buf.len() < 8 {
+        return None;
+    }
+    let (icmp_type, code) = (buf[0], buf[1]);
+    if icmp_type == ICMPV6_ECHO_REPLY {
+        return (u16::from_be_bytes([buf[4], buf[5]]) == id).then_some(Icmpv6ProbeReply::EchoReply);
+    }
+    // Error messages have types below 128
+    if icmp_type >= 128 {
+        return None;
+    }
+    let quoted = buf.get(8..8 + IPV6_HEADER_LEN)?;
+    let quoted_flow =
+        u32::from_be_bytes([quoted[0], quoted[1], quoted[2], quoted[3]]) & 0x000f_ffff;
+    let quoted_dst: [u8; 16] = quoted[24..40].try_into().ok()?;
+    if quoted_flow != flow_label & 0x000f_ffff || Ipv6Addr::from(quoted_dst) != dst {
+        return None;
+    }
+    let pointer = (icmp_type == ICMPV6_PARAMETER_PROBLEM)
+        .then(|| u32::from_be_bytes([buf[4], buf[5], buf[6], buf[7]]));
+    Some(Icmpv6ProbeReply::Error {
+        icmp_type,
+        code,
+        pointer,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn echo_reply_v4(id: u16, seq: u16) -> Vec<u8> {
+        let mut icmp = vec![0, 0, 0, 0];
+        icmp.extend_from_slice(&id.to_be_bytes());
+        icmp.extend_from_slice(&seq.to_be_bytes());
+        icmp.extend_from_slice(b"np:hs");
+        let sum = internet_checksum(&icmp);
+        icmp[2..4].copy_from_slice(&sum.to_be_bytes());
+        icmp
+    }
+
+    /// Prepend an IPv4 header as delivered on RAW sockets
+    fn with_ipv4_header(icmp: &[u8]) -> Vec<u8> {
+        let mut ip = vec![0u8; IPV4_HEADER_LEN];
+        ip[0] = 0x45;
+        ip[2..4].copy_from_slice(&((IPV4_HEADER_LEN + icmp.len()) as u16).to_be_bytes());
+        ip[8] = IPV4_DEFAULT_TTL;
+        ip[9] = 1;
+        ip[12..16].copy_from_slice(&[192, 0, 2, 2]);
+        ip[16..20].copy_from_slice(&[192, 0, 2, 1]);
+        let sum = internet_checksum(&ip);
+        ip[10..12].copy_from_slice(&sum.to_be_bytes());
+        ip.extend_from_slice(icmp);
+        ip
+    }
+
+    fn echo_reply_v6(id: u16, seq: u16) -> Vec<u8> {
+        let mut icmp = vec![129, 0, 0, 0];
+        icmp.extend_from_slice(&id.to_be_bytes());
+        icmp.extend_from_slice(&seq.to_be_bytes());
+        icmp.extend_from_slice(b"np:hs");
+        icmp
+    }
+
+    #[test]
+    fn echo_v4_matches_own_id() {
+        let icmp = echo_reply_v4(0x1111, 1);
+        // Linux DGRAM sockets deliver the ICMP message only
+        assert!(parse_icmp_echo_v4(&icmp, 0x1111).is_some());
+        // RAW sockets include the IPv4 header
+        assert!(parse_icmp_echo_v4(&with_ipv4_header(&icmp), 0x1111).is_some());
+    }
+
+    #[test]
+    fn echo_v4_ignores_other_id() {
+        let icmp = echo_reply_v4(0x2222, 1);
+        assert!(parse_icmp_echo_v4(&icmp, 0x1111).is_none());
+        assert!(parse_icmp_echo_v4(&with_ipv4_header(&icmp), 0x1111).is_none());
+    }
+
+    #[test]
+    fn echo_v6_ignores_other_id_and_type() {
+        assert!(parse_icmp_echo_v6(&echo_reply_v6(0x1111, 1), 0x1111).is_some());
+        assert!(parse_icmp_echo_v6(&echo_reply_v6(0x2222, 1), 0x1111).is_none());
+        // Destination Unreachable is not an echo reply
+        let mut unreach = echo_reply_v6(0x1111, 1);
+        unreach[0] = 1;
+        assert!(parse_icmp_echo_v6(&unreach, 0x1111).is_none());
+    }
+
+    #[test]
+    fn unreachable_matches_quoted_echo() {
+        let mut request = echo_reply_v4(0x1111, 1);
+        request[0] = ICMP_ECHO_REQUEST;
+        let quoted = with_ipv4_header(&request);
+        let mut icmp = vec![ICMP_DEST_UNREACHABLE, 13, 0, 0, 0, 0, 0, 0];
+        icmp.extend_from_slice(&quoted);
+        let expected = EchoUnreachable {
+            dst: IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1)),
+            id: 0x1111,
+            code: 13,
+        };
+        assert_eq!(parse_icmp_unreachable_v4(&icmp), Some(expected));
+        assert_eq!(
+            parse_icmp_unreachable_v4(&with_ipv4_header(&icmp)),
+            Some(expected)
+        );
+        // An unreachable quoting an echo reply is not about our request
+        icmp[8 + IPV4_HEADER_LEN] = 0;
+        assert!(parse_icmp_unreachable_v4(&icmp).is_none());
+        assert_eq!(
+            icmp_unreachable_reason(13),
+            "communication administratively prohibited"
+        );
+
+        let dst: Ipv6Addr = "2001:db8::1".parse().unwrap();
+        let mut request = echo_reply_v6(0x1111, 1);
+        request[0] = ICMPV6_ECHO_REQUEST;
+        let mut icmp = vec![ICMPV6_DEST_UNREACHABLE, 3, 0, 0, 0, 0, 0, 0];
+        icmp.extend_from_slice(&build_ipv6_packet(
+            Ipv6Addr::LOCALHOST,
+            dst,
+            0,
+            &[],
+            IPV6_NEXT_ICMPV6,
+            &request,
+        ));
+        let unreach = parse_icmpv6_unreachable(&icmp).unwrap();
+        assert_eq!((unreach.dst, unreach.id), (IpAddr::V6(dst), 0x1111));
+        assert_eq!(
+            icmpv6_unreachable_reason(unreach.code),
+            "address unreachable"
+        );
+        // Echo replies are not errors
+        assert!(parse_icmpv6_unreachable(&request).is_none());
+    }
+
+    /// Read a length/offset field as `put_hdrincl_u16` stored it
+    fn hdrincl_u16(buf: &[u8]) -> u16 {
+        let bytes = [buf[0], buf[1]];
+        #[cfg(any(target_os = "macos", target_os = "ios"))]
+        let value = u16::from_ne_bytes(bytes);
+        #[cfg(not(any(target_os = "macos", target_os = "ios")))]
+        let value = u16::from_be_bytes(bytes);
+        value
+    }
+
+    #[test]
+    fn fragments_reassemble_to_payload() {
+        let src = Ipv4Addr::new(192, 0, 2, 1);
+        let dst = Ipv4Addr::new(192, 0, 2, 2);
+        let payload: Vec<u8> = (0..30).collect();
+        // 20 rounds down to 16 payload bytes per fragment
+        let fragments = build_ipv4_fragments(src, dst, 6, &payload, 20);
+        assert_eq!(fragments.len(), 2);
+
+        let mut reassembled = Vec::new();
+        for (i, frag) in fragments.iter().enumerate() {
+            let header = &frag[..IPV4_HEADER_LEN];
+            assert_eq!(internet_checksum(header), 0);
+            assert_eq!(hdrincl_u16(&header[2..4]) as usize, frag.len());
+            // Every fragment carries the identification of the first
+            assert_eq!(header[4..6], fragments[0][4..6]);
+            assert_eq!((header[9], &header[12..16]), (6, &src.octets()[..]));
+            assert_eq!(&header[16..20], &dst.octets());
+
+            let field = hdrincl_u16(&header[6..8]);
+            let offset = ((field & 0x1fff) * 8) as usize;
+            let more = field & IPV4_MORE_FRAGMENTS != 0;
+            assert_eq!(offset, i * 16);
+            assert_eq!(more, i + 1 < fragments.len());
+            reassembled.extend_from_slice(&frag[IPV4_HEADER_LEN..]);
+        }
+        assert_eq!(reassembled, payload);
+    }
+
+    #[test]
+    fn fragment_size_has_a_floor_of_eight() {
+        let src = Ipv4Addr::new(192, 0, 2, 1);
+        let dst = Ipv4Addr::new(192, 0, 2, 2);
+        let fragments = build_ipv4_fragments(src, dst, 17, &[0u8; 20], 1);
+        let sizes: Vec<_> = fragments
+            .iter()
+            .map(|f| f.len() - IPV4_HEADER_LEN)
+            .collect();
+        assert_eq!(sizes, [8, 8, 4]);
+        // An empty payload is still sent, unfragmented
+        let empty = build_ipv4_fragments(src, dst, 17, &[], 8);
+        assert_eq!(empty.len(), 1);
+        assert_eq!(hdrincl_u16(&empty[0][6..8]), 0);
+    }
 }
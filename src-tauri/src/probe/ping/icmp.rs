@@ -1,20 +1,29 @@
 use anyhow::Result;
 use std::{
-    net::{IpAddr, SocketAddr},
+    io,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
     sync::Arc,
-    time::{Duration, Instant},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
 use tauri::{AppHandle, Emitter};
 
 use crate::{
     model::{
         ping::{
-            PingDonePayload, PingProgressPayload, PingProtocol, PingSample, PingSetting, PingStat,
+            IcmpQueryKind, IcmpQueryResult, Ipv6ExtHeader, Ipv6ExtProbeOutcome, Ipv6ExtProbeReport,
+            Ipv6ExtProbeResult, PingDonePayload, PingProgressPayload, PingProtocol, PingSample,
+            PingSetting, PingStat,
         },
         probe::{ProbeStatus, ProbeStatusKind},
     },
-    probe::packet::{build_icmp_echo_bytes, parse_icmp_echo_v4, parse_icmp_echo_v6},
-    socket::icmp::{AsyncIcmpSocket, IcmpConfig, IcmpKind},
+    probe::packet::{
+        build_icmp_address_mask_request, build_icmp_echo_bytes, build_icmp_timestamp_request,
+        build_icmpv6_echo_request, build_ipv6_packet, parse_icmp_echo_v4, parse_icmp_echo_v6,
+        parse_icmp_query_reply, parse_icmpv6_probe_reply, IcmpQueryReply, Icmpv6ProbeReply,
+        IPV6_NEXT_ICMPV6,
+    },
+    socket::icmp::{AsyncIcmpSocket, IcmpConfig, IcmpKind, IcmpSocketType},
+    socket::ip::AsyncRawIpSocket,
 };
 
 fn summarize_rtts(rtts_ms: &[u64]) -> (Option<u64>, Option<u64>, Option<u64>) {
@@ -56,13 +65,15 @@ pub async fn icmp_ping(
     let socket = match AsyncIcmpSocket::new(&cfg).await {
         Ok(s) => Arc::new(s),
         Err(e) => {
-            return Err(anyhow::anyhow!("failed to create ICMP socket: {}", e));
+            return Err(
+                io::Error::new(e.kind(), format!("failed to create ICMP socket: {}", e)).into(),
+            );
         }
     };
 
     let target = SocketAddr::new(setting.ip_addr, 0);
 
-    let echo_id: u16 = 0x1234;
+    let echo_id: u16 = rand::random();
     let payload = b"np:ping";
 
     let mut samples = Vec::with_capacity(setting.count as usize);
@@ -81,32 +92,37 @@ pub async fn icmp_ping(
         if let Err(e) = socket.send_to(&pkt, target).await {
             status = ProbeStatus::with_error_message(format!("send error: {e}"));
         } else {
-            // Wait for response (with timeout)
+            // Wait for our reply (with timeout), skipping unrelated ICMP traffic
             let mut buf = vec![0u8; 2048];
             let to = Duration::from_millis(setting.timeout_ms);
-            match tokio::time::timeout(to, socket.recv_from(&mut buf)).await {
-                Err(_) => {
-                    status = ProbeStatus::with_timeout_message(format!(
-                        "timeout (>{}ms)",
-                        setting.timeout_ms
-                    ));
-                }
-                Ok(Err(e)) => {
-                    status = ProbeStatus::with_error_message(format!("recv error: {e}"));
-                }
-                Ok(Ok((n, _addr))) => {
-                    let ok = match setting.ip_addr {
-                        IpAddr::V4(_) => parse_icmp_echo_v4(&buf[..n]).is_some(),
-                        IpAddr::V6(_) => parse_icmp_echo_v6(&buf[..n]).is_some(),
-                    };
-                    if ok {
+            let deadline = tokio::time::Instant::now() + to;
+            let id = socket.echo_id(echo_id);
+            loop {
+                match tokio::time::timeout_at(deadline, socket.recv_from(&mut buf)).await {
+                    Err(_) => {
+                        status = ProbeStatus::with_timeout_message(format!(
+                            "timeout (>{}ms)",
+                            setting.timeout_ms
+                        ));
+                    }
+                    Ok(Err(e)) => {
+                        status = ProbeStatus::with_error_message(format!("recv error: {e}"));
+                    }
+                    Ok(Ok((n, addr))) => {
+                        let ok = addr.ip() == setting.ip_addr
+                            && match setting.ip_addr {
+                                IpAddr::V4(_) => parse_icmp_echo_v4(&buf[..n], id).is_some(),
+                                IpAddr::V6(_) => parse_icmp_echo_v6(&buf[..n], id).is_some(),
+                            };
+                        if !ok {
+                            continue;
+                        }
                         let rtt = sent_at.elapsed().as_millis() as u64;
                         rtt_ms = Some(rtt);
                         rtts_ok.push(rtt);
-                    } else {
-                        status = ProbeStatus::with_error_message("unexpected reply".to_string());
                     }
                 }
+                break;
             }
         }
 
@@ -177,3 +193,199 @@ pub async fn icmp_ping(
 
     Ok(stat)
 }
+
+const MS_PER_DAY: i64 = 86_400_000;
+
+/// Current time in milliseconds since midnight UT, as used by ICMP timestamps
+fn ms_since_midnight_ut() -> u32 {
+    let ms = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis())
+        .unwrap_or(0);
+    (ms % MS_PER_DAY as u128) as u32
+}
+
+/// Difference of two times of day, wrapped into (-12h, 12h]
+fn day_time_diff(a: u32, b: u32) -> i64 {
+    let mut diff = (a as i64 - b as i64) % MS_PER_DAY;
+    if diff > MS_PER_DAY / 2 {
+        diff -= MS_PER_DAY;
+    } else if diff <= -MS_PER_DAY / 2 {
+        diff += MS_PER_DAY;
+    }
+    diff
+}
+
+/// Send an ICMP Timestamp or Address Mask request and wait for the reply.
+/// A host that does not answer yields `responded: false` rather than an error.
+pub async fn icmp_query(
+    ip_addr: Ipv4Addr,
+    kind: IcmpQueryKind,
+    timeout: Duration,
+) -> Result<IcmpQueryResult> {
+    // Ping sockets (DGRAM) only allow echo requests on Linux
+    let cfg = IcmpConfig::new(IcmpKind::V4).with_sock_type(IcmpSocketType::Raw);
+    let socket = AsyncIcmpSocket::new(&cfg)
+        .await
+        .map_err(|e| io::Error::new(e.kind(), format!("failed to create ICMP socket: {}", e)))?;
+
+    let id: u16 = rand::random();
+    let originate = ms_since_midnight_ut();
+    let pkt = match kind {
+        IcmpQueryKind::Timestamp => build_icmp_timestamp_request(id, 1, originate),
+        IcmpQueryKind::AddressMask => build_icmp_address_mask_request(id, 1),
+    };
+    let target = SocketAddr::new(IpAddr::V4(ip_addr), 0);
+
+    let mut result = IcmpQueryResult {
+        ip_addr: IpAddr::V4(ip_addr),
+        kind,
+        responded: false,
+        rtt_ms: None,
+        remote_receive_ms: None,
+        remote_transmit_ms: None,
+        clock_offset_ms: None,
+        netmask: None,
+        message: None,
+    };
+
+    let sent_at = Instant::now();
+    socket.send_to(&pkt, target).await?;
+
+    let deadline = tokio::time::Instant::now() + timeout;
+    let mut buf = vec![0u8; 2048];
+    let reply = loop {
+        match tokio::time::timeout_at(deadline, socket.recv_from(&mut buf)).await {
+            Err(_) => break None,
+            Ok(Err(e)) => return Err(e.into()),
+            Ok(Ok((n, addr))) => {
+                if addr.ip() != IpAddr::V4(ip_addr) {
+                    continue;
+                }
+                if let Some(reply) = parse_icmp_query_reply(&buf[..n], id) {
+                    break Some(reply);
+                }
+            }
+        }
+    };
+    let local_receive = ms_since_midnight_ut();
+
+    match reply {
+        None => {
+            result.message = Some(format!("no response (>{}ms)", timeout.as_millis()));
+        }
+        Some(IcmpQueryReply::Timestamp {
+            receive, transmit, ..
+        }) => {
+            result.responded = true;
+            result.rtt_ms = Some(sent_at.elapsed().as_millis() as u64);
+            result.remote_receive_ms = Some(receive);
+            result.remote_transmit_ms = Some(transmit);
+            // The high-order bit marks a non-standard time value (RFC 792)
+            if receive & 0x8000_0000 == 0 && transmit & 0x8000_0000 == 0 {
+                let offset = (day_time_diff(receive, originate)
+                    + day_time_diff(transmit, local_receive))
+                    / 2;
+                result.clock_offset_ms = Some(offset);
+            } else {
+                result.message = Some("non-standard timestamp".to_string());
+            }
+        }
+        Some(IcmpQueryReply::AddressMask(mask)) => {
+            result.responded = true;
+            result.rtt_ms = Some(sent_at.elapsed().as_millis() as u64);
+            result.netmask = Some(mask);
+        }
+    }
+    Ok(result)
+}
+
+/// Local address the kernel would use to reach `dst`
+fn source_addr_v6(dst: Ipv6Addr) -> std::io::Result<Ipv6Addr> {
+    let socket = std::net::UdpSocket::bind((Ipv6Addr::UNSPECIFIED, 0))?;
+    socket.connect((dst, 9))?;
+    match socket.local_addr()?.ip() {
+        IpAddr::V6(addr) => Ok(addr),
+        IpAddr::V4(_) => Err(std::io::Error::from(std::io::ErrorKind::AddrNotAvailable)),
+    }
+}
+
+/// Send one ICMPv6 echo per extension header chain and record how the
+/// target (or a middlebox on the path) answered each. Linux only.
+pub async fn ipv6_ext_probe(
+    ip_addr: Ipv6Addr,
+    chains: Vec<Vec<Ipv6ExtHeader>>,
+    timeout: Duration,
+) -> Result<Ipv6ExtProbeReport> {
+    let src = source_addr_v6(ip_addr)
+        .map_err(|e| io::Error::new(e.kind(), format!("no route to {}: {}", ip_addr, e)))?;
+    let sender = AsyncRawIpSocket::new_v6_header_included().map_err(|e| {
+        io::Error::new(e.kind(), format!("failed to create raw IPv6 socket: {}", e))
+    })?;
+    // Replies to header-included probes do not reach ping (DGRAM) sockets
+    let cfg = IcmpConfig::new(IcmpKind::V6).with_sock_type(IcmpSocketType::Raw);
+    let receiver = AsyncIcmpSocket::new(&cfg)
+        .await
+        .map_err(|e| io::Error::new(e.kind(), format!("failed to create ICMPv6 socket: {}", e)))?;
+    let target = SocketAddr::new(IpAddr::V6(ip_addr), 0);
+
+    let mut results = Vec::with_capacity(chains.len());
+    let mut buf = vec![0u8; 2048];
+    for (seq, chain) in chains.into_iter().enumerate() {
+        let id: u16 = rand::random();
+        let flow_label: u32 = rand::random::<u32>() & 0x000f_ffff;
+        let echo = build_icmpv6_echo_request(src, ip_addr, id, seq as u16, b"np:ext6");
+        let pkt = build_ipv6_packet(src, ip_addr, flow_label, &chain, IPV6_NEXT_ICMPV6, &echo);
+
+        let sent_at = Instant::now();
+        sender.send_to(&pkt, target).await?;
+
+        let deadline = tokio::time::Instant::now() + timeout;
+        let reply = loop {
+            match tokio::time::timeout_at(deadline, receiver.recv_from(&mut buf)).await {
+                Err(_) => break None,
+                Ok(Err(e)) => return Err(e.into()),
+                Ok(Ok((n, addr))) => {
+                    if let Some(reply) =
+                        parse_icmpv6_probe_reply(&buf[..n], id, ip_addr, flow_label)
+                    {
+                        break Some((reply, addr.ip()));
+                    }
+                }
+            }
+        };
+
+        let mut result = Ipv6ExtProbeResult {
+            ext_headers: chain,
+            outcome: Ipv6ExtProbeOutcome::NoResponse,
+            rtt_ms: None,
+            responder: None,
+            icmp_type: None,
+            icmp_code: None,
+            pointer: None,
+        };
+        if let Some((reply, responder)) = reply {
+            result.rtt_ms = Some(sent_at.elapsed().as_millis() as u64);
+            result.responder = Some(responder);
+            match reply {
+                Icmpv6ProbeReply::EchoReply => result.outcome = Ipv6ExtProbeOutcome::EchoReply,
+                Icmpv6ProbeReply::Error {
+                    icmp_type,
+                    code,
+                    pointer,
+                } => {
+                    result.outcome = if pointer.is_some() {
+                        Ipv6ExtProbeOutcome::ParameterProblem
+                    } else {
+                        Ipv6ExtProbeOutcome::IcmpError
+                    };
+                    result.icmp_type = Some(icmp_type);
+                    result.icmp_code = Some(code);
+                    result.pointer = pointer;
+                }
+            }
+        }
+        results.push(result);
+    }
+    Ok(Ipv6ExtProbeReport { ip_addr, results })
+}
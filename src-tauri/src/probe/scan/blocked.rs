@@ -0,0 +1,261 @@
+use std::collections::HashMap;
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::socket::icmp::{AsyncIcmpSocket, IcmpConfig, IcmpKind, IcmpSocketType};
+
+/// Time given to the ICMP listener to record the error that failed a connect
+const ICMP_GRACE: Duration = Duration::from_millis(20);
+
+/// Evidence that a firewall rejected a connect probe. A RST is not one:
+/// it proves the port (or a device answering for it) processed the SYN,
+/// so the port is reported closed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlockSignal {
+    /// The local stack refused to send the SYN: netfilter DROP on output
+    /// (EPERM), a `prohibit` route (EACCES) or an outbound Windows Firewall
+    /// rule (WSAEACCES)
+    LocalPolicy,
+    /// ICMP destination unreachable with an administratively prohibited code
+    IcmpProhibited(u8),
+}
+
+impl BlockSignal {
+    pub fn reason(&self) -> String {
+        match self {
+            BlockSignal::LocalPolicy => "blocked by local firewall or route policy".to_string(),
+            BlockSignal::IcmpProhibited(code) => {
+                format!(
+                    "administratively prohibited (ICMP unreachable code {})",
+                    code
+                )
+            }
+        }
+    }
+}
+
+/// Whether an ICMP destination unreachable code is a filter's rejection:
+/// network or host administratively prohibited (9, 10) and communication
+/// administratively prohibited (13) for ICMP, code 1 for ICMPv6.
+fn is_prohibited(v6: bool, code: u8) -> bool {
+    if v6 {
+        code == 1
+    } else {
+        matches!(code, 9 | 10 | 13)
+    }
+}
+
+/// TCP destination port and code of an ICMP destination unreachable quoting
+/// a probe to `target`. IPv4 raw sockets deliver the IP header too, ICMPv6
+/// raw sockets the message only.
+fn parse_unreachable(packet: &[u8], target: IpAddr) -> Option<(u16, u8)> {
+    const TCP: u8 = 6;
+    let (icmp, unreachable_type) = match target {
+        IpAddr::V4(_) => {
+            let ihl = ((packet.first()? & 0x0f) as usize) * 4;
+            (packet.get(ihl..)?, 3)
+        }
+        IpAddr::V6(_) => (packet, 1),
+    };
+    if *icmp.first()? != unreachable_type {
+        return None;
+    }
+    let code = *icmp.get(1)?;
+    let quoted = icmp.get(8..)?;
+    let (quoted_dst, protocol, tcp) = match target {
+        IpAddr::V4(_) => {
+            let ihl = ((quoted.first()? & 0x0f) as usize) * 4;
+            let dst: [u8; 4] = quoted.get(16..20)?.try_into().ok()?;
+            (
+                IpAddr::from(Ipv4Addr::from(dst)),
+                *quoted.get(9)?,
+                quoted.get(ihl..)?,
+            )
+        }
+        IpAddr::V6(_) => {
+            let dst: [u8; 16] = quoted.get(24..40)?.try_into().ok()?;
+            (
+                IpAddr::from(Ipv6Addr::from(dst)),
+                *quoted.get(6)?,
+                quoted.get(40..)?,
+            )
+        }
+    };
+    if quoted_dst != target || protocol != TCP {
+        return None;
+    }
+    let port = u16::from_be_bytes([*tcp.get(2)?, *tcp.get(3)?]);
+    Some((port, code))
+}
+
+/// Collects firewall rejections of the probes to one target. Listens for
+/// ICMP administratively prohibited errors when a raw ICMP socket can be
+/// opened; local policy rejections are detected without it.
+pub struct BlockDetector {
+    /// Prohibited codes seen, by probed port. `None` without the listener.
+    codes: Option<Arc<Mutex<HashMap<u16, u8>>>>,
+    listener: Option<tokio::task::JoinHandle<()>>,
+}
+
+impl BlockDetector {
+    pub async fn start(target: IpAddr) -> Self {
+        let kind = if target.is_ipv4() {
+            IcmpKind::V4
+        } else {
+            IcmpKind::V6
+        };
+        let cfg = IcmpConfig::new(kind).with_sock_type(IcmpSocketType::Raw);
+        let socket = match AsyncIcmpSocket::new(&cfg).await {
+            Ok(s) if s.socket_type().is_raw() => s,
+            Ok(_) => return Self::local_only(),
+            Err(e) => {
+                tracing::debug!("no ICMP listener for blocked detection: {}", e);
+                return Self::local_only();
+            }
+        };
+        let codes = Arc::new(Mutex::new(HashMap::new()));
+        let seen = codes.clone();
+        let listener = tokio::spawn(async move {
+            let mut buf = vec![0u8; 2048];
+            while let Ok((n, _addr)) = socket.recv_from(&mut buf).await {
+                let Some((port, code)) = parse_unreachable(&buf[..n], target) else {
+                    continue;
+                };
+                if is_prohibited(target.is_ipv6(), code) {
+                    seen.lock()
+                        .unwrap_or_else(|e| e.into_inner())
+                        .insert(port, code);
+                }
+            }
+        });
+        Self {
+            codes: Some(codes),
+            listener: Some(listener),
+        }
+    }
+
+    /// Detector of local policy rejections only
+    pub fn local_only() -> Self {
+        Self {
+            codes: None,
+            listener: None,
+        }
+    }
+
+    /// Firewall rejection behind a failed connect to `port`, if any
+    pub async fn signal(&self, e: &io::Error, port: u16) -> Option<BlockSignal> {
+        use io::ErrorKind as E;
+        match e.kind() {
+            E::PermissionDenied => return Some(BlockSignal::LocalPolicy),
+            // The SYN was answered
+            E::ConnectionRefused | E::ConnectionReset => return None,
+            _ => {}
+        }
+        let codes = self.codes.as_ref()?;
+        let take = || {
+            codes
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .remove(&port)
+        };
+        if let Some(code) = take() {
+            return Some(BlockSignal::IcmpProhibited(code));
+        }
+        // The listener may not have handled the error that failed the connect yet
+        tokio::time::sleep(ICMP_GRACE).await;
+        take().map(BlockSignal::IcmpProhibited)
+    }
+}
+
+impl Drop for BlockDetector {
+    fn drop(&mut self) {
+        if let Some(listener) = &self.listener {
+            listener.abort();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TARGET_V4: Ipv4Addr = Ipv4Addr::new(192, 0, 2, 7);
+
+    /// ICMP destination unreachable as received on a raw IPv4 socket,
+    /// quoting a SYN to `dst:port`
+    fn unreachable_v4(code: u8, dst: Ipv4Addr, protocol: u8, port: u16) -> Vec<u8> {
+        let mut p = vec![
+            0x45, 0, 0, 0, 0, 0, 0, 0, 64, 1, 0, 0, 192, 0, 2, 254, 192, 0, 2, 1,
+        ];
+        p.extend_from_slice(&[3, code, 0, 0, 0, 0, 0, 0]);
+        p.extend_from_slice(&[0x45, 0, 0, 0, 0, 0, 0, 0, 64, protocol, 0, 0, 192, 0, 2, 1]);
+        p.extend_from_slice(&dst.octets());
+        p.extend_from_slice(&40000u16.to_be_bytes());
+        p.extend_from_slice(&port.to_be_bytes());
+        p.extend_from_slice(&[0, 0, 0, 0]);
+        p
+    }
+
+    #[test]
+    fn parses_quoted_tcp_probe() {
+        let target = IpAddr::V4(TARGET_V4);
+        assert_eq!(
+            parse_unreachable(&unreachable_v4(13, TARGET_V4, 6, 443), target),
+            Some((443, 13))
+        );
+        // Another target, or not a TCP probe
+        let other = Ipv4Addr::new(192, 0, 2, 8);
+        assert_eq!(
+            parse_unreachable(&unreachable_v4(13, other, 6, 443), target),
+            None
+        );
+        assert_eq!(
+            parse_unreachable(&unreachable_v4(13, TARGET_V4, 17, 443), target),
+            None
+        );
+
+        let v6: Ipv6Addr = "2001:db8::7".parse().unwrap();
+        let mut p = vec![1, 1, 0, 0, 0, 0, 0, 0];
+        let mut quoted = vec![0x60, 0, 0, 0, 0, 20, 6, 64];
+        quoted.extend_from_slice(&Ipv6Addr::LOCALHOST.octets());
+        quoted.extend_from_slice(&v6.octets());
+        p.extend_from_slice(&quoted);
+        p.extend_from_slice(&[0x9c, 0x40, 0, 22]);
+        assert_eq!(parse_unreachable(&p, IpAddr::V6(v6)), Some((22, 1)));
+    }
+
+    #[test]
+    fn prohibited_codes() {
+        assert!([9, 10, 13].iter().all(|&c| is_prohibited(false, c)));
+        // Host and port unreachable are not rejections
+        assert!(!is_prohibited(false, 1) && !is_prohibited(false, 3));
+        assert!(is_prohibited(true, 1) && !is_prohibited(true, 3));
+    }
+
+    #[tokio::test]
+    async fn signals() {
+        let local = BlockDetector::local_only();
+        let denied = io::Error::from(io::ErrorKind::PermissionDenied);
+        assert_eq!(
+            local.signal(&denied, 80).await,
+            Some(BlockSignal::LocalPolicy)
+        );
+        let unreachable = io::Error::from(io::ErrorKind::HostUnreachable);
+        assert_eq!(local.signal(&unreachable, 80).await, None);
+
+        let detector = BlockDetector {
+            codes: Some(Arc::new(Mutex::new(HashMap::from([(80, 13), (443, 10)])))),
+            listener: None,
+        };
+        assert_eq!(
+            detector.signal(&unreachable, 80).await,
+            Some(BlockSignal::IcmpProhibited(13))
+        );
+        // A RST wins over a stale ICMP error
+        let refused = io::Error::from(io::ErrorKind::ConnectionRefused);
+        assert_eq!(detector.signal(&refused, 443).await, None);
+        assert_eq!(detector.signal(&unreachable, 22).await, None);
+    }
+}
@@ -0,0 +1,94 @@
+use anyhow::Result;
+use std::net::IpAddr;
+use std::time::Duration;
+
+use crate::model::endpoint::{Endpoint, Port, TransportProtocol};
+use crate::model::scan::{PortScanProtocol, PortScanSample, PortState, ScanRouting};
+use crate::probe::scan::{quic, tcp};
+use crate::probe::service::{DetectionIntensity, ServiceDetector, ServiceProbeConfig};
+
+/// Probe exactly one port, without presets, events or history.
+/// Uses the scanners' socket logic so the state matches a full scan.
+pub async fn check_port(
+    ip: IpAddr,
+    port: u16,
+    protocol: PortScanProtocol,
+    timeout: Duration,
+    service_detection: bool,
+) -> Result<PortScanSample> {
+    let mut sample = PortScanSample {
+        ip_addr: ip,
+        port,
+        state: PortState::Unknown,
+        rtt_ms: None,
+        message: None,
+        service_name: None,
+        service_info: None,
+        reply_ttl: None,
+        quic_handshake: None,
+        done: 1,
+        total: 1,
+    };
+    let transport = match protocol {
+        PortScanProtocol::Tcp => {
+            let (state, rtt_ms, message) = tcp::probe_port(ip, port, timeout).await;
+            sample.state = state;
+            sample.rtt_ms = rtt_ms;
+            sample.message = message;
+            sample.service_name = ndb_tcp_service::TcpServiceDb::bundled()
+                .get(port)
+                .map(|entry| entry.name.clone());
+            TransportProtocol::Tcp
+        }
+        PortScanProtocol::Quic => {
+            let outcome = quic::probe_port(ip, port, None, timeout).await;
+            sample.state = outcome.state;
+            sample.rtt_ms = outcome.rtt_ms;
+            sample.message = outcome.message;
+            sample.service_info = outcome.service_info;
+            sample.quic_handshake = outcome.handshake;
+            sample.service_name = ndb_udp_service::UdpServiceDb::bundled()
+                .get(port)
+                .map(|entry| entry.name.clone());
+            TransportProtocol::Quic
+        }
+    };
+    if !service_detection || sample.state != PortState::Open {
+        return Ok(sample);
+    }
+
+    let detector = ServiceDetector::new(ServiceProbeConfig {
+        timeout: Duration::from_secs(2),
+        max_concurrency: 1,
+        max_read_size: 1024 * 1024,
+        sni: true,
+        skip_cert_verify: true,
+        intensity: DetectionIntensity::Normal,
+        read_only: false,
+        raw_hex_limit: None,
+        skip_intrusive: false,
+        tls_resumption: false,
+        tls_enum: false,
+        routing: ScanRouting::default(),
+    });
+    let mut endpoint = Endpoint::new(ip);
+    endpoint.upsert_port(Port {
+        number: port,
+        transport,
+    });
+    let result = detector.run_service_detection(vec![endpoint]).await?;
+    if let Some(res) = result.results.into_iter().find(|r| r.port == port) {
+        let mut info = res.service_info;
+        // Keep the QUIC version and ALPN seen during the handshake
+        if let Some(scan_info) = sample.service_info.take() {
+            if info.quic_version.is_none() {
+                info.quic_version = scan_info.quic_version;
+            }
+            if info.tls_info.is_none() {
+                info.tls_info = scan_info.tls_info;
+            }
+        }
+        sample.service_info = Some(info);
+    }
+    Ok(sample)
+}
@@ -0,0 +1,281 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, OnceLock, Weak};
+use std::time::Duration;
+use tokio::sync::{Mutex, Notify};
+
+use crate::checkpoint::Checkpointer;
+use crate::probe::scan::progress::SharedProgress;
+use crate::probe::scan::sink::ProgressEmitter;
+
+/// Run-time control shared between a running scan and the commands driving it.
+#[derive(Debug)]
+pub struct ScanControl {
+    /// Run ID of the scan.
+    run_id: String,
+    /// Event name prefix (e.g. "portscan", "hostscan").
+    event_prefix: &'static str,
+    /// Whether the scan is currently paused.
+    paused: AtomicBool,
+    /// Whether the scan should stop issuing new probes.
+    cancelled: AtomicBool,
+    /// Wakes up workers waiting on a paused scan.
+    notify: Notify,
+    /// Progress persisted so the run can be resumed if interrupted.
+    checkpoint: OnceLock<Checkpointer>,
+    /// Overall progress of the multi-phase scan this run is part of.
+    shared_progress: OnceLock<Arc<SharedProgress>>,
+    /// Run this one is part of; its pause and cancel apply here too.
+    parent: Option<Arc<ScanControl>>,
+    /// Runs started as part of this one, woken along with it.
+    children: std::sync::Mutex<Vec<Weak<ScanControl>>>,
+}
+
+impl ScanControl {
+    pub fn new(run_id: &str, event_prefix: &'static str) -> Self {
+        Self {
+            run_id: run_id.to_string(),
+            event_prefix,
+            paused: AtomicBool::new(false),
+            cancelled: AtomicBool::new(false),
+            notify: Notify::new(),
+            checkpoint: OnceLock::new(),
+            shared_progress: OnceLock::new(),
+            parent: None,
+            children: std::sync::Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Control of a run started as part of this one, e.g. the port scan of
+    /// one host after discovery. Pausing, resuming or cancelling this run
+    /// applies to the child as well.
+    pub fn child(self: &Arc<Self>, run_id: &str, event_prefix: &'static str) -> Arc<Self> {
+        let child = Arc::new(Self {
+            parent: Some(self.clone()),
+            ..Self::new(run_id, event_prefix)
+        });
+        let mut children = self.children.lock().expect("scan control poisoned");
+        children.retain(|c| c.strong_count() > 0);
+        children.push(Arc::downgrade(&child));
+        child
+    }
+
+    pub fn run_id(&self) -> &str {
+        &self.run_id
+    }
+
+    pub fn event_prefix(&self) -> &'static str {
+        self.event_prefix
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::Acquire) || self.parent.as_ref().is_some_and(|p| p.is_paused())
+    }
+
+    /// Pause the scan. Returns false if it was already paused.
+    pub fn pause(&self) -> bool {
+        !self.paused.swap(true, Ordering::AcqRel)
+    }
+
+    /// Resume the scan. Returns false if it was not paused.
+    pub fn resume(&self) -> bool {
+        let was_paused = self.paused.swap(false, Ordering::AcqRel);
+        if was_paused {
+            self.wake();
+        }
+        was_paused
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Acquire)
+            || self.parent.as_ref().is_some_and(|p| p.is_cancelled())
+    }
+
+    /// Stop issuing new probes. In-flight probes complete and the report is finalized.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Release);
+        // Release workers blocked on a paused scan
+        self.wake();
+    }
+
+    /// Wake workers waiting on this run or any of its children.
+    fn wake(&self) {
+        self.notify.notify_waiters();
+        let children: Vec<Arc<ScanControl>> = self
+            .children
+            .lock()
+            .expect("scan control poisoned")
+            .iter()
+            .filter_map(Weak::upgrade)
+            .collect();
+        for child in children {
+            child.wake();
+        }
+    }
+
+    /// Persist the progress of this run. Only the first checkpointer is kept.
+    pub fn set_checkpoint(&self, checkpointer: Checkpointer) {
+        let _ = self.checkpoint.set(checkpointer);
+    }
+
+    /// Checkpointer of this run, if its progress is persisted.
+    pub fn checkpoint(&self) -> Option<&Checkpointer> {
+        self.checkpoint.get()
+    }
+
+    /// Count this run's probes toward `progress` as well.
+    pub fn set_shared_progress(&self, progress: Arc<SharedProgress>) {
+        let _ = self.shared_progress.set(progress);
+    }
+
+    /// Report one finished probe to the shared progress, if any.
+    /// Workers call this next to their own progress update.
+    pub fn advance_shared(&self, sink: &impl ProgressEmitter) {
+        if let Some(progress) = self.shared_progress.get() {
+            progress.advance(sink);
+        }
+    }
+
+    /// Wait until the scan is not paused (or has been cancelled).
+    /// Workers call this before issuing each probe.
+    pub async fn wait_if_paused(&self) {
+        loop {
+            let notified = self.notify.notified();
+            tokio::pin!(notified);
+            // Register interest before re-checking the flags so a concurrent
+            // resume() or cancel() cannot slip in between the check and the await.
+            notified.as_mut().enable();
+            if !self.is_paused() || self.is_cancelled() {
+                return;
+            }
+            notified.await;
+        }
+    }
+
+    /// Wait until the scan is cancelled. Workers select on this next to
+    /// waits of their own, such as the spacing between probes.
+    pub async fn cancelled(&self) {
+        loop {
+            let notified = self.notify.notified();
+            tokio::pin!(notified);
+            notified.as_mut().enable();
+            if self.is_cancelled() {
+                return;
+            }
+            notified.await;
+        }
+    }
+}
+
+/// Time given to running scans to finalize on shutdown (default)
+pub const DEFAULT_SCAN_SHUTDOWN_TIMEOUT_MS: u64 = 3000;
+
+/// Registry of running scans keyed by run ID.
+#[derive(Debug, Default)]
+pub struct ScanRegistry {
+    scans: Mutex<HashMap<String, Arc<ScanControl>>>,
+    /// Wakes up `shutdown_all` when a scan leaves the registry.
+    removed: Notify,
+}
+
+impl ScanRegistry {
+    /// Create and register a control for a new run.
+    pub async fn register(&self, run_id: &str, event_prefix: &'static str) -> Arc<ScanControl> {
+        let control = Arc::new(ScanControl::new(run_id, event_prefix));
+        self.scans
+            .lock()
+            .await
+            .insert(run_id.to_string(), control.clone());
+        control
+    }
+
+    /// Register a run under `parent`, see `ScanControl::child`.
+    pub async fn register_child(
+        &self,
+        parent: &Arc<ScanControl>,
+        run_id: &str,
+        event_prefix: &'static str,
+    ) -> Arc<ScanControl> {
+        let control = parent.child(run_id, event_prefix);
+        self.scans
+            .lock()
+            .await
+            .insert(run_id.to_string(), control.clone());
+        control
+    }
+
+    pub async fn get(&self, run_id: &str) -> Option<Arc<ScanControl>> {
+        self.scans.lock().await.get(run_id).cloned()
+    }
+
+    pub async fn remove(&self, run_id: &str) -> Option<Arc<ScanControl>> {
+        let control = self.scans.lock().await.remove(run_id);
+        self.removed.notify_waiters();
+        control
+    }
+
+    /// Cancel every running scan and wait up to `timeout` for them to
+    /// finalize and leave the registry. Returns the run IDs still running.
+    pub async fn shutdown_all(&self, timeout: Duration) -> Vec<String> {
+        let deadline = tokio::time::Instant::now() + timeout;
+        loop {
+            let removed = self.removed.notified();
+            tokio::pin!(removed);
+            // Register before checking so a removal in between is not missed
+            removed.as_mut().enable();
+            {
+                let scans = self.scans.lock().await;
+                if scans.is_empty() {
+                    return Vec::new();
+                }
+                // Also reaches scans registered since the last pass
+                for control in scans.values() {
+                    control.cancel();
+                }
+            }
+            if tokio::time::timeout_at(deadline, removed).await.is_err() {
+                let mut remaining: Vec<String> = self.scans.lock().await.keys().cloned().collect();
+                remaining.sort();
+                return remaining;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn child_follows_parent_pause_and_cancel() {
+        let parent = Arc::new(ScanControl::new("parent", "hostscan"));
+        let child = parent.child("child", "portscan");
+
+        assert!(parent.pause());
+        assert!(child.is_paused());
+        let waiter = tokio::spawn({
+            let child = child.clone();
+            async move { child.wait_if_paused().await }
+        });
+        tokio::task::yield_now().await;
+        assert!(!waiter.is_finished());
+        assert!(parent.resume());
+        tokio::time::timeout(Duration::from_secs(1), waiter)
+            .await
+            .expect("child not woken by parent resume")
+            .unwrap();
+
+        let cancelled = tokio::spawn({
+            let child = child.clone();
+            async move { child.cancelled().await }
+        });
+        tokio::task::yield_now().await;
+        assert!(!cancelled.is_finished());
+        parent.cancel();
+        assert!(child.is_cancelled());
+        tokio::time::timeout(Duration::from_secs(1), cancelled)
+            .await
+            .expect("child not woken by parent cancel")
+            .unwrap();
+    }
+}
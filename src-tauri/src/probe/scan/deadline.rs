@@ -0,0 +1,57 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+use crate::model::scan::DeadlineReachedPayload;
+use crate::probe::scan::control::ScanControl;
+use crate::probe::scan::sink::ProgressEmitter;
+
+/// Overall time limit of a scan (`max_duration_ms`).
+/// Checked before each probe is issued; in-flight probes are left to complete.
+#[derive(Debug)]
+pub struct ScanDeadline {
+    at: Option<Instant>,
+    max_duration_ms: u64,
+    reached: AtomicBool,
+}
+
+impl ScanDeadline {
+    /// Starts counting now. `None` never expires.
+    pub fn new(max_duration_ms: Option<u64>) -> Self {
+        Self {
+            at: max_duration_ms.map(|ms| Instant::now() + Duration::from_millis(ms)),
+            max_duration_ms: max_duration_ms.unwrap_or(0),
+            reached: AtomicBool::new(false),
+        }
+    }
+
+    /// Whether the deadline has passed.
+    /// The first call to notice emits `<prefix>:deadline_reached` for the run.
+    pub fn check(&self, control: &ScanControl, sink: &impl ProgressEmitter) -> bool {
+        match self.at {
+            Some(at) if Instant::now() >= at => {
+                if !self.reached.swap(true, Ordering::AcqRel) {
+                    tracing::info!(
+                        "{} {} reached its {} ms deadline",
+                        control.event_prefix(),
+                        control.run_id(),
+                        self.max_duration_ms
+                    );
+                    sink.emit(
+                        &format!("{}:deadline_reached", control.event_prefix()),
+                        DeadlineReachedPayload {
+                            run_id: control.run_id().to_string(),
+                            max_duration_ms: self.max_duration_ms,
+                        },
+                    );
+                }
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Whether a `check` has seen the deadline pass.
+    pub fn reached(&self) -> bool {
+        self.reached.load(Ordering::Acquire)
+    }
+}
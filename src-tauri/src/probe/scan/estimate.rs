@@ -0,0 +1,224 @@
+//! Cost of a scan worked out from its setting before it runs, so the UI
+//! can warn before an aggressive scan ("~16M packets, ~12 minutes").
+//!
+//! Durations assume no probe is answered and each waits out its timeout,
+//! in waves of the scanner's concurrency. Service detection is not counted.
+
+use anyhow::Result;
+use std::net::IpAddr;
+
+use crate::model::scan::{
+    AddressFamily, HostScanSetting, PortScanProtocol, PortScanSetting, ScanCostEstimate,
+    ScanCostSetting,
+};
+use crate::probe::packet::{build_quic_version_probe, IPV4_HEADER_LEN, IPV6_HEADER_LEN};
+use crate::probe::scan::exclude::ExcludeSet;
+use crate::probe::scan::icmp::DEFAULT_ECHO_PAYLOAD;
+use crate::probe::scan::runner::planned_port_count;
+use crate::probe::scan::tuner::{hosts_concurrency, ports_concurrency};
+
+/// TCP header of a SYN with the usual options (MSS, SACK, timestamps, window scale)
+const TCP_SYN_LEN: u64 = 40;
+const UDP_HEADER_LEN: u64 = 8;
+const ICMP_ECHO_HEADER_LEN: u64 = 8;
+/// Initial TCP retransmission timeout. An unanswered SYN is sent again
+/// 1, 3, 7... seconds after the first.
+const TCP_INITIAL_RTO_MS: u64 = 1000;
+/// SYN retransmissions before a connect fails (Linux `tcp_syn_retries`)
+const TCP_SYN_RETRIES: u64 = 6;
+
+fn ip_header_len(ip: &IpAddr) -> u64 {
+    match ip {
+        IpAddr::V4(_) => IPV4_HEADER_LEN as u64,
+        IpAddr::V6(_) => IPV6_HEADER_LEN as u64,
+    }
+}
+
+/// SYNs a connect sends before it gives up after `timeout_ms`
+fn syn_count(timeout_ms: u64) -> u64 {
+    let mut count = 0;
+    let mut sent_at = 0;
+    while sent_at < timeout_ms && count <= TCP_SYN_RETRIES {
+        count += 1;
+        sent_at = sent_at * 2 + TCP_INITIAL_RTO_MS;
+    }
+    count.max(1)
+}
+
+/// Estimate the cost of a scan with the concurrency it would run at now.
+pub fn estimate_scan_cost(setting: &ScanCostSetting) -> Result<ScanCostEstimate> {
+    match setting {
+        ScanCostSetting::PortScan(setting) => Ok(estimate_port_scan(setting, ports_concurrency())),
+        ScanCostSetting::HostScan(request) => estimate_host_scan(
+            &HostScanSetting::from_request(request.clone()),
+            hosts_concurrency(),
+        ),
+    }
+}
+
+/// Port scan running `tuned` probes at once unless `max_per_host` is lower
+fn estimate_port_scan(setting: &PortScanSetting, tuned: usize) -> ScanCostEstimate {
+    let probes = planned_port_count(setting) as u64;
+    let concurrency = setting.max_per_host.map_or(tuned, |n| tuned.min(n)).max(1);
+    let ip_header = ip_header_len(&setting.ip_addr);
+    let (packets_per_probe, bytes_per_packet) = match setting.protocol {
+        PortScanProtocol::Tcp => (syn_count(setting.timeout_ms), ip_header + TCP_SYN_LEN),
+        PortScanProtocol::Quic => (
+            1,
+            ip_header + UDP_HEADER_LEN + build_quic_version_probe().len() as u64,
+        ),
+    };
+    let estimate = ScanCostEstimate {
+        targets: 1,
+        probes,
+        packets: probes * packets_per_probe,
+        bytes: probes * packets_per_probe * bytes_per_packet,
+        concurrency,
+        duration_ms: probes.div_ceil(concurrency as u64) * setting.timeout_ms,
+        ..Default::default()
+    };
+    finish(estimate, setting.max_duration_ms)
+}
+
+/// Host scan with `tuned` probes in flight per family unless the setting
+/// gives its own limits. Each family runs side by side.
+fn estimate_host_scan(setting: &HostScanSetting, tuned: usize) -> Result<ScanCostEstimate> {
+    let exclude = ExcludeSet::parse(&setting.exclude_targets)?;
+    let family = setting.family.unwrap_or_default();
+    let (mut hosts_v4, mut hosts_v6) = (0u64, 0u64);
+    for target in &setting.targets {
+        match target.ip {
+            Some(ip) if !family.matches(&ip) || exclude.contains(&ip) => {}
+            Some(IpAddr::V6(_)) => hosts_v6 += 1,
+            // Names are not resolved here; count each as one address
+            None if family == AddressFamily::V6 => hosts_v6 += 1,
+            _ => hosts_v4 += 1,
+        }
+    }
+    let concurrency_v4 = setting.concurrency.unwrap_or(tuned).max(1);
+    let concurrency_v6 = setting.concurrency_v6.unwrap_or(concurrency_v4).max(1);
+    let count = setting.count.max(1) as u64;
+    // Probes to one host go one after another, at least `interval_ms` apart
+    let per_host_ms = count * setting.timeout_ms.max(setting.interval_ms.unwrap_or(0));
+    let payload_len = setting
+        .payload
+        .as_deref()
+        .unwrap_or(DEFAULT_ECHO_PAYLOAD)
+        .len() as u64;
+    let echo_len = ICMP_ECHO_HEADER_LEN + payload_len;
+
+    let mut concurrency = 0;
+    if hosts_v4 > 0 {
+        concurrency += concurrency_v4;
+    }
+    if hosts_v6 > 0 {
+        concurrency += concurrency_v6;
+    }
+    let waves = hosts_v4
+        .div_ceil(concurrency_v4 as u64)
+        .max(hosts_v6.div_ceil(concurrency_v6 as u64));
+    let estimate = ScanCostEstimate {
+        targets: hosts_v4 + hosts_v6,
+        probes: (hosts_v4 + hosts_v6) * count,
+        packets: (hosts_v4 + hosts_v6) * count,
+        bytes: count
+            * (hosts_v4 * (IPV4_HEADER_LEN as u64 + echo_len)
+                + hosts_v6 * (IPV6_HEADER_LEN as u64 + echo_len)),
+        concurrency,
+        duration_ms: waves * per_host_ms,
+        ..Default::default()
+    };
+    Ok(finish(estimate, setting.max_duration_ms))
+}
+
+/// Cut the estimate down to what fits in the time budget and derive the bandwidth.
+fn finish(mut estimate: ScanCostEstimate, max_duration_ms: Option<u64>) -> ScanCostEstimate {
+    if let Some(max) = max_duration_ms.filter(|&max| max < estimate.duration_ms) {
+        let scale = |n: u64| (n as u128 * max as u128 / estimate.duration_ms as u128) as u64;
+        estimate.probes = scale(estimate.probes);
+        estimate.packets = scale(estimate.packets);
+        estimate.bytes = scale(estimate.bytes);
+        estimate.duration_ms = max;
+        estimate.truncated = true;
+    }
+    if estimate.duration_ms > 0 {
+        estimate.bandwidth_bps = estimate.bytes * 8 * 1000 / estimate.duration_ms;
+    }
+    estimate
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::scan::HostScanRequest;
+
+    fn port_setting(value: serde_json::Value) -> PortScanSetting {
+        let mut base = serde_json::json!({
+            "ip_addr": "192.0.2.1",
+            "target_ports_preset": "Full",
+            "user_ports": [],
+            "protocol": "Tcp",
+            "timeout_ms": 1000,
+            "service_detection": false,
+        });
+        base.as_object_mut()
+            .unwrap()
+            .extend(value.as_object().unwrap().clone());
+        serde_json::from_value(base).unwrap()
+    }
+
+    #[test]
+    fn syn_retransmits_follow_rto_backoff() {
+        assert_eq!(syn_count(500), 1);
+        assert_eq!(syn_count(1000), 1);
+        assert_eq!(syn_count(3500), 3);
+    }
+
+    #[test]
+    fn full_tcp_scan_runs_in_waves() {
+        let estimate = estimate_port_scan(&port_setting(serde_json::json!({})), 1000);
+        assert_eq!(estimate.probes, 65535);
+        assert_eq!(estimate.packets, 65535);
+        assert_eq!(estimate.bytes, 65535 * 60);
+        // 66 waves of 1000 probes, each waiting out the 1s timeout
+        assert_eq!(estimate.duration_ms, 66_000);
+        assert_eq!(estimate.bandwidth_bps, 65535 * 60 * 8 / 66);
+        assert!(!estimate.truncated);
+
+        let slow = estimate_port_scan(&port_setting(serde_json::json!({"max_per_host": 10})), 1000);
+        assert_eq!(slow.concurrency, 10);
+        assert_eq!(slow.duration_ms, 6_554_000);
+    }
+
+    #[test]
+    fn time_budget_truncates_estimate() {
+        let setting = port_setting(serde_json::json!({"max_duration_ms": 33_000}));
+        let estimate = estimate_port_scan(&setting, 1000);
+        assert!(estimate.truncated);
+        assert_eq!(estimate.duration_ms, 33_000);
+        assert_eq!(estimate.probes, 65535 / 2);
+    }
+
+    #[test]
+    fn host_scan_counts_targets_per_family() {
+        let request: HostScanRequest = serde_json::from_value(serde_json::json!({
+            "targets": ["192.0.2.1", "192.0.2.2", "192.0.2.3", "2001:db8::1", "example.com"],
+            "hop_limit": 64,
+            "timeout_ms": 1000,
+            "count": 2,
+            "payload": null,
+            "concurrency": 2,
+            "exclude_targets": ["192.0.2.3"],
+        }))
+        .unwrap();
+        let setting = HostScanSetting::from_request(request);
+        let estimate = estimate_host_scan(&setting, 64).unwrap();
+        assert_eq!(estimate.targets, 4);
+        assert_eq!(estimate.probes, 8);
+        // Three IPv4 echo requests and one IPv6, two probes each
+        assert_eq!(estimate.bytes, 2 * (3 * (20 + 8 + 5) + (40 + 8 + 5)));
+        assert_eq!(estimate.concurrency, 4);
+        // Two waves of IPv4 hosts, two probes of 1s each
+        assert_eq!(estimate.duration_ms, 4000);
+    }
+}
@@ -0,0 +1,176 @@
+use crate::error::ScanError;
+use anyhow::{bail, Result};
+use netdev::ipnet::IpNet;
+use std::net::IpAddr;
+
+/// One excluded address block
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum ExcludeEntry {
+    Net(IpNet),
+    /// Inclusive address range
+    Range(IpAddr, IpAddr),
+}
+
+impl ExcludeEntry {
+    fn contains(&self, ip: &IpAddr) -> bool {
+        match self {
+            ExcludeEntry::Net(net) => net.contains(ip),
+            ExcludeEntry::Range(start, end) => {
+                start.is_ipv4() == ip.is_ipv4() && start <= ip && ip <= end
+            }
+        }
+    }
+}
+
+/// Hosts excluded from a scan.
+/// Accepts single IPs, CIDRs (`10.0.0.0/24`), full ranges
+/// (`10.0.0.10-10.0.0.20`) and IPv4 last-octet ranges (`10.0.0.10-20`).
+#[derive(Debug, Clone, Default)]
+pub struct ExcludeSet {
+    entries: Vec<ExcludeEntry>,
+}
+
+impl ExcludeSet {
+    pub fn parse(inputs: &[String]) -> Result<Self> {
+        let mut entries = Vec::new();
+        for s in inputs {
+            let t = s.trim();
+            if t.is_empty() {
+                continue;
+            }
+            entries.push(parse_entry(t)?);
+        }
+        Ok(ExcludeSet { entries })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn contains(&self, ip: &IpAddr) -> bool {
+        self.entries.iter().any(|e| e.contains(ip))
+    }
+}
+
+fn parse_entry(s: &str) -> Result<ExcludeEntry> {
+    if let Ok(ip) = s.parse::<IpAddr>() {
+        return Ok(ExcludeEntry::Net(IpNet::from(ip)));
+    }
+    if let Ok(net) = s.parse::<IpNet>() {
+        return Ok(ExcludeEntry::Net(net.trunc()));
+    }
+    if let Some((a, b)) = s.split_once('-') {
+        let (a, b) = (a.trim(), b.trim());
+        let Ok(start) = a.parse::<IpAddr>() else {
+            bail!(ScanError::invalid_input(format!(
+                "Invalid exclude range start: {}",
+                s
+            )));
+        };
+        let end = match (start, b.parse::<IpAddr>(), b.parse::<u8>()) {
+            (_, Ok(end), _) => end,
+            (IpAddr::V4(v4), _, Ok(last)) => {
+                let [o1, o2, o3, _] = v4.octets();
+                IpAddr::from([o1, o2, o3, last])
+            }
+            _ => bail!(ScanError::invalid_input(format!(
+                "Invalid exclude range end: {}",
+                s
+            ))),
+        };
+        if start.is_ipv4() != end.is_ipv4() {
+            bail!(ScanError::invalid_input(format!(
+                "Exclude range mixes IPv4 and IPv6: {}",
+                s
+            )));
+        }
+        if start > end {
+            bail!(ScanError::invalid_input(format!(
+                "Exclude range start is after its end: {}",
+                s
+            )));
+        }
+        return Ok(ExcludeEntry::Range(start, end));
+    }
+    bail!(ScanError::invalid_input(format!(
+        "Invalid exclude target (expected IP, CIDR or range): {}",
+        s
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::Ipv4Addr;
+
+    fn set(inputs: &[&str]) -> ExcludeSet {
+        ExcludeSet::parse(&inputs.iter().map(|s| s.to_string()).collect::<Vec<_>>()).unwrap()
+    }
+
+    fn v4(last: u8) -> IpAddr {
+        IpAddr::V4(Ipv4Addr::new(10, 0, 0, last))
+    }
+
+    #[test]
+    fn cidr_overlapping_range_excludes_union() {
+        let ex = set(&["10.0.0.0/30", "10.0.0.2-10.0.0.5"]);
+        let kept: Vec<u8> = (0..=8).filter(|&i| !ex.contains(&v4(i))).collect();
+        assert_eq!(kept, vec![6, 7, 8]);
+    }
+
+    #[test]
+    fn range_inside_cidr_and_short_range() {
+        let ex = set(&["10.0.0.8/29", "10.0.0.10-12", "10.0.0.20-10.0.0.22"]);
+        for i in 8..=15 {
+            assert!(ex.contains(&v4(i)), "10.0.0.{} should be excluded", i);
+        }
+        for i in 20..=22 {
+            assert!(ex.contains(&v4(i)));
+        }
+        assert!(!ex.contains(&v4(7)));
+        assert!(!ex.contains(&v4(16)));
+        assert!(!ex.contains(&v4(23)));
+    }
+
+    #[test]
+    fn cidr_host_bits_are_truncated() {
+        let ex = set(&["10.0.0.5/30"]);
+        assert!(ex.contains(&v4(4)));
+        assert!(ex.contains(&v4(7)));
+        assert!(!ex.contains(&v4(8)));
+    }
+
+    #[test]
+    fn ipv6_entries_do_not_match_ipv4() {
+        let ex = set(&["::-::ffff", "2001:db8::/32"]);
+        assert!(!ex.contains(&v4(1)));
+        assert!(ex.contains(&"2001:db8::1".parse().unwrap()));
+        assert!(ex.contains(&"::1".parse().unwrap()));
+    }
+
+    #[test]
+    fn excluded_ports_removed_after_expansion() {
+        use crate::model::scan::TargetPortsPreset;
+        let ports = crate::probe::scan::expand_ports(
+            &TargetPortsPreset::WellKnown,
+            &[8080, 9100],
+            &[22, 9100, 60000],
+        );
+        // 1..=1023 plus 8080, minus 22
+        assert_eq!(ports.len(), 1023);
+        assert!(!ports.contains(&22));
+        assert!(!ports.contains(&9100));
+        assert!(ports.contains(&8080));
+    }
+
+    #[test]
+    fn invalid_entries_are_rejected() {
+        for bad in ["example.com", "10.0.0.9-3", "10.0.0.1-::1", "10.0.0.0/33"] {
+            assert!(
+                ExcludeSet::parse(&[bad.to_string()]).is_err(),
+                "{} should be rejected",
+                bad
+            );
+        }
+    }
+}
@@ -0,0 +1,205 @@
+use std::collections::HashMap;
+
+use crate::model::scan::{PortScanSample, PortState};
+use crate::probe::service::models::ServiceResponse;
+
+/// Minimum ports scanned before the open ratio is considered
+const MIN_PORTS_FOR_RATIO: usize = 20;
+/// Share of scanned ports open above which every port "appears open"
+const ALL_OPEN_RATIO: f64 = 0.9;
+/// Minimum service-probed ports before the silent ratio is considered
+const MIN_PROBED_FOR_SILENCE: usize = 5;
+/// Share of probed ports silent or trickling above which the host looks like a tarpit
+const SILENT_RATIO: f64 = 0.8;
+/// Responses shorter than this within the probe timeout count as trickling
+const TRICKLE_BYTES: usize = 4;
+/// Open ports above which a single host is implausible
+const MANY_OPEN_PORTS: usize = 64;
+/// Open ports above which repeated banners are suspicious
+const GENERIC_BANNER_MIN_OPEN: usize = 16;
+/// Distinct ports sharing one banner to count it as generic
+const GENERIC_BANNER_PORTS: usize = 5;
+
+/// Verdict of the tarpit / honeypot heuristics
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Suspicion {
+    pub reasons: Vec<String>,
+}
+
+impl Suspicion {
+    pub fn is_suspicious(&self) -> bool {
+        !self.reasons.is_empty()
+    }
+
+    /// Reasons joined into one line, or `None` if nothing was flagged.
+    pub fn reason(&self) -> Option<String> {
+        self.is_suspicious().then(|| self.reasons.join("; "))
+    }
+}
+
+/// Flag likely tarpits and honeypots from the samples of one port scan.
+/// `scanned` is the number of ports probed; `samples` may hold only open ports.
+///
+/// Tarpit: nearly every scanned port accepts connections, or most open
+/// ports accept but never send more than a few bytes.
+/// Honeypot: implausibly many open ports, or many ports with the same banner.
+/// Connect scans cannot observe the advertised TCP window, so tiny-window
+/// tarpits are caught by their behaviour instead.
+pub fn assess(scanned: usize, samples: &[PortScanSample]) -> Suspicion {
+    let open: Vec<&PortScanSample> = samples
+        .iter()
+        .filter(|s| s.state == PortState::Open)
+        .collect();
+    let mut reasons = Vec::new();
+
+    reasons.extend(open_ratio_reason(scanned, open.len()));
+
+    let probed: Vec<_> = open
+        .iter()
+        .filter_map(|s| s.service_info.as_ref())
+        .filter(|i| i.response != ServiceResponse::Unknown)
+        .collect();
+    if probed.len() >= MIN_PROBED_FOR_SILENCE {
+        let silent = probed
+            .iter()
+            .filter(|i| match i.response {
+                ServiceResponse::NoResponse => true,
+                _ => i.raw.as_ref().is_some_and(|r| r.len() < TRICKLE_BYTES),
+            })
+            .count();
+        if silent as f64 / probed.len() as f64 >= SILENT_RATIO {
+            reasons.push(format!(
+                "tarpit: {} of {} probed ports accept but send no data",
+                silent,
+                probed.len()
+            ));
+        }
+    }
+
+    reasons.extend(open_count_reason(open.len()));
+
+    if open.len() >= GENERIC_BANNER_MIN_OPEN {
+        let mut banners: HashMap<&str, usize> = HashMap::new();
+        for info in open.iter().filter_map(|s| s.service_info.as_ref()) {
+            if let Some(banner) = info.banner.as_deref().map(str::trim) {
+                if !banner.is_empty() {
+                    *banners.entry(banner).or_default() += 1;
+                }
+            }
+        }
+        if let Some((banner, count)) = banners
+            .into_iter()
+            .filter(|(_, n)| *n >= GENERIC_BANNER_PORTS)
+            .max_by_key(|(_, n)| *n)
+        {
+            reasons.push(format!(
+                "honeypot: {} ports share the banner {:?}",
+                count, banner
+            ));
+        }
+    }
+
+    Suspicion { reasons }
+}
+
+/// Flag likely tarpits and honeypots from the number of open ports alone,
+/// for scans whose samples are not kept in memory.
+pub fn assess_open_count(scanned: usize, open: usize) -> Suspicion {
+    let reasons = open_ratio_reason(scanned, open)
+        .into_iter()
+        .chain(open_count_reason(open))
+        .collect();
+    Suspicion { reasons }
+}
+
+fn open_ratio_reason(scanned: usize, open: usize) -> Option<String> {
+    let ratio = open as f64 / scanned as f64;
+    (scanned >= MIN_PORTS_FOR_RATIO && ratio >= ALL_OPEN_RATIO).then(|| {
+        format!(
+            "tarpit: {} of {} scanned ports accept connections",
+            open, scanned
+        )
+    })
+}
+
+fn open_count_reason(open: usize) -> Option<String> {
+    (open >= MANY_OPEN_PORTS).then(|| format!("honeypot: {} open ports", open))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::probe::service::models::ServiceInfo;
+    use std::net::{IpAddr, Ipv4Addr};
+
+    fn open(port: u16, info: Option<ServiceInfo>) -> PortScanSample {
+        let ip = IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1));
+        PortScanSample {
+            service_info: info,
+            ..PortScanSample::fixture(ip, port, PortState::Open)
+        }
+    }
+
+    fn responded(banner: &str) -> Option<ServiceInfo> {
+        Some(ServiceInfo {
+            banner: Some(banner.to_string()),
+            raw: Some(banner.to_string()),
+            response: ServiceResponse::Responded,
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn ordinary_host_is_not_suspicious() {
+        let samples = [
+            open(22, responded("SSH-2.0-OpenSSH_9.6")),
+            open(80, responded("HTTP/1.1 200 OK")),
+        ];
+        let verdict = assess(1000, &samples);
+        assert!(!verdict.is_suspicious());
+        assert_eq!(verdict.reason(), None);
+        // Too few ports scanned to judge the open ratio
+        assert!(!assess_open_count(10, 10).is_suspicious());
+    }
+
+    #[test]
+    fn flags_tarpits() {
+        let verdict = assess_open_count(100, 95);
+        assert!(verdict
+            .reason()
+            .unwrap()
+            .contains("95 of 100 scanned ports"));
+
+        let silent = || {
+            Some(ServiceInfo {
+                response: ServiceResponse::NoResponse,
+                ..Default::default()
+            })
+        };
+        let trickle = responded("\r\n");
+        let mut samples: Vec<_> = (1..=5).map(|p| open(p, silent())).collect();
+        samples.push(open(6, trickle));
+        let verdict = assess(1000, &samples);
+        assert_eq!(
+            verdict.reasons,
+            ["tarpit: 6 of 6 probed ports accept but send no data"]
+        );
+    }
+
+    #[test]
+    fn flags_honeypots() {
+        assert!(assess_open_count(65535, MANY_OPEN_PORTS)
+            .reason()
+            .unwrap()
+            .contains("honeypot"));
+
+        let samples: Vec<_> = (1..=GENERIC_BANNER_MIN_OPEN as u16)
+            .map(|p| open(p, responded("220 Service ready")))
+            .collect();
+        let verdict = assess(1000, &samples);
+        assert_eq!(
+            verdict.reasons,
+            ["honeypot: 16 ports share the banner \"220 Service ready\""]
+        );
+    }
+}
@@ -0,0 +1,59 @@
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::{Arc, Mutex, OnceLock, Weak};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+/// Caps simultaneous probes to one destination host.
+/// Shared by every running scan of that host, so parallel or scheduled
+/// scans of a fragile device do not add up.
+#[derive(Debug)]
+pub struct HostLimit {
+    limit: usize,
+    semaphore: Arc<Semaphore>,
+}
+
+impl HostLimit {
+    pub fn limit(&self) -> usize {
+        self.limit
+    }
+
+    /// Wait for a slot on the host.
+    pub async fn acquire(&self) -> OwnedSemaphorePermit {
+        self.semaphore
+            .clone()
+            .acquire_owned()
+            .await
+            .expect("host semaphore is never closed")
+    }
+}
+
+static HOST_LIMITS: OnceLock<Mutex<HashMap<IpAddr, Weak<HostLimit>>>> = OnceLock::new();
+
+/// Get the limit for `ip`, creating it with `limit` slots if no running scan
+/// holds one. A limit already in use keeps its size.
+pub fn host_limit(ip: IpAddr, limit: usize) -> Arc<HostLimit> {
+    let limit = limit.max(1);
+    let mut map = HOST_LIMITS
+        .get_or_init(|| Mutex::new(HashMap::new()))
+        .lock()
+        .expect("HOST_LIMITS poisoned");
+    // Drop entries of finished scans
+    map.retain(|_, w| w.strong_count() > 0);
+    if let Some(existing) = map.get(&ip).and_then(Weak::upgrade) {
+        if existing.limit != limit {
+            tracing::debug!(
+                "{}: per-host limit {} already in use, ignoring {}",
+                ip,
+                existing.limit,
+                limit
+            );
+        }
+        return existing;
+    }
+    let host = Arc::new(HostLimit {
+        limit,
+        semaphore: Arc::new(Semaphore::new(limit)),
+    });
+    map.insert(ip, Arc::downgrade(&host));
+    host
+}
@@ -1,87 +1,366 @@
 use anyhow::Result;
-use futures::{stream, StreamExt};
-use rand::{seq::SliceRandom, thread_rng, Rng};
-use std::collections::HashMap;
-use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use futures::{future, stream, StreamExt};
+use rand::Rng;
+use std::collections::{BTreeMap, HashMap};
+use std::future::Future;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV6};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
-use tauri::{AppHandle, Emitter};
 use tokio::sync::{oneshot, Mutex};
 
+use crate::error::ScanError;
 use crate::model::endpoint::Host;
-use crate::model::scan::{HostScanProgress, HostScanReport, HostScanSetting, HostState};
-use crate::probe::packet::{build_icmp_echo_bytes, parse_icmp_echo_v4, parse_icmp_echo_v6};
+use crate::model::scan::{
+    EchoSample, HostScanLimitPayload, HostScanProgress, HostScanReport, HostScanSetting,
+    HostScanUnresolvedPayload, HostState, ScanRouting,
+};
+use crate::model::stats::HostScanSummary;
+use crate::probe::packet::{
+    build_icmp_echo_bytes, icmp_unreachable_reason, icmpv6_unreachable_reason, parse_icmp_echo_v4,
+    parse_icmp_echo_v6, parse_icmp_unreachable_v4, parse_icmpv6_unreachable,
+};
+use crate::probe::scan::control::ScanControl;
+use crate::probe::scan::deadline::ScanDeadline;
+use crate::probe::scan::order_hosts;
 use crate::probe::scan::progress::ThrottledProgress;
+use crate::probe::scan::routing::{apply_icmp, check_routing};
+use crate::probe::scan::sink::ProgressEmitter;
 use crate::probe::scan::tuner::hosts_concurrency;
 use crate::socket::icmp::{AsyncIcmpSocket, IcmpConfig, IcmpKind};
 use crate::socket::SocketFamily;
 
+/// Echo request payload when the setting has none
+pub const DEFAULT_ECHO_PAYLOAD: &str = "np:hs";
+
 struct Pending {
     #[allow(dead_code)]
     ip: IpAddr,
+    /// Echo identifier used for this host
+    id: u16,
     sent_at: Instant,
-    tx: oneshot::Sender<u64>,
+    /// Echo reply, or the reason a router reported the host unreachable
+    tx: oneshot::Sender<Result<EchoReply, String>>,
+}
+
+/// Matched echo reply.
+struct EchoReply {
+    rtt_ms: u64,
+    ttl: Option<u8>,
+}
+
+/// Wait for the reply to the request registered for `dst_ip`.
+/// On timeout the pending entry is removed under the lock the receiver
+/// delivers under, then the channel is checked once more, so a reply that
+/// arrived as the timer fired is not discarded.
+/// A destination unreachable for the request fails with its reason.
+async fn wait_reply(
+    mut rx: oneshot::Receiver<Result<EchoReply, String>>,
+    timeout: Duration,
+    pending: &Mutex<HashMap<IpAddr, Pending>>,
+    dst_ip: IpAddr,
+) -> Result<EchoReply, String> {
+    match tokio::time::timeout(timeout, &mut rx).await {
+        Ok(Ok(reply)) => reply,
+        Ok(Err(_canceled)) => Err("wait canceled".into()),
+        Err(_to) => {
+            pending.lock().await.remove(&dst_ip);
+            rx.try_recv()
+                .unwrap_or_else(|_| Err(format!("timeout (>{}ms)", timeout.as_millis())))
+        }
+    }
+}
+
+/// How long the receiver keeps reading queued replies after the scan ends
+const RECEIVER_DRAIN: Duration = Duration::from_millis(100);
+
+/// Socket the receiver task reads echo replies from
+trait ReplySource: Send + Sync + 'static {
+    fn recv<'a>(
+        &'a self,
+        buf: &'a mut [u8],
+    ) -> impl Future<Output = std::io::Result<(usize, SocketAddr, Option<u8>)>> + Send + 'a;
+    /// Identifier the replies to a request sent with `requested` carry
+    fn echo_id(&self, requested: u16) -> u16;
+}
+
+impl ReplySource for AsyncIcmpSocket {
+    fn recv<'a>(
+        &'a self,
+        buf: &'a mut [u8],
+    ) -> impl Future<Output = std::io::Result<(usize, SocketAddr, Option<u8>)>> + Send + 'a {
+        self.recv_from_with_ttl(buf)
+    }
+
+    fn echo_id(&self, requested: u16) -> u16 {
+        AsyncIcmpSocket::echo_id(self, requested)
+    }
+}
+
+/// Running receiver task, stopped with `shutdown`
+struct Receiver {
+    stop: oneshot::Sender<()>,
+    handle: tokio::task::JoinHandle<()>,
+}
+
+impl Receiver {
+    /// Let the task drain replies already queued, then wait for it to exit.
+    async fn shutdown(self) {
+        let _ = self.stop.send(());
+        if let Err(e) = self.handle.await {
+            tracing::warn!("ICMP receiver task failed: {}", e);
+        }
+    }
 }
 
-fn spawn_receiver(
-    socket: Arc<AsyncIcmpSocket>,
+fn spawn_receiver<S: ReplySource>(
+    socket: Arc<S>,
     pending: Arc<Mutex<HashMap<IpAddr, Pending>>>,
     is_v6: bool,
-) -> tokio::task::JoinHandle<()> {
-    tokio::spawn(async move {
+) -> Receiver {
+    let (stop, mut stop_rx) = oneshot::channel::<()>();
+    let handle = tokio::spawn(async move {
         let mut buf = vec![0u8; 2048];
+        // Set once stopped: read until then, or until nothing is pending
+        let mut drain_until: Option<tokio::time::Instant> = None;
         loop {
-            let Ok((n, addr)) = socket.recv_from(&mut buf).await else {
+            let received = match drain_until {
+                Some(deadline) => {
+                    if pending.lock().await.is_empty() {
+                        break;
+                    }
+                    match tokio::time::timeout_at(deadline, socket.recv(&mut buf)).await {
+                        Ok(received) => received,
+                        Err(_) => break,
+                    }
+                }
+                None => tokio::select! {
+                    received = socket.recv(&mut buf) => received,
+                    _ = &mut stop_rx => {
+                        drain_until = Some(tokio::time::Instant::now() + RECEIVER_DRAIN);
+                        continue;
+                    }
+                },
+            };
+            let Ok((n, addr, ttl)) = received else {
                 // Error on recv, socket might be closed
                 break;
             };
-            let is_echo_reply = if !is_v6 {
-                // IPv4
-                parse_icmp_echo_v4(&buf[..n]).is_some()
-            } else {
-                // IPv6
-                parse_icmp_echo_v6(&buf[..n]).is_some()
-            };
+            let mut map = pending.lock().await;
+            // Only accept replies carrying the identifier sent to this host,
+            // other ICMP flows may share the socket
+            let is_echo_reply = map.get(&addr.ip()).is_some_and(|p| {
+                let id = socket.echo_id(p.id);
+                if !is_v6 {
+                    // IPv4
+                    parse_icmp_echo_v4(&buf[..n], id).is_some()
+                } else {
+                    // IPv6
+                    parse_icmp_echo_v6(&buf[..n], id).is_some()
+                }
+            });
 
             if is_echo_reply {
-                let mut map = pending.lock().await;
                 if let Some(p) = map.remove(&addr.ip()) {
-                    let _ = p.tx.send(p.sent_at.elapsed().as_millis() as u64);
+                    let _ = p.tx.send(Ok(EchoReply {
+                        rtt_ms: p.sent_at.elapsed().as_millis() as u64,
+                        ttl,
+                    }));
+                }
+                continue;
+            }
+
+            // A router may answer for the host; the quoted request names it
+            if let Some((dst_ip, reason)) = unreachable_reason(&*socket, &map, &buf[..n], is_v6) {
+                if let Some(p) = map.remove(&dst_ip) {
+                    let _ = p.tx.send(Err(format!("{} (from {})", reason, addr.ip())));
                 }
             }
         }
-    })
+        // Waiters still pending see their request as canceled
+        pending.lock().await.clear();
+    });
+    Receiver { stop, handle }
+}
+
+/// Target and reason of a destination unreachable quoting the request
+/// pending for that target. Only RAW sockets receive these errors; on DGRAM
+/// sockets the request runs into its timeout instead.
+fn unreachable_reason<S: ReplySource>(
+    socket: &S,
+    pending: &HashMap<IpAddr, Pending>,
+    buf: &[u8],
+    is_v6: bool,
+) -> Option<(IpAddr, &'static str)> {
+    let (unreach, reason) = if !is_v6 {
+        let u = parse_icmp_unreachable_v4(buf)?;
+        (u, icmp_unreachable_reason(u.code))
+    } else {
+        let u = parse_icmpv6_unreachable(buf)?;
+        (u, icmpv6_unreachable_reason(u.code))
+    };
+    let p = pending.get(&unreach.dst)?;
+    (socket.echo_id(p.id) == unreach.id).then_some((unreach.dst, reason))
+}
+
+/// Per-probe results of one host, kept and emitted as `hostscan:sample`
+/// in verbose scans only
+struct EchoSamples {
+    ip_addr: IpAddr,
+    verbose: bool,
+    samples: Vec<EchoSample>,
+}
+
+impl EchoSamples {
+    fn new(ip_addr: IpAddr, verbose: bool) -> Self {
+        Self {
+            ip_addr,
+            verbose,
+            samples: Vec::new(),
+        }
+    }
+
+    fn record(
+        &mut self,
+        sink: &impl ProgressEmitter,
+        seq: u32,
+        rtt_ms: Option<u64>,
+        reply_ttl: Option<u8>,
+        message: Option<String>,
+    ) {
+        if !self.verbose {
+            return;
+        }
+        let sample = EchoSample {
+            ip_addr: self.ip_addr,
+            seq,
+            rtt_ms,
+            reply_ttl,
+            message,
+        };
+        sink.emit("hostscan:sample", sample.clone());
+        self.samples.push(sample);
+    }
+
+    /// Whether to stop probing the host after a reply. Verbose scans send
+    /// every probe for the RTT distribution.
+    fn done_after_reply(&self) -> bool {
+        !self.verbose
+    }
+}
+
+/// Wait until `interval` after the previous probe to the same host, like
+/// `ping -i`. Returns false if the scan is cancelled meanwhile.
+async fn wait_interval(
+    control: &ScanControl,
+    interval: Option<Duration>,
+    last_sent: Option<Instant>,
+) -> bool {
+    let (Some(interval), Some(sent)) = (interval, last_sent) else {
+        return true;
+    };
+    tokio::select! {
+        _ = tokio::time::sleep_until((sent + interval).into()) => true,
+        _ = control.cancelled() => false,
+    }
+}
+
+/// Bind to the interface's source address; a link-local IPv6 source gets
+/// the interface as scope.
+fn bind_source(cfg: IcmpConfig, iface: &netdev::Interface, src_ip: Option<IpAddr>) -> IcmpConfig {
+    match src_ip {
+        Some(IpAddr::V6(v6)) if v6.segments()[0] & 0xffc0 == 0xfe80 => {
+            cfg.with_bind(SocketAddr::V6(SocketAddrV6::new(v6, 0, 0, iface.index)))
+        }
+        Some(ip) if !ip.is_unspecified() => cfg.with_bind(SocketAddr::new(ip, 0)),
+        _ => cfg,
+    }
+}
+
+/// Open an ICMP socket tied to the scan interface, so replies are read on the
+/// path the probes left by: SO_BINDTODEVICE on Linux, the source address
+/// elsewhere. Kernels that require CAP_NET_RAW for SO_BINDTODEVICE fall back
+/// to the source address. A `bind_device` in the scan routing replaces both.
+async fn open_socket(
+    cfg: IcmpConfig,
+    iface: Option<&netdev::Interface>,
+    src_ip: Option<IpAddr>,
+    routing: &ScanRouting,
+) -> std::io::Result<AsyncIcmpSocket> {
+    let iface = match iface {
+        Some(iface) if routing.bind_device.is_none() => iface,
+        _ => return AsyncIcmpSocket::new(&apply_icmp(cfg, routing)).await,
+    };
+    if !cfg!(any(target_os = "linux", target_os = "android")) {
+        return AsyncIcmpSocket::new(&apply_icmp(bind_source(cfg, iface, src_ip), routing)).await;
+    }
+    let bound = apply_icmp(cfg.clone().with_interface(iface.name.clone()), routing);
+    match AsyncIcmpSocket::new(&bound).await {
+        Err(e) if e.kind() == std::io::ErrorKind::PermissionDenied => {
+            tracing::debug!(
+                "cannot bind ICMP socket to {}: {}, binding source address",
+                iface.name,
+                e
+            );
+            AsyncIcmpSocket::new(&apply_icmp(bind_source(cfg, iface, src_ip), routing)).await
+        }
+        r => r,
+    }
 }
 
 pub async fn host_scan(
-    app: &AppHandle,
+    sink: &impl ProgressEmitter,
     run_id: &str,
     src_ipv4: Option<IpAddr>,
     src_ipv6: Option<IpAddr>,
     mut setting: HostScanSetting,
+    control: Arc<ScanControl>,
 ) -> Result<HostScanReport> {
+    // Name resolution counts toward the time budget
+    let deadline = ScanDeadline::new(setting.max_duration_ms);
     let timeout = Duration::from_millis(setting.timeout_ms);
     let payload = setting
         .payload
         .clone()
-        .unwrap_or_else(|| "np:hs".to_string());
-    let concurrency = setting.concurrency.unwrap_or(hosts_concurrency());
-    if !setting.ordered {
-        setting.targets.shuffle(&mut thread_rng());
+        .unwrap_or_else(|| DEFAULT_ECHO_PAYLOAD.to_string());
+    let concurrency_v4 = setting.concurrency.unwrap_or(hosts_concurrency());
+    let concurrency_v6 = setting.concurrency_v6.unwrap_or(concurrency_v4);
+    order_hosts(&mut setting.targets, setting.order);
+    check_routing(&setting.routing)?;
+
+    let (target_hosts, unresolved) = setting.resolve_targets().await?;
+    if !unresolved.is_empty() {
+        // Tell the user up front which names will not be scanned
+        sink.emit(
+            "hostscan:unresolved",
+            HostScanUnresolvedPayload {
+                run_id: run_id.to_string(),
+                unresolved: unresolved.clone(),
+            },
+        );
     }
-
-    let target_hosts: Vec<Host> = setting.resolve_targets().await;
     let target_map: HashMap<IpAddr, Host> =
         target_hosts.iter().map(|h| (h.ip, h.clone())).collect();
 
     let total = target_map.len() as u32;
 
     let progress = Arc::new(ThrottledProgress::new(total));
+    tracing::debug!("host scan started: {} targets", total);
+
+    let iface = match setting.iface_name.as_deref() {
+        Some(name) => Some(
+            crate::net::interface::find_interface(name)
+                .ok_or_else(|| ScanError::no_interface(format!("Interface not found: {}", name)))?,
+        ),
+        None => None,
+    };
 
     let socket_v4 = if target_map.keys().into_iter().any(|ip| ip.is_ipv4()) {
         let mut cfg = IcmpConfig::new(IcmpKind::V4);
         cfg = cfg.with_ttl(setting.hop_limit.max(1) as u32);
-        Some(Arc::new(AsyncIcmpSocket::new(&cfg).await?))
+        cfg = cfg.with_recv_ttl(true);
+        Some(Arc::new(
+            open_socket(cfg, iface.as_ref(), src_ipv4, &setting.routing).await?,
+        ))
     } else {
         None
     };
@@ -89,7 +368,10 @@ pub async fn host_scan(
     let socket_v6 = if target_map.keys().into_iter().any(|ip| ip.is_ipv6()) {
         let mut cfg = IcmpConfig::new(IcmpKind::V6);
         cfg = cfg.with_hoplimit(setting.hop_limit.max(1) as u32);
-        Some(Arc::new(AsyncIcmpSocket::new(&cfg).await?))
+        cfg = cfg.with_recv_ttl(true);
+        Some(Arc::new(
+            open_socket(cfg, iface.as_ref(), src_ipv6, &setting.routing).await?,
+        ))
     } else {
         None
     };
@@ -112,174 +394,497 @@ pub async fn host_scan(
     let pending_v4_for_tasks = pending_v4.clone();
     let pending_v6_for_tasks = pending_v6.clone();
 
-    let app_cl = app.clone();
     let timeout_cl = timeout;
     let payload_cl = payload.clone();
     let count_cl = setting.count.max(1);
+    let verbose = setting.verbose;
+    let interval = setting.interval_ms.map(Duration::from_millis);
     let total_cl = total;
     let progress_cl = progress.clone();
+    let max_alive = setting.max_alive;
+    let limit_control = control.clone();
+    let stop_control = control.clone();
+    let checkpoint_control = control.clone();
+    let stop_deadline = &deadline;
+
+    // Stop issuing new probes once the scan is cancelled or out of time
+    let keep_sending = move |_: &IpAddr| {
+        future::ready(!stop_control.is_cancelled() && !stop_deadline.check(&stop_control, sink))
+    };
+    let probe_host = move |dst_ip: IpAddr| {
+        let socket_v4 = socket_v4_for_tasks.clone();
+        let socket_v6 = socket_v6_for_tasks.clone();
+        let pending_v4 = pending_v4_for_tasks.clone();
+        let pending_v6 = pending_v6_for_tasks.clone();
+        let timeout = timeout_cl;
+        let payload = payload_cl.clone();
+        let cnt = count_cl;
+        let total = total_cl;
+        let src_ipv4 = src_ipv4;
+        let src_ipv6 = src_ipv6;
+        let progress = progress_cl.clone();
+        let control = control.clone();
+
+        async move {
+            // Hold off new probes while the scan is paused
+            control.wait_if_paused().await;
+            if control.is_cancelled() {
+                return None;
+            }
+
+            // If no suitable socket, mark unreachable
+            let (sock_opt, pending_map, src_ip) = match SocketFamily::from_ip(&dst_ip) {
+                SocketFamily::IPV4 => (
+                    socket_v4.clone(),
+                    pending_v4.clone(),
+                    src_ipv4.unwrap_or(IpAddr::V4(Ipv4Addr::UNSPECIFIED)),
+                ),
+                SocketFamily::IPV6 => (
+                    socket_v6.clone(),
+                    pending_v6.clone(),
+                    src_ipv6.unwrap_or(IpAddr::V6(Ipv6Addr::UNSPECIFIED)),
+                ),
+            };
 
-    let mut stream_send = stream::iter(target_map.keys().cloned().into_iter())
-        .map(move |dst_ip| {
-            let app = app_cl.clone();
-            let socket_v4 = socket_v4_for_tasks.clone();
-            let socket_v6 = socket_v6_for_tasks.clone();
-            let pending_v4 = pending_v4_for_tasks.clone();
-            let pending_v6 = pending_v6_for_tasks.clone();
-            let timeout = timeout_cl;
-            let payload = payload_cl.clone();
-            let cnt = count_cl;
-            let total = total_cl;
-            let src_ipv4 = src_ipv4;
-            let src_ipv6 = src_ipv6;
-            let progress = progress_cl.clone();
+            let mut samples = EchoSamples::new(dst_ip, verbose);
+            let (state, rtt_ms, reply_ttl, message) = if let Some(sock) = sock_opt {
+                let target = SocketAddr::new(dst_ip, 0);
+                let mut best_rtt: Option<u64> = None;
+                let mut reply_ttl: Option<u8> = None;
+                let mut last_err: Option<String> = None;
+
+                // Random echo identifier per host, validated on receive
+                let id: u16 = rand::thread_rng().gen();
+                let mut last_sent: Option<Instant> = None;
+                for seq in 1..=cnt {
+                    if !wait_interval(&control, interval, last_sent).await {
+                        break;
+                    }
+                    last_sent = Some(Instant::now());
 
-            async move {
-                // If no suitable socket, mark unreachable
-                let (sock_opt, pending_map, src_ip) = match SocketFamily::from_ip(&dst_ip) {
-                    SocketFamily::IPV4 => (
-                        socket_v4.clone(),
-                        pending_v4.clone(),
-                        src_ipv4.unwrap_or(IpAddr::V4(Ipv4Addr::UNSPECIFIED)),
-                    ),
-                    SocketFamily::IPV6 => (
-                        socket_v6.clone(),
-                        pending_v6.clone(),
-                        src_ipv6.unwrap_or(IpAddr::V6(Ipv6Addr::UNSPECIFIED)),
-                    ),
-                };
-
-                let (state, rtt_ms, message) = if let Some(sock) = sock_opt {
-                    let target = SocketAddr::new(dst_ip, 0);
-                    let mut best_rtt: Option<u64> = None;
-                    let mut last_err: Option<String> = None;
-
-                    for seq in 1..=cnt {
-                        // Register pending
-                        let id: u16 = rand::thread_rng().gen();
-                        let (tx, rx) = oneshot::channel::<u64>();
-
-                        {
-                            let mut map = pending_map.lock().await;
-                            map.insert(
-                                dst_ip,
-                                Pending {
-                                    ip: dst_ip,
-                                    sent_at: Instant::now(),
-                                    tx,
-                                },
-                            );
-                        }
+                    // Register pending
+                    let (tx, rx) = oneshot::channel();
 
-                        // Build ICMP Echo Request packet
-                        let pkt = build_icmp_echo_bytes(
-                            src_ip,
+                    {
+                        let mut map = pending_map.lock().await;
+                        map.insert(
                             dst_ip,
-                            id,
-                            seq as u16,
-                            payload.as_bytes(),
+                            Pending {
+                                ip: dst_ip,
+                                id,
+                                sent_at: Instant::now(),
+                                tx,
+                            },
                         );
+                    }
 
-                        // Send ICMP Echo Request
-                        if let Err(e) = sock.send_to(&pkt, target).await {
-                            let mut map = pending_map.lock().await;
-                            map.remove(&dst_ip);
-                            last_err = Some(format!("send error: {}", e));
-                            continue;
-                        }
+                    // Build ICMP Echo Request packet
+                    let pkt =
+                        build_icmp_echo_bytes(src_ip, dst_ip, id, seq as u16, payload.as_bytes());
+
+                    // Send ICMP Echo Request
+                    if let Err(e) = sock.send_to(&pkt, target).await {
+                        let mut map = pending_map.lock().await;
+                        map.remove(&dst_ip);
+                        let err = format!("send error: {}", e);
+                        samples.record(sink, seq, None, None, Some(err.clone()));
+                        last_err = Some(err);
+                        continue;
+                    }
 
-                        // Wait for reply or timeout
-                        match tokio::time::timeout(timeout, rx).await {
-                            Ok(Ok(rtt)) => {
-                                best_rtt = Some(best_rtt.map_or(rtt, |b| b.min(rtt)));
+                    // Wait for reply or timeout
+                    match wait_reply(rx, timeout, &pending_map, dst_ip).await {
+                        Ok(reply) => {
+                            let rtt = reply.rtt_ms;
+                            best_rtt = Some(best_rtt.map_or(rtt, |b| b.min(rtt)));
+                            reply_ttl = reply.ttl;
+                            samples.record(sink, seq, Some(rtt), reply.ttl, None);
+                            if samples.done_after_reply() {
                                 break;
                             }
-                            Ok(Err(_canceled)) => {
-                                last_err = Some("wait canceled".into());
-                            }
-                            Err(_to) => {
-                                let mut map = pending_map.lock().await;
-                                map.remove(&dst_ip);
-                                last_err = Some(format!("timeout (>{}ms)", timeout.as_millis()));
-                            }
+                        }
+                        Err(e) => {
+                            samples.record(sink, seq, None, None, Some(e.clone()));
+                            last_err = Some(e);
                         }
                     }
+                }
 
-                    if let Some(rtt) = best_rtt {
-                        (HostState::Alive, Some(rtt), None)
-                    } else {
-                        (HostState::Unreachable, None, last_err)
-                    }
+                if let Some(rtt) = best_rtt {
+                    (HostState::Alive, Some(rtt), reply_ttl, None)
                 } else {
-                    (
-                        HostState::Unreachable,
-                        None,
-                        Some("no suitable socket for IP family".into()),
-                    )
-                };
-
-                let (done, should_emit) = progress.on_advance();
-
-                let progress_sample = HostScanProgress {
-                    ip_addr: dst_ip,
-                    state,
-                    rtt_ms,
-                    message,
-                    done,
-                    total,
-                };
-
-                // Emit alive host event with detailed info
-                if matches!(progress_sample.state, HostState::Alive) {
-                    let _ = app.emit("hostscan:alive", progress_sample.clone());
+                    (HostState::Unreachable, None, None, last_err)
                 }
+            } else {
+                (
+                    HostState::Unreachable,
+                    None,
+                    None,
+                    Some("no suitable socket for IP family".into()),
+                )
+            };
 
-                // Lightweight progress event: (done, total)
-                if should_emit {
-                    let _ = app.emit("hostscan:progress", (done, total));
-                }
+            let (done, should_emit) = progress.on_advance();
+            control.advance_shared(sink);
+
+            let progress_sample = HostScanProgress {
+                ip_addr: dst_ip,
+                state,
+                rtt_ms,
+                message,
+                reply_ttl,
+                done,
+                total,
+            };
+
+            // Emit alive host event with detailed info
+            if matches!(progress_sample.state, HostState::Alive) {
+                sink.emit("hostscan:alive", progress_sample.clone());
+            }
 
-                progress_sample
+            // Lightweight progress event: (done, total)
+            if should_emit {
+                sink.emit("hostscan:progress", (done, total));
             }
-        })
-        .buffer_unordered(concurrency);
+
+            Some((progress_sample, samples.samples))
+        }
+    };
+    // One stream per family with its own limit: the kernel paces ICMPv4 and
+    // ICMPv6 differently, and a shared buffer lets the slower family's
+    // waiting probes hold the slots of the other.
+    let family_stream = |targets: Vec<IpAddr>, concurrency: usize| {
+        stream::iter(targets)
+            .take_while(keep_sending.clone())
+            .map(probe_host.clone())
+            .buffer_unordered(concurrency.max(1))
+    };
+    let (targets_v4, targets_v6): (Vec<IpAddr>, Vec<IpAddr>) =
+        target_map.keys().copied().partition(|ip| ip.is_ipv4());
+    let mut stream_send = stream::select(
+        family_stream(targets_v4, concurrency_v4),
+        family_stream(targets_v6, concurrency_v6),
+    );
 
     // Collect results
     let mut alive: Vec<(Host, u64)> = Vec::new();
     let mut unreachable: Vec<Host> = Vec::new();
+    let mut samples: BTreeMap<IpAddr, Vec<EchoSample>> = BTreeMap::new();
+
+    let mut limit_reached = false;
 
     while let Some(p) = stream_send.next().await {
+        // Skipped after cancellation
+        let Some((p, probe_samples)) = p else {
+            continue;
+        };
+        if !probe_samples.is_empty() {
+            samples.insert(p.ip_addr, probe_samples);
+        }
         match p.state {
             HostState::Alive => {
                 if let Some(host) = target_map.get(&p.ip_addr) {
                     alive.push((host.clone(), p.rtt_ms.unwrap_or(0)));
                 }
+                if let Some(max_alive) = max_alive {
+                    if !limit_reached && alive.len() >= max_alive {
+                        limit_reached = true;
+                        limit_control.cancel();
+                        tracing::debug!("host scan: max_alive ({}) reached", max_alive);
+                        sink.emit(
+                            "hostscan:limit_reached",
+                            HostScanLimitPayload {
+                                run_id: run_id.to_string(),
+                                max_alive,
+                            },
+                        );
+                    }
+                }
             }
             HostState::Unreachable => {
                 if let Some(host) = target_map.get(&p.ip_addr) {
+                    if let Some(checkpoint) = checkpoint_control.checkpoint() {
+                        checkpoint.host_unreachable(host.clone());
+                    }
                     unreachable.push(host.clone());
                 }
             }
         }
     }
 
-    // Drop sockets to gracefully terminate receiver tasks
+    // Stop the receivers once they have drained late replies
+    if let Some(rx) = rx_v4 {
+        rx.shutdown().await;
+    }
+    if let Some(rx) = rx_v6 {
+        rx.shutdown().await;
+    }
     drop(socket_v4);
     drop(socket_v6);
-    if let Some(h) = rx_v4 {
-        let _ = h.abort();
-    }
-    if let Some(h) = rx_v6 {
-        let _ = h.abort();
+
+    // Hosts completed before a resume belong to the same run
+    let mut total = total;
+    if let Some(checkpoint) = checkpoint_control.checkpoint() {
+        let prior = checkpoint.prior_unreachable();
+        unreachable.extend_from_slice(prior);
+        total += prior.len() as u32;
     }
 
+    // Sort by IP (v4 before v6) so reruns produce the same order
+    alive.sort_by_key(|(host, _)| host.ip);
+    unreachable.sort_by_key(|host| host.ip);
+
     // Report results
     let report = HostScanReport {
         run_id: run_id.to_string(),
+        summary: HostScanSummary::from_alive(&alive, total),
+        unresolved,
         alive,
         unreachable,
         total,
+        deadline_reached: deadline.reached(),
+        samples,
     };
-    let _ = app.emit("hostscan:done", report.clone());
+    tracing::debug!(
+        "host scan finished: {} alive, {} unreachable",
+        report.alive.len(),
+        report.unreachable.len()
+    );
+    sink.emit("hostscan:done", report.clone());
     Ok(report)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::sync::mpsc;
+
+    /// Replies fed through a channel instead of a socket
+    struct ChannelSource {
+        rx: Mutex<mpsc::UnboundedReceiver<(Vec<u8>, SocketAddr)>>,
+    }
+
+    impl ReplySource for ChannelSource {
+        async fn recv(&self, buf: &mut [u8]) -> std::io::Result<(usize, SocketAddr, Option<u8>)> {
+            match self.rx.lock().await.recv().await {
+                Some((bytes, addr)) => {
+                    buf[..bytes.len()].copy_from_slice(&bytes);
+                    Ok((bytes.len(), addr, Some(64)))
+                }
+                None => Err(std::io::ErrorKind::BrokenPipe.into()),
+            }
+        }
+
+        fn echo_id(&self, requested: u16) -> u16 {
+            requested
+        }
+    }
+
+    fn echo_reply_v6(id: u16) -> Vec<u8> {
+        let mut icmp = vec![129, 0, 0, 0];
+        icmp.extend_from_slice(&id.to_be_bytes());
+        icmp.extend_from_slice(&1u16.to_be_bytes());
+        icmp.extend_from_slice(b"np:hs");
+        icmp
+    }
+
+    fn source() -> (
+        Arc<ChannelSource>,
+        mpsc::UnboundedSender<(Vec<u8>, SocketAddr)>,
+    ) {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let source = ChannelSource { rx: Mutex::new(rx) };
+        (Arc::new(source), tx)
+    }
+
+    async fn register(
+        pending: &Mutex<HashMap<IpAddr, Pending>>,
+        ip: IpAddr,
+        id: u16,
+    ) -> oneshot::Receiver<Result<EchoReply, String>> {
+        let (tx, rx) = oneshot::channel();
+        let entry = Pending {
+            ip,
+            id,
+            sent_at: Instant::now(),
+            tx,
+        };
+        pending.lock().await.insert(ip, entry);
+        rx
+    }
+
+    #[tokio::test]
+    async fn shutdown_delivers_reply_queued_at_tail() {
+        let (source, replies) = source();
+        let pending = Arc::new(Mutex::new(HashMap::new()));
+        let ip: IpAddr = "2001:db8::1".parse().unwrap();
+        let waiter = register(&pending, ip, 0x1234).await;
+        let receiver = spawn_receiver(source, pending.clone(), true);
+
+        // The last reply arrives just as the scan finishes
+        replies
+            .send((echo_reply_v6(0x1234), SocketAddr::new(ip, 0)))
+            .unwrap();
+        receiver.shutdown().await;
+
+        let reply = waiter
+            .await
+            .expect("reply dropped at shutdown")
+            .expect("echo reply");
+        assert_eq!(reply.ttl, Some(64));
+        assert!(pending.lock().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn unreachable_from_router_fails_request_with_reason() {
+        let (source, replies) = source();
+        let pending = Arc::new(Mutex::new(HashMap::new()));
+        let ip: IpAddr = "2001:db8::3".parse().unwrap();
+        let waiter = register(&pending, ip, 0x1234).await;
+        let receiver = spawn_receiver(source, pending.clone(), true);
+
+        // Destination unreachable, code 1, quoting our echo request to `ip`
+        let IpAddr::V6(dst) = ip else { unreachable!() };
+        let mut request = echo_reply_v6(0x1234);
+        request[0] = 128;
+        let quoted = crate::probe::packet::build_ipv6_packet(
+            Ipv6Addr::LOCALHOST,
+            dst,
+            0,
+            &[],
+            crate::probe::packet::IPV6_NEXT_ICMPV6,
+            &request,
+        );
+        let mut unreach = vec![1, 1, 0, 0, 0, 0, 0, 0];
+        unreach.extend_from_slice(&quoted);
+        let router: IpAddr = "2001:db8::fe".parse().unwrap();
+        replies.send((unreach, SocketAddr::new(router, 0))).unwrap();
+
+        let result = waiter.await.expect("error dropped");
+        assert_eq!(
+            result.err().as_deref(),
+            Some("administratively prohibited (from 2001:db8::fe)")
+        );
+        assert!(pending.lock().await.is_empty());
+        receiver.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn reply_racing_timeout_is_kept() {
+        let pending = Arc::new(Mutex::new(HashMap::new()));
+        let ip: IpAddr = "192.0.2.1".parse().unwrap();
+        let rx = register(&pending, ip, 0x1234).await;
+
+        // Hold the lock so the timeout fires before the reply is delivered
+        let mut map = pending.lock().await;
+        let waiter = tokio::spawn({
+            let pending = pending.clone();
+            async move { wait_reply(rx, Duration::from_millis(10), &pending, ip).await }
+        });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        // Deliver like the receiver does, before the waiter gets the lock
+        let entry = map.remove(&ip).unwrap();
+        let _ = entry.tx.send(Ok(EchoReply {
+            rtt_ms: 9,
+            ttl: Some(64),
+        }));
+        drop(map);
+
+        let reply = waiter.await.unwrap().expect("reply lost on timeout");
+        assert_eq!(reply.rtt_ms, 9);
+    }
+
+    #[tokio::test]
+    async fn timeout_without_reply_clears_pending() {
+        let pending = Arc::new(Mutex::new(HashMap::new()));
+        let ip: IpAddr = "192.0.2.2".parse().unwrap();
+        let rx = register(&pending, ip, 0x1234).await;
+
+        let result = wait_reply(rx, Duration::from_millis(10), &pending, ip).await;
+        assert_eq!(result.err().as_deref(), Some("timeout (>10ms)"));
+        assert!(pending.lock().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn shutdown_gives_up_on_silent_hosts() {
+        let (source, _replies) = source();
+        let pending = Arc::new(Mutex::new(HashMap::new()));
+        let ip: IpAddr = "2001:db8::2".parse().unwrap();
+        let waiter = register(&pending, ip, 0x1234).await;
+        let receiver = spawn_receiver(source, pending.clone(), true);
+
+        let start = Instant::now();
+        receiver.shutdown().await;
+        assert!(start.elapsed() < RECEIVER_DRAIN * 10);
+        // Unanswered requests are released, not left hanging
+        assert!(waiter.await.is_err());
+        assert!(pending.lock().await.is_empty());
+    }
+
+    /// Counts `hostscan:sample` events
+    #[derive(Default)]
+    struct SampleCounter(std::sync::Mutex<usize>);
+
+    impl ProgressEmitter for SampleCounter {
+        fn emit<T: serde::Serialize + Clone>(&self, event: &str, _payload: T) {
+            if event == "hostscan:sample" {
+                *self.0.lock().unwrap() += 1;
+            }
+        }
+    }
+
+    #[test]
+    fn verbose_records_and_emits_every_probe() {
+        let ip: IpAddr = "192.0.2.4".parse().unwrap();
+        let sink = SampleCounter::default();
+
+        let mut quiet = EchoSamples::new(ip, false);
+        quiet.record(&sink, 1, Some(3), Some(64), None);
+        assert!(quiet.done_after_reply());
+        assert!(quiet.samples.is_empty());
+        assert_eq!(*sink.0.lock().unwrap(), 0);
+
+        let mut verbose = EchoSamples::new(ip, true);
+        verbose.record(&sink, 1, None, None, Some("timeout (>10ms)".into()));
+        verbose.record(&sink, 2, Some(3), Some(64), None);
+        assert!(!verbose.done_after_reply());
+        let seqs: Vec<_> = verbose.samples.iter().map(|s| (s.seq, s.rtt_ms)).collect();
+        assert_eq!(seqs, [(1, None), (2, Some(3))]);
+        assert_eq!(*sink.0.lock().unwrap(), 2);
+    }
+
+    #[tokio::test]
+    async fn interval_spaces_probes_and_stops_on_cancel() {
+        let control = Arc::new(ScanControl::new("test", "hostscan"));
+        let interval = Some(Duration::from_millis(50));
+
+        // Nothing to wait for before the first probe or without an interval
+        let start = Instant::now();
+        assert!(wait_interval(&control, interval, None).await);
+        assert!(wait_interval(&control, None, Some(start)).await);
+        assert!(start.elapsed() < Duration::from_millis(50));
+
+        assert!(wait_interval(&control, interval, Some(start)).await);
+        assert!(start.elapsed() >= Duration::from_millis(50));
+
+        // A long interval does not hold up a cancelled scan
+        let waiter = tokio::spawn({
+            let control = control.clone();
+            async move {
+                wait_interval(
+                    &control,
+                    Some(Duration::from_secs(60)),
+                    Some(Instant::now()),
+                )
+                .await
+            }
+        });
+        tokio::task::yield_now().await;
+        control.cancel();
+        let finished = tokio::time::timeout(Duration::from_secs(1), waiter)
+            .await
+            .expect("interval wait not cancelled")
+            .unwrap();
+        assert!(!finished);
+    }
+}
@@ -0,0 +1,500 @@
+use anyhow::{bail, Result};
+use futures::{stream, StreamExt};
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::{oneshot, Mutex};
+
+use crate::error::ScanError;
+use crate::model::scan::{
+    IpProtocolScanReport, IpProtocolScanSample, IpProtocolScanSetting, IpProtocolState,
+};
+use crate::probe::packet::{build_ipv4_fragments, internet_checksum};
+use crate::probe::scan::control::ScanControl;
+use crate::probe::scan::progress::ThrottledProgress;
+use crate::probe::scan::sink::ProgressEmitter;
+use crate::socket::icmp::{AsyncIcmpSocket, IcmpConfig, IcmpKind, IcmpSocketType};
+use crate::socket::ip::AsyncRawIpSocket;
+
+/// Protocols probed when the setting lists none
+pub const DEFAULT_IP_PROTOCOLS: [u8; 10] = [1, 2, 4, 6, 17, 41, 47, 50, 51, 132];
+
+/// Destination port of the UDP probe. Unassigned, so closed on most hosts.
+const UDP_PROBE_PORT: u16 = 40125;
+/// Destination port of the TCP probe
+const TCP_PROBE_PORT: u16 = 80;
+
+/// ICMP destination unreachable codes
+const ICMP_PROTOCOL_UNREACHABLE: u8 = 2;
+const ICMP_PORT_UNREACHABLE: u8 = 3;
+
+/// Well-known name of an IP protocol number
+pub fn ip_protocol_name(protocol: u8) -> String {
+    let name = match protocol {
+        1 => "icmp",
+        2 => "igmp",
+        4 => "ipip",
+        6 => "tcp",
+        17 => "udp",
+        41 => "ipv6",
+        47 => "gre",
+        50 => "esp",
+        51 => "ah",
+        58 => "ipv6-icmp",
+        89 => "ospf",
+        103 => "pim",
+        112 => "vrrp",
+        115 => "l2tp",
+        132 => "sctp",
+        _ => return format!("proto-{}", protocol),
+    };
+    name.to_string()
+}
+
+/// Build the payload sent after the kernel-built IPv4 header.
+/// Protocols without a specific probe get an empty payload.
+/// TCP and UDP probes use `src_port`, or a random port above 1024.
+fn build_probe_payload(
+    protocol: u8,
+    src: Ipv4Addr,
+    dst: Ipv4Addr,
+    src_port: Option<u16>,
+) -> Vec<u8> {
+    let src_port = src_port.unwrap_or_else(|| rand::random::<u16>().max(1024));
+    match protocol {
+        // ICMP echo request
+        1 => {
+            let mut buf = vec![8, 0, 0, 0];
+            buf.extend_from_slice(&rand::random::<u16>().to_be_bytes());
+            buf.extend_from_slice(&1u16.to_be_bytes());
+            let sum = internet_checksum(&buf);
+            buf[2..4].copy_from_slice(&sum.to_be_bytes());
+            buf
+        }
+        // IGMPv2 general membership query
+        2 => {
+            let mut buf = vec![0x11, 0, 0, 0, 0, 0, 0, 0];
+            let sum = internet_checksum(&buf);
+            buf[2..4].copy_from_slice(&sum.to_be_bytes());
+            buf
+        }
+        // TCP ACK. Any live stack answers it with a RST.
+        6 => {
+            let mut buf = Vec::with_capacity(20);
+            buf.extend_from_slice(&src_port.to_be_bytes());
+            buf.extend_from_slice(&TCP_PROBE_PORT.to_be_bytes());
+            buf.extend_from_slice(&0u32.to_be_bytes());
+            buf.extend_from_slice(&rand::random::<u32>().to_be_bytes());
+            // Data offset 5, ACK flag
+            buf.extend_from_slice(&[0x50, 0x10]);
+            buf.extend_from_slice(&1024u16.to_be_bytes());
+            buf.extend_from_slice(&[0, 0, 0, 0]);
+            let mut pseudo = Vec::with_capacity(12 + buf.len());
+            pseudo.extend_from_slice(&src.octets());
+            pseudo.extend_from_slice(&dst.octets());
+            pseudo.extend_from_slice(&[0, 6]);
+            pseudo.extend_from_slice(&(buf.len() as u16).to_be_bytes());
+            pseudo.extend_from_slice(&buf);
+            let sum = internet_checksum(&pseudo);
+            buf[16..18].copy_from_slice(&sum.to_be_bytes());
+            buf
+        }
+        // UDP header only. Checksum 0 means "none" over IPv4.
+        17 => {
+            let mut buf = Vec::with_capacity(8);
+            buf.extend_from_slice(&src_port.to_be_bytes());
+            buf.extend_from_slice(&UDP_PROBE_PORT.to_be_bytes());
+            buf.extend_from_slice(&8u16.to_be_bytes());
+            buf.extend_from_slice(&[0, 0]);
+            buf
+        }
+        _ => Vec::new(),
+    }
+}
+
+/// Sends probes with a caller-built IPv4 header, for fragmentation and decoys
+struct RawProbeSender {
+    socket: AsyncRawIpSocket,
+    /// Payload bytes per fragment, if probes are fragmented
+    fragment_size: Option<usize>,
+    /// Spoofed source addresses sent alongside each real probe
+    decoys: Vec<Ipv4Addr>,
+}
+
+impl RawProbeSender {
+    async fn send(
+        &self,
+        protocol: u8,
+        src: Ipv4Addr,
+        dst: Ipv4Addr,
+        payload: &[u8],
+    ) -> std::io::Result<()> {
+        let target = SocketAddr::new(IpAddr::V4(dst), 0);
+        let fragment_size = self.fragment_size.unwrap_or(usize::MAX);
+        for packet in build_ipv4_fragments(src, dst, protocol, payload, fragment_size) {
+            self.socket.send_to(&packet, target).await?;
+        }
+        Ok(())
+    }
+
+    /// Send a copy of the probe from each decoy address.
+    /// The payload is rebuilt per source so checksums stay valid.
+    async fn send_decoys(&self, protocol: u8, dst: Ipv4Addr, src_port: Option<u16>) {
+        for decoy in &self.decoys {
+            let payload = build_probe_payload(protocol, *decoy, dst, src_port);
+            if let Err(e) = self.send(protocol, *decoy, dst, &payload).await {
+                tracing::debug!("decoy probe from {} failed: {}", decoy, e);
+            }
+        }
+    }
+}
+
+/// Check whether a raw packet from the target is a reply to the probe `sent`
+/// in the probed protocol. Protocols with a specific probe must answer it
+/// (echo ID and sequence, swapped ports); any other packet of the protocol
+/// counts for the rest.
+fn is_protocol_reply(protocol: u8, packet: &[u8], sent: &[u8]) -> bool {
+    if packet.len() < 20 || packet[9] != protocol {
+        return false;
+    }
+    let ihl = ((packet[0] & 0x0f) as usize) * 4;
+    let Some(reply) = packet.get(ihl..) else {
+        return false;
+    };
+    // Raw sockets see our own probe when scanning a local address
+    if reply == sent {
+        return false;
+    }
+    match protocol {
+        // Echo reply with the ID and sequence of our request; errors are
+        // handled by the ICMP listener
+        1 => reply.len() >= 8 && reply[0] == 0 && reply[4..8] == sent[4..8],
+        // Membership report (v1, v2, v3), not another router's query
+        2 => matches!(reply.first(), Some(0x12 | 0x16 | 0x22)),
+        // From the probed port back to our source port
+        6 | 17 => reply.len() >= 4 && reply[0..2] == sent[2..4] && reply[2..4] == sent[0..2],
+        _ => true,
+    }
+}
+
+/// Receive ICMP destination unreachable messages quoting a probe to `target`
+/// and hand the code to the waiting probe.
+fn spawn_unreachable_listener(
+    socket: Arc<AsyncIcmpSocket>,
+    target: Ipv4Addr,
+    pending: Arc<Mutex<HashMap<u8, oneshot::Sender<u8>>>>,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut buf = vec![0u8; 2048];
+        loop {
+            let Ok((n, _addr)) = socket.recv_from(&mut buf).await else {
+                break;
+            };
+            let packet = &buf[..n];
+            if packet.len() < 20 {
+                continue;
+            }
+            let ihl = ((packet[0] & 0x0f) as usize) * 4;
+            let icmp = &packet[ihl..];
+            // Destination unreachable with at least the quoted IPv4 header
+            if icmp.len() < 8 + 20 || icmp[0] != 3 {
+                continue;
+            }
+            let quoted = &icmp[8..];
+            let quoted_dst = Ipv4Addr::new(quoted[16], quoted[17], quoted[18], quoted[19]);
+            if quoted_dst != target {
+                continue;
+            }
+            if let Some(tx) = pending.lock().await.remove(&quoted[9]) {
+                let _ = tx.send(icmp[1]);
+            }
+        }
+    })
+}
+
+/// Probe a single IP protocol and classify the response.
+async fn probe_protocol(
+    protocol: u8,
+    src: Ipv4Addr,
+    dst: Ipv4Addr,
+    timeout: Duration,
+    pending: Arc<Mutex<HashMap<u8, oneshot::Sender<u8>>>>,
+    raw_sender: Option<Arc<RawProbeSender>>,
+    src_port: Option<u16>,
+) -> (IpProtocolState, Option<u64>, Option<String>) {
+    let socket = match AsyncRawIpSocket::new_v4(protocol) {
+        Ok(s) => s,
+        Err(e) => {
+            return (
+                IpProtocolState::Filtered,
+                None,
+                Some(format!("raw socket error: {}", e)),
+            )
+        }
+    };
+    let (tx, rx) = oneshot::channel();
+    pending.lock().await.insert(protocol, tx);
+
+    let payload = build_probe_payload(protocol, src, dst, src_port);
+    let start = Instant::now();
+    if let Some(raw) = &raw_sender {
+        raw.send_decoys(protocol, dst, src_port).await;
+    }
+    let sent = match raw_sender.as_ref().filter(|r| r.fragment_size.is_some()) {
+        Some(raw) => raw.send(protocol, src, dst, &payload).await,
+        None => socket
+            .send_to(&payload, SocketAddr::new(IpAddr::V4(dst), 0))
+            .await
+            .map(|_| ()),
+    };
+    if let Err(e) = sent {
+        pending.lock().await.remove(&protocol);
+        return (
+            IpProtocolState::Filtered,
+            None,
+            Some(format!("send error: {}", e)),
+        );
+    }
+
+    let reply = async {
+        let mut buf = vec![0u8; 2048];
+        loop {
+            match socket.recv_from(&mut buf).await {
+                Ok((n, addr)) if addr.ip() == IpAddr::V4(dst) => {
+                    if is_protocol_reply(protocol, &buf[..n], &payload) {
+                        return;
+                    }
+                }
+                Ok(_) => continue,
+                // Keep waiting for the ICMP listener or the timeout
+                Err(_) => std::future::pending::<()>().await,
+            }
+        }
+    };
+
+    let result = tokio::select! {
+        _ = reply => (IpProtocolState::Open, None),
+        code = rx => match code {
+            Ok(ICMP_PROTOCOL_UNREACHABLE) => (IpProtocolState::Closed, Some("protocol unreachable".to_string())),
+            // Only the UDP probe elicits this, and it proves UDP is processed
+            Ok(ICMP_PORT_UNREACHABLE) => (IpProtocolState::Open, Some("port unreachable".to_string())),
+            Ok(code) => (IpProtocolState::Filtered, Some(format!("icmp unreachable code {}", code))),
+            Err(_) => (IpProtocolState::OpenFiltered, None),
+        },
+        _ = tokio::time::sleep(timeout) => (IpProtocolState::OpenFiltered, None),
+    };
+    pending.lock().await.remove(&protocol);
+
+    let rtt_ms = match result.0 {
+        IpProtocolState::OpenFiltered => None,
+        _ => Some(start.elapsed().as_millis() as u64),
+    };
+    (result.0, rtt_ms, result.1)
+}
+
+pub async fn ip_protocol_scan(
+    sink: &impl ProgressEmitter,
+    run_id: &str,
+    src_ip: Ipv4Addr,
+    setting: IpProtocolScanSetting,
+    control: Arc<ScanControl>,
+) -> Result<IpProtocolScanReport> {
+    let IpAddr::V4(dst) = setting.ip_addr else {
+        bail!(ScanError::unsupported(
+            "IP protocol scan supports IPv4 targets only"
+        ));
+    };
+    let mut protocols = if setting.protocols.is_empty() {
+        DEFAULT_IP_PROTOCOLS.to_vec()
+    } else {
+        setting.protocols.clone()
+    };
+    protocols.sort_unstable();
+    protocols.dedup();
+
+    let icmp_cfg = IcmpConfig::new(IcmpKind::V4).with_sock_type(IcmpSocketType::Raw);
+    let icmp_socket = Arc::new(AsyncIcmpSocket::new(&icmp_cfg).await?);
+    if !icmp_socket.socket_type().is_raw() {
+        bail!(ScanError::permission_denied(
+            "IP protocol scan requires raw socket privileges"
+        ));
+    }
+    let decoys = setting
+        .decoys
+        .iter()
+        .map(|ip| match ip {
+            IpAddr::V4(v4) => Ok(*v4),
+            IpAddr::V6(_) => {
+                Err(ScanError::invalid_input(format!("Decoy {} is not an IPv4 address", ip)).into())
+            }
+        })
+        .collect::<Result<Vec<Ipv4Addr>>>()?;
+    let raw_sender = if setting.fragment_size.is_some() || !decoys.is_empty() {
+        let socket = AsyncRawIpSocket::new_v4_header_included().map_err(|e| {
+            ScanError::permission_denied(format!(
+                "Fragmented and decoy probes require raw socket privileges: {}",
+                e
+            ))
+        })?;
+        if let Some(size) = setting.fragment_size {
+            tracing::debug!("sending probes as fragments of {} bytes", size);
+        }
+        if !decoys.is_empty() {
+            tracing::debug!("sending probes with {} decoys", decoys.len());
+        }
+        Some(Arc::new(RawProbeSender {
+            socket,
+            fragment_size: setting.fragment_size.map(|size| size as usize),
+            decoys,
+        }))
+    } else {
+        None
+    };
+    let pending: Arc<Mutex<HashMap<u8, oneshot::Sender<u8>>>> =
+        Arc::new(Mutex::new(HashMap::new()));
+    let listener = spawn_unreachable_listener(icmp_socket, dst, pending.clone());
+
+    let timeout = Duration::from_millis(setting.timeout_ms);
+    let source_port = setting.source_port;
+    let total = protocols.len() as u32;
+    let progress = Arc::new(ThrottledProgress::new(total));
+    tracing::debug!("ip protocol scan started: {} protocols", total);
+
+    // Probes are keyed by protocol in the pending map, so each protocol is probed once.
+    let mut tasks = stream::iter(protocols.into_iter())
+        .map(|protocol| {
+            let progress = progress.clone();
+            let control = control.clone();
+            let pending = pending.clone();
+            let raw_sender = raw_sender.clone();
+            async move {
+                // Hold off new probes while the scan is paused
+                control.wait_if_paused().await;
+
+                let (state, rtt_ms, message) = probe_protocol(
+                    protocol,
+                    src_ip,
+                    dst,
+                    timeout,
+                    pending,
+                    raw_sender,
+                    source_port,
+                )
+                .await;
+                let (done, should_emit) = progress.on_advance();
+                let sample = IpProtocolScanSample {
+                    protocol,
+                    name: ip_protocol_name(protocol),
+                    state,
+                    rtt_ms,
+                    message,
+                    done,
+                    total,
+                };
+                sink.emit("ipprotoscan:sample", sample.clone());
+                if should_emit {
+                    sink.emit("ipprotoscan:progress", (done, total));
+                }
+                sample
+            }
+        })
+        .buffer_unordered(DEFAULT_IP_PROTOCOLS.len());
+
+    let mut samples = Vec::new();
+    while let Some(sample) = tasks.next().await {
+        samples.push(sample);
+    }
+    listener.abort();
+    samples.sort_by_key(|s| s.protocol);
+
+    let report = IpProtocolScanReport {
+        run_id: run_id.to_string(),
+        ip_addr: setting.ip_addr,
+        hostname: setting.hostname.clone(),
+        samples,
+    };
+    tracing::debug!(
+        "ip protocol scan finished: {} open of {} protocols",
+        report
+            .samples
+            .iter()
+            .filter(|s| s.state == IpProtocolState::Open)
+            .count(),
+        total
+    );
+    sink.emit("ipprotoscan:done", report.clone());
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SRC: Ipv4Addr = Ipv4Addr::new(192, 0, 2, 1);
+    const DST: Ipv4Addr = Ipv4Addr::new(192, 0, 2, 2);
+
+    /// `l4` behind a minimal IPv4 header of `protocol`
+    fn packet(protocol: u8, l4: &[u8]) -> Vec<u8> {
+        let mut p = vec![0x45, 0, 0, 0, 0, 0, 0, 0, 64, protocol, 0, 0];
+        p.extend_from_slice(&DST.octets());
+        p.extend_from_slice(&SRC.octets());
+        p.extend_from_slice(l4);
+        p
+    }
+
+    #[test]
+    fn echo_reply_must_match_id_and_sequence() {
+        let sent = build_probe_payload(1, SRC, DST, None);
+        let mut reply = sent.clone();
+        reply[0] = 0;
+        assert!(is_protocol_reply(1, &packet(1, &reply), &sent));
+        assert!(!is_protocol_reply(1, &packet(1, &sent), &sent));
+        reply[5] ^= 0xff;
+        assert!(!is_protocol_reply(1, &packet(1, &reply), &sent));
+    }
+
+    #[test]
+    fn tcp_and_udp_replies_must_swap_ports() {
+        for protocol in [6, 17] {
+            let sent = build_probe_payload(protocol, SRC, DST, Some(40000));
+            let mut reply = sent.clone();
+            reply[0..2].copy_from_slice(&sent[2..4]);
+            reply[2..4].copy_from_slice(&sent[0..2]);
+            assert!(is_protocol_reply(
+                protocol,
+                &packet(protocol, &reply),
+                &sent
+            ));
+            // Own probe, or traffic of another connection
+            assert!(!is_protocol_reply(
+                protocol,
+                &packet(protocol, &sent),
+                &sent
+            ));
+            reply[2..4].copy_from_slice(&40001u16.to_be_bytes());
+            assert!(!is_protocol_reply(
+                protocol,
+                &packet(protocol, &reply),
+                &sent
+            ));
+        }
+    }
+
+    #[test]
+    fn igmp_queries_are_not_replies() {
+        let sent = build_probe_payload(2, SRC, DST, None);
+        assert!(!is_protocol_reply(2, &packet(2, &sent), &sent));
+        assert!(!is_protocol_reply(
+            2,
+            &packet(2, &[0x11, 0, 0, 0, 224, 0, 0, 1]),
+            &sent
+        ));
+        assert!(is_protocol_reply(
+            2,
+            &packet(2, &[0x16, 0, 0, 0, 224, 0, 0, 1]),
+            &sent
+        ));
+        assert!(!is_protocol_reply(6, &packet(2, &[0x16, 0, 0, 0]), &sent));
+    }
+}
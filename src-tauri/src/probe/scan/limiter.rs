@@ -0,0 +1,256 @@
+use std::io;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::Notify;
+
+/// Lowest in-flight limit the controller backs off to.
+const MIN_LIMIT: usize = 4;
+/// Attempts per probe when it fails on local resource exhaustion.
+pub const MAX_RESOURCE_RETRIES: u32 = 3;
+/// Errors within this window after a backoff count as the same burst.
+const BACKOFF_COOLDOWN: Duration = Duration::from_millis(500);
+/// Wait before the first retry of a probe hit by resource exhaustion, doubled per attempt.
+const RESOURCE_RETRY_DELAY: Duration = Duration::from_millis(100);
+
+/// Wait before retry `attempt` (1-based) so in-flight probes can release
+/// their ports and descriptors.
+pub fn retry_delay(attempt: u32) -> Duration {
+    RESOURCE_RETRY_DELAY * 2u32.pow(attempt.saturating_sub(1).min(4))
+}
+
+/// Whether an I/O error means no local port was free for the connect:
+/// the ephemeral range is used up (EADDRNOTAVAIL) or the chosen source
+/// port is taken (EADDRINUSE, also Windows' answer to exhaustion).
+pub fn is_source_port_exhaustion(e: &io::Error) -> bool {
+    matches!(
+        e.kind(),
+        io::ErrorKind::AddrNotAvailable | io::ErrorKind::AddrInUse
+    )
+}
+
+/// Whether an I/O error indicates local resource exhaustion
+/// (out of file descriptors, ephemeral ports or socket buffers)
+/// rather than a property of the target.
+pub fn is_resource_exhaustion(e: &io::Error) -> bool {
+    if e.kind() == io::ErrorKind::WouldBlock || is_source_port_exhaustion(e) {
+        return true;
+    }
+    match e.raw_os_error() {
+        #[cfg(unix)]
+        Some(code) => matches!(code, libc::EMFILE | libc::ENFILE | libc::ENOBUFS),
+        // WSAEMFILE, WSAENOBUFS
+        #[cfg(windows)]
+        Some(code) => matches!(code, 10024 | 10055),
+        _ => false,
+    }
+}
+
+/// AIMD controller for the number of in-flight probes.
+/// Halves the limit on a burst of resource errors and grows it by one
+/// after a full window of successful probes, up to the tuned maximum.
+#[derive(Debug)]
+pub struct AdaptiveLimiter {
+    /// Tuned concurrency, the upper bound of `limit`.
+    max: usize,
+    min: usize,
+    /// Current in-flight limit.
+    limit: AtomicUsize,
+    in_flight: AtomicUsize,
+    /// Successful probes since the limit last changed.
+    successes: AtomicUsize,
+    /// Time of the last backoff.
+    last_backoff: Mutex<Option<Instant>>,
+    /// Wakes up workers waiting for a slot.
+    notify: Notify,
+}
+
+/// Slot held by an in-flight probe. Released on drop.
+#[derive(Debug)]
+pub struct LimiterPermit {
+    limiter: Arc<AdaptiveLimiter>,
+}
+
+impl Drop for LimiterPermit {
+    fn drop(&mut self) {
+        self.limiter.in_flight.fetch_sub(1, Ordering::AcqRel);
+        self.limiter.notify.notify_one();
+    }
+}
+
+impl AdaptiveLimiter {
+    pub fn new(max: usize) -> Self {
+        let max = max.max(1);
+        Self {
+            max,
+            min: MIN_LIMIT.min(max),
+            limit: AtomicUsize::new(max),
+            in_flight: AtomicUsize::new(0),
+            successes: AtomicUsize::new(0),
+            last_backoff: Mutex::new(None),
+            notify: Notify::new(),
+        }
+    }
+
+    pub fn limit(&self) -> usize {
+        self.limit.load(Ordering::Acquire)
+    }
+
+    /// Wait for an in-flight slot.
+    pub async fn acquire(self: &Arc<Self>) -> LimiterPermit {
+        loop {
+            let notified = self.notify.notified();
+            tokio::pin!(notified);
+            // Register before checking so a concurrent release cannot be missed
+            notified.as_mut().enable();
+            let current = self.in_flight.load(Ordering::Acquire);
+            if current < self.limit() {
+                if self
+                    .in_flight
+                    .compare_exchange(current, current + 1, Ordering::AcqRel, Ordering::Acquire)
+                    .is_ok()
+                {
+                    return LimiterPermit {
+                        limiter: self.clone(),
+                    };
+                }
+                continue;
+            }
+            notified.await;
+        }
+    }
+
+    /// Record the outcome of a probe. Returns true if the error was
+    /// resource exhaustion, in which case the caller should retry the probe.
+    pub fn on_result(&self, err: Option<&io::Error>) -> bool {
+        match err {
+            Some(e) if is_resource_exhaustion(e) => {
+                self.back_off();
+                true
+            }
+            _ => {
+                self.on_success();
+                false
+            }
+        }
+    }
+
+    fn on_success(&self) {
+        let limit = self.limit();
+        if limit >= self.max {
+            return;
+        }
+        let successes = self.successes.fetch_add(1, Ordering::AcqRel) + 1;
+        if successes >= limit {
+            self.successes.store(0, Ordering::Release);
+            self.limit
+                .store((limit + 1).min(self.max), Ordering::Release);
+            self.notify.notify_one();
+        }
+    }
+
+    fn back_off(&self) {
+        let mut last = self
+            .last_backoff
+            .lock()
+            .expect("AdaptiveLimiter::last_backoff poisoned");
+        if last.is_some_and(|t| t.elapsed() < BACKOFF_COOLDOWN) {
+            return;
+        }
+        *last = Some(Instant::now());
+        let limit = self.limit();
+        let reduced = (limit / 2).max(self.min);
+        self.limit.store(reduced, Ordering::Release);
+        self.successes.store(0, Ordering::Release);
+        tracing::debug!(
+            "resource errors: reducing concurrency {} -> {}",
+            limit,
+            reduced
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn retry_delay_doubles_up_to_a_cap() {
+        assert_eq!(retry_delay(1), RESOURCE_RETRY_DELAY);
+        assert_eq!(retry_delay(2), RESOURCE_RETRY_DELAY * 2);
+        assert_eq!(retry_delay(3), RESOURCE_RETRY_DELAY * 4);
+        assert_eq!(retry_delay(5), RESOURCE_RETRY_DELAY * 16);
+        assert_eq!(retry_delay(40), RESOURCE_RETRY_DELAY * 16);
+        // Attempt 0 is treated as the first
+        assert_eq!(retry_delay(0), RESOURCE_RETRY_DELAY);
+    }
+
+    #[test]
+    fn source_port_exhaustion_errors() {
+        assert!(is_source_port_exhaustion(
+            &io::ErrorKind::AddrNotAvailable.into()
+        ));
+        assert!(is_source_port_exhaustion(&io::ErrorKind::AddrInUse.into()));
+        assert!(!is_source_port_exhaustion(&io::ErrorKind::TimedOut.into()));
+        assert!(!is_source_port_exhaustion(
+            &io::ErrorKind::ConnectionRefused.into()
+        ));
+    }
+
+    #[test]
+    fn backs_off_once_per_burst() {
+        let limiter = AdaptiveLimiter::new(64);
+        let exhausted = io::Error::from(io::ErrorKind::AddrNotAvailable);
+        assert!(limiter.on_result(Some(&exhausted)));
+        assert!(limiter.on_result(Some(&exhausted)));
+        assert_eq!(limiter.limit(), 32);
+        // Errors about the target do not count
+        assert!(!limiter.on_result(Some(&io::ErrorKind::ConnectionRefused.into())));
+        assert_eq!(limiter.limit(), 32);
+    }
+
+    #[test]
+    fn resource_exhaustion_errors() {
+        assert!(is_resource_exhaustion(&io::ErrorKind::WouldBlock.into()));
+        #[cfg(unix)]
+        assert!(is_resource_exhaustion(&io::Error::from_raw_os_error(
+            libc::EMFILE
+        )));
+        assert!(!is_resource_exhaustion(&io::ErrorKind::TimedOut.into()));
+    }
+
+    #[test]
+    fn ramps_up_after_a_window_of_successes() {
+        let limiter = AdaptiveLimiter::new(8);
+        limiter.back_off();
+        assert_eq!(limiter.limit(), MIN_LIMIT);
+        // One step per full window at the current limit
+        for _ in 0..MIN_LIMIT - 1 {
+            limiter.on_result(None);
+        }
+        assert_eq!(limiter.limit(), MIN_LIMIT);
+        limiter.on_result(None);
+        assert_eq!(limiter.limit(), MIN_LIMIT + 1);
+        for _ in 0..100 {
+            limiter.on_result(None);
+        }
+        assert_eq!(limiter.limit(), 8);
+    }
+
+    #[tokio::test]
+    async fn acquire_waits_for_a_released_slot() {
+        let limiter = Arc::new(AdaptiveLimiter::new(1));
+        let permit = limiter.acquire().await;
+        let waiter = tokio::spawn({
+            let limiter = limiter.clone();
+            async move { limiter.acquire().await }
+        });
+        tokio::task::yield_now().await;
+        assert!(!waiter.is_finished());
+        drop(permit);
+        tokio::time::timeout(Duration::from_secs(1), waiter)
+            .await
+            .expect("waiter not woken by release")
+            .unwrap();
+    }
+}
@@ -1,14 +1,47 @@
+use std::collections::HashMap;
 use std::net::IpAddr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use anyhow::Result;
-use tauri::{AppHandle, Emitter};
+use futures::{stream, StreamExt};
+use netdev::MacAddr;
 
-use crate::model::scan::{NeighborHost, NeighborScanReport};
+use crate::model::scan::{
+    NeighborDiscoveryMethod, NeighborHost, NeighborScanOptions, NeighborScanReport, PortState,
+};
+use crate::probe::scan::control::ScanControl;
+use crate::probe::scan::limiter::AdaptiveLimiter;
+use crate::probe::scan::progress::ThrottledProgress;
+use crate::probe::scan::sink::ProgressEmitter;
+use crate::socket::icmp::{AsyncIcmpSocket, IcmpConfig, IcmpKind};
+
+/// Echo timeout of the quick LAN sweep; LAN hosts answer within a few ms
+const QUICK_ICMP_TIMEOUT_MS: u64 = 500;
+const QUICK_CONCURRENCY: usize = 256;
+/// Connect timeout of the TCP fallback
+const QUICK_TCP_TIMEOUT: Duration = Duration::from_millis(700);
+/// Ports tried by the TCP fallback. A refused connect proves the host is up
+/// as well as an accepted one, so common client and server ports both work.
+const QUICK_TCP_PORTS: [u16; 6] = [80, 443, 22, 445, 139, 62078];
 
 pub async fn neighbor_scan(
-    app: &AppHandle,
+    sink: &impl ProgressEmitter,
+    run_id: &str,
+    iface: netdev::Interface,
+    opts: NeighborScanOptions,
+    control: Arc<ScanControl>,
+) -> Result<NeighborScanReport> {
+    icmp_sweep(sink, run_id, iface, &opts, None, control).await
+}
+
+async fn icmp_sweep(
+    sink: &impl ProgressEmitter,
     run_id: &str,
     iface: netdev::Interface,
+    opts: &NeighborScanOptions,
+    quick: Option<(u64, usize)>,
+    control: Arc<ScanControl>,
 ) -> Result<NeighborScanReport> {
     //let iface = netdev::get_default_interface().map_err(|e| anyhow::anyhow!("Failed to get default interface: {}", e))?;
     let src_ipv4_opt = iface
@@ -22,30 +55,62 @@ pub async fn neighbor_scan(
         .next()
         .map(std::net::IpAddr::V6);
 
-    let _ = app.emit(
+    sink.emit(
         "hostscan:start",
         crate::model::scan::HostScanStartPayload {
             run_id: run_id.to_string(),
         },
     );
 
-    let setting = crate::model::scan::HostScanSetting::neighbor_scan_default(&iface);
+    let mut setting = crate::model::scan::HostScanSetting::neighbor_scan_default(&iface, opts);
+    if let Some((timeout_ms, concurrency)) = quick {
+        setting.timeout_ms = timeout_ms;
+        setting.concurrency = Some(concurrency);
+    }
 
     // Perform host scan
     // hostscan:progress and hostscan:done events will be emitted during the scan
-    let hostscan_result =
-        crate::probe::scan::icmp::host_scan(&app, &run_id, src_ipv4_opt, src_ipv6_opt, setting)
-            .await?;
+    let hostscan_result = crate::probe::scan::icmp::host_scan(
+        sink,
+        &run_id,
+        src_ipv4_opt,
+        src_ipv6_opt,
+        setting,
+        control,
+    )
+    .await?;
 
-    let neigh_table = crate::net::neigh::get_neighbor_table()?;
+    let alive = hostscan_result
+        .alive
+        .into_iter()
+        .map(|(host, rtt)| (host.ip, Some(rtt)))
+        .collect();
+    let neighbors = enrich(&iface, alive, &crate::net::neigh::get_neighbor_table()?);
+
+    sink.emit("neighborscan:done", run_id.to_string());
+
+    Ok(NeighborScanReport {
+        run_id: run_id.to_string(),
+        neighbors,
+        total: hostscan_result.total,
+        method: NeighborDiscoveryMethod::Icmp,
+        unknown: Vec::new(),
+    })
+}
 
+/// Add MAC address, vendor and role tags to the alive hosts.
+fn enrich(
+    iface: &netdev::Interface,
+    alive: Vec<(IpAddr, Option<u64>)>,
+    neigh_table: &HashMap<IpAddr, MacAddr>,
+) -> Vec<NeighborHost> {
     let oui_db = ndb_oui::OuiDb::bundled();
     let self_ips: Vec<IpAddr> = iface.ip_addrs();
 
     let mut neighbors: Vec<NeighborHost> = Vec::new();
 
-    for (host, rtt) in hostscan_result.alive {
-        let mac_addr = neigh_table.get(&host.ip).cloned();
+    for (ip, rtt) in alive {
+        let mac_addr = neigh_table.get(&ip).cloned();
         let vendor = match mac_addr {
             Some(mac) => match oui_db.lookup_mac(&mac) {
                 Some(oui_info) => oui_info.vendor_detail.clone(),
@@ -56,11 +121,11 @@ pub async fn neighbor_scan(
 
         // Classify tags
         let mut tags = Vec::new();
-        if self_ips.contains(&host.ip) {
+        if self_ips.contains(&ip) {
             tags.push("Self".to_string());
         }
         if let Some(gw) = &iface.gateway {
-            match host.ip {
+            match ip {
                 IpAddr::V4(ipv4) => {
                     if gw.ipv4.contains(&ipv4) {
                         tags.push("Gateway".to_string());
@@ -74,26 +139,217 @@ pub async fn neighbor_scan(
             }
         }
 
-        if iface.dns_servers.contains(&host.ip) {
+        if iface.dns_servers.contains(&ip) {
             tags.push("DNS".to_string());
         }
 
         neighbors.push(NeighborHost {
-            ip_addr: host.ip,
+            ip_addr: ip,
             mac_addr,
             vendor,
-            rtt_ms: Some(rtt),
+            rtt_ms: rtt,
             tags,
         });
     }
+    neighbors
+}
+
+/// Whether ICMP echo can be sent without further privileges
+async fn icmp_available() -> bool {
+    match AsyncIcmpSocket::new(&IcmpConfig::new(IcmpKind::V4)).await {
+        Ok(_) => true,
+        Err(e) => {
+            tracing::info!("ICMP unavailable ({}), falling back to TCP discovery", e);
+            false
+        }
+    }
+}
+
+/// Outcome of the TCP connects to one host
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Liveness {
+    /// A port accepted or refused the connect, with the fastest connect time
+    Up(u64),
+    /// Every connect timed out or was unreachable
+    Down,
+    /// No port answered and some connects failed locally (e.g. out of descriptors)
+    Unknown,
+}
+
+impl Liveness {
+    /// A host is up if any port accepts or refuses the connect. It is only
+    /// down if every connect actually reached the network.
+    fn of(results: impl IntoIterator<Item = (PortState, Option<u64>)>) -> Self {
+        let mut liveness = Liveness::Down;
+        for (state, rtt_ms) in results {
+            liveness = match (state, liveness) {
+                (PortState::Open | PortState::Closed, Liveness::Up(best)) => {
+                    Liveness::Up(best.min(rtt_ms.unwrap_or(0)))
+                }
+                (PortState::Open | PortState::Closed, _) => Liveness::Up(rtt_ms.unwrap_or(0)),
+                (PortState::Unknown, Liveness::Down) => Liveness::Unknown,
+                (_, current) => current,
+            };
+        }
+        liveness
+    }
+}
+
+async fn tcp_alive(ip: IpAddr, limiter: &Arc<AdaptiveLimiter>) -> Liveness {
+    let attempts = QUICK_TCP_PORTS.map(|port| async move {
+        let (state, rtt_ms, _) =
+            crate::probe::scan::tcp::probe_port_limited(ip, port, QUICK_TCP_TIMEOUT, limiter).await;
+        (state, rtt_ms)
+    });
+    Liveness::of(futures::future::join_all(attempts).await)
+}
+
+/// Unprivileged sweep: TCP connects to common ports. Every connect also
+/// makes the OS resolve the target's MAC, so hosts that drop the
+/// connects still show up in the neighbor table afterwards.
+async fn tcp_sweep(
+    sink: &impl ProgressEmitter,
+    run_id: &str,
+    iface: netdev::Interface,
+    opts: &NeighborScanOptions,
+    control: Arc<ScanControl>,
+) -> Result<NeighborScanReport> {
+    let subnets = crate::model::scan::neighbor_subnets_v4(&iface, opts);
+    let targets: Vec<IpAddr> = subnets
+        .iter()
+        .flat_map(|net| net.hosts())
+        .map(IpAddr::V4)
+        .collect();
+    let total = targets.len() as u32;
+    sink.emit(
+        "hostscan:start",
+        crate::model::scan::HostScanStartPayload {
+            run_id: run_id.to_string(),
+        },
+    );
+
+    let progress = ThrottledProgress::new(total);
+    // Bounds the connects of all hosts together, each host tries several ports
+    let limiter = Arc::new(AdaptiveLimiter::new(QUICK_CONCURRENCY));
+    let results: Vec<(IpAddr, Liveness)> = stream::iter(targets)
+        .take_while(|_| futures::future::ready(!control.is_cancelled()))
+        .map(|ip| {
+            let limiter = limiter.clone();
+            async move { (ip, tcp_alive(ip, &limiter).await) }
+        })
+        .buffer_unordered(QUICK_CONCURRENCY)
+        .inspect(|_| {
+            let (done, should_emit) = progress.on_advance();
+            if should_emit {
+                sink.emit("hostscan:progress", (done, total));
+            }
+        })
+        .collect()
+        .await;
+    let mut alive: Vec<(IpAddr, Option<u64>)> = Vec::new();
+    let mut unknown: Vec<IpAddr> = Vec::new();
+    for (ip, liveness) in results {
+        match liveness {
+            Liveness::Up(rtt) => alive.push((ip, Some(rtt))),
+            Liveness::Unknown => unknown.push(ip),
+            Liveness::Down => {}
+        }
+    }
 
-    let total = hostscan_result.total;
+    let neigh_table = crate::net::neigh::get_neighbor_table()?;
+    for (ip, mac) in &neigh_table {
+        let in_subnet = match ip {
+            IpAddr::V4(v4) => subnets.iter().any(|net| net.contains(v4)),
+            IpAddr::V6(_) => false,
+        };
+        // Incomplete entries have an all-zero MAC
+        let resolved = crate::net::wol::parse_mac(&mac.to_string()).is_ok_and(|m| m != [0; 6]);
+        if in_subnet && resolved && !alive.iter().any(|(a, _)| a == ip) {
+            alive.push((*ip, None));
+        }
+    }
+    // The sweep does not connect to ourselves
+    for ip in iface.ipv4_addrs() {
+        let ip = IpAddr::V4(ip);
+        if subnets.iter().any(|net| net.contains(&ip)) && !alive.iter().any(|(a, _)| *a == ip) {
+            alive.push((ip, None));
+        }
+    }
+    alive.sort_by_key(|(ip, _)| *ip);
+    unknown.retain(|ip| !alive.iter().any(|(a, _)| a == ip));
+    unknown.sort();
+    if !unknown.is_empty() {
+        tracing::warn!(
+            "quick LAN scan: {} hosts not checked, local connects failed",
+            unknown.len()
+        );
+    }
 
-    let _ = app.emit("neighborscan:done", run_id.to_string());
+    let neighbors = enrich(&iface, alive, &neigh_table);
+    sink.emit("neighborscan:done", run_id.to_string());
 
     Ok(NeighborScanReport {
         run_id: run_id.to_string(),
         neighbors,
         total,
+        method: NeighborDiscoveryMethod::Tcp,
+        unknown,
     })
 }
+
+/// One-shot "who is on my network" scan of the interface's own subnet,
+/// with short timeouts. Falls back to TCP discovery without ICMP privileges.
+pub async fn quick_lan_scan(
+    sink: &impl ProgressEmitter,
+    run_id: &str,
+    iface: netdev::Interface,
+    control: Arc<ScanControl>,
+) -> Result<NeighborScanReport> {
+    let opts = NeighborScanOptions::default();
+    let started = Instant::now();
+    let report = if icmp_available().await {
+        icmp_sweep(
+            sink,
+            run_id,
+            iface,
+            &opts,
+            Some((QUICK_ICMP_TIMEOUT_MS, QUICK_CONCURRENCY)),
+            control,
+        )
+        .await?
+    } else {
+        tcp_sweep(sink, run_id, iface, &opts, control).await?
+    };
+    tracing::debug!(
+        "quick LAN scan: {} of {} hosts up in {:?} ({:?})",
+        report.neighbors.len(),
+        report.total,
+        started.elapsed(),
+        report.method
+    );
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn local_errors_do_not_mark_hosts_down() {
+        let timed_out = || (PortState::Filtered, None);
+        let local_error = || (PortState::Unknown, None);
+        assert_eq!(Liveness::of([timed_out(), timed_out()]), Liveness::Down);
+        assert_eq!(
+            Liveness::of([timed_out(), local_error()]),
+            Liveness::Unknown
+        );
+        assert_eq!(
+            Liveness::of([
+                local_error(),
+                (PortState::Closed, Some(3)),
+                (PortState::Open, Some(1))
+            ]),
+            Liveness::Up(1)
+        );
+    }
+}
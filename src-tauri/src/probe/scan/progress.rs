@@ -1,7 +1,10 @@
 use std::sync::atomic::{AtomicU32, Ordering};
-use std::sync::Mutex;
+use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 
+use crate::model::scan::{ScanPhase, ScanProgressPayload};
+use crate::probe::scan::sink::ProgressEmitter;
+
 /// Throttles high-frequency progress updates from port scan workers.
 #[derive(Debug)]
 pub struct ThrottledProgress {
@@ -35,6 +38,14 @@ impl ThrottledProgress {
         }
     }
 
+    pub fn total(&self) -> u32 {
+        self.total
+    }
+
+    pub fn done(&self) -> u32 {
+        self.done.load(Ordering::Relaxed)
+    }
+
     /// Mark one item as finished.
     pub fn on_advance(&self) -> (u32, bool) {
         let done = self.done.fetch_add(1, Ordering::Relaxed) + 1;
@@ -64,3 +75,79 @@ impl ThrottledProgress {
         (done, should_emit)
     }
 }
+
+/// Overall progress of a multi-phase scan, reported as one `scan:progress` stream.
+/// Each phase is sized when it begins, so the total only moves between phases.
+#[derive(Debug)]
+pub struct SharedProgress {
+    run_id: String,
+    current: Mutex<PhaseProgress>,
+}
+
+#[derive(Debug)]
+struct PhaseProgress {
+    phase: ScanPhase,
+    /// Items completed in earlier phases.
+    base: u32,
+    progress: Arc<ThrottledProgress>,
+}
+
+impl SharedProgress {
+    pub fn new(run_id: &str) -> Self {
+        Self {
+            run_id: run_id.to_string(),
+            current: Mutex::new(PhaseProgress {
+                phase: ScanPhase::Discovery,
+                base: 0,
+                progress: Arc::new(ThrottledProgress::new(0)),
+            }),
+        }
+    }
+
+    /// Start a phase of `total` items on top of what earlier phases completed.
+    pub fn begin(&self, phase: ScanPhase, total: u32) {
+        let mut current = self
+            .current
+            .lock()
+            .expect("SharedProgress::current poisoned");
+        current.base += current.progress.done();
+        current.phase = phase;
+        current.progress = Arc::new(ThrottledProgress::new(total));
+    }
+
+    /// Mark one item of the current phase as finished.
+    pub fn advance(&self, sink: &impl ProgressEmitter) {
+        let (phase, base, progress) = self.snapshot();
+        let (done, should_emit) = progress.on_advance();
+        if should_emit {
+            self.emit(sink, phase, base + done, base + progress.total());
+        }
+    }
+
+    /// Report the current phase as complete, even if some items were skipped.
+    pub fn finish(&self, sink: &impl ProgressEmitter) {
+        let (phase, base, progress) = self.snapshot();
+        let total = base + progress.total().max(progress.done());
+        self.emit(sink, phase, total, total);
+    }
+
+    fn snapshot(&self) -> (ScanPhase, u32, Arc<ThrottledProgress>) {
+        let current = self
+            .current
+            .lock()
+            .expect("SharedProgress::current poisoned");
+        (current.phase, current.base, current.progress.clone())
+    }
+
+    fn emit(&self, sink: &impl ProgressEmitter, phase: ScanPhase, done: u32, total: u32) {
+        sink.emit(
+            "scan:progress",
+            ScanProgressPayload {
+                run_id: self.run_id.clone(),
+                phase,
+                done,
+                total,
+            },
+        );
+    }
+}
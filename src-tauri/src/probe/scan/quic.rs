@@ -1,149 +1,399 @@
-use anyhow::Result;
-use futures::{stream, StreamExt};
-use rand::{seq::SliceRandom, thread_rng};
+use anyhow::{bail, Result};
+use futures::{future, stream, StreamExt};
 use std::net::{IpAddr, SocketAddr};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
-use tauri::{AppHandle, Emitter};
 
+use crate::error::ScanError;
 use crate::model::endpoint::Endpoint;
-use crate::model::scan::{PortScanReport, PortScanSample, PortScanSetting, PortState};
-use crate::probe::scan::expand_ports;
+use crate::model::scan::{
+    port_ranges, PortScanReport, PortScanSample, PortScanSetting, PortState, QuicHandshakeInfo,
+};
+use crate::model::stats::PortScanSummary;
+use crate::probe::packet::{build_quic_version_probe, is_quic_version_negotiation};
+use crate::probe::scan::control::ScanControl;
+use crate::probe::scan::deadline::ScanDeadline;
+use crate::probe::scan::heuristic;
+use crate::probe::scan::hostlimit::host_limit;
+use crate::probe::scan::limiter::{retry_delay, AdaptiveLimiter, MAX_RESOURCE_RETRIES};
 use crate::probe::scan::progress::ThrottledProgress;
+use crate::probe::scan::safe::exclude_fragile_ports;
+use crate::probe::scan::sink::ProgressEmitter;
 use crate::probe::scan::tuner::ports_concurrency;
+use crate::probe::scan::{expand_ports, order_ports};
+use crate::probe::service::models::{ServiceInfo, ServiceResponse, TlsInfo};
 use crate::probe::service::{ServiceDetector, ServiceProbeConfig};
+use crate::socket::quic::{negotiated_alpn, quic_version_label, AsyncQuicSocket};
+use crate::socket::udp::AsyncUdpSocket;
+
+/// Upper bound for the connected-UDP follow-up after a silent QUIC handshake
+const UDP_FOLLOW_UP_TIMEOUT: Duration = Duration::from_millis(1000);
+
+/// Collect handshake diagnostics for an established connection, then close it.
+async fn finish_connection(
+    ep: &AsyncQuicSocket,
+    conn: quinn::Connection,
+    addr: SocketAddr,
+    server_name: &str,
+    timeout: Duration,
+) -> QuicHandshakeInfo {
+    // Give the server a round trip to deliver session tickets and NEW_TOKEN frames
+    tokio::time::sleep(conn.rtt().min(timeout)).await;
+    let stats = conn.stats();
+    let retry = ep.retry_received();
+    conn.close(0u32.into(), b"done");
+    let zero_rtt_accepted = match ep.probe_zero_rtt(&addr, server_name, timeout).await {
+        Ok(accepted) => accepted,
+        Err(e) => {
+            tracing::debug!("0-RTT probe failed on {}: {}", addr, e);
+            None
+        }
+    };
+    QuicHandshakeInfo {
+        retry,
+        address_validation: retry || stats.frame_rx.path_challenge > 0,
+        new_token: stats.frame_rx.new_token > 0,
+        zero_rtt_accepted,
+    }
+}
+
+/// Classify a failed QUIC connection attempt.
+async fn classify_connect_error(
+    err: &anyhow::Error,
+    addr: SocketAddr,
+    timeout: Duration,
+) -> PortState {
+    if let Some(conn_err) = err.downcast_ref::<quinn::ConnectionError>() {
+        use quinn::ConnectionError as C;
+        match conn_err {
+            // The peer spoke QUIC, even if it rejected the handshake
+            C::VersionMismatch
+            | C::TransportError(_)
+            | C::ConnectionClosed(_)
+            | C::ApplicationClosed(_)
+            | C::Reset => return PortState::Open,
+            C::TimedOut => {}
+            C::LocallyClosed | C::CidsExhausted => return PortState::Unknown,
+        }
+    } else if err.downcast_ref::<tokio::time::error::Elapsed>().is_none() {
+        // Local errors (e.g. invalid server name) say nothing about the port
+        return PortState::Unknown;
+    }
+    // No QUIC answer. quinn's unconnected socket never sees ICMP port unreachable,
+    // so ask again over a connected UDP socket.
+    udp_follow_up(addr, timeout.min(UDP_FOLLOW_UP_TIMEOUT)).await
+}
+
+/// Send a version-forcing QUIC datagram over a connected UDP socket.
+async fn udp_follow_up(addr: SocketAddr, timeout: Duration) -> PortState {
+    let sock = if addr.is_ipv4() {
+        AsyncUdpSocket::v4_dgram()
+    } else {
+        AsyncUdpSocket::v6_dgram()
+    };
+    let Ok(sock) = sock.and_then(|s| s.into_tokio_socket()) else {
+        return PortState::Unknown;
+    };
+    if sock.connect(addr).await.is_err() {
+        return PortState::Unknown;
+    }
+    if let Err(e) = sock.send(&build_quic_version_probe()).await {
+        return if e.kind() == std::io::ErrorKind::ConnectionRefused {
+            PortState::Closed
+        } else {
+            PortState::Filtered
+        };
+    }
+    let mut buf = [0u8; 1500];
+    match tokio::time::timeout(timeout, sock.recv(&mut buf)).await {
+        Ok(Ok(n)) => {
+            if !is_quic_version_negotiation(&buf[..n]) {
+                tracing::debug!("non-QUIC UDP reply from {}", addr);
+            }
+            PortState::Open
+        }
+        // ICMP port unreachable
+        Ok(Err(e)) if e.kind() == std::io::ErrorKind::ConnectionRefused => PortState::Closed,
+        // No reply: an open UDP port may stay silent just like a filtered one
+        _ => PortState::OpenFiltered,
+    }
+}
+
+/// Endpoint config for probing `ip`.
+fn quic_config(ip: IpAddr) -> crate::socket::quic::QuicConfig {
+    let family = if ip.is_ipv4() {
+        crate::socket::SocketFamily::IPV4
+    } else {
+        crate::socket::SocketFamily::IPV6
+    };
+    crate::socket::quic::QuicConfig {
+        skip_verify: true,
+        alpn: vec![b"h3".to_vec(), b"hq-29".to_vec(), b"hq-interop".to_vec()],
+        family,
+    }
+}
+
+/// Result of one QUIC port probe
+pub struct ProbeOutcome {
+    pub state: PortState,
+    pub rtt_ms: Option<u64>,
+    pub message: Option<String>,
+    pub service_info: Option<ServiceInfo>,
+    pub handshake: Option<QuicHandshakeInfo>,
+}
+
+impl ProbeOutcome {
+    fn endpoint_error(e: anyhow::Error) -> Self {
+        ProbeOutcome {
+            state: PortState::Unknown,
+            rtt_ms: None,
+            message: Some(format!("quic endpoint error: {}", e)),
+            service_info: None,
+            handshake: None,
+        }
+    }
+}
+
+/// Attempt a handshake with `addr` and classify the port.
+async fn handshake_probe(
+    ep: &AsyncQuicSocket,
+    addr: SocketAddr,
+    server_name: &str,
+    timeout: Duration,
+) -> ProbeOutcome {
+    let start = Instant::now();
+    match ep.connect_timeout(&addr, server_name, timeout).await {
+        Ok(conn) => {
+            let rtt = start.elapsed().as_millis() as u64;
+            let alpn = negotiated_alpn(&conn);
+            let quic_version = quic_version_label(ep.version());
+            let handshake = finish_connection(ep, conn, addr, server_name, timeout).await;
+            let msg = format!(
+                "{} / QUIC v{}",
+                alpn.as_deref().unwrap_or("-"),
+                quic_version
+            );
+            let info = ServiceInfo {
+                quic_version: Some(quic_version),
+                tls_info: Some(TlsInfo {
+                    // Fixed to TLS 1.3 for QUIC
+                    version: Some("TLSv1_3".into()),
+                    alpn,
+                    ..Default::default()
+                }),
+                response: ServiceResponse::Responded,
+                ..Default::default()
+            };
+            ProbeOutcome {
+                state: PortState::Open,
+                rtt_ms: Some(rtt),
+                message: Some(msg),
+                service_info: Some(info),
+                handshake: Some(handshake),
+            }
+        }
+        Err(e) => ProbeOutcome {
+            state: classify_connect_error(&e, addr, timeout).await,
+            rtt_ms: None,
+            message: Some(e.to_string()),
+            service_info: None,
+            handshake: None,
+        },
+    }
+}
+
+/// Probe a single port with the same endpoint and classification as `port_scan`.
+pub async fn probe_port(
+    ip: IpAddr,
+    port: u16,
+    hostname: Option<&str>,
+    timeout: Duration,
+) -> ProbeOutcome {
+    let server_name = hostname.map_or_else(|| ip.to_string(), str::to_string);
+    match AsyncQuicSocket::from_config(&quic_config(ip)) {
+        Ok(ep) => handshake_probe(&ep, SocketAddr::new(ip, port), &server_name, timeout).await,
+        Err(e) => ProbeOutcome::endpoint_error(e),
+    }
+}
 
 pub async fn port_scan(
-    app: &AppHandle,
+    sink: &impl ProgressEmitter,
     run_id: &str,
     _src_ip: IpAddr,
     setting: PortScanSetting,
+    control: Arc<ScanControl>,
 ) -> Result<PortScanReport> {
-    let mut ports = expand_ports(&setting.target_ports_preset, &setting.user_ports);
-    if !setting.ordered {
-        ports.shuffle(&mut thread_rng());
+    let started = Instant::now();
+    // Each QUIC probe needs its own UDP socket; a shared port would mix replies
+    if setting.source_port.is_some() {
+        bail!(ScanError::unsupported(
+            "A fixed source port is only supported for TCP scans"
+        ));
+    }
+    if !setting.routing.is_empty() {
+        bail!(ScanError::unsupported(
+            "A bind device or fwmark is only supported for TCP scans"
+        ));
+    }
+    let mut ports = expand_ports(
+        &setting.target_ports_preset,
+        &setting.user_ports,
+        &setting.exclude_ports,
+    );
+    order_ports(&mut ports, setting.order);
+    if setting.safe_mode {
+        let skipped = exclude_fragile_ports(&mut ports);
+        if skipped > 0 {
+            tracing::info!("safe mode: skipping {} fragile ports", skipped);
+        }
     }
 
-    let app = app.clone();
     let ip = setting.ip_addr;
     let timeout = Duration::from_millis(setting.timeout_ms);
+    let verbose = setting.verbose;
 
     let total = ports.len() as u32;
     let progress = Arc::new(ThrottledProgress::new(total));
+    let concurrency = ports_concurrency();
+    let limiter = Arc::new(AdaptiveLimiter::new(concurrency));
+    let host_slots = setting.max_per_host.map(|n| host_limit(ip, n));
+    tracing::debug!("quic port scan started: {} ports", total);
 
-    let hostname_opt = setting.hostname.clone();
+    // SNI needs the ASCII form of IDN names
+    let hostname_opt = setting
+        .hostname
+        .as_deref()
+        .map(|h| crate::net::dns::idn::to_ascii(h).unwrap_or_else(|_| h.to_string()));
+
+    let deadline = ScanDeadline::new(setting.max_duration_ms);
 
     // Create tasks for each port and collect results as they complete.
     let mut tasks = stream::iter(ports.into_iter())
+        // Stop issuing new probes once the scan is cancelled or out of time
+        .take_while(|_| future::ready(!control.is_cancelled() && !deadline.check(&control, sink)))
         .map(|port| {
-            let app = app.clone();
             let progress = progress.clone();
+            let control = control.clone();
             let hostname_opt = hostname_opt.clone();
+            let limiter = limiter.clone();
+            let host_slots = host_slots.clone();
 
             async move {
-                let family = if ip.is_ipv4() {
-                    crate::socket::SocketFamily::IPV4
-                } else {
-                    crate::socket::SocketFamily::IPV6
-                };
+                // Hold off new probes while the scan is paused
+                control.wait_if_paused().await;
 
-                let quic_cfg = crate::socket::quic::QuicConfig {
-                    skip_verify: true,
-                    alpn: vec![b"h3".to_vec(), b"hq-29".to_vec(), b"hq-interop".to_vec()],
-                    family,
+                // Take the per-host slot first so waiting does not hold a global one
+                let _host_permit = match &host_slots {
+                    Some(h) => Some(h.acquire().await),
+                    None => None,
                 };
 
-                let (state, rtt_ms, msg) =
-                    match crate::socket::quic::AsyncQuicSocket::from_config(&quic_cfg) {
+                let quic_cfg = quic_config(ip);
+
+                // Hold a slot for the whole probe; retry endpoint creation
+                // that failed on local resource exhaustion.
+                let mut attempt = 0;
+                let (endpoint, _permit) = loop {
+                    let permit = limiter.acquire().await;
+                    match AsyncQuicSocket::from_config(&quic_cfg) {
                         Ok(ep) => {
-                            let server_name =
-                                hostname_opt.clone().unwrap_or_else(|| ip.to_string());
-                            let start = Instant::now();
-                            match ep
-                                .connect_timeout(&SocketAddr::new(ip, port), &server_name, timeout)
-                                .await
-                            {
-                                Ok(conn) => {
-                                    conn.close(0u32.into(), b"done");
-                                    (
-                                        PortState::Open,
-                                        Some(start.elapsed().as_millis() as u64),
-                                        None,
-                                    )
-                                }
-                                Err(e) => {
-                                    let st = if let Some(ioe) = e.downcast_ref::<std::io::Error>() {
-                                        if ioe.kind() == std::io::ErrorKind::TimedOut {
-                                            PortState::Filtered
-                                        } else {
-                                            PortState::Closed
-                                        }
-                                    } else {
-                                        PortState::Closed
-                                    };
-                                    (st, None, Some(e.to_string()))
-                                }
+                            limiter.on_result(None);
+                            break (Ok(ep), permit);
+                        }
+                        Err(e) => {
+                            let exhausted = e
+                                .downcast_ref::<std::io::Error>()
+                                .is_some_and(|io| limiter.on_result(Some(io)));
+                            if exhausted && attempt < MAX_RESOURCE_RETRIES {
+                                attempt += 1;
+                                drop(permit);
+                                tokio::time::sleep(retry_delay(attempt)).await;
+                                continue;
                             }
+                            break (Err(e), permit);
                         }
-                        Err(e) => (
-                            PortState::Filtered,
-                            None,
-                            Some(format!("quic endpoint error: {}", e)),
-                        ),
-                    };
+                    }
+                };
+
+                let server_name = hostname_opt.clone().unwrap_or_else(|| ip.to_string());
+                let outcome = match endpoint {
+                    Ok(ep) => {
+                        handshake_probe(&ep, SocketAddr::new(ip, port), &server_name, timeout).await
+                    }
+                    Err(e) => ProbeOutcome::endpoint_error(e),
+                };
 
                 let (done, should_emit) = progress.on_advance();
+                control.advance_shared(sink);
 
                 let sample = PortScanSample {
                     ip_addr: ip,
                     port,
-                    state,
-                    rtt_ms,
-                    message: msg,
+                    state: outcome.state,
+                    rtt_ms: outcome.rtt_ms,
+                    message: outcome.message,
                     service_name: None,
-                    service_info: None,
+                    service_info: outcome.service_info,
+                    reply_ttl: None,
+                    quic_handshake: outcome.handshake,
                     done,
                     total,
                 };
 
                 // Open port: emit detailed info
                 if sample.state == PortState::Open {
-                    let _ = app.emit("portscan:open", sample.clone());
+                    sink.emit_sample("portscan:open", &sample);
+                }
+
+                // Verbose: emit every completed sample
+                if verbose {
+                    sink.emit_sample("portscan:sample", &sample);
                 }
 
                 // Progress event
                 if should_emit {
-                    let _ = app.emit("portscan:progress", (done, total));
+                    sink.emit("portscan:progress", (done, total));
                 }
 
                 sample
             }
         })
-        .buffer_unordered(ports_concurrency());
+        .buffer_unordered(concurrency);
 
     // Collect only Open samples
     let mut open_samples: Vec<PortScanSample> = Vec::new();
     let udp_service_db = ndb_udp_service::UdpServiceDb::bundled();
+    let mut probed = Vec::with_capacity(total as usize);
     while let Some(mut sample) = tasks.next().await {
+        probed.push(sample.port);
         if sample.state == PortState::Open {
             sample.service_name = udp_service_db
                 .get(sample.port)
                 .map(|entry| entry.name.clone());
             open_samples.push(sample);
+        } else if let Some(checkpoint) = control.checkpoint() {
+            checkpoint.port_done(sample.port);
         }
     }
 
     open_samples.sort_by_key(|s| s.port);
 
-    // Service detection
-    if setting.service_detection && !open_samples.is_empty() {
-        let _ = app.emit("portscan:service_detection_start", run_id.to_string());
+    // Service detection, skipped when the time budget is spent
+    if setting.service_detection && !open_samples.is_empty() && !deadline.check(&control, sink) {
+        sink.emit("portscan:service_detection_start", run_id.to_string());
         let service_probe_setting = ServiceProbeConfig {
             timeout: Duration::from_secs(2),
-            max_concurrency: 100,
+            max_concurrency: setting.max_per_host.map_or(100, |n| n.clamp(1, 100)),
             max_read_size: 1024 * 1024,
             sni: true,
             skip_cert_verify: true,
+            intensity: setting.detection_intensity,
+            read_only: setting.safe_mode,
+            raw_hex_limit: setting.raw_hex_limit(),
+            skip_intrusive: setting.skip_intrusive,
+            tls_resumption: false,
+            tls_enum: false,
+            routing: setting.routing.clone(),
         };
         let detector = ServiceDetector::new(service_probe_setting);
         let mut endpoint = Endpoint::new(ip);
@@ -162,10 +412,28 @@ pub async fn port_scan(
                 .iter()
                 .find(|r| r.port == sample.port)
             {
-                sample.service_info = Some(res.service_info.clone());
+                let mut info = res.service_info.clone();
+                // Keep the version and ALPN seen during the scan if the probe didn't report them
+                if let Some(scan_info) = &sample.service_info {
+                    if info.quic_version.is_none() {
+                        info.quic_version = scan_info.quic_version.clone();
+                    }
+                    if info.tls_info.is_none() {
+                        info.tls_info = scan_info.tls_info.clone();
+                    }
+                }
+                sample.service_info = Some(info);
             }
         }
-        let _ = app.emit("portscan:service_detection_done", run_id.to_string());
+        if setting.vuln_lookup {
+            crate::probe::service::vuln::annotate_samples(&mut open_samples);
+        }
+        sink.emit("portscan:service_detection_done", run_id.to_string());
+    }
+
+    let suspicion = heuristic::assess(total as usize, &open_samples);
+    if let Some(reason) = suspicion.reason() {
+        tracing::info!("{} looks suspicious: {}", ip, reason);
     }
 
     let report = PortScanReport {
@@ -173,9 +441,23 @@ pub async fn port_scan(
         ip_addr: setting.ip_addr,
         hostname: setting.hostname.clone(),
         protocol: setting.protocol,
+        summary: PortScanSummary::from_samples(&open_samples),
         samples: open_samples,
+        suspicious: suspicion.is_suspicious(),
+        suspicious_reason: suspicion.reason(),
+        deadline_reached: deadline.reached(),
+        elapsed_ms: Some(started.elapsed().as_millis() as u64),
+        ports_preset: Some(setting.target_ports_preset),
+        ports_scanned: Some(total),
+        scanned_ports: port_ranges(probed),
+        results_file: None,
     };
 
-    let _ = app.emit("portscan:done", report.clone());
+    tracing::debug!(
+        "quic port scan finished: {} open of {} ports",
+        report.samples.len(),
+        total
+    );
+    sink.emit("portscan:done", report.clone());
     Ok(report)
 }
@@ -0,0 +1,65 @@
+use anyhow::{bail, Result};
+
+use crate::error::ScanError;
+use crate::model::scan::ScanRouting;
+use crate::socket::icmp::IcmpConfig;
+use crate::socket::tcp::{AsyncTcpSocket, TcpConfig};
+
+/// Apply the routing context to a TCP probe socket config.
+pub fn apply_tcp(mut cfg: TcpConfig, routing: &ScanRouting) -> TcpConfig {
+    if let Some(dev) = &routing.bind_device {
+        cfg = cfg.with_bind_device(dev.clone());
+    }
+    if let Some(mark) = routing.fwmark {
+        cfg = cfg.with_fwmark(mark);
+    }
+    cfg
+}
+
+/// Apply the routing context to an ICMP socket config.
+pub fn apply_icmp(mut cfg: IcmpConfig, routing: &ScanRouting) -> IcmpConfig {
+    if let Some(dev) = &routing.bind_device {
+        cfg = cfg.with_interface(dev.clone());
+    }
+    if let Some(mark) = routing.fwmark {
+        cfg = cfg.with_fwmark(mark);
+    }
+    cfg
+}
+
+/// Validate the routing context before scanning, so an unknown device or
+/// missing privilege fails the scan instead of every probe.
+pub fn check_routing(routing: &ScanRouting) -> Result<()> {
+    if routing.is_empty() {
+        return Ok(());
+    }
+    if !cfg!(target_os = "linux") {
+        bail!(ScanError::unsupported(
+            "Scanning through a VRF or fwmark is only supported on Linux"
+        ));
+    }
+    if let Some(dev) = &routing.bind_device {
+        if dev.trim().is_empty() {
+            bail!(ScanError::invalid_input("Bind device must not be empty"));
+        }
+        if !netdev::get_interfaces().iter().any(|i| &i.name == dev) {
+            bail!(ScanError::no_interface(format!(
+                "Interface not found: {}",
+                dev
+            )));
+        }
+    }
+    // SO_MARK needs CAP_NET_ADMIN; SO_BINDTODEVICE needs CAP_NET_RAW on older kernels
+    match AsyncTcpSocket::from_config(&apply_tcp(TcpConfig::v4_stream(), routing)) {
+        Ok(_) => Ok(()),
+        Err(e) if e.kind() == std::io::ErrorKind::PermissionDenied => {
+            bail!(ScanError::permission_denied(
+                "Setting the bind device or fwmark requires CAP_NET_ADMIN / CAP_NET_RAW"
+            ))
+        }
+        Err(e) => bail!(ScanError::socket_error(format!(
+            "Failed to apply routing options: {}",
+            e
+        ))),
+    }
+}
@@ -0,0 +1,233 @@
+use anyhow::Result;
+use futures::{stream, StreamExt};
+use std::net::IpAddr;
+use std::sync::Arc;
+
+use crate::error::ScanError;
+use crate::model::endpoint::Host;
+use crate::model::scan::{
+    HostPortScan, HostPortScanReport, HostScanReport, HostScanSetting, HostScanStartPayload,
+    PortScanProtocol, PortScanReport, PortScanSetting, PortScanStartPayload, ScanPhase,
+    TargetPortsPreset,
+};
+use crate::probe::scan::control::ScanControl;
+use crate::probe::scan::expand_ports;
+use crate::probe::scan::progress::SharedProgress;
+use crate::probe::scan::safe::exclude_fragile_ports;
+use crate::probe::scan::sink::ProgressEmitter;
+use crate::probe::scan::tuner::ports_concurrency;
+
+/// Alive hosts port scanned at once after discovery (default)
+pub const DEFAULT_HOST_FANOUT: usize = 4;
+
+/// First address of the default interface in the family of `target`.
+fn default_src_ip(target: IpAddr) -> Result<IpAddr> {
+    let iface = netdev::get_default_interface()
+        .map_err(|e| ScanError::no_interface(format!("Failed to get default interface: {}", e)))?;
+    match target {
+        IpAddr::V4(_) => iface
+            .ipv4_addrs()
+            .into_iter()
+            .next()
+            .map(IpAddr::V4)
+            .ok_or_else(|| {
+                ScanError::no_interface("No IPv4 address found on default interface").into()
+            }),
+        IpAddr::V6(_) => iface
+            .ipv6_addrs()
+            .into_iter()
+            .next()
+            .map(IpAddr::V6)
+            .ok_or_else(|| {
+                ScanError::no_interface("No IPv6 address found on default interface").into()
+            }),
+    }
+}
+
+/// Run a port scan with the scanner of its protocol, reporting to `sink`.
+/// Used by the Tauri commands and the CLI.
+pub async fn scan_ports(
+    sink: &impl ProgressEmitter,
+    run_id: &str,
+    mut setting: PortScanSetting,
+    control: Arc<ScanControl>,
+) -> Result<PortScanReport> {
+    setting.apply_family().await?;
+    // The Services preset exists to identify what runs on the ports
+    if matches!(setting.target_ports_preset, TargetPortsPreset::Services) {
+        setting.service_detection = true;
+    }
+    let src_ip = default_src_ip(setting.ip_addr)?;
+    sink.emit(
+        "portscan:start",
+        PortScanStartPayload {
+            run_id: run_id.to_string(),
+        },
+    );
+    match setting.protocol {
+        PortScanProtocol::Tcp => {
+            crate::probe::scan::tcp::port_scan(sink, run_id, src_ip, setting, control).await
+        }
+        PortScanProtocol::Quic => {
+            crate::probe::scan::quic::port_scan(sink, run_id, src_ip, setting, control).await
+        }
+    }
+}
+
+/// Interfaces a host scan can run from: up, not loopback, with an address.
+pub fn scan_interfaces() -> Vec<netdev::Interface> {
+    crate::net::interface::list_interfaces()
+        .into_iter()
+        .filter(|i| i.is_up() && !i.is_loopback())
+        .filter(|i| !i.ipv4.is_empty() || !i.ipv6.is_empty())
+        .collect()
+}
+
+/// Run an ICMP host scan from `iface_name` or the default interface,
+/// reporting to `sink`.
+pub async fn scan_hosts(
+    sink: &impl ProgressEmitter,
+    run_id: &str,
+    mut setting: HostScanSetting,
+    control: Arc<ScanControl>,
+) -> Result<HostScanReport> {
+    let iface = match setting.iface_name.as_deref() {
+        Some(name) => crate::net::interface::find_interface(name)
+            .ok_or_else(|| ScanError::no_interface(format!("Interface not found: {}", name)))?,
+        None => netdev::get_default_interface().map_err(|e| {
+            ScanError::no_interface(format!("Failed to get default interface: {}", e))
+        })?,
+    };
+    // The scanner binds its sockets to this interface
+    setting.iface_name = Some(iface.name.clone());
+    // Open no socket for a family the scan is restricted away from
+    let family = setting.family.unwrap_or_default();
+    let src_ipv4 = iface
+        .ipv4_addrs()
+        .into_iter()
+        .next()
+        .map(IpAddr::V4)
+        .filter(|ip| family.matches(ip));
+    let src_ipv6 = iface
+        .ipv6_addrs()
+        .into_iter()
+        .next()
+        .map(IpAddr::V6)
+        .filter(|ip| family.matches(ip));
+    sink.emit(
+        "hostscan:start",
+        HostScanStartPayload {
+            run_id: run_id.to_string(),
+        },
+    );
+    crate::probe::scan::icmp::host_scan(sink, run_id, src_ipv4, src_ipv6, setting, control).await
+}
+
+/// Number of ports a scan with `setting` probes, counted as the scanners do.
+pub fn planned_port_count(setting: &PortScanSetting) -> usize {
+    let mut ports = expand_ports(
+        &setting.target_ports_preset,
+        &setting.user_ports,
+        &setting.exclude_ports,
+    );
+    if setting.safe_mode {
+        exclude_fragile_ports(&mut ports);
+    }
+    ports.len()
+}
+
+/// Probes per host in a multi-host run: the configured cap, else an equal
+/// share of the port concurrency among the hosts scanned at once, so a
+/// subnet scan spreads its load instead of hitting each host at full rate.
+fn per_host_cap(max_per_host: Option<usize>, concurrency: usize, fanout: usize) -> usize {
+    max_per_host.unwrap_or(concurrency / fanout.max(1)).max(1)
+}
+
+/// Sweep the targets of `host_setting`, then port scan each alive host with
+/// `port_setting`, `fanout` hosts at a time. Both phases feed one
+/// `scan:progress` stream; the port phase is sized alive hosts × ports.
+pub async fn scan_hosts_ports(
+    sink: &impl ProgressEmitter,
+    run_id: &str,
+    host_setting: HostScanSetting,
+    port_setting: PortScanSetting,
+    fanout: usize,
+    control: Arc<ScanControl>,
+) -> Result<HostPortScanReport> {
+    let progress = Arc::new(SharedProgress::new(run_id));
+    progress.begin(ScanPhase::Discovery, host_setting.targets.len() as u32);
+    control.set_shared_progress(progress.clone());
+    let discovery = scan_hosts(sink, run_id, host_setting, control.clone()).await?;
+    progress.finish(sink);
+
+    let hosts: Vec<Host> = discovery
+        .alive
+        .iter()
+        .map(|(host, _)| host.clone())
+        .collect();
+    let port_count = planned_port_count(&port_setting);
+    progress.begin(ScanPhase::Ports, (hosts.len() * port_count) as u32);
+    tracing::debug!(
+        "port scanning {} alive hosts, {} ports each",
+        hosts.len(),
+        port_count
+    );
+
+    let max_per_host = per_host_cap(port_setting.max_per_host, ports_concurrency(), fanout);
+    let mut scans: Vec<HostPortScan> = stream::iter(hosts)
+        .map(|host| {
+            let mut setting = port_setting.clone();
+            setting.ip_addr = host.ip;
+            setting.hostname = host.hostname.clone();
+            setting.max_per_host = Some(max_per_host);
+            // Each host is a port scan run of its own, with its own events,
+            // paused and cancelled along with the whole run
+            let host_run_id = uuid::Uuid::new_v4().to_string();
+            let host_control = control.child(&host_run_id, "portscan");
+            host_control.set_shared_progress(progress.clone());
+            async move {
+                match scan_ports(sink, &host_run_id, setting, host_control).await {
+                    Ok(report) => HostPortScan {
+                        host,
+                        report: Some(report),
+                        error: None,
+                    },
+                    Err(e) => {
+                        tracing::warn!("port scan of {} failed: {}", host.ip, e);
+                        HostPortScan {
+                            host,
+                            report: None,
+                            error: Some(e.to_string()),
+                        }
+                    }
+                }
+            }
+        })
+        .buffer_unordered(fanout.max(1))
+        .collect()
+        .await;
+    scans.sort_by_key(|scan| scan.host.ip);
+    progress.finish(sink);
+
+    let report = HostPortScanReport {
+        run_id: run_id.to_string(),
+        discovery,
+        scans,
+    };
+    sink.emit("scan:done", report.clone());
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn multi_host_runs_share_concurrency_per_host() {
+        assert_eq!(per_host_cap(None, 1000, 4), 250);
+        assert_eq!(per_host_cap(Some(8), 1000, 4), 8);
+        // Never zero, whatever the fanout
+        assert_eq!(per_host_cap(None, 2, 16), 1);
+        assert_eq!(per_host_cap(None, 100, 0), 100);
+    }
+}
@@ -0,0 +1,55 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+/// User file replacing the bundled fragile port list
+pub const FRAGILE_PORTS_FILE_NAME: &str = "netpulse-fragile-ports.json";
+
+/// Port excluded in safe mode
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct FragilePort {
+    pub port: u16,
+    /// What usually listens there
+    #[serde(default)]
+    pub name: String,
+}
+
+/// Ports known to crash or misbehave on fragile devices when probed.
+/// The `meta` block of the bundled file is ignored.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct FragilePortDb {
+    pub ports: Vec<FragilePort>,
+}
+
+/// Ports skipped by safe mode scans.
+/// A `netpulse-fragile-ports.json` in the app dir replaces the bundled list;
+/// it is read on every scan so edits apply without a restart.
+pub fn fragile_ports() -> HashSet<u16> {
+    let db = crate::fs::get_user_file_path(FRAGILE_PORTS_FILE_NAME)
+        .filter(|path| path.exists())
+        .and_then(|path| match std::fs::read_to_string(&path) {
+            Ok(content) => match serde_json::from_str::<FragilePortDb>(&content) {
+                Ok(db) => Some(db),
+                Err(e) => {
+                    tracing::error!("Invalid {}: {:?}", path.display(), e);
+                    None
+                }
+            },
+            Err(e) => {
+                tracing::error!("{:?}", e);
+                None
+            }
+        })
+        .unwrap_or_else(|| {
+            serde_json::from_str(crate::resources::FRAGILE_PORTS_JSON)
+                .expect("Invalid np-fragile-ports.json format")
+        });
+    db.ports.into_iter().map(|p| p.port).collect()
+}
+
+/// Drop fragile ports from the scan list. Returns the number removed.
+pub fn exclude_fragile_ports(ports: &mut Vec<u16>) -> usize {
+    let fragile = fragile_ports();
+    let before = ports.len();
+    ports.retain(|p| !fragile.contains(p));
+    before - ports.len()
+}
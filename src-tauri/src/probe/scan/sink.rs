@@ -0,0 +1,135 @@
+use serde::Serialize;
+use std::io::Write;
+use tauri::ipc::Channel;
+use tauri::{AppHandle, Emitter};
+
+use crate::model::scan::PortScanSample;
+
+/// Receiver of scan events (`portscan:open`, `hostscan:progress`, ...).
+/// The GUI forwards them to the frontend; the CLI prints them as JSON lines.
+pub trait ProgressEmitter: Send + Sync {
+    fn emit<T: Serialize + Clone>(&self, event: &str, payload: T);
+
+    /// Report a completed port (`portscan:open`, `portscan:sample`).
+    /// Sinks that consume samples directly override this; the rest get it
+    /// as a regular event.
+    fn emit_sample(&self, event: &str, sample: &PortScanSample) {
+        self.emit(event, sample.clone());
+    }
+}
+
+impl ProgressEmitter for AppHandle {
+    fn emit<T: Serialize + Clone>(&self, event: &str, payload: T) {
+        let _ = Emitter::emit(self, event, payload);
+    }
+}
+
+/// Prints each event as one JSON object per line on stdout:
+/// `{"event":"portscan:open","payload":{...}}`.
+pub struct StdoutSink;
+
+impl ProgressEmitter for StdoutSink {
+    fn emit<T: Serialize + Clone>(&self, event: &str, payload: T) {
+        let line = serde_json::json!({ "event": event, "payload": payload });
+        // Holding the lock keeps lines from concurrent tasks whole
+        let mut out = std::io::stdout().lock();
+        let _ = writeln!(out, "{}", line);
+        let _ = out.flush();
+    }
+}
+
+/// One scan event delivered on a per-run channel
+#[derive(Serialize, Clone, Debug)]
+pub struct ScanEvent {
+    /// Name the event has as a global app event, e.g. `portscan:progress`
+    pub event: String,
+    pub payload: serde_json::Value,
+}
+
+/// Delivers the events of one run on the caller's `Channel`, so concurrent
+/// scans never see each other's progress. Without a channel, or with
+/// `global` set, events are also emitted app-wide as before.
+pub struct ChannelSink {
+    app: AppHandle,
+    channel: Option<Channel<ScanEvent>>,
+    global: bool,
+}
+
+impl ChannelSink {
+    /// `global_events` defaults to off when a channel is given.
+    pub fn new(
+        app: AppHandle,
+        channel: Option<Channel<ScanEvent>>,
+        global_events: Option<bool>,
+    ) -> Self {
+        let global = channel.is_none() || global_events.unwrap_or(false);
+        Self {
+            app,
+            channel,
+            global,
+        }
+    }
+}
+
+/// One scan event of a scheduled job's run
+#[derive(Serialize, Clone, Debug)]
+pub struct JobScanEvent {
+    pub job_id: String,
+    /// Name the event has as a global app event, e.g. `portscan:progress`
+    pub event: String,
+    pub payload: serde_json::Value,
+}
+
+/// Emits the events of a scheduled job's runs app-wide as `schedule:progress`,
+/// tagged with the job, so they never show up as an interactive scan's progress.
+pub struct JobSink {
+    app: AppHandle,
+    job_id: String,
+}
+
+impl JobSink {
+    pub fn new(app: AppHandle, job_id: &str) -> Self {
+        Self {
+            app,
+            job_id: job_id.to_string(),
+        }
+    }
+}
+
+impl ProgressEmitter for JobSink {
+    fn emit<T: Serialize + Clone>(&self, event: &str, payload: T) {
+        match serde_json::to_value(&payload) {
+            Ok(payload) => {
+                let _ = Emitter::emit(
+                    &self.app,
+                    "schedule:progress",
+                    JobScanEvent {
+                        job_id: self.job_id.clone(),
+                        event: event.to_string(),
+                        payload,
+                    },
+                );
+            }
+            Err(e) => tracing::warn!("Failed to serialize {} event: {}", event, e),
+        }
+    }
+}
+
+impl ProgressEmitter for ChannelSink {
+    fn emit<T: Serialize + Clone>(&self, event: &str, payload: T) {
+        if let Some(channel) = &self.channel {
+            match serde_json::to_value(&payload) {
+                Ok(payload) => {
+                    let _ = channel.send(ScanEvent {
+                        event: event.to_string(),
+                        payload,
+                    });
+                }
+                Err(e) => tracing::warn!("Failed to serialize {} event: {}", event, e),
+            }
+        }
+        if self.global {
+            let _ = Emitter::emit(&self.app, event, payload);
+        }
+    }
+}
@@ -0,0 +1,176 @@
+//! Port scan results as an async stream, for Rust consumers (CLI, tests)
+//! that would rather iterate samples than implement a `ProgressEmitter`.
+//!
+//! The `port_scan` command reports the same samples as `portscan:open`
+//! (and, when `verbose` is set, `portscan:sample`) events; a stream yields
+//! exactly the samples those events carry, in completion order.
+
+use anyhow::Result;
+use futures::channel::mpsc;
+use futures::Stream;
+use serde::Serialize;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use tokio::task::JoinHandle;
+
+use crate::model::scan::{PortScanReport, PortScanSample, PortScanSetting};
+use crate::probe::scan::control::ScanControl;
+use crate::probe::scan::sink::ProgressEmitter;
+
+/// Forwards the samples of one run to a channel and drops the other events.
+struct SampleSink {
+    /// `portscan:sample` when the scan is verbose; it includes the open ports
+    event: &'static str,
+    tx: mpsc::UnboundedSender<PortScanSample>,
+}
+
+impl SampleSink {
+    fn new(verbose: bool, tx: mpsc::UnboundedSender<PortScanSample>) -> Self {
+        let event = if verbose {
+            "portscan:sample"
+        } else {
+            "portscan:open"
+        };
+        Self { event, tx }
+    }
+}
+
+impl ProgressEmitter for SampleSink {
+    fn emit<T: Serialize + Clone>(&self, _event: &str, _payload: T) {}
+
+    fn emit_sample(&self, event: &str, sample: &PortScanSample) {
+        if event == self.event {
+            // The receiver may be gone; the scan still runs to its report
+            let _ = self.tx.unbounded_send(sample.clone());
+        }
+    }
+}
+
+/// Samples of a running port scan. Ends when the scan does;
+/// `finish` then returns the report, with service detection results.
+pub struct PortScanStream {
+    samples: mpsc::UnboundedReceiver<PortScanSample>,
+    task: JoinHandle<Result<PortScanReport>>,
+}
+
+impl PortScanStream {
+    /// Wait for the scan to end and return its report.
+    /// Samples not yet read are dropped.
+    pub async fn finish(self) -> Result<PortScanReport> {
+        self.task.await?
+    }
+}
+
+impl Stream for PortScanStream {
+    type Item = PortScanSample;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut self.samples).poll_next(cx)
+    }
+}
+
+/// Start a port scan on the current Tokio runtime and stream its samples:
+/// the open ports, or every completed port when `setting.verbose` is set.
+/// Cancel or pause it through `control`. Nothing is stored in history.
+pub fn port_scan_stream(
+    run_id: &str,
+    setting: PortScanSetting,
+    control: Arc<ScanControl>,
+) -> PortScanStream {
+    let run_id = run_id.to_string();
+    stream_samples(setting.verbose, move |sink| async move {
+        crate::probe::scan::runner::scan_ports(&sink, &run_id, setting, control).await
+    })
+}
+
+/// Run `scan` on its own task with a sink feeding the returned stream
+fn stream_samples<F, Fut>(verbose: bool, scan: F) -> PortScanStream
+where
+    F: FnOnce(SampleSink) -> Fut,
+    Fut: Future<Output = Result<PortScanReport>> + Send + 'static,
+{
+    let (tx, samples) = mpsc::unbounded();
+    let task = tokio::spawn(scan(SampleSink::new(verbose, tx)));
+    PortScanStream { samples, task }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::scan::PortState;
+    use futures::StreamExt;
+    use std::net::{IpAddr, Ipv4Addr};
+
+    fn sample(port: u16, state: PortState) -> PortScanSample {
+        PortScanSample::fixture(IpAddr::V4(Ipv4Addr::LOCALHOST), port, state)
+    }
+
+    #[tokio::test]
+    async fn forwards_only_samples() {
+        let (tx, rx) = mpsc::unbounded();
+        let sink = SampleSink::new(false, tx);
+        sink.emit("portscan:progress", (1u32, 2u32));
+        sink.emit_sample("portscan:open", &sample(22, PortState::Open));
+        sink.emit_sample("portscan:sample", &sample(22, PortState::Open));
+        sink.emit_sample("portscan:sample", &sample(23, PortState::Closed));
+        drop(sink);
+        let ports: Vec<u16> = rx.map(|s| s.port).collect().await;
+        assert_eq!(ports, [22]);
+
+        let (tx, rx) = mpsc::unbounded();
+        let sink = SampleSink::new(true, tx);
+        sink.emit_sample("portscan:open", &sample(22, PortState::Open));
+        sink.emit_sample("portscan:sample", &sample(22, PortState::Open));
+        sink.emit_sample("portscan:sample", &sample(23, PortState::Closed));
+        drop(sink);
+        let ports: Vec<u16> = rx.map(|s| s.port).collect().await;
+        assert_eq!(ports, [22, 23]);
+    }
+
+    #[tokio::test]
+    async fn streams_samples_of_a_scan() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let open_port = listener.local_addr().unwrap().port();
+        let closed_port = {
+            let l = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+            l.local_addr().unwrap().port()
+        };
+        // Settings as the CLI reads them
+        let setting: PortScanSetting = serde_json::from_value(serde_json::json!({
+            "ip_addr": "127.0.0.1",
+            "hostname": null,
+            "target_ports_preset": "Custom",
+            "user_ports": [open_port, closed_port],
+            "protocol": "Tcp",
+            "timeout_ms": 1000,
+            "service_detection": false,
+            "verbose": true,
+        }))
+        .unwrap();
+        let control = Arc::new(ScanControl::new("test", "portscan"));
+
+        let mut stream = stream_samples(setting.verbose, move |sink| async move {
+            crate::probe::scan::tcp::port_scan(
+                &sink,
+                "test",
+                IpAddr::V4(Ipv4Addr::LOCALHOST),
+                setting,
+                control,
+            )
+            .await
+        });
+        let mut ports = Vec::new();
+        while let Some(sample) = stream.next().await {
+            ports.push((sample.port, sample.state == PortState::Open));
+        }
+        ports.sort_by_key(|(port, _)| *port);
+        let mut expected = vec![(open_port, true), (closed_port, false)];
+        expected.sort_by_key(|(port, _)| *port);
+        assert_eq!(ports, expected);
+
+        let report = stream.finish().await.unwrap();
+        assert_eq!(report.open_ports().unwrap(), [open_port]);
+    }
+}
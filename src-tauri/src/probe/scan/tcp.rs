@@ -1,100 +1,348 @@
-use anyhow::Result;
-use futures::{stream, StreamExt};
-use rand::{seq::SliceRandom, thread_rng};
-use std::net::{IpAddr, SocketAddr};
+use anyhow::{bail, Result};
+use futures::{future, stream, StreamExt};
+use rand::Rng;
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
-use tauri::{AppHandle, Emitter};
 
+use crate::error::ScanError;
 use crate::model::endpoint::Endpoint;
-use crate::model::scan::{PortScanReport, PortScanSample, PortScanSetting, PortState};
-use crate::probe::scan::expand_ports;
+use crate::model::scan::{port_ranges, PortScanReport, PortScanSample, PortScanSetting, PortState};
+use crate::model::stats::PortScanSummary;
+use crate::probe::scan::blocked::{BlockDetector, BlockSignal};
+use crate::probe::scan::control::ScanControl;
+use crate::probe::scan::deadline::ScanDeadline;
+use crate::probe::scan::heuristic;
+use crate::probe::scan::hostlimit::host_limit;
+use crate::probe::scan::limiter::{
+    is_source_port_exhaustion, retry_delay, AdaptiveLimiter, MAX_RESOURCE_RETRIES,
+};
 use crate::probe::scan::progress::ThrottledProgress;
+use crate::probe::scan::routing::{apply_tcp, check_routing};
+use crate::probe::scan::safe::exclude_fragile_ports;
+use crate::probe::scan::sink::ProgressEmitter;
 use crate::probe::scan::tuner::ports_concurrency;
+use crate::probe::scan::{expand_ports, order_ports};
+use crate::probe::service::models::ServiceResponse;
 use crate::probe::service::{ServiceDetector, ServiceProbeConfig};
+use crate::results::{self, SampleReader, SampleWriter};
+use crate::socket::tcp::{AsyncTcpSocket, TcpConfig};
+use tokio::net::TcpStream;
+
+enum ProbeError {
+    /// Failed to create or bind the socket
+    Socket(std::io::Error),
+    /// Connect failed
+    Connect(std::io::Error),
+}
+
+impl ProbeError {
+    fn io(&self) -> &std::io::Error {
+        match self {
+            ProbeError::Socket(e) | ProbeError::Connect(e) => e,
+        }
+    }
+}
+
+/// Socket config for a connect probe to `ip`, optionally from a fixed local port.
+/// Address and port reuse let concurrent probes share the source port.
+fn probe_config(ip: IpAddr, source_port: Option<u16>, reset_on_close: bool) -> TcpConfig {
+    let mut cfg = if ip.is_ipv4() {
+        TcpConfig::v4_stream()
+    } else {
+        TcpConfig::v6_stream()
+    };
+    if reset_on_close {
+        cfg = cfg.with_reset_on_close();
+    }
+    match source_port {
+        Some(port) => cfg
+            .with_reuseaddr(true)
+            .with_reuseport(true)
+            .with_bind(SocketAddr::new(unspecified(ip), port)),
+        None => cfg,
+    }
+}
+
+fn unspecified(ip: IpAddr) -> IpAddr {
+    if ip.is_ipv4() {
+        IpAddr::V4(Ipv4Addr::UNSPECIFIED)
+    } else {
+        IpAddr::V6(Ipv6Addr::UNSPECIFIED)
+    }
+}
+
+/// `cfg` bound to a random local port in `first..=last`, so concurrent
+/// connects draw from a wider pool than the OS ephemeral range.
+/// Address reuse lets a port still in TIME_WAIT be picked again.
+fn with_random_source_port(cfg: &TcpConfig, ip: IpAddr, (first, last): (u16, u16)) -> TcpConfig {
+    let port = rand::thread_rng().gen_range(first..=last);
+    cfg.clone()
+        .with_reuseaddr(true)
+        .with_bind(SocketAddr::new(unspecified(ip), port))
+}
+
+/// Open-port connections kept for service detection at most. Ports beyond
+/// it are closed and reconnected, so the scan keeps descriptors to probe with.
+const MAX_OPEN_STREAMS: usize = 256;
+
+/// Connect to `addr` once. Returns the connect time in milliseconds and the
+/// connection, which closes when dropped.
+async fn connect_probe(
+    cfg: &TcpConfig,
+    addr: SocketAddr,
+    timeout: Duration,
+) -> Result<(u64, TcpStream), ProbeError> {
+    let start = Instant::now();
+    match AsyncTcpSocket::from_config(cfg) {
+        Ok(sock) => sock
+            .connect_timeout(addr, timeout)
+            .await
+            .map(|stream| (start.elapsed().as_millis() as u64, stream))
+            .map_err(ProbeError::Connect),
+        Err(e) => Err(ProbeError::Socket(e)),
+    }
+}
+
+/// Connect to `addr` under `limiter`. Connects that failed on local resource
+/// exhaustion are retried once the limiter has reduced concurrency; `cfg`
+/// gives the socket config of each attempt.
+async fn connect_limited(
+    limiter: &Arc<AdaptiveLimiter>,
+    addr: SocketAddr,
+    timeout: Duration,
+    cfg: impl Fn() -> TcpConfig,
+) -> Result<(u64, TcpStream), ProbeError> {
+    let mut attempt = 0;
+    loop {
+        let permit = limiter.acquire().await;
+        let result = connect_probe(&cfg(), addr, timeout).await;
+        let exhausted = limiter.on_result(result.as_ref().err().map(ProbeError::io));
+        if exhausted && attempt < MAX_RESOURCE_RETRIES {
+            attempt += 1;
+            drop(permit);
+            tokio::time::sleep(retry_delay(attempt)).await;
+            continue;
+        }
+        return result;
+    }
+}
+
+/// Map a probe result to the port state, RTT and message of a sample.
+/// A failed connect with a firewall rejection `block` becomes `Blocked`.
+fn classify(
+    result: Result<u64, ProbeError>,
+    block: Option<BlockSignal>,
+) -> (PortState, Option<u64>, Option<String>) {
+    match result {
+        Ok(rtt) => (PortState::Open, Some(rtt), None),
+        // Still out of local ports after the retries: says nothing about the target
+        Err(ProbeError::Socket(e)) | Err(ProbeError::Connect(e))
+            if is_source_port_exhaustion(&e) =>
+        {
+            (
+                PortState::Unknown,
+                None,
+                Some(format!("local source ports exhausted: {}", e)),
+            )
+        }
+        Err(ProbeError::Socket(e)) => (
+            PortState::Unknown,
+            None,
+            Some(format!("tcp socket error: {}", e)),
+        ),
+        Err(ProbeError::Connect(e)) => {
+            use std::io::ErrorKind as E;
+            if let Some(signal) = block {
+                return (
+                    PortState::Blocked,
+                    None,
+                    Some(format!("{}: {}", signal.reason(), e)),
+                );
+            }
+            let st = match e.kind() {
+                E::TimedOut => PortState::Filtered,
+                E::ConnectionRefused | E::ConnectionReset | E::NotConnected => PortState::Closed,
+                E::NetworkUnreachable | E::HostUnreachable => PortState::Filtered,
+                _ => PortState::Unknown,
+            };
+            (st, None, Some(e.to_string()))
+        }
+    }
+}
+
+/// Probe a single port with the same socket and classification as `port_scan`.
+/// Returns the port state, connect time and error message.
+pub async fn probe_port(
+    ip: IpAddr,
+    port: u16,
+    timeout: Duration,
+) -> (PortState, Option<u64>, Option<String>) {
+    let cfg = probe_config(ip, None, false);
+    classify(
+        connect_probe(&cfg, SocketAddr::new(ip, port), timeout)
+            .await
+            .map(|(rtt, _stream)| rtt),
+        None,
+    )
+}
+
+/// `probe_port` sharing an in-flight limit with other probes, for sweeps
+/// that probe many hosts at once. Local resource errors are retried and
+/// then reported as `Unknown`.
+pub async fn probe_port_limited(
+    ip: IpAddr,
+    port: u16,
+    timeout: Duration,
+    limiter: &Arc<AdaptiveLimiter>,
+) -> (PortState, Option<u64>, Option<String>) {
+    let cfg = probe_config(ip, None, false);
+    classify(
+        connect_limited(limiter, SocketAddr::new(ip, port), timeout, || cfg.clone())
+            .await
+            .map(|(rtt, _stream)| rtt),
+        None,
+    )
+}
+
+/// Bind the fixed source port once before scanning, so a refused bind
+/// fails the scan with a clear error instead of marking every port filtered.
+fn check_source_port(ip: IpAddr, port: u16) -> Result<()> {
+    if port == 0 {
+        bail!(ScanError::invalid_input(
+            "Source port must be between 1 and 65535"
+        ));
+    }
+    match AsyncTcpSocket::from_config(&probe_config(ip, Some(port), false)) {
+        Ok(_) => Ok(()),
+        Err(e) if e.kind() == std::io::ErrorKind::PermissionDenied => {
+            bail!(ScanError::permission_denied(format!(
+                "Binding source port {} requires administrator privileges (ports below 1024 are privileged)",
+                port
+            )))
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::AddrInUse => {
+            bail!(ScanError::socket_error(format!(
+                "Source port {} is already in use",
+                port
+            )))
+        }
+        Err(e) => bail!(ScanError::socket_error(format!(
+            "Failed to bind source port {}: {}",
+            port, e
+        ))),
+    }
+}
 
 pub async fn port_scan(
-    app: &AppHandle,
+    sink: &impl ProgressEmitter,
     run_id: &str,
     _src_ip: IpAddr,
     setting: PortScanSetting,
+    control: Arc<ScanControl>,
 ) -> Result<PortScanReport> {
-    let mut ports = expand_ports(&setting.target_ports_preset, &setting.user_ports);
-    if !setting.ordered {
-        ports.shuffle(&mut thread_rng());
+    let started = Instant::now();
+    let mut ports = expand_ports(
+        &setting.target_ports_preset,
+        &setting.user_ports,
+        &setting.exclude_ports,
+    );
+    order_ports(&mut ports, setting.order);
+    if setting.safe_mode {
+        let skipped = exclude_fragile_ports(&mut ports);
+        if skipped > 0 {
+            tracing::info!("safe mode: skipping {} fragile ports", skipped);
+        }
     }
 
-    let app = app.clone();
     let ip = setting.ip_addr;
     let timeout = Duration::from_millis(setting.timeout_ms);
+    let verbose = setting.verbose;
+    let source_port = setting.source_port;
+    let reset_on_close = setting.reset_on_close;
+    // Firewall rejections are told apart by ICMP codes and local errors
+    let blocked = if setting.detect_blocked {
+        Some(Arc::new(BlockDetector::start(ip).await))
+    } else {
+        None
+    };
+    let keep_open = setting.reuse_connection && setting.service_detection;
+    if let Some(port) = source_port {
+        check_source_port(ip, port)?;
+    }
+    let source_port_range = setting.source_port_range;
+    if let Some((first, last)) = source_port_range {
+        if source_port.is_some() {
+            bail!(ScanError::invalid_input(
+                "Source port and source port range cannot be combined"
+            ));
+        }
+        if first == 0 || first > last {
+            bail!(ScanError::invalid_input(format!(
+                "Invalid source port range: {}-{}",
+                first, last
+            )));
+        }
+    }
+    check_routing(&setting.routing)?;
+    let probe_cfg = apply_tcp(
+        probe_config(ip, source_port, reset_on_close),
+        &setting.routing,
+    );
 
     let total = ports.len() as u32;
     let progress = Arc::new(ThrottledProgress::new(total));
+    let concurrency = ports_concurrency();
+    let limiter = Arc::new(AdaptiveLimiter::new(concurrency));
+    let host_slots = setting.max_per_host.map(|n| host_limit(ip, n));
+    tracing::debug!("tcp port scan started: {} ports", total);
+
+    let deadline = ScanDeadline::new(setting.max_duration_ms);
 
     // Create tasks for each port and collect results as they complete.
     let mut tasks = stream::iter(ports.into_iter())
+        // Stop issuing new probes once the scan is cancelled or out of time
+        .take_while(|_| future::ready(!control.is_cancelled() && !deadline.check(&control, sink)))
         .map(|port| {
-            let app = app.clone();
             let progress = progress.clone();
+            let control = control.clone();
+            let limiter = limiter.clone();
+            let cfg = probe_cfg.clone();
+            let host_slots = host_slots.clone();
+            let blocked = blocked.clone();
             async move {
-                let cfg = if ip.is_ipv4() {
-                    crate::socket::tcp::TcpConfig::v4_stream()
-                } else {
-                    crate::socket::tcp::TcpConfig::v6_stream()
+                // Hold off new probes while the scan is paused
+                control.wait_if_paused().await;
+
+                // Take the per-host slot first so waiting does not hold a global one
+                let _host_permit = match &host_slots {
+                    Some(h) => Some(h.acquire().await),
+                    None => None,
                 };
 
                 let sock_addr = SocketAddr::new(ip, port);
-                let sock = match crate::socket::tcp::AsyncTcpSocket::from_config(&cfg) {
-                    Ok(s) => s,
-                    Err(e) => {
-                        let (done, should_emit) = progress.on_advance();
-
-                        if should_emit {
-                            let _ = app.emit("portscan:progress", (done, total));
-                        }
-
-                        return PortScanSample {
-                            ip_addr: ip,
-                            port,
-                            state: PortState::Filtered,
-                            rtt_ms: None,
-                            message: Some(format!("tcp socket error: {}", e)),
-                            service_name: None,
-                            service_info: None,
-                            done,
-                            total,
-                        };
-                    }
-                };
 
-                let start = Instant::now();
-
-                let (state, rtt_ms, msg) = match sock.connect_timeout(sock_addr, timeout).await {
-                    Ok(stream) => {
-                        drop(stream);
-                        (
-                            PortState::Open,
-                            Some(start.elapsed().as_millis() as u64),
-                            None,
-                        )
-                    }
-                    Err(e) => {
-                        use std::io::ErrorKind as E;
-                        let st = match e.kind() {
-                            E::TimedOut => PortState::Filtered,
-                            E::ConnectionRefused | E::ConnectionReset | E::NotConnected => {
-                                PortState::Closed
-                            }
-                            E::NetworkUnreachable | E::HostUnreachable | E::AddrNotAvailable => {
-                                PortState::Filtered
-                            }
-                            _ => PortState::Closed,
-                        };
-                        (st, None, Some(e.to_string()))
-                    }
+                // A fresh random source port per attempt
+                let result =
+                    connect_limited(&limiter, sock_addr, timeout, || match source_port_range {
+                        Some(range) => with_random_source_port(&cfg, ip, range),
+                        None => cfg.clone(),
+                    })
+                    .await;
+                let (result, stream) = match result {
+                    Ok((rtt, stream)) => (Ok(rtt), Some(stream).filter(|_| keep_open)),
+                    Err(e) => (Err(e), None),
+                };
+                let block = match (&blocked, &result) {
+                    (Some(detector), Err(ProbeError::Connect(e))) => detector.signal(e, port).await,
+                    _ => None,
                 };
 
+                let (state, rtt_ms, msg) = classify(result, block);
+
                 let (done, should_emit) = progress.on_advance();
+                control.advance_shared(sink);
 
                 let sample = PortScanSample {
                     ip_addr: ip,
@@ -104,72 +352,122 @@ pub async fn port_scan(
                     message: msg,
                     service_name: None,
                     service_info: None,
+                    reply_ttl: None,
+                    quic_handshake: None,
                     done,
                     total,
                 };
 
                 // Open port: emit detailed info
                 if sample.state == PortState::Open {
-                    let _ = app.emit("portscan:open", sample.clone());
+                    sink.emit_sample("portscan:open", &sample);
+                }
+
+                // Verbose: emit every completed sample
+                if verbose {
+                    sink.emit_sample("portscan:sample", &sample);
                 }
 
                 // Progress event
                 if should_emit {
-                    let _ = app.emit("portscan:progress", (done, total));
+                    sink.emit("portscan:progress", (done, total));
                 }
 
-                sample
+                (sample, stream)
             }
         })
-        .buffer_unordered(ports_concurrency());
+        .buffer_unordered(concurrency);
 
-    // Collect Open results only
+    // Collect Open results only, in memory or appended to the results file
     let mut open_samples = Vec::new();
+    let mut spill = if setting.spill_results {
+        Some(SampleWriter::create(results::partial_path(run_id)?)?)
+    } else {
+        None
+    };
     let tcp_db = ndb_tcp_service::TcpServiceDb::bundled();
+    // Open connections handed to service detection, by port
+    let mut open_streams: HashMap<u16, TcpStream> = HashMap::new();
+    let mut probed = Vec::with_capacity(total as usize);
 
-    while let Some(mut sample) = tasks.next().await {
+    while let Some((mut sample, stream)) = tasks.next().await {
+        probed.push(sample.port);
+        if let Some(stream) = stream.filter(|_| open_streams.len() < MAX_OPEN_STREAMS) {
+            open_streams.insert(sample.port, stream);
+        }
         if sample.state == PortState::Open {
             if let Some(entry) = tcp_db.get(sample.port) {
                 sample.service_name = Some(entry.name.clone());
             }
-            open_samples.push(sample);
+            match &mut spill {
+                Some(writer) => writer.push(&sample)?,
+                None => open_samples.push(sample),
+            }
+        } else if let Some(checkpoint) = control.checkpoint() {
+            checkpoint.port_done(sample.port);
         }
     }
 
     // Sort by port
     open_samples.sort_by_key(|s| s.port);
 
-    // Service detection
-    if setting.service_detection && !open_samples.is_empty() {
-        let _ = app.emit("portscan:service_detection_start", run_id.to_string());
-        let service_probe_setting = ServiceProbeConfig {
-            timeout: Duration::from_secs(2),
-            max_concurrency: 100,
-            max_read_size: 1024 * 1024,
-            sni: true,
-            skip_cert_verify: true,
-        };
-        let detector = ServiceDetector::new(service_probe_setting);
-        let mut endpoint = Endpoint::new(ip);
-        endpoint.hostname = setting.hostname.clone();
-        for sample in &open_samples {
-            endpoint.upsert_port(crate::model::endpoint::Port {
-                number: sample.port,
-                transport: crate::model::endpoint::TransportProtocol::Tcp,
-            });
-        }
-        let active_endpoints: Vec<Endpoint> = vec![endpoint];
-        let service_result = detector.run_service_detection(active_endpoints).await?;
-        for sample in &mut open_samples {
-            if let Some(res) = service_result
-                .results
-                .iter()
-                .find(|r| r.port == sample.port)
-            {
-                sample.service_info = Some(res.service_info.clone());
+    let open_count = spill
+        .as_ref()
+        .map_or(open_samples.len(), SampleWriter::count);
+    // Service detection, skipped when the time budget is spent
+    let detect = setting.service_detection && open_count > 0 && !deadline.check(&control, sink);
+    if detect {
+        sink.emit("portscan:service_detection_start", run_id.to_string());
+    }
+
+    let (summary, suspicion, results_file) = match spill {
+        Some(writer) => {
+            // Detect services a batch at a time, so memory stays bounded
+            let partial = writer.finish()?;
+            let mut reader = SampleReader::open(&partial)?;
+            let mut out = SampleWriter::create(results::results_path(run_id)?)?;
+            loop {
+                let mut batch = reader.next_batch(results::RESULTS_BATCH)?;
+                if batch.is_empty() {
+                    break;
+                }
+                if detect {
+                    detect_services(&setting, &mut batch, &mut open_streams).await?;
+                }
+                retain_responsive(&setting, &mut batch);
+                for sample in &batch {
+                    out.push(sample)?;
+                }
             }
+            let path = out.finish()?;
+            if let Err(e) = std::fs::remove_file(&partial) {
+                tracing::warn!("Failed to remove {}: {}", partial.display(), e);
+            }
+            (
+                results::summarize(&path)?,
+                heuristic::assess_open_count(total as usize, open_count),
+                Some(path.to_string_lossy().to_string()),
+            )
         }
-        let _ = app.emit("portscan:service_detection_done", run_id.to_string());
+        None => {
+            if detect {
+                detect_services(&setting, &mut open_samples, &mut open_streams).await?;
+            }
+            // Assess before filtering, silent ports are part of the evidence
+            let suspicion = heuristic::assess(total as usize, &open_samples);
+            retain_responsive(&setting, &mut open_samples);
+            (
+                PortScanSummary::from_samples(&open_samples),
+                suspicion,
+                None,
+            )
+        }
+    };
+    if detect {
+        sink.emit("portscan:service_detection_done", run_id.to_string());
+    }
+    if let Some(reason) = suspicion.reason() {
+        tracing::info!("{} looks suspicious: {}", ip, reason);
     }
 
     let report = PortScanReport {
@@ -177,9 +475,198 @@ pub async fn port_scan(
         ip_addr: setting.ip_addr,
         hostname: setting.hostname.clone(),
         protocol: setting.protocol,
+        summary,
         samples: open_samples,
+        suspicious: suspicion.is_suspicious(),
+        suspicious_reason: suspicion.reason(),
+        deadline_reached: deadline.reached(),
+        elapsed_ms: Some(started.elapsed().as_millis() as u64),
+        ports_preset: Some(setting.target_ports_preset),
+        ports_scanned: Some(total),
+        scanned_ports: port_ranges(probed),
+        results_file,
     };
 
-    let _ = app.emit("portscan:done", report.clone());
+    tracing::debug!(
+        "tcp port scan finished: {} open of {} ports",
+        open_count,
+        total
+    );
+    sink.emit("portscan:done", report.clone());
     Ok(report)
 }
+
+/// Run service detection on open samples and attach what was found.
+/// Connections in `open_streams` for these ports are reused by the probes.
+async fn detect_services(
+    setting: &PortScanSetting,
+    samples: &mut [PortScanSample],
+    open_streams: &mut HashMap<u16, TcpStream>,
+) -> Result<()> {
+    let service_probe_setting = ServiceProbeConfig {
+        timeout: Duration::from_secs(2),
+        max_concurrency: setting.max_per_host.map_or(100, |n| n.clamp(1, 100)),
+        max_read_size: 1024 * 1024,
+        sni: true,
+        skip_cert_verify: true,
+        intensity: setting.detection_intensity,
+        read_only: setting.safe_mode,
+        raw_hex_limit: setting.raw_hex_limit(),
+        skip_intrusive: setting.skip_intrusive,
+        tls_resumption: setting.tls_resumption,
+        tls_enum: setting.tls_enum,
+        routing: setting.routing.clone(),
+    };
+    let streams = samples
+        .iter()
+        .filter_map(|s| {
+            let stream = open_streams.remove(&s.port)?;
+            Some((SocketAddr::new(setting.ip_addr, s.port), stream))
+        })
+        .collect();
+    let detector = ServiceDetector::new(service_probe_setting).with_open_streams(streams);
+    let mut endpoint = Endpoint::new(setting.ip_addr);
+    endpoint.hostname = setting.hostname.clone();
+    for sample in samples.iter() {
+        endpoint.upsert_port(crate::model::endpoint::Port {
+            number: sample.port,
+            transport: crate::model::endpoint::TransportProtocol::Tcp,
+        });
+    }
+    let active_endpoints: Vec<Endpoint> = vec![endpoint];
+    let service_result = detector.run_service_detection(active_endpoints).await?;
+    for sample in samples.iter_mut() {
+        if let Some(res) = service_result
+            .results
+            .iter()
+            .find(|r| r.port == sample.port)
+        {
+            sample.service_info = Some(res.service_info.clone());
+        }
+    }
+    if setting.vuln_lookup {
+        crate::probe::service::vuln::annotate_samples(samples);
+    }
+    Ok(())
+}
+
+/// Drop open ports that sent nothing to any service probe, if requested
+fn retain_responsive(setting: &PortScanSetting, samples: &mut Vec<PortScanSample>) {
+    if setting.responsive_only {
+        samples.retain(|s| {
+            !matches!(&s.service_info, Some(info) if info.response == ServiceResponse::NoResponse)
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    /// Records every event instead of sending it to a frontend
+    #[derive(Default)]
+    struct RecordingSink {
+        events: Mutex<Vec<(String, serde_json::Value)>>,
+    }
+
+    impl ProgressEmitter for RecordingSink {
+        fn emit<T: serde::Serialize + Clone>(&self, event: &str, payload: T) {
+            let value = serde_json::to_value(payload).unwrap();
+            self.events.lock().unwrap().push((event.to_string(), value));
+        }
+    }
+
+    impl RecordingSink {
+        fn named(&self, event: &str) -> Vec<serde_json::Value> {
+            self.events
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|(name, _)| name == event)
+                .map(|(_, v)| v.clone())
+                .collect()
+        }
+    }
+
+    fn setting(ports: Vec<u16>) -> PortScanSetting {
+        PortScanSetting {
+            ip_addr: IpAddr::V4(Ipv4Addr::LOCALHOST),
+            user_ports: ports,
+            timeout_ms: 1000,
+            verbose: true,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn blocked_only_with_a_rejection_signal() {
+        let unreachable = || {
+            Err(ProbeError::Connect(
+                std::io::ErrorKind::HostUnreachable.into(),
+            ))
+        };
+        assert!(classify(unreachable(), None).0 == PortState::Filtered);
+        let (state, _, message) = classify(unreachable(), Some(BlockSignal::IcmpProhibited(13)));
+        assert!(state == PortState::Blocked);
+        assert!(message.unwrap().contains("code 13"));
+    }
+
+    #[test]
+    fn source_port_exhaustion_is_not_filtered() {
+        let exhausted = || {
+            Err(ProbeError::Connect(
+                std::io::ErrorKind::AddrNotAvailable.into(),
+            ))
+        };
+        let (state, _, message) = classify(exhausted(), None);
+        assert!(state == PortState::Unknown);
+        assert!(message.unwrap().contains("source ports exhausted"));
+        // Not a rejection by the target either
+        let (state, _, _) = classify(exhausted(), Some(BlockSignal::IcmpProhibited(13)));
+        assert!(state == PortState::Unknown);
+        let in_use = Err(ProbeError::Socket(std::io::ErrorKind::AddrInUse.into()));
+        assert!(classify(in_use, None).0 == PortState::Unknown);
+    }
+
+    #[tokio::test]
+    async fn port_scan_emits_events_to_sink() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let open_port = listener.local_addr().unwrap().port();
+        let closed_port = {
+            let l = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+            l.local_addr().unwrap().port()
+        };
+
+        let sink = RecordingSink::default();
+        let control = Arc::new(ScanControl::new("test", "portscan"));
+        let report = port_scan(
+            &sink,
+            "test",
+            IpAddr::V4(Ipv4Addr::LOCALHOST),
+            setting(vec![open_port, closed_port]),
+            control,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(report.samples.len(), 1);
+        assert_eq!(report.samples[0].port, open_port);
+
+        let open = sink.named("portscan:open");
+        assert_eq!(open.len(), 1);
+        assert_eq!(open[0]["port"], open_port);
+        assert_eq!(open[0]["state"], "Open");
+
+        // Verbose scans report every port
+        let samples = sink.named("portscan:sample");
+        assert_eq!(samples.len(), 2);
+        let closed = samples.iter().find(|s| s["port"] == closed_port).unwrap();
+        assert_eq!(closed["state"], "Closed");
+
+        let done = sink.named("portscan:done");
+        assert_eq!(done.len(), 1);
+        assert_eq!(done[0]["run_id"], "test");
+        drop(listener);
+    }
+}
@@ -33,6 +33,14 @@ impl ScanProfile {
     }
 }
 
+/// File descriptors left free for everything besides scan sockets
+/// (log files, IPC, service probes, the resolver).
+const FD_HEADROOM: u64 = 256;
+/// Lowest concurrency the FD budget may clamp to.
+const MIN_FD_CONCURRENCY: usize = 16;
+/// Soft limit we try to raise to. More is never needed at the max concurrency.
+const FD_LIMIT_TARGET: u64 = 65536;
+
 /// Final tuned concurrency settings.
 #[derive(Debug, Clone, Copy)]
 pub struct ScanConcurrency {
@@ -43,21 +51,61 @@ pub struct ScanConcurrency {
 /// Global lazy-initialized tuner.
 pub static SCAN_CONCURRENCY: LazyLock<ScanConcurrency> = LazyLock::new(|| {
     let profile = ScanProfile::from_env();
-    let tuned = calc_scan_concurrency(profile);
+    let fd_limit = raise_fd_limit();
+    let tuned = calc_scan_concurrency(profile, fd_limit);
 
     tracing::debug!(
-        "Scan concurrency tuned: hosts={}, ports={} (profile={:?}, cpu={})",
+        "Scan concurrency tuned: hosts={}, ports={} (profile={:?}, cpu={}, fd_limit={:?})",
         tuned.hosts,
         tuned.ports,
         profile,
         num_cpus::get(),
+        fd_limit,
     );
 
     tuned
 });
 
-/// Compute concurrency values based on CPU count, OS behavior, and scan profile.
-pub fn calc_scan_concurrency(profile: ScanProfile) -> ScanConcurrency {
+/// Raise the soft open-files limit toward the hard limit and return the resulting soft limit.
+/// `None` on platforms without RLIMIT_NOFILE.
+#[cfg(unix)]
+pub fn raise_fd_limit() -> Option<u64> {
+    let mut lim = libc::rlimit {
+        rlim_cur: 0,
+        rlim_max: 0,
+    };
+    if unsafe { libc::getrlimit(libc::RLIMIT_NOFILE, &mut lim) } != 0 {
+        return None;
+    }
+    let current = lim.rlim_cur as u64;
+    #[allow(unused_mut)]
+    let mut target = (lim.rlim_max as u64).min(FD_LIMIT_TARGET);
+    // macOS rejects soft limits above OPEN_MAX
+    #[cfg(target_os = "macos")]
+    {
+        target = target.min(10240);
+    }
+    if target > current {
+        let raised = libc::rlimit {
+            rlim_cur: target as libc::rlim_t,
+            rlim_max: lim.rlim_max,
+        };
+        if unsafe { libc::setrlimit(libc::RLIMIT_NOFILE, &raised) } == 0 {
+            return Some(target);
+        }
+        tracing::debug!("failed to raise open files limit from {}", current);
+    }
+    Some(current)
+}
+
+#[cfg(not(unix))]
+pub fn raise_fd_limit() -> Option<u64> {
+    None
+}
+
+/// Compute concurrency values based on CPU count, OS behavior, scan profile,
+/// and the open-files limit (each in-flight probe holds a socket).
+pub fn calc_scan_concurrency(profile: ScanProfile, fd_limit: Option<u64>) -> ScanConcurrency {
     let cpu = num_cpus::get().max(1);
 
     // Host scanning concurrency
@@ -110,6 +158,13 @@ pub fn calc_scan_concurrency(profile: ScanProfile) -> ScanConcurrency {
     let mut ports = (base_ports as f32 * os_factor_ports * profile.factor()) as usize;
     ports = ports.clamp(300, 3000);
 
+    // Leave headroom below the FD limit to avoid "too many open files" mid-scan
+    if let Some(limit) = fd_limit {
+        let budget = (limit.saturating_sub(FD_HEADROOM) as usize).max(MIN_FD_CONCURRENCY);
+        hosts = hosts.min(budget);
+        ports = ports.min(budget);
+    }
+
     ScanConcurrency { hosts, ports }
 }
 
@@ -0,0 +1,129 @@
+use anyhow::{bail, Result};
+use std::net::IpAddr;
+use std::time::Duration;
+use url::{Host, Url};
+
+use crate::error::ScanError;
+use crate::model::scan::PortScanSetting;
+
+/// Host and port extracted from a URL
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UrlTarget {
+    pub scheme: String,
+    /// Host name, `None` for IP literals
+    pub hostname: Option<String>,
+    pub ip: Option<IpAddr>,
+    pub port: u16,
+}
+
+/// Parse a URL into a scan target. A missing scheme is taken as `https`;
+/// the port defaults to the scheme's well-known port (80 / 443).
+pub fn parse_url_target(input: &str) -> Result<UrlTarget> {
+    let input = input.trim();
+    if input.is_empty() {
+        bail!(ScanError::invalid_input("URL must not be empty"));
+    }
+    let url = if input.contains("://") {
+        Url::parse(input)
+    } else {
+        Url::parse(&format!("https://{}", input))
+    }
+    .map_err(|e| ScanError::invalid_input(format!("Invalid URL {}: {}", input, e)))?;
+    let Some(port) = url.port_or_known_default() else {
+        bail!(ScanError::invalid_input(format!(
+            "No port in URL and no default for scheme {}",
+            url.scheme()
+        )));
+    };
+    let (hostname, ip) = match url.host() {
+        Some(Host::Domain(d)) => (Some(d.trim_end_matches('.').to_string()), None),
+        Some(Host::Ipv4(v4)) => (None, Some(IpAddr::V4(v4))),
+        Some(Host::Ipv6(v6)) => (None, Some(IpAddr::V6(v6))),
+        None => bail!(ScanError::invalid_input(format!(
+            "No host in URL: {}",
+            input
+        ))),
+    };
+    Ok(UrlTarget {
+        scheme: url.scheme().to_string(),
+        hostname,
+        ip,
+        port,
+    })
+}
+
+/// Resolve the URL host and build a single-port TCP scan with service
+/// detection. The host name is kept on the setting so TLS probes send it as SNI.
+pub async fn url_scan_setting(input: &str, timeout_ms: u64) -> Result<PortScanSetting> {
+    let target = parse_url_target(input)?;
+    let ip = match (target.ip, &target.hostname) {
+        (Some(ip), _) => ip,
+        (None, Some(name)) => crate::net::dns::lookup_ip(name, Duration::from_secs(5))
+            .await
+            .and_then(|ips| ips.first().copied())
+            .ok_or_else(|| ScanError::resolution_failed(format!("Failed to resolve {}", name)))?,
+        (None, None) => bail!(ScanError::invalid_input(format!(
+            "No host in URL: {}",
+            input
+        ))),
+    };
+    Ok(PortScanSetting {
+        ip_addr: ip,
+        hostname: target.hostname,
+        user_ports: vec![target.port],
+        timeout_ms,
+        service_detection: true,
+        ..Default::default()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn defaults_scheme_and_port() {
+        let t = parse_url_target("https://example.com:8443/path?q=1").unwrap();
+        assert_eq!(
+            (t.scheme.as_str(), t.hostname.as_deref(), t.port),
+            ("https", Some("example.com"), 8443)
+        );
+        let t = parse_url_target("http://example.com/").unwrap();
+        assert_eq!(t.port, 80);
+        // No scheme: https
+        let t = parse_url_target(" example.com. ").unwrap();
+        assert_eq!((t.scheme.as_str(), t.port), ("https", 443));
+        assert_eq!(t.hostname.as_deref(), Some("example.com"));
+    }
+
+    #[test]
+    fn ip_literals_need_no_resolution() {
+        let t = parse_url_target("http://192.0.2.5:8080").unwrap();
+        assert_eq!(
+            (t.hostname, t.ip),
+            (None, Some("192.0.2.5".parse().unwrap()))
+        );
+        let t = parse_url_target("https://[2001:db8::1]/").unwrap();
+        assert_eq!(t.ip, Some("2001:db8::1".parse().unwrap()));
+        assert_eq!(t.port, 443);
+    }
+
+    #[test]
+    fn rejects_unusable_urls() {
+        for input in ["", "https://", "foo://example.com", "https://exa mple.com"] {
+            let err = parse_url_target(input).unwrap_err();
+            assert!(err.downcast_ref::<ScanError>().is_some(), "{:?}", input);
+        }
+    }
+
+    #[tokio::test]
+    async fn setting_targets_the_url_port_with_detection() {
+        let setting = url_scan_setting("https://127.0.0.1:8443", 1500)
+            .await
+            .unwrap();
+        assert_eq!(setting.ip_addr, IpAddr::from([127, 0, 0, 1]));
+        assert_eq!(setting.user_ports, [8443]);
+        assert!(setting.service_detection);
+        assert_eq!(setting.timeout_ms, 1500);
+    }
+}
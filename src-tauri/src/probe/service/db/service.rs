@@ -1,11 +1,12 @@
 use crate::{
-    model::endpoint::Port,
+    model::endpoint::{Port, TransportProtocol},
+    probe::service::payload::check_template,
     probe::service::probe::{
         PortProbeDb, ProbePayload, ProbePayloadDb, ResponseSignature, ResponseSignaturesDb,
         ServiceProbe,
     },
 };
-use anyhow::Result;
+use anyhow::{Context, Result};
 use ndb_tcp_service::TcpServiceDb;
 use ndb_udp_service::UdpServiceDb;
 use std::{collections::HashMap, sync::OnceLock};
@@ -15,6 +16,8 @@ pub static UDP_SERVICE_DB: OnceLock<UdpServiceDb> = OnceLock::new();
 pub static PORT_PROBE_DB: OnceLock<HashMap<Port, Vec<ServiceProbe>>> = OnceLock::new();
 pub static SERVICE_PROBE_DB: OnceLock<HashMap<ServiceProbe, ProbePayload>> = OnceLock::new();
 pub static RESPONSE_SIGNATURES_DB: OnceLock<Vec<ResponseSignature>> = OnceLock::new();
+/// Lowercase service name -> registered TCP/UDP ports
+pub static SERVICE_PORT_INDEX: OnceLock<HashMap<String, Vec<Port>>> = OnceLock::new();
 
 /// Get a reference to the initialized TCP service database.
 pub fn tcp_service_db() -> &'static TcpServiceDb {
@@ -49,6 +52,13 @@ pub fn response_signatures_db() -> &'static Vec<ResponseSignature> {
         .expect("RESPONSE_SIGNATURES_DB not initialized")
 }
 
+/// Get a reference to the initialized service name index.
+pub fn service_port_index() -> &'static HashMap<String, Vec<Port>> {
+    SERVICE_PORT_INDEX
+        .get()
+        .expect("SERVICE_PORT_INDEX not initialized")
+}
+
 pub fn init_tcp_service_db() -> Result<()> {
     let tcp_svc_db = ndb_tcp_service::TcpServiceDb::bundled();
     TCP_SERVICE_DB
@@ -96,6 +106,10 @@ pub fn init_service_probe_db() -> Result<()> {
     for probe_payload in probe_payload_db.probes {
         let service_probe: ServiceProbe =
             ServiceProbe::from_str(&probe_payload.id).expect("Invalid service probe format");
+        if probe_payload.template {
+            check_template(&probe_payload.payload, probe_payload.payload_encoding)
+                .with_context(|| format!("Invalid payload template of {}", probe_payload.id))?;
+        }
         service_probe_map.insert(service_probe, probe_payload);
     }
     SERVICE_PROBE_DB
@@ -114,3 +128,47 @@ pub fn init_response_signatures_db() -> Result<()> {
         .map_err(|_| anyhow::anyhow!("Failed to set RESPONSE_SIGNATURES_DB in OnceLock"))?;
     Ok(())
 }
+
+/// Initialize the service name index over the TCP and UDP service databases.
+/// Requires both databases to be initialized.
+pub fn init_service_port_index() -> Result<()> {
+    let tcp_db = tcp_service_db();
+    let udp_db = udp_service_db();
+    let mut index: HashMap<String, Vec<Port>> = HashMap::new();
+    for number in 0..=u16::MAX {
+        if let Some(entry) = tcp_db.get(number) {
+            index
+                .entry(entry.name.to_ascii_lowercase())
+                .or_default()
+                .push(Port::new(number, TransportProtocol::Tcp));
+        }
+        if let Some(entry) = udp_db.get(number) {
+            index
+                .entry(entry.name.to_ascii_lowercase())
+                .or_default()
+                .push(Port::new(number, TransportProtocol::Udp));
+        }
+    }
+    SERVICE_PORT_INDEX
+        .set(index)
+        .map_err(|_| anyhow::anyhow!("Failed to set SERVICE_PORT_INDEX in OnceLock"))?;
+    Ok(())
+}
+
+/// Ports registered for a service, ascending. Matches the name case-insensitively,
+/// including its `-` variants (`http` also finds `http-alt`, `http-proxy`).
+pub fn ports_for_service(name: &str) -> Vec<Port> {
+    let name = name.trim().to_ascii_lowercase();
+    if name.is_empty() {
+        return Vec::new();
+    }
+    let prefix = format!("{}-", name);
+    let mut ports: Vec<Port> = service_port_index()
+        .iter()
+        .filter(|(service, _)| **service == name || service.starts_with(&prefix))
+        .flat_map(|(_, ports)| ports.iter().copied())
+        .collect();
+    ports.sort();
+    ports.dedup();
+    ports
+}
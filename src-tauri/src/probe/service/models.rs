@@ -11,6 +11,132 @@ pub struct ServiceInfo {
     pub raw: Option<String>,
     pub cpes: Vec<String>,
     pub tls_info: Option<TlsInfo>,
+    /// Whether the port sent any data to the service probes
+    #[serde(default)]
+    pub response: ServiceResponse,
+    /// Time from sending the probe to the first response byte
+    /// (TLS: the handshake), in milliseconds
+    #[serde(default)]
+    pub response_latency_ms: Option<u64>,
+    /// Known CVEs of the detected CPEs, from the offline CVE index
+    #[serde(default)]
+    pub vuln_summary: Option<VulnSummary>,
+    /// Host identity reported over SMB/NetBIOS (ports 139/445)
+    #[serde(default)]
+    pub smb_info: Option<SmbInfo>,
+    /// Security layer negotiated by an RDP server
+    #[serde(default)]
+    pub rdp_info: Option<RdpInfo>,
+    /// Algorithms and host key offered by an SSH server
+    #[serde(default)]
+    pub ssh_info: Option<SshInfo>,
+}
+
+/// SSH server configuration from its KEXINIT and key exchange reply
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SshInfo {
+    pub kex_algorithms: Vec<String>,
+    pub host_key_algorithms: Vec<String>,
+    /// Ciphers of both directions, client to server first
+    pub encryption_algorithms: Vec<String>,
+    /// MACs of both directions, client to server first
+    pub mac_algorithms: Vec<String>,
+    pub compression_algorithms: Vec<String>,
+    /// Type of the host key the server signed with, e.g. "ssh-ed25519"
+    pub host_key_type: Option<String>,
+    /// Host key fingerprint as printed by OpenSSH, `SHA256:...`
+    pub host_key_fingerprint: Option<String>,
+    /// Offered algorithms considered weak (SHA-1 key exchange, DSA, CBC, ...)
+    pub weak_algorithms: Vec<String>,
+}
+
+/// Outcome of the RDP security negotiation (X.224 connection request)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RdpInfo {
+    /// Protocol selected when offered TLS and CredSSP: "RDP", "TLS", "CredSSP", ...
+    pub security_protocol: Option<String>,
+    /// Why the server refused the offer, when it did
+    pub negotiation_failure: Option<String>,
+    /// Whether the server requires Network Level Authentication (CredSSP)
+    pub nla_required: Option<bool>,
+    /// Whether legacy standard RDP security, without TLS, is accepted
+    pub rdp_security_allowed: Option<bool>,
+    /// Whether TLS without CredSSP is accepted
+    pub tls_allowed: Option<bool>,
+}
+
+/// Host identity learned from a NetBIOS node status query and an SMB2
+/// negotiate with an anonymous NTLMSSP challenge
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SmbInfo {
+    /// Highest SMB dialect the server accepted, e.g. "3.0.2"
+    pub dialect: Option<String>,
+    /// Whether the server requires signed SMB messages
+    pub signing_required: Option<bool>,
+    /// NetBIOS computer name
+    pub computer_name: Option<String>,
+    /// NetBIOS domain, or workgroup of hosts outside a domain
+    pub domain: Option<String>,
+    pub dns_computer_name: Option<String>,
+    pub dns_domain: Option<String>,
+    /// OS version from the NTLMSSP challenge, e.g. "10.0.19041"
+    pub os_version: Option<String>,
+    /// Unique `<00>` name from the NetBIOS name service
+    pub netbios_name: Option<String>,
+    /// Group `<00>` name from the NetBIOS name service
+    pub workgroup: Option<String>,
+}
+
+/// CVE severity rating
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Severity {
+    Critical,
+    High,
+    Medium,
+    Low,
+}
+
+impl Severity {
+    /// Rating of a CVSS v3 base score
+    pub fn from_cvss(score: f32) -> Self {
+        if score >= 9.0 {
+            Severity::Critical
+        } else if score >= 7.0 {
+            Severity::High
+        } else if score >= 4.0 {
+            Severity::Medium
+        } else {
+            Severity::Low
+        }
+    }
+}
+
+/// Count of known CVEs matching a service, by severity
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct VulnSummary {
+    pub total: usize,
+    pub critical: usize,
+    pub high: usize,
+    pub medium: usize,
+    pub low: usize,
+    /// CVEs without a score or severity
+    pub unknown: usize,
+    pub max_cvss: Option<f32>,
+    /// Highest scored CVE ids, at most 10
+    pub cve_ids: Vec<String>,
+}
+
+/// Outcome of service probing on an open port
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ServiceResponse {
+    /// Not probed, or every probe failed for another reason
+    #[default]
+    Unknown,
+    /// At least one probe received data
+    Responded,
+    /// Accepted the connection but sent nothing to any probe (open|no-response),
+    /// typical of tarpits
+    NoResponse,
 }
 
 /// TLS information extracted from a TLS handshake
@@ -26,10 +152,49 @@ pub struct TlsInfo {
     pub not_before: Option<String>,
     /// Not after date in RFC2822 format
     pub not_after: Option<String>,
+    /// Not before date as a Unix timestamp in seconds
+    #[serde(default)]
+    pub not_before_unix: Option<i64>,
+    /// Not after date as a Unix timestamp in seconds
+    #[serde(default)]
+    pub not_after_unix: Option<i64>,
+    /// Issuer and subject are the same name
+    #[serde(default)]
+    pub self_signed: Option<bool>,
     pub san_list: Vec<String>,
     pub serial_hex: Option<String>,
     /// Signature algorithm name
     pub sig_algorithm: Option<String>,
     /// Public key algorithm name
     pub pubkey_algorithm: Option<String>,
+    /// Full handshake time in milliseconds
+    #[serde(default)]
+    pub handshake_ms: Option<u64>,
+    /// Result of the resuming handshake, when requested
+    #[serde(default)]
+    pub resumption: Option<TlsResumption>,
+    /// Versions accepted by the server, when enumerated, oldest first
+    #[serde(default)]
+    pub supported_versions: Vec<String>,
+    /// Cipher suites accepted with any of `supported_versions`
+    #[serde(default)]
+    pub supported_ciphers: Vec<String>,
+    /// Protocol the connection was upgraded from with STARTTLS, e.g. "smtp"
+    #[serde(default)]
+    pub starttls: Option<String>,
+}
+
+/// Second TLS handshake made with the session of the first one
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TlsResumption {
+    /// The server issued a session ticket (TLS 1.3) or session ID (TLS 1.2)
+    pub offered: bool,
+    /// The second handshake resumed the session instead of a full handshake
+    pub resumed: bool,
+    /// Time of the second handshake in milliseconds
+    pub handshake_ms: Option<u64>,
+    /// Early (0-RTT) data the ticket allows, in bytes. 0 when 0-RTT is not offered
+    pub max_early_data: u32,
+    /// Why the second handshake could not be made
+    pub error: Option<String>,
 }
@@ -1,12 +1,90 @@
-use crate::probe::service::probe::{PayloadEncoding, PortProbe};
-use anyhow::Result;
+use crate::probe::service::probe::{PayloadEncoding, PortProbe, ProbeContext};
+use anyhow::{anyhow, bail, Result};
 use base64::{engine::general_purpose, Engine as _};
+use std::net::IpAddr;
 
 /// Context for building payloads
 #[derive(Default, Clone)]
 pub struct PayloadContext<'a> {
     pub hostname: Option<&'a str>,
+    /// Request path for `{path}`, `/` when unset
     pub path: Option<&'a str>,
+    /// Target address, the `{host}` of templates without a hostname
+    pub ip: Option<IpAddr>,
+    pub port: Option<u16>,
+    /// Name the TLS handshake is made with, for `{sni}`
+    pub server_name: Option<String>,
+}
+
+impl<'a> PayloadContext<'a> {
+    /// Target variables of the probed port
+    pub fn for_probe(ctx: &'a ProbeContext) -> Self {
+        PayloadContext {
+            hostname: ctx.hostname.as_deref(),
+            path: None,
+            ip: Some(ctx.ip),
+            port: Some(ctx.probe.port),
+            server_name: Some(ctx.server_name()),
+        }
+    }
+
+    /// Value of a template variable
+    fn var(&self, name: &str) -> Result<String> {
+        let host = || match (self.hostname, self.ip) {
+            (Some(hostname), _) => Ok(hostname.to_string()),
+            // Bracketed as in a Host header or URI
+            (None, Some(IpAddr::V6(ip))) => Ok(format!("[{}]", ip)),
+            (None, Some(ip)) => Ok(ip.to_string()),
+            (None, None) => Err(anyhow!("no target for {{host}}")),
+        };
+        match name {
+            "host" => host(),
+            "port" => self
+                .port
+                .map(|p| p.to_string())
+                .ok_or_else(|| anyhow!("no target for {{port}}")),
+            "sni" => self.server_name.clone().map_or_else(host, Ok),
+            "path" => Ok(self.path.unwrap_or("/").to_string()),
+            _ => bail!("unknown payload variable {{{}}}", name),
+        }
+    }
+}
+
+/// Substitute `{host}`, `{port}`, `{sni}` and `{path}` in a templated payload.
+/// `{{` is a literal brace.
+fn render_template(template: &str, ctx: &PayloadContext) -> Result<String> {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        if let Some(after) = after.strip_prefix('{') {
+            out.push('{');
+            rest = after;
+            continue;
+        }
+        let end = after
+            .find('}')
+            .ok_or_else(|| anyhow!("unclosed payload variable"))?;
+        out.push_str(&ctx.var(&after[..end])?);
+        rest = &after[end + 1..];
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+/// Check a payload marked `template` in the probe database: raw encoding,
+/// and known variables only
+pub fn check_template(payload: &str, encoding: PayloadEncoding) -> Result<()> {
+    if !matches!(encoding, PayloadEncoding::Raw) {
+        bail!("templates require a raw payload");
+    }
+    let sample = PayloadContext {
+        hostname: Some("example.test"),
+        port: Some(1),
+        ..Default::default()
+    };
+    render_template(payload, &sample).map(|_| ())
 }
 
 /// Payload builder for service detection
@@ -20,25 +98,17 @@ impl PayloadBuilder {
     }
 
     /// Decode payload bytes (raw/base64). Returns error on decode failure.
+    /// Templated payloads get the target variables of `ctx` substituted.
     pub fn payload(&self, ctx: PayloadContext) -> Result<Vec<u8>> {
         match self.probe.payload_encoding {
             PayloadEncoding::Raw => {
-                let mut s = self.probe.payload.clone();
-
-                if s.contains("$HOST") {
-                    let host = ctx.hostname.ok_or_else(|| {
-                        anyhow::anyhow!(
-                            "probe {} requires hostname (found $HOST in payload)",
-                            self.probe.probe_id.as_str()
-                        )
-                    })?;
-                    s = s.replace("$HOST", host);
-                }
-                if s.contains("$PATH") {
-                    let path = ctx.path.unwrap_or("/");
-                    s = s.replace("$PATH", path);
-                }
-
+                let s = if self.probe.template {
+                    render_template(&self.probe.payload, &ctx).map_err(|e| {
+                        anyhow!("template of {} failed: {}", self.probe.probe_id.as_str(), e)
+                    })?
+                } else {
+                    self.probe.payload.clone()
+                };
                 Ok(s.into_bytes())
             }
             PayloadEncoding::Base64 => general_purpose::STANDARD
@@ -53,3 +123,86 @@ impl PayloadBuilder {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::endpoint::TransportProtocol;
+    use crate::probe::service::probe::ServiceProbe;
+
+    fn probe(payload: &str, template: bool) -> PortProbe {
+        PortProbe {
+            probe_id: ServiceProbe::TcpGenericLines,
+            probe_name: "tcp:generic_lines".into(),
+            port: 5060,
+            transport: TransportProtocol::Tcp,
+            payload: payload.to_string(),
+            payload_encoding: PayloadEncoding::Raw,
+            template,
+        }
+    }
+
+    #[test]
+    fn substitutes_target_variables() {
+        let payload = "OPTIONS sip:{host}:{port} SIP/2.0\r\nX-Sni: {sni}\r\nX-Brace: {{x}\r\n";
+        let ctx = PayloadContext {
+            ip: Some("2001:db8::1".parse().unwrap()),
+            port: Some(5060),
+            ..Default::default()
+        };
+        let out = PayloadBuilder::new(probe(payload, true))
+            .payload(ctx)
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "OPTIONS sip:[2001:db8::1]:5060 SIP/2.0\r\nX-Sni: [2001:db8::1]\r\nX-Brace: {x}\r\n"
+        );
+    }
+
+    #[test]
+    fn literal_payloads_are_sent_unchanged() {
+        let payload = "{\"jsonrpc\":\"2.0\",\"id\":{port}}\r\n";
+        let out = PayloadBuilder::new(probe(payload, false))
+            .payload(PayloadContext::default())
+            .unwrap();
+        assert_eq!(out, payload.as_bytes());
+    }
+
+    #[test]
+    fn rejects_unknown_variables_and_encoded_templates() {
+        assert!(check_template(
+            "GET / HTTP/1.1\r\nHost: {host}\r\n\r\n",
+            PayloadEncoding::Raw
+        )
+        .is_ok());
+        assert!(check_template("{hostname}", PayloadEncoding::Raw).is_err());
+        assert!(check_template("{host", PayloadEncoding::Raw).is_err());
+        assert!(check_template("e2hvc3R9", PayloadEncoding::Base64).is_err());
+    }
+
+    #[test]
+    fn bundled_https_get_sends_the_target_as_host() {
+        let db: crate::probe::service::probe::ProbePayloadDb =
+            serde_json::from_str(crate::resources::SERVICE_PROBES_JSON).unwrap();
+        let https_get = db
+            .probes
+            .into_iter()
+            .find(|p| p.id == "tcp:https_get")
+            .unwrap();
+        assert!(https_get.template);
+        check_template(&https_get.payload, https_get.payload_encoding).unwrap();
+
+        let mut probe = probe(&https_get.payload, true);
+        probe.probe_id = ServiceProbe::TcpHTTPSGet;
+        let ctx = PayloadContext {
+            path: Some("/"),
+            ip: Some("192.0.2.10".parse().unwrap()),
+            port: Some(443),
+            ..Default::default()
+        };
+        let out = PayloadBuilder::new(probe).payload(ctx).unwrap();
+        assert!(String::from_utf8(out)
+            .unwrap()
+            .starts_with("GET / HTTP/1.1\r\nHost: 192.0.2.10\r\n"));
+    }
+}
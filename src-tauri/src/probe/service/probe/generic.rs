@@ -5,8 +5,7 @@ use crate::probe::service::probe::{PortProbeResult, ProbeContext};
 use crate::probe::service::read_timeout;
 use crate::probe::service::{build_regex, expand_cpe_templates};
 use anyhow::Result;
-use std::net::SocketAddr;
-use tokio::{io::AsyncWriteExt, net::TcpStream, time::timeout};
+use tokio::{io::AsyncWriteExt, time::timeout};
 
 #[derive(Debug, Default, Clone)]
 struct BannerLite {
@@ -71,15 +70,13 @@ impl GenericProbe {
     /// Run the generic probe with the given context.
     pub async fn run(ctx: ProbeContext) -> Result<PortProbeResult> {
         tracing::debug!("Generic Probe: {}:{} - Connecting", ctx.ip, ctx.probe.port);
-        let addr: SocketAddr = SocketAddr::new(ctx.ip, ctx.probe.port);
-        let mut stream = timeout(ctx.timeout, TcpStream::connect(addr)).await??;
+        let mut stream = ctx.connect().await?;
 
         tracing::debug!("Generic Probe: {}:{} - Connected", ctx.ip, ctx.probe.port);
 
         // If payload is present, send it
-        let payload = PayloadBuilder::new(ctx.probe.clone())
-            .payload(PayloadContext::default())
-            .unwrap_or_default();
+        let payload =
+            PayloadBuilder::new(ctx.probe.clone()).payload(PayloadContext::for_probe(&ctx))?;
         if !payload.is_empty() {
             timeout(ctx.timeout, stream.write_all(&payload)).await??;
             stream.flush().await?;
@@ -94,7 +91,7 @@ impl GenericProbe {
             ctx.probe.port,
             total.as_millis()
         );
-        let bytes = read_timeout(&mut stream, idle, total, ctx.max_read_size).await?;
+        let (bytes, first_byte) = read_timeout(&mut stream, idle, total, ctx.max_read_size).await?;
 
         // Extract banner
         let banner = parse_banner(&bytes, 64 * 1024);
@@ -119,7 +116,8 @@ impl GenericProbe {
         }
         // If name is still empty, keep banner
         svc.banner = banner.first_line.clone();
-        svc.raw = Some(banner.raw_text);
+        svc.raw = Some(ctx.raw_value(&bytes, banner.raw_text));
+        svc.response_latency_ms = Some(first_byte.as_millis() as u64);
         let probe_result: PortProbeResult = PortProbeResult {
             ip: ctx.ip,
             hostname: ctx.hostname,
@@ -1,9 +1,9 @@
-use std::{collections::HashMap, net::SocketAddr};
+use std::collections::HashMap;
 
 use anyhow::Result;
 use rustls_pki_types::ServerName;
 use std::sync::Arc;
-use tokio::{io::AsyncWriteExt, net::TcpStream, time::timeout};
+use tokio::{io::AsyncWriteExt, time::timeout};
 use tokio_rustls::{
     rustls::{ClientConfig, RootCertStore},
     TlsConnector,
@@ -133,9 +133,7 @@ pub struct HttpProbe;
 impl HttpProbe {
     /// Run the HTTP probe with the given context.
     pub async fn run(ctx: ProbeContext) -> Result<PortProbeResult> {
-        let addr: SocketAddr = SocketAddr::new(ctx.ip, ctx.probe.port);
-        let hostname = ctx.hostname.clone().unwrap_or_else(|| ctx.ip.to_string());
-        let mut tcp_stream = timeout(ctx.timeout, TcpStream::connect(addr)).await??;
+        let mut tcp_stream = ctx.connect().await?;
         let payload_builder = PayloadBuilder::new(ctx.probe.clone());
         let tcp_svc_db = tcp_service_db();
         match ctx.probe.probe_id {
@@ -145,10 +143,10 @@ impl HttpProbe {
                     ctx.ip,
                     ctx.probe.port
                 );
-                let payload: Vec<u8> = payload_builder.payload(PayloadContext::default())?;
+                let payload: Vec<u8> = payload_builder.payload(PayloadContext::for_probe(&ctx))?;
                 timeout(ctx.timeout, tcp_stream.write_all(&payload)).await??;
                 tcp_stream.flush().await?;
-                let res: Vec<u8> =
+                let (res, first_byte) =
                     read_timeout(&mut tcp_stream, ctx.timeout, ctx.timeout, ctx.max_read_size)
                         .await?;
                 let http_res = parse_http_response(&res, 64 * 1024);
@@ -162,7 +160,8 @@ impl HttpProbe {
                 svc.name = tcp_svc_db.get_name(ctx.probe.port).map(|s| s.to_string());
                 svc.banner = http_res.status_line.clone();
                 svc.product = http_res.headers.get("server").cloned();
-                svc.raw = Some(http_res.raw_text.clone());
+                svc.raw = Some(ctx.raw_value(&res, http_res.raw_text.clone()));
+                svc.response_latency_ms = Some(first_byte.as_millis() as u64);
 
                 tracing::debug!(
                     "HTTP Probe: {}:{} - Banner: {:?}, Server {:?}",
@@ -195,8 +194,8 @@ impl HttpProbe {
                     ctx.probe.port
                 );
                 let payload_ctx = PayloadContext {
-                    hostname: ctx.hostname.as_deref(),
                     path: Some("/".into()),
+                    ..PayloadContext::for_probe(&ctx)
                 };
                 let payload: Vec<u8> = payload_builder.payload(payload_ctx)?;
 
@@ -220,11 +219,7 @@ impl HttpProbe {
                 }
 
                 let connector = TlsConnector::from(Arc::new(config));
-                let sni_name = if ctx.sni {
-                    ServerName::try_from(hostname)?
-                } else {
-                    ServerName::try_from("localhost")?
-                };
+                let sni_name = ServerName::try_from(ctx.server_name())?;
 
                 let mut tls_stream =
                     timeout(ctx.timeout, connector.connect(sni_name, tcp_stream)).await??;
@@ -238,7 +233,7 @@ impl HttpProbe {
 
                 tls_stream.write_all(&payload).await?;
                 tls_stream.flush().await?;
-                let res: Vec<u8> =
+                let (res, first_byte) =
                     read_timeout(&mut tls_stream, ctx.timeout, ctx.timeout, ctx.max_read_size)
                         .await?;
                 let http_res = parse_http_response(&res, 64 * 1024);
@@ -250,7 +245,8 @@ impl HttpProbe {
                 );
                 svc.banner = http_res.status_line.clone();
                 svc.product = http_res.headers.get("server").cloned();
-                svc.raw = Some(http_res.raw_text.clone());
+                svc.raw = Some(ctx.raw_value(&res, http_res.raw_text.clone()));
+                svc.response_latency_ms = Some(first_byte.as_millis() as u64);
 
                 tracing::debug!(
                     "HTTPS Probe: {}:{} - Banner: {:?}, Server {:?}",
@@ -283,10 +279,10 @@ impl HttpProbe {
                     ctx.ip,
                     ctx.probe.port
                 );
-                let payload: Vec<u8> = payload_builder.payload(PayloadContext::default())?;
+                let payload: Vec<u8> = payload_builder.payload(PayloadContext::for_probe(&ctx))?;
                 timeout(ctx.timeout, tcp_stream.write_all(&payload)).await??;
                 tcp_stream.flush().await?;
-                let res: Vec<u8> =
+                let (res, first_byte) =
                     read_timeout(&mut tcp_stream, ctx.timeout, ctx.timeout, ctx.max_read_size)
                         .await?;
                 let http_res = parse_http_response(&res, 64 * 1024);
@@ -300,7 +296,8 @@ impl HttpProbe {
                 svc.name = tcp_svc_db.get_name(ctx.probe.port).map(|s| s.to_string());
                 svc.banner = http_res.status_line.clone();
                 svc.product = http_res.headers.get("server").cloned();
-                svc.raw = Some(http_res.raw_text.clone());
+                svc.raw = Some(ctx.raw_value(&res, http_res.raw_text.clone()));
+                svc.response_latency_ms = Some(first_byte.as_millis() as u64);
 
                 tracing::debug!(
                     "HTTP Probe: {}:{} - Banner: {:?}, Server {:?}",
@@ -328,6 +325,10 @@ impl HttpProbe {
             }
             _ => {}
         }
-        Err(anyhow::anyhow!("Failed to probe HTTP service at {}", addr))
+        Err(anyhow::anyhow!(
+            "Failed to probe HTTP service at {}:{}",
+            ctx.ip,
+            ctx.probe.port
+        ))
     }
 }
@@ -3,12 +3,25 @@ pub mod generic;
 pub mod http;
 pub mod null;
 pub mod quic;
+pub mod rdp;
+pub mod smb;
+pub mod ssh;
+pub mod starttls;
 pub mod tls;
+mod tlsenum;
 
 use super::models::ServiceInfo;
 use crate::model::endpoint::TransportProtocol;
+use crate::model::scan::ScanRouting;
+use crate::probe::scan::routing::apply_tcp;
+use crate::socket::tcp::{AsyncTcpSocket, TcpConfig};
+use anyhow::Result;
 use serde::{Deserialize, Serialize};
+use std::mem::MaybeUninit;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
 use std::{collections::BTreeMap, net::IpAddr, time::Duration};
+use tokio::net::TcpStream;
 
 /// Metadata for the database
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -28,6 +41,10 @@ pub enum ServiceProbe {
     TcpDNSVersionBindReq,
     TcpHelp,
     TcpTlsSession,
+    TcpSmbNegotiate,
+    TcpRdpNegotiate,
+    TcpSshKexInit,
+    TcpStartTls,
     UdpDNSVersionBindReq,
     UdpQuic,
 }
@@ -44,6 +61,10 @@ impl ServiceProbe {
             ServiceProbe::TcpDNSVersionBindReq => "tcp:dns_version_bind_req",
             ServiceProbe::TcpHelp => "tcp:help",
             ServiceProbe::TcpTlsSession => "tcp:tls_session",
+            ServiceProbe::TcpSmbNegotiate => "tcp:smb_negotiate",
+            ServiceProbe::TcpRdpNegotiate => "tcp:rdp_negotiate",
+            ServiceProbe::TcpSshKexInit => "tcp:ssh_kexinit",
+            ServiceProbe::TcpStartTls => "tcp:starttls",
             ServiceProbe::UdpDNSVersionBindReq => "udp:dns_version_bind_req",
             ServiceProbe::UdpQuic => "udp:quic",
         }
@@ -59,6 +80,10 @@ impl ServiceProbe {
             "tcp:dns_version_bind_req" => Some(ServiceProbe::TcpDNSVersionBindReq),
             "tcp:help" => Some(ServiceProbe::TcpHelp),
             "tcp:tls_session" => Some(ServiceProbe::TcpTlsSession),
+            "tcp:smb_negotiate" => Some(ServiceProbe::TcpSmbNegotiate),
+            "tcp:rdp_negotiate" => Some(ServiceProbe::TcpRdpNegotiate),
+            "tcp:ssh_kexinit" => Some(ServiceProbe::TcpSshKexInit),
+            "tcp:starttls" => Some(ServiceProbe::TcpStartTls),
             "udp:dns_version_bind_req" => Some(ServiceProbe::UdpDNSVersionBindReq),
             "udp:quic" => Some(ServiceProbe::UdpQuic),
             _ => None,
@@ -74,10 +99,20 @@ impl ServiceProbe {
             | ServiceProbe::TcpHTTPOptions
             | ServiceProbe::TcpDNSVersionBindReq
             | ServiceProbe::TcpHelp
-            | ServiceProbe::TcpTlsSession => TransportProtocol::Tcp,
+            | ServiceProbe::TcpTlsSession
+            | ServiceProbe::TcpSmbNegotiate
+            | ServiceProbe::TcpRdpNegotiate
+            | ServiceProbe::TcpSshKexInit
+            | ServiceProbe::TcpStartTls => TransportProtocol::Tcp,
             ServiceProbe::UdpDNSVersionBindReq | ServiceProbe::UdpQuic => TransportProtocol::Udp,
         }
     }
+    /// Whether the probe only reads or sends a well-formed query of its
+    /// protocol. Free-form probes (lines, HELP) may be executed as commands
+    /// or printed by devices on the port.
+    pub fn is_read_only(&self) -> bool {
+        !matches!(self, ServiceProbe::TcpGenericLines | ServiceProbe::TcpHelp)
+    }
 }
 
 /// Encoding type for probe payloads
@@ -105,6 +140,13 @@ pub struct ProbePayload {
     pub payload: String,
     pub payload_encoding: PayloadEncoding,
     pub wait_ms: Option<u64>,
+    /// Sends application data that may trigger alerts or side effects
+    #[serde(default)]
+    pub intrusive: bool,
+    /// Substitute `{host}`, `{port}`, `{sni}` and `{path}` in a raw payload at
+    /// send time. Other payloads are sent as written.
+    #[serde(default)]
+    pub template: bool,
     pub ports: Vec<u16>,
 }
 
@@ -143,6 +185,8 @@ pub struct PortProbe {
     pub transport: TransportProtocol,
     pub payload: String, // Raw or Base64
     pub payload_encoding: PayloadEncoding,
+    /// `payload` has target variables, see `ProbePayload::template`
+    pub template: bool,
 }
 
 impl PortProbe {
@@ -154,10 +198,41 @@ impl PortProbe {
             transport,
             payload: String::new(),
             payload_encoding: PayloadEncoding::Raw,
+            template: false,
+        }
+    }
+}
+
+/// Connection the port scan left open to the probed port.
+/// Shared by the probes of the port; the first one to connect takes it.
+#[derive(Clone, Default)]
+pub struct OpenStream(Arc<Mutex<Option<TcpStream>>>);
+
+impl OpenStream {
+    pub fn new(stream: Option<TcpStream>) -> Self {
+        OpenStream(Arc::new(Mutex::new(stream)))
+    }
+
+    /// The connection, unless taken already or closed by the peer meanwhile
+    fn take(&self) -> Option<TcpStream> {
+        let stream = self.0.lock().unwrap_or_else(|e| e.into_inner()).take()?;
+        // Peek without waiting: EOF or an error means the peer gave up on it
+        let mut buf = [MaybeUninit::<u8>::uninit(); 1];
+        match socket2::SockRef::from(&stream).peek(&mut buf) {
+            Ok(0) => None,
+            Ok(_) => Some(stream),
+            Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => Some(stream),
+            Err(_) => None,
         }
     }
 }
 
+impl std::fmt::Debug for OpenStream {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("OpenStream")
+    }
+}
+
 /// Context for running a probe against a target
 #[derive(Debug, Clone)]
 pub struct ProbeContext {
@@ -168,6 +243,63 @@ pub struct ProbeContext {
     pub max_read_size: usize,
     pub sni: bool,
     pub skip_cert_verify: bool,
+    /// Dump responses as hex (bounded) instead of lossy text
+    pub raw_hex_limit: Option<usize>,
+    /// Time a second, resuming handshake in the TLS probe
+    pub tls_resumption: bool,
+    /// Enumerate accepted TLS versions and cipher suites in the TLS probe
+    pub tls_enum: bool,
+    /// Connection handed over by the port scan, if any
+    pub open_stream: OpenStream,
+    /// Bind device and fwmark of the scan, applied to new connections
+    pub routing: ScanRouting,
+}
+
+impl ProbeContext {
+    /// Connect to the probed port, or take over the connection the port
+    /// scan left open, so stateful services see a single connection.
+    pub async fn connect(&self) -> Result<TcpStream> {
+        if let Some(stream) = self.open_stream.take() {
+            tracing::debug!("{}:{} - Reusing scan connection", self.ip, self.probe.port);
+            return Ok(stream);
+        }
+        let addr = SocketAddr::new(self.ip, self.probe.port);
+        if self.routing.is_empty() {
+            return Ok(tokio::time::timeout(self.timeout, TcpStream::connect(addr)).await??);
+        }
+        let cfg = if self.ip.is_ipv4() {
+            TcpConfig::v4_stream()
+        } else {
+            TcpConfig::v6_stream()
+        };
+        let socket = AsyncTcpSocket::from_config(&apply_tcp(cfg, &self.routing))?;
+        Ok(socket.connect_timeout(addr, self.timeout).await?)
+    }
+
+    /// Value for `ServiceInfo.raw`: a hex dump of `bytes` when raw capture
+    /// is enabled, otherwise the already decoded `text`.
+    pub fn raw_value(&self, bytes: &[u8], text: String) -> String {
+        match self.raw_hex_limit {
+            Some(limit) => crate::probe::service::hex_dump(bytes, limit),
+            None => text,
+        }
+    }
+
+    /// Name the TLS handshake is made with: the scan hostname, or the IP
+    /// address when there is none. "localhost" when SNI is disabled.
+    pub fn server_name(&self) -> String {
+        if self.sni {
+            self.hostname.clone().unwrap_or_else(|| self.ip.to_string())
+        } else {
+            "localhost".to_string()
+        }
+    }
+
+    /// Name sent in the SNI extension. IP addresses are never sent as SNI.
+    pub fn sni_name(&self) -> Option<String> {
+        let name = self.server_name();
+        name.parse::<IpAddr>().is_err().then_some(name)
+    }
 }
 
 /// Result of running a probe against a target
@@ -1,6 +1,5 @@
 use anyhow::{bail, Result};
-use std::net::SocketAddr;
-use tokio::{io::AsyncWriteExt, net::TcpStream, time::timeout};
+use tokio::{io::AsyncWriteExt, time::timeout};
 
 use crate::probe::service::db;
 use crate::probe::service::payload::{PayloadBuilder, PayloadContext};
@@ -67,7 +66,7 @@ fn parse_banner(bytes: &[u8], max_preview: usize) -> BannerLite {
 
 /// Match response text against known service signatures for tcp:NULL probes.
 /// (service, cpes)
-fn match_null_signatures(
+pub(super) fn match_null_signatures(
     probe_id: &str,
     text: &str,
 ) -> anyhow::Result<Option<(String, Vec<String>)>> {
@@ -102,15 +101,13 @@ impl NullProbe {
         }
 
         tracing::debug!("Null Probe: {}:{} - Connecting", ctx.ip, ctx.probe.port);
-        let addr: SocketAddr = SocketAddr::new(ctx.ip, ctx.probe.port);
-        let mut stream = timeout(ctx.timeout, TcpStream::connect(addr)).await??;
+        let mut stream = ctx.connect().await?;
 
         tracing::debug!("Null Probe: {}:{} - Connected", ctx.ip, ctx.probe.port);
 
         // if payload is present, send it (should not happen for tcp:null, but just in case)
-        let payload = PayloadBuilder::new(ctx.probe.clone())
-            .payload(PayloadContext::default())
-            .unwrap_or_default();
+        let payload =
+            PayloadBuilder::new(ctx.probe.clone()).payload(PayloadContext::for_probe(&ctx))?;
         if !payload.is_empty() {
             timeout(ctx.timeout, stream.write_all(&payload)).await??;
             stream.flush().await?;
@@ -125,7 +122,7 @@ impl NullProbe {
             ctx.probe.port,
             total.as_millis()
         );
-        let bytes = read_timeout(&mut stream, idle, total, ctx.max_read_size).await?;
+        let (bytes, first_byte) = read_timeout(&mut stream, idle, total, ctx.max_read_size).await?;
 
         // Parse banner from response
         let banner = parse_banner(&bytes, 64 * 1024);
@@ -151,7 +148,8 @@ impl NullProbe {
         }
         // Even if name is still unknown, keep the banner
         svc.banner = banner.first_line.clone();
-        svc.raw = Some(banner.raw_text);
+        svc.raw = Some(ctx.raw_value(&bytes, banner.raw_text));
+        svc.response_latency_ms = Some(first_byte.as_millis() as u64);
         let probe_result: PortProbeResult = PortProbeResult {
             ip: ctx.ip,
             hostname: ctx.hostname,
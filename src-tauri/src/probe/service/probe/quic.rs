@@ -35,7 +35,6 @@ pub struct QuicProbe;
 impl QuicProbe {
     pub async fn run(ctx: ProbeContext) -> Result<PortProbeResult> {
         let addr = SocketAddr::new(ctx.ip, ctx.probe.port);
-        let hostname = ctx.hostname.clone().unwrap_or_else(|| ctx.ip.to_string());
 
         // Set ALPN protocols
         let alpn = [
@@ -61,12 +60,8 @@ impl QuicProbe {
         endpoint.set_default_client_config(client_cfg);
 
         // Connect to the server (SNI is hostname or "localhost")
-        let server_name = if ctx.sni {
-            hostname.as_str()
-        } else {
-            "localhost"
-        };
-        let connect_fut = endpoint.connect(addr, server_name)?;
+        let server_name = ctx.server_name();
+        let connect_fut = endpoint.connect(addr, &server_name)?;
         let quinn_conn = tokio::time::timeout(ctx.timeout, connect_fut).await??;
 
         // QUIC connection is established. Get ALPN
@@ -95,6 +90,7 @@ impl QuicProbe {
         tls_info.alpn = alpn_proto.clone();
         // Fixed to TLS 1.3 for QUIC
         tls_info.version = Some("TLSv1_3".into());
+        tls_info.sni = ctx.sni_name();
         if let Some(bytes) = cert_der_bytes.as_deref() {
             if let Ok((_, x509)) = x509_parser::prelude::X509Certificate::from_der(bytes) {
                 tls_info.subject = x509
@@ -122,6 +118,9 @@ impl QuicProbe {
                 tls_info.san_list = sans;
                 tls_info.not_before = Some(x509.validity().not_before.to_string());
                 tls_info.not_after = Some(x509.validity().not_after.to_string());
+                tls_info.not_before_unix = Some(x509.validity().not_before.timestamp());
+                tls_info.not_after_unix = Some(x509.validity().not_after.timestamp());
+                tls_info.self_signed = Some(x509.subject() == x509.issuer());
                 tls_info.serial_hex = Some(x509.raw_serial_as_string());
                 tls_info.sig_algorithm = Some(db::tls::oid_sig_name(
                     x509.signature_algorithm.oid().to_id_string().as_str(),
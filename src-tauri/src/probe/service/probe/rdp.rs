@@ -0,0 +1,229 @@
+use anyhow::{bail, Result};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    time::{timeout, Instant},
+};
+
+use crate::probe::service::db::service::tcp_service_db;
+use crate::probe::service::models::{RdpInfo, ServiceInfo};
+use crate::probe::service::probe::{PortProbeResult, ProbeContext};
+
+/// Standard RDP security, no TLS
+const PROTOCOL_RDP: u32 = 0x0000_0000;
+const PROTOCOL_SSL: u32 = 0x0000_0001;
+/// CredSSP, i.e. Network Level Authentication
+const PROTOCOL_HYBRID: u32 = 0x0000_0002;
+const PROTOCOL_RDSTLS: u32 = 0x0000_0004;
+const PROTOCOL_HYBRID_EX: u32 = 0x0000_0008;
+const PROTOCOL_RDSAAD: u32 = 0x0000_0010;
+
+const TYPE_RDP_NEG_RSP: u8 = 0x02;
+const TYPE_RDP_NEG_FAILURE: u8 = 0x03;
+const X224_CONNECTION_CONFIRM: u8 = 0xd0;
+/// Largest TPKT we read; a connection confirm is 19 bytes
+const MAX_TPKT: usize = 1024;
+
+/// Answer of the server to one connection request
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Negotiation {
+    /// Security protocol the server selected
+    Selected(u32),
+    /// RDP_NEG_FAILURE code
+    Failed(u32),
+}
+
+/// Name of a security protocol selected by the server
+fn protocol_name(protocol: u32) -> String {
+    match protocol {
+        PROTOCOL_RDP => "RDP".to_string(),
+        PROTOCOL_SSL => "TLS".to_string(),
+        PROTOCOL_HYBRID => "CredSSP".to_string(),
+        PROTOCOL_RDSTLS => "RDSTLS".to_string(),
+        PROTOCOL_HYBRID_EX => "CredSSP (early user auth)".to_string(),
+        PROTOCOL_RDSAAD => "RDSAAD".to_string(),
+        other => format!("0x{:08x}", other),
+    }
+}
+
+/// Description of an RDP_NEG_FAILURE code
+fn failure_name(code: u32) -> String {
+    match code {
+        1 => "TLS required by server".to_string(),
+        2 => "TLS not allowed by server".to_string(),
+        3 => "TLS certificate not on server".to_string(),
+        4 => "inconsistent flags".to_string(),
+        5 => "CredSSP required by server".to_string(),
+        6 => "TLS with user authentication required by server".to_string(),
+        other => format!("failure code {}", other),
+    }
+}
+
+/// TPKT framed X.224 connection request carrying an RDP_NEG_REQ
+fn connection_request(protocols: u32) -> Vec<u8> {
+    // Length indicator (set below), connection request code,
+    // destination and source references, class 0
+    let mut x224 = vec![0x00, 0xe0, 0x00, 0x00, 0x00, 0x00, 0x00];
+    // RDP_NEG_REQ
+    x224.extend_from_slice(&[0x01, 0x00, 0x08, 0x00]);
+    x224.extend_from_slice(&protocols.to_le_bytes());
+    x224[0] = (x224.len() - 1) as u8;
+
+    let mut out = vec![0x03, 0x00];
+    out.extend_from_slice(&((x224.len() + 4) as u16).to_be_bytes());
+    out.extend(x224);
+    out
+}
+
+/// Parse the TPKT framed X.224 connection confirm.
+/// A confirm without negotiation data comes from servers predating
+/// RDP 5.2, which only speak standard RDP security.
+fn parse_confirm(tpkt: &[u8]) -> Result<Negotiation> {
+    if tpkt.len() < 11 || tpkt[0] != 0x03 {
+        bail!("not an RDP server (no TPKT response)");
+    }
+    let x224 = &tpkt[4..];
+    if x224[1] & 0xf0 != X224_CONNECTION_CONFIRM {
+        bail!("not an RDP server (no X.224 connection confirm)");
+    }
+    let Some(neg) = x224.get(7..15) else {
+        return Ok(Negotiation::Selected(PROTOCOL_RDP));
+    };
+    let value = u32::from_le_bytes([neg[4], neg[5], neg[6], neg[7]]);
+    match neg[0] {
+        TYPE_RDP_NEG_RSP => Ok(Negotiation::Selected(value)),
+        TYPE_RDP_NEG_FAILURE => Ok(Negotiation::Failed(value)),
+        other => bail!("unexpected RDP negotiation type 0x{:02x}", other),
+    }
+}
+
+/// Send one connection request offering `protocols` on a new connection,
+/// made through the scan's routing. The first one may take over the
+/// connection the port scan left open.
+async fn negotiate(ctx: &ProbeContext, protocols: u32) -> Result<Negotiation> {
+    let exchange = async {
+        let mut stream = ctx.connect().await?;
+        stream.write_all(&connection_request(protocols)).await?;
+        let mut header = [0u8; 4];
+        stream.read_exact(&mut header).await?;
+        let len = u16::from_be_bytes([header[2], header[3]]) as usize;
+        if header[0] != 0x03 || !(4..=MAX_TPKT).contains(&len) {
+            bail!("not an RDP server (no TPKT response)");
+        }
+        let mut tpkt = header.to_vec();
+        tpkt.resize(len, 0);
+        stream.read_exact(&mut tpkt[4..]).await?;
+        parse_confirm(&tpkt)
+    };
+    timeout(ctx.timeout, exchange).await?
+}
+
+/// Whether the server accepts a connection secured only by `protocol`.
+/// `None` when the attempt did not get a negotiation answer.
+async fn accepts(ctx: &ProbeContext, protocol: u32) -> Option<bool> {
+    match negotiate(ctx, protocol).await {
+        Ok(Negotiation::Selected(selected)) => Some(selected == protocol),
+        Ok(Negotiation::Failed(_)) => Some(false),
+        Err(e) => {
+            tracing::debug!("RDP negotiation offering {} failed: {}", protocol, e);
+            None
+        }
+    }
+}
+
+/// Fingerprint the security layer of an RDP server.
+/// Offers every protocol to learn the preferred one, then offers standard
+/// RDP security and TLS alone: if neither is accepted, NLA is required.
+pub struct RdpProbe;
+
+impl RdpProbe {
+    pub async fn run(ctx: ProbeContext) -> Result<PortProbeResult> {
+        tracing::debug!("RDP Probe: {}:{}", ctx.ip, ctx.probe.port);
+        let started = Instant::now();
+        let preferred =
+            negotiate(&ctx, PROTOCOL_SSL | PROTOCOL_HYBRID | PROTOCOL_HYBRID_EX).await?;
+        let latency = started.elapsed();
+
+        let mut info = RdpInfo::default();
+        match preferred {
+            Negotiation::Selected(protocol) => {
+                info.security_protocol = Some(protocol_name(protocol))
+            }
+            Negotiation::Failed(code) => info.negotiation_failure = Some(failure_name(code)),
+        }
+        info.rdp_security_allowed = accepts(&ctx, PROTOCOL_RDP).await;
+        info.tls_allowed = accepts(&ctx, PROTOCOL_SSL).await;
+        info.nla_required = match (info.rdp_security_allowed, info.tls_allowed) {
+            (Some(false), Some(false)) => Some(true),
+            (Some(true), _) | (_, Some(true)) => Some(false),
+            _ => None,
+        };
+
+        let banner = match (&info.security_protocol, info.nla_required) {
+            (Some(protocol), Some(true)) => format!("RDP {}, NLA required", protocol),
+            (Some(protocol), Some(false)) => format!("RDP {}, NLA not required", protocol),
+            (Some(protocol), None) => format!("RDP {}", protocol),
+            (None, _) => format!(
+                "RDP negotiation failed: {}",
+                info.negotiation_failure.as_deref().unwrap_or("-")
+            ),
+        };
+        let svc = ServiceInfo {
+            name: tcp_service_db()
+                .get_name(ctx.probe.port)
+                .map(|s| s.to_string()),
+            banner: Some(banner.clone()),
+            raw: Some(banner),
+            response_latency_ms: Some(latency.as_millis() as u64),
+            rdp_info: Some(info),
+            ..Default::default()
+        };
+        Ok(PortProbeResult {
+            ip: ctx.ip,
+            hostname: ctx.hostname,
+            port: ctx.probe.port,
+            transport: ctx.probe.transport,
+            probe_id: ctx.probe.probe_id,
+            service_info: svc,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builds_connection_request() {
+        let req = connection_request(PROTOCOL_SSL | PROTOCOL_HYBRID);
+        assert_eq!(
+            req,
+            [
+                0x03, 0x00, 0x00, 0x13, 0x0e, 0xe0, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x08,
+                0x00, 0x03, 0x00, 0x00, 0x00
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_connection_confirm() {
+        let confirm = |neg: &[u8]| {
+            let mut tpkt = vec![0x03, 0x00, 0x00, 0x00, 0x0e, 0xd0, 0, 0, 0x12, 0x34, 0x00];
+            tpkt.extend_from_slice(neg);
+            tpkt
+        };
+        assert_eq!(
+            parse_confirm(&confirm(&[0x02, 0x1f, 0x08, 0x00, 0x02, 0, 0, 0])).unwrap(),
+            Negotiation::Selected(PROTOCOL_HYBRID)
+        );
+        assert_eq!(
+            parse_confirm(&confirm(&[0x03, 0x00, 0x08, 0x00, 0x05, 0, 0, 0])).unwrap(),
+            Negotiation::Failed(5)
+        );
+        // Pre RDP 5.2 servers confirm without negotiation data
+        assert_eq!(
+            parse_confirm(&confirm(&[])).unwrap(),
+            Negotiation::Selected(PROTOCOL_RDP)
+        );
+        assert!(parse_confirm(b"HTTP/1.1 400").is_err());
+    }
+}
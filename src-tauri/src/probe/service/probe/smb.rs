@@ -0,0 +1,537 @@
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::time::Duration;
+
+use anyhow::{bail, Result};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpStream, UdpSocket},
+    time::{timeout, timeout_at, Instant},
+};
+
+use crate::probe::service::db::service::tcp_service_db;
+use crate::probe::service::models::{ServiceInfo, SmbInfo};
+use crate::probe::service::probe::{PortProbeResult, ProbeContext};
+
+/// NetBIOS name service port
+const NBNS_PORT: u16 = 137;
+/// Longest wait for the node status answer, within the probe timeout
+const NBNS_WAIT: Duration = Duration::from_secs(2);
+/// NetBIOS session service port, which needs a session request before SMB
+const NETBIOS_SESSION_PORT: u16 = 139;
+/// Upper bound of a session service message we accept
+const MAX_SMB_MESSAGE: usize = 64 * 1024;
+
+const SMB2_NEGOTIATE: u16 = 0x0000;
+const SMB2_SESSION_SETUP: u16 = 0x0001;
+/// Dialects offered in the negotiate. 3.1.1 is left out: it requires
+/// negotiate contexts, and 3.0.2 is enough to learn the signing policy.
+const SMB2_DIALECTS: [u16; 4] = [0x0202, 0x0210, 0x0300, 0x0302];
+const SMB2_SIGNING_ENABLED: u16 = 0x0001;
+const SMB2_SIGNING_REQUIRED: u16 = 0x0002;
+const STATUS_MORE_PROCESSING_REQUIRED: u32 = 0xC000_0016;
+
+const NTLMSSP_SIGNATURE: &[u8; 8] = b"NTLMSSP\0";
+/// UNICODE | REQUEST_TARGET | NTLM | ALWAYS_SIGN | EXTENDED_SESSIONSECURITY |
+/// TARGET_INFO | VERSION | 128 | KEY_EXCH | 56
+const NTLMSSP_NEGOTIATE_FLAGS: u32 = 0xE288_8205;
+const NTLMSSP_NEGOTIATE_VERSION: u32 = 0x0200_0000;
+
+/// DER element with a short or long form length
+fn der(tag: u8, content: &[u8]) -> Vec<u8> {
+    let mut out = vec![tag];
+    let len = content.len();
+    if len < 0x80 {
+        out.push(len as u8);
+    } else {
+        let bytes: Vec<u8> = len
+            .to_be_bytes()
+            .into_iter()
+            .skip_while(|b| *b == 0)
+            .collect();
+        out.push(0x80 | bytes.len() as u8);
+        out.extend_from_slice(&bytes);
+    }
+    out.extend_from_slice(content);
+    out
+}
+
+/// NTLMSSP NEGOTIATE message asking for the target info and OS version
+fn ntlmssp_negotiate() -> Vec<u8> {
+    let mut msg = Vec::with_capacity(40);
+    msg.extend_from_slice(NTLMSSP_SIGNATURE);
+    msg.extend_from_slice(&1u32.to_le_bytes());
+    msg.extend_from_slice(&NTLMSSP_NEGOTIATE_FLAGS.to_le_bytes());
+    // Empty domain and workstation fields, then an empty version
+    msg.extend_from_slice(&[0u8; 24]);
+    msg
+}
+
+/// SPNEGO NegTokenInit offering NTLMSSP with `token` as its first token
+fn spnego_init(token: &[u8]) -> Vec<u8> {
+    // 1.3.6.1.4.1.311.2.2.10
+    let ntlmssp_oid = der(
+        0x06,
+        &[0x2b, 0x06, 0x01, 0x04, 0x01, 0x82, 0x37, 0x02, 0x02, 0x0a],
+    );
+    let mech_types = der(0xa0, &der(0x30, &ntlmssp_oid));
+    let mech_token = der(0xa2, &der(0x04, token));
+    let neg_token_init = der(0xa0, &der(0x30, &[mech_types, mech_token].concat()));
+    // 1.3.6.1.5.5.2
+    let spnego_oid = der(0x06, &[0x2b, 0x06, 0x01, 0x05, 0x05, 0x02]);
+    der(0x60, &[spnego_oid, neg_token_init].concat())
+}
+
+/// SMB2 header of a request
+fn smb2_header(command: u16, message_id: u64) -> Vec<u8> {
+    let mut h = Vec::with_capacity(64);
+    h.extend_from_slice(b"\xfeSMB");
+    h.extend_from_slice(&64u16.to_le_bytes());
+    // Credit charge, status
+    h.extend_from_slice(&[0u8; 6]);
+    h.extend_from_slice(&command.to_le_bytes());
+    // Credits requested
+    h.extend_from_slice(&1u16.to_le_bytes());
+    // Flags, next command
+    h.extend_from_slice(&[0u8; 8]);
+    h.extend_from_slice(&message_id.to_le_bytes());
+    // Process id, tree id, session id, signature
+    h.extend_from_slice(&[0u8; 36]);
+    h
+}
+
+fn smb2_negotiate_request() -> Vec<u8> {
+    let mut msg = smb2_header(SMB2_NEGOTIATE, 0);
+    msg.extend_from_slice(&36u16.to_le_bytes());
+    msg.extend_from_slice(&(SMB2_DIALECTS.len() as u16).to_le_bytes());
+    msg.extend_from_slice(&SMB2_SIGNING_ENABLED.to_le_bytes());
+    // Reserved, capabilities
+    msg.extend_from_slice(&[0u8; 6]);
+    let guid: [u8; 16] = std::array::from_fn(|_| fastrand::u8(..));
+    msg.extend_from_slice(&guid);
+    // Client start time
+    msg.extend_from_slice(&[0u8; 8]);
+    for dialect in SMB2_DIALECTS {
+        msg.extend_from_slice(&dialect.to_le_bytes());
+    }
+    msg
+}
+
+fn smb2_session_setup_request(security_blob: &[u8]) -> Vec<u8> {
+    let mut msg = smb2_header(SMB2_SESSION_SETUP, 1);
+    msg.extend_from_slice(&25u16.to_le_bytes());
+    // Flags, security mode
+    msg.push(0);
+    msg.push(SMB2_SIGNING_ENABLED as u8);
+    // Capabilities, channel
+    msg.extend_from_slice(&[0u8; 8]);
+    // The blob follows the 64 byte header and 24 byte fixed body
+    msg.extend_from_slice(&88u16.to_le_bytes());
+    msg.extend_from_slice(&(security_blob.len() as u16).to_le_bytes());
+    // Previous session id
+    msg.extend_from_slice(&[0u8; 8]);
+    msg.extend_from_slice(security_blob);
+    msg
+}
+
+/// First level encoding of a NetBIOS name (RFC 1001 14.1), length prefixed.
+/// `name` is padded with spaces to 15 characters, `*` with NULs.
+fn netbios_name(name: &str, suffix: u8) -> Vec<u8> {
+    let pad = if name == "*" { 0 } else { b' ' };
+    let mut raw = [pad; 16];
+    for (dst, src) in raw
+        .iter_mut()
+        .zip(name.to_ascii_uppercase().bytes().take(15))
+    {
+        *dst = src;
+    }
+    raw[15] = suffix;
+    let mut out = Vec::with_capacity(34);
+    out.push(32);
+    for b in raw {
+        out.push(b'A' + (b >> 4));
+        out.push(b'A' + (b & 0x0f));
+    }
+    out.push(0);
+    out
+}
+
+/// Names registered by a host, from a NetBIOS node status response
+#[derive(Debug, Default, PartialEq, Eq)]
+struct NodeStatus {
+    /// Unique `<00>` name
+    computer_name: Option<String>,
+    /// Group `<00>` name
+    workgroup: Option<String>,
+}
+
+fn node_status_request() -> Vec<u8> {
+    let mut msg = Vec::with_capacity(50);
+    msg.extend_from_slice(&fastrand::u16(..).to_be_bytes());
+    // Flags, one question, no records
+    msg.extend_from_slice(&[0, 0, 0, 1, 0, 0, 0, 0, 0, 0]);
+    msg.extend(netbios_name("*", 0));
+    // NBSTAT, IN
+    msg.extend_from_slice(&[0x00, 0x21, 0x00, 0x01]);
+    msg
+}
+
+fn parse_node_status(buf: &[u8]) -> Option<NodeStatus> {
+    // Header, then the answer name: labels or a compression pointer
+    let mut pos = 12;
+    loop {
+        let len = *buf.get(pos)? as usize;
+        if len & 0xc0 == 0xc0 {
+            pos += 2;
+            break;
+        }
+        pos += 1 + len;
+        if len == 0 {
+            break;
+        }
+    }
+    // Type, class, TTL, data length
+    pos += 10;
+    let count = *buf.get(pos)? as usize;
+    pos += 1;
+    let mut status = NodeStatus::default();
+    for entry in buf.get(pos..)?.chunks_exact(18).take(count) {
+        let name = String::from_utf8_lossy(&entry[..15]).trim_end().to_string();
+        let group = entry[16] & 0x80 != 0;
+        if entry[15] != 0x00 || name.is_empty() {
+            continue;
+        }
+        let slot = if group {
+            &mut status.workgroup
+        } else {
+            &mut status.computer_name
+        };
+        slot.get_or_insert(name);
+    }
+    Some(status)
+}
+
+/// Ask the NetBIOS name service of `ip` for its registered names.
+async fn query_node_status(ip: IpAddr, wait: Duration) -> Result<NodeStatus> {
+    let IpAddr::V4(_) = ip else {
+        bail!("NetBIOS name service is IPv4 only");
+    };
+    let sock = UdpSocket::bind(SocketAddr::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), 0)).await?;
+    sock.connect(SocketAddr::new(ip, NBNS_PORT)).await?;
+    sock.send(&node_status_request()).await?;
+    let mut buf = vec![0u8; 1024];
+    let n = timeout(wait, sock.recv(&mut buf)).await??;
+    parse_node_status(&buf[..n]).ok_or_else(|| anyhow::anyhow!("malformed node status response"))
+}
+
+/// Send one message framed for the session service
+async fn send_message(stream: &mut TcpStream, kind: u8, msg: &[u8]) -> Result<()> {
+    let len = msg.len() as u32;
+    let mut framed = Vec::with_capacity(msg.len() + 4);
+    framed.push(kind);
+    framed.extend_from_slice(&len.to_be_bytes()[1..]);
+    framed.extend_from_slice(msg);
+    stream.write_all(&framed).await?;
+    Ok(())
+}
+
+/// Read one session service message: (type, payload)
+async fn recv_message(stream: &mut TcpStream) -> Result<(u8, Vec<u8>)> {
+    let mut header = [0u8; 4];
+    stream.read_exact(&mut header).await?;
+    let len = u32::from_be_bytes([0, header[1], header[2], header[3]]) as usize;
+    if len > MAX_SMB_MESSAGE {
+        bail!("SMB message too large: {} bytes", len);
+    }
+    let mut payload = vec![0u8; len];
+    stream.read_exact(&mut payload).await?;
+    Ok((header[0], payload))
+}
+
+/// Open a NetBIOS session on port 139, called by the computer name when known
+async fn netbios_session_request(stream: &mut TcpStream, called: Option<&str>) -> Result<()> {
+    let mut msg = netbios_name(called.unwrap_or("*SMBSERVER"), 0x20);
+    msg.extend(netbios_name("NETPULSE", 0x00));
+    send_message(stream, 0x81, &msg).await?;
+    match recv_message(stream).await?.0 {
+        0x82 => Ok(()),
+        kind => bail!("NetBIOS session refused (response type 0x{:02x})", kind),
+    }
+}
+
+/// SMB2 response: (status, body after the header)
+fn smb2_response(msg: &[u8], command: u16) -> Result<(u32, &[u8])> {
+    if msg.len() < 64 || &msg[..4] != b"\xfeSMB" {
+        bail!("not an SMB2 response");
+    }
+    if u16::from_le_bytes([msg[12], msg[13]]) != command {
+        bail!("unexpected SMB2 command in response");
+    }
+    let status = u32::from_le_bytes([msg[8], msg[9], msg[10], msg[11]]);
+    Ok((status, &msg[64..]))
+}
+
+fn dialect_name(dialect: u16) -> String {
+    match dialect {
+        0x0202 => "2.0.2".to_string(),
+        0x0210 => "2.1".to_string(),
+        0x0300 => "3.0".to_string(),
+        0x0302 => "3.0.2".to_string(),
+        0x0311 => "3.1.1".to_string(),
+        other => format!("0x{:04x}", other),
+    }
+}
+
+fn utf16le(bytes: &[u8]) -> String {
+    let units: Vec<u16> = bytes
+        .chunks_exact(2)
+        .map(|c| u16::from_le_bytes([c[0], c[1]]))
+        .collect();
+    String::from_utf16_lossy(&units)
+}
+
+/// Fill names and OS version from an NTLMSSP CHALLENGE found in `blob`
+fn parse_ntlm_challenge(blob: &[u8], info: &mut SmbInfo) -> Option<()> {
+    let start = blob
+        .windows(NTLMSSP_SIGNATURE.len())
+        .position(|w| w == NTLMSSP_SIGNATURE)?;
+    let msg = &blob[start..];
+    let u16_at = |i: usize| Some(u16::from_le_bytes([*msg.get(i)?, *msg.get(i + 1)?]));
+    let u32_at = |i: usize| Some(u32::from_le_bytes(msg.get(i..i + 4)?.try_into().ok()?));
+    if u32_at(8)? != 2 {
+        return None;
+    }
+    let flags = u32_at(20)?;
+    if flags & NTLMSSP_NEGOTIATE_VERSION != 0 {
+        let version = msg.get(48..52)?;
+        let build = u16::from_le_bytes([version[2], version[3]]);
+        info.os_version = Some(format!("{}.{}.{}", version[0], version[1], build));
+    }
+    let len = u16_at(40)? as usize;
+    let offset = u32_at(44)? as usize;
+    let mut pairs = msg.get(offset..offset.checked_add(len)?)?;
+    while pairs.len() >= 4 {
+        let id = u16::from_le_bytes([pairs[0], pairs[1]]);
+        let len = u16::from_le_bytes([pairs[2], pairs[3]]) as usize;
+        let value = pairs.get(4..4 + len)?;
+        let slot = match id {
+            // MsvAvEOL
+            0 => break,
+            1 => &mut info.computer_name,
+            2 => &mut info.domain,
+            3 => &mut info.dns_computer_name,
+            4 => &mut info.dns_domain,
+            _ => {
+                pairs = &pairs[4 + len..];
+                continue;
+            }
+        };
+        let name = utf16le(value);
+        if !name.is_empty() {
+            *slot = Some(name);
+        }
+        pairs = &pairs[4 + len..];
+    }
+    Some(())
+}
+
+/// SMB2 negotiate and an anonymous NTLMSSP session setup round trip.
+/// The session is never authenticated: the server's CHALLENGE already
+/// carries its names and OS version.
+async fn smb2_exchange(stream: &mut TcpStream, info: &mut SmbInfo) -> Result<()> {
+    send_message(stream, 0x00, &smb2_negotiate_request()).await?;
+    let (_, msg) = recv_message(stream).await?;
+    let (status, body) = smb2_response(&msg, SMB2_NEGOTIATE)?;
+    if status != 0 || body.len() < 8 {
+        bail!("SMB2 negotiate failed (status 0x{:08x})", status);
+    }
+    let security_mode = u16::from_le_bytes([body[2], body[3]]);
+    info.signing_required = Some(security_mode & SMB2_SIGNING_REQUIRED != 0);
+    info.dialect = Some(dialect_name(u16::from_le_bytes([body[4], body[5]])));
+
+    send_message(
+        stream,
+        0x00,
+        &smb2_session_setup_request(&spnego_init(&ntlmssp_negotiate())),
+    )
+    .await?;
+    let (_, msg) = recv_message(stream).await?;
+    let (status, body) = smb2_response(&msg, SMB2_SESSION_SETUP)?;
+    if status != STATUS_MORE_PROCESSING_REQUIRED {
+        bail!("SMB2 session setup failed (status 0x{:08x})", status);
+    }
+    if parse_ntlm_challenge(body, info).is_none() {
+        tracing::debug!("SMB2 session setup response without an NTLMSSP challenge");
+    }
+    Ok(())
+}
+
+/// Short summary used as banner: `SMB 3.0.2, HOST (WORKGROUP), OS 10.0.19041, signing required`
+fn summarize(info: &SmbInfo) -> String {
+    let mut parts = Vec::new();
+    if let Some(dialect) = &info.dialect {
+        parts.push(format!("SMB {}", dialect));
+    }
+    match (&info.computer_name, &info.domain) {
+        (Some(name), Some(domain)) => parts.push(format!("{} ({})", name, domain)),
+        (Some(name), None) => parts.push(name.clone()),
+        (None, Some(domain)) => parts.push(format!("({})", domain)),
+        (None, None) => {}
+    }
+    if let Some(version) = &info.os_version {
+        parts.push(format!("OS {}", version));
+    }
+    match info.signing_required {
+        Some(true) => parts.push("signing required".to_string()),
+        Some(false) => parts.push("signing not required".to_string()),
+        None => {}
+    }
+    parts.join(", ")
+}
+
+/// Identify a Windows/Samba host on 139/445 through a NetBIOS node status
+/// query and an SMB2 negotiate.
+pub struct SmbProbe;
+
+impl SmbProbe {
+    pub async fn run(ctx: ProbeContext) -> Result<PortProbeResult> {
+        tracing::debug!("SMB Probe: {}:{}", ctx.ip, ctx.probe.port);
+        // Node status, connect and exchange all share the probe timeout
+        let deadline = Instant::now() + ctx.timeout;
+        let mut info = SmbInfo::default();
+        match timeout_at(deadline, query_node_status(ctx.ip, NBNS_WAIT)).await {
+            Ok(Ok(status)) => {
+                info.netbios_name = status.computer_name;
+                info.workgroup = status.workgroup;
+            }
+            Ok(Err(e)) => tracing::debug!("NetBIOS node status of {} failed: {}", ctx.ip, e),
+            Err(_) => tracing::debug!("NetBIOS node status of {} timed out", ctx.ip),
+        }
+
+        let Ok(connected) = timeout_at(deadline, ctx.connect()).await else {
+            bail!("SMB connect timed out");
+        };
+        let mut stream = connected?;
+        let started = Instant::now();
+        let exchange = async {
+            if ctx.probe.port == NETBIOS_SESSION_PORT {
+                netbios_session_request(&mut stream, info.netbios_name.clone().as_deref()).await?;
+            }
+            smb2_exchange(&mut stream, &mut info).await
+        };
+        let negotiated = timeout_at(deadline, exchange).await;
+        let latency = started.elapsed();
+        match negotiated {
+            Ok(Ok(())) => {}
+            Ok(Err(e)) if info.dialect.is_some() => {
+                tracing::debug!("SMB Probe: {}:{} - {}", ctx.ip, ctx.probe.port, e)
+            }
+            Ok(Err(e)) => return Err(e),
+            Err(_) if info.dialect.is_some() => {}
+            Err(_) => bail!("SMB negotiate timed out"),
+        }
+        // NetBIOS names fill in for servers that hide them in the challenge
+        if info.computer_name.is_none() {
+            info.computer_name = info.netbios_name.clone();
+        }
+        if info.domain.is_none() {
+            info.domain = info.workgroup.clone();
+        }
+
+        let summary = summarize(&info);
+        let svc = ServiceInfo {
+            name: tcp_service_db()
+                .get_name(ctx.probe.port)
+                .map(|s| s.to_string()),
+            banner: Some(summary.clone()),
+            raw: Some(summary),
+            response_latency_ms: Some(latency.as_millis() as u64),
+            smb_info: Some(info),
+            ..Default::default()
+        };
+        Ok(PortProbeResult {
+            ip: ctx.ip,
+            hostname: ctx.hostname,
+            port: ctx.probe.port,
+            transport: ctx.probe.transport,
+            probe_id: ctx.probe.probe_id,
+            service_info: svc,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utf16(s: &str) -> Vec<u8> {
+        s.encode_utf16().flat_map(|u| u.to_le_bytes()).collect()
+    }
+
+    #[test]
+    fn encodes_netbios_names() {
+        let name = netbios_name("*", 0);
+        assert_eq!(name.len(), 34);
+        assert_eq!(&name[1..3], b"CK");
+        assert_eq!(&name[3..33], "AA".repeat(15).as_bytes());
+        assert_eq!(&netbios_name("host", 0x20)[1..9], b"EIEPFDFE");
+    }
+
+    #[test]
+    fn parses_node_status_names() {
+        let mut buf = vec![0u8; 12];
+        buf.extend(netbios_name("*", 0));
+        buf.extend_from_slice(&[0, 0x21, 0, 1, 0, 0, 0, 0, 0, 0]);
+        buf.push(3);
+        for (name, suffix, flags) in [
+            ("FILESRV", 0x20u8, 0u8),
+            ("FILESRV", 0, 0),
+            ("CORP", 0, 0x80),
+        ] {
+            let mut entry = [b' '; 18];
+            entry[..name.len()].copy_from_slice(name.as_bytes());
+            entry[15] = suffix;
+            entry[16] = flags;
+            entry[17] = 0;
+            buf.extend_from_slice(&entry);
+        }
+        assert_eq!(
+            parse_node_status(&buf),
+            Some(NodeStatus {
+                computer_name: Some("FILESRV".into()),
+                workgroup: Some("CORP".into()),
+            })
+        );
+    }
+
+    #[test]
+    fn parses_ntlm_challenge_target_info() {
+        let mut target_info = Vec::new();
+        for (id, value) in [(1u16, "FILESRV"), (2, "CORP"), (4, "corp.example")] {
+            let value = utf16(value);
+            target_info.extend_from_slice(&id.to_le_bytes());
+            target_info.extend_from_slice(&(value.len() as u16).to_le_bytes());
+            target_info.extend(value);
+        }
+        target_info.extend_from_slice(&[0; 4]);
+
+        let mut msg = NTLMSSP_SIGNATURE.to_vec();
+        msg.extend_from_slice(&2u32.to_le_bytes());
+        msg.extend_from_slice(&[0; 8]);
+        msg.extend_from_slice(&NTLMSSP_NEGOTIATE_VERSION.to_le_bytes());
+        msg.extend_from_slice(&[0; 16]);
+        msg.extend_from_slice(&(target_info.len() as u16).to_le_bytes());
+        msg.extend_from_slice(&(target_info.len() as u16).to_le_bytes());
+        msg.extend_from_slice(&56u32.to_le_bytes());
+        msg.extend_from_slice(&[10, 0, 0x61, 0x4a, 0, 0, 0, 15]);
+        msg.extend(target_info);
+        // Wrapped in a SPNEGO response the parser does not need to understand
+        let blob = [vec![0xa1, 0x81, 0x00], msg].concat();
+
+        let mut info = SmbInfo::default();
+        assert!(parse_ntlm_challenge(&blob, &mut info).is_some());
+        assert_eq!(info.computer_name.as_deref(), Some("FILESRV"));
+        assert_eq!(info.domain.as_deref(), Some("CORP"));
+        assert_eq!(info.dns_domain.as_deref(), Some("corp.example"));
+        assert_eq!(info.os_version.as_deref(), Some("10.0.19041"));
+    }
+}
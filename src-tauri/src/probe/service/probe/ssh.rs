@@ -0,0 +1,381 @@
+use anyhow::{bail, Result};
+use base64::Engine;
+use sha2::{Digest, Sha256};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpStream,
+    time::{timeout, Instant},
+};
+
+use crate::probe::service::db;
+use crate::probe::service::db::service::tcp_service_db;
+use crate::probe::service::models::{ServiceInfo, SshInfo};
+use crate::probe::service::probe::{PortProbeResult, ProbeContext};
+use crate::probe::service::{build_regex, expand_cpe_templates};
+
+const CLIENT_BANNER: &[u8] = b"SSH-2.0-netpulse\r\n";
+/// Lines a server may send before its identification string (RFC 4253 4.2)
+const MAX_PRE_BANNER_LINES: usize = 16;
+const MAX_BANNER_LEN: usize = 255;
+/// Largest packet an implementation must accept (RFC 4253 6.1)
+const MAX_PACKET_LEN: usize = 35000;
+
+const SSH_MSG_KEXINIT: u8 = 20;
+const SSH_MSG_KEX_ECDH_INIT: u8 = 30;
+const SSH_MSG_KEX_ECDH_REPLY: u8 = 31;
+
+/// Key exchanges we can start without any crypto: a random 32 byte string
+/// is a valid X25519 public key, and the exchange is dropped before keys
+/// are derived.
+const CLIENT_KEX: &str = "curve25519-sha256,curve25519-sha256@libssh.org";
+const CLIENT_HOST_KEYS: &str = "ssh-ed25519,ecdsa-sha2-nistp256,ecdsa-sha2-nistp384,\
+ecdsa-sha2-nistp521,rsa-sha2-512,rsa-sha2-256,ssh-rsa,ssh-dss";
+const CLIENT_CIPHERS: &str = "chacha20-poly1305@openssh.com,aes128-gcm@openssh.com,\
+aes256-gcm@openssh.com,aes128-ctr,aes256-ctr";
+const CLIENT_MACS: &str = "hmac-sha2-256-etm@openssh.com,hmac-sha2-256,hmac-sha2-512,hmac-sha1";
+
+/// Algorithms considered weak: SHA-1 or small DH groups, DSA, CBC mode,
+/// RC4, and MD5 or truncated MACs
+fn is_weak_algorithm(name: &str) -> bool {
+    matches!(
+        name,
+        "diffie-hellman-group1-sha1"
+            | "diffie-hellman-group14-sha1"
+            | "diffie-hellman-group-exchange-sha1"
+            | "gss-group1-sha1-toWM5Slw5Ew8Mqkay+al2g=="
+            | "ssh-dss"
+            | "ssh-rsa"
+            | "hmac-sha1-96"
+            | "none"
+    ) || name.ends_with("-cbc")
+        || name.starts_with("arcfour")
+        || name.starts_with("hmac-md5")
+}
+
+/// Algorithm name-lists of a KEXINIT message
+#[derive(Debug, Default, PartialEq, Eq)]
+struct KexInit {
+    kex: Vec<String>,
+    host_keys: Vec<String>,
+    ciphers: Vec<String>,
+    macs: Vec<String>,
+    compression: Vec<String>,
+}
+
+/// Reader of SSH wire encoded fields (RFC 4251 5)
+struct Fields<'a>(&'a [u8]);
+
+impl<'a> Fields<'a> {
+    fn bytes(&mut self, n: usize) -> Option<&'a [u8]> {
+        let (head, rest) = (self.0.get(..n)?, self.0.get(n..)?);
+        self.0 = rest;
+        Some(head)
+    }
+
+    fn string(&mut self) -> Option<&'a [u8]> {
+        let len = u32::from_be_bytes(self.bytes(4)?.try_into().ok()?) as usize;
+        self.bytes(len)
+    }
+
+    fn name_list(&mut self) -> Option<Vec<String>> {
+        let list = String::from_utf8_lossy(self.string()?).to_string();
+        Some(
+            list.split(',')
+                .filter(|s| !s.is_empty())
+                .map(str::to_string)
+                .collect(),
+        )
+    }
+}
+
+fn parse_kexinit(payload: &[u8]) -> Option<KexInit> {
+    let mut f = Fields(payload);
+    if f.bytes(1)? != [SSH_MSG_KEXINIT] {
+        return None;
+    }
+    // Cookie
+    f.bytes(16)?;
+    let kex = f.name_list()?;
+    let host_keys = f.name_list()?;
+    let ciphers = f.name_list()?;
+    let ciphers_s2c = f.name_list()?;
+    let macs = f.name_list()?;
+    let macs_s2c = f.name_list()?;
+    let compression = f.name_list()?;
+    // Both directions are listed once, client to server first
+    let merge = |mut a: Vec<String>, b: Vec<String>| {
+        for name in b {
+            if !a.contains(&name) {
+                a.push(name);
+            }
+        }
+        a
+    };
+    Some(KexInit {
+        kex,
+        host_keys,
+        ciphers: merge(ciphers, ciphers_s2c),
+        macs: merge(macs, macs_s2c),
+        compression,
+    })
+}
+
+fn put_string(buf: &mut Vec<u8>, s: &[u8]) {
+    buf.extend_from_slice(&(s.len() as u32).to_be_bytes());
+    buf.extend_from_slice(s);
+}
+
+fn client_kexinit() -> Vec<u8> {
+    let mut payload = vec![SSH_MSG_KEXINIT];
+    let cookie: [u8; 16] = std::array::from_fn(|_| fastrand::u8(..));
+    payload.extend_from_slice(&cookie);
+    for list in [
+        CLIENT_KEX,
+        CLIENT_HOST_KEYS,
+        CLIENT_CIPHERS,
+        CLIENT_CIPHERS,
+        CLIENT_MACS,
+        CLIENT_MACS,
+        "none",
+        "none",
+        "",
+        "",
+    ] {
+        put_string(&mut payload, list.as_bytes());
+    }
+    // first_kex_packet_follows, reserved
+    payload.extend_from_slice(&[0; 5]);
+    payload
+}
+
+/// Unencrypted binary packet (RFC 4253 6) around `payload`
+fn packet(payload: &[u8]) -> Vec<u8> {
+    let mut padding = 8 - (5 + payload.len()) % 8;
+    if padding < 4 {
+        padding += 8;
+    }
+    let mut out = Vec::with_capacity(5 + payload.len() + padding);
+    out.extend_from_slice(&((1 + payload.len() + padding) as u32).to_be_bytes());
+    out.push(padding as u8);
+    out.extend_from_slice(payload);
+    out.extend(std::iter::repeat_n(0u8, padding));
+    out
+}
+
+async fn read_packet(stream: &mut TcpStream) -> Result<Vec<u8>> {
+    let mut len = [0u8; 4];
+    stream.read_exact(&mut len).await?;
+    let len = u32::from_be_bytes(len) as usize;
+    if !(5..=MAX_PACKET_LEN).contains(&len) {
+        bail!("invalid SSH packet length {}", len);
+    }
+    let mut body = vec![0u8; len];
+    stream.read_exact(&mut body).await?;
+    let padding = body[0] as usize;
+    if padding + 1 > len {
+        bail!("invalid SSH padding length {}", padding);
+    }
+    body.truncate(len - padding);
+    body.remove(0);
+    Ok(body)
+}
+
+/// Read the server identification string, skipping any lines before it.
+async fn read_banner(stream: &mut TcpStream) -> Result<String> {
+    for _ in 0..MAX_PRE_BANNER_LINES {
+        let mut line = Vec::new();
+        loop {
+            let b = stream.read_u8().await?;
+            if b == b'\n' {
+                break;
+            }
+            if line.len() >= MAX_BANNER_LEN {
+                bail!("SSH identification line too long");
+            }
+            line.push(b);
+        }
+        let line = String::from_utf8_lossy(&line)
+            .trim_end_matches('\r')
+            .to_string();
+        if line.starts_with("SSH-") {
+            return Ok(line);
+        }
+    }
+    bail!("no SSH identification string")
+}
+
+/// `SHA256:<base64>` as printed by OpenSSH
+fn fingerprint(host_key: &[u8]) -> String {
+    let digest = Sha256::digest(host_key);
+    format!(
+        "SHA256:{}",
+        base64::engine::general_purpose::STANDARD_NO_PAD.encode(digest)
+    )
+}
+
+/// Run an ECDH key exchange up to the server's reply, which carries its
+/// host key: (key type, fingerprint)
+async fn fetch_host_key(stream: &mut TcpStream) -> Result<(String, String)> {
+    stream.write_all(&packet(&client_kexinit())).await?;
+    let mut init = vec![SSH_MSG_KEX_ECDH_INIT];
+    let ephemeral: [u8; 32] = std::array::from_fn(|_| fastrand::u8(..));
+    put_string(&mut init, &ephemeral);
+    stream.write_all(&packet(&init)).await?;
+
+    let reply = read_packet(stream).await?;
+    let mut f = Fields(&reply);
+    if f.bytes(1) != Some(&[SSH_MSG_KEX_ECDH_REPLY][..]) {
+        bail!(
+            "unexpected SSH message {:?} instead of KEX_ECDH_REPLY",
+            reply.first()
+        );
+    }
+    let host_key = f
+        .string()
+        .ok_or_else(|| anyhow::anyhow!("truncated KEX_ECDH_REPLY"))?;
+    let key_type = Fields(host_key)
+        .string()
+        .map(|t| String::from_utf8_lossy(t).to_string())
+        .unwrap_or_default();
+    Ok((key_type, fingerprint(host_key)))
+}
+
+/// Match the identification string against the banner signatures.
+/// (service, cpes)
+fn match_banner_signatures(text: &str) -> Result<Option<(String, Vec<String>)>> {
+    let sigdb = db::service::response_signatures_db();
+    for sig in sigdb {
+        if !sig.probe_id.eq_ignore_ascii_case("tcp:null") {
+            continue;
+        }
+        let re = match build_regex(&sig.regex, "") {
+            Ok(r) => r,
+            Err(_) => build_regex(&sig.regex, "i")?,
+        };
+        if let Some(caps) = re.captures(text) {
+            let cpes = expand_cpe_templates(&sig.cpe, &caps);
+            return Ok(Some((sig.service.clone(), cpes)));
+        }
+    }
+    Ok(None)
+}
+
+/// Read the SSH banner and KEXINIT of a server, then start a key exchange
+/// to learn its host key fingerprint. No authentication is attempted.
+pub struct SshProbe;
+
+impl SshProbe {
+    pub async fn run(ctx: ProbeContext) -> Result<PortProbeResult> {
+        tracing::debug!("SSH Probe: {}:{}", ctx.ip, ctx.probe.port);
+        let mut stream = ctx.connect().await?;
+
+        let started = Instant::now();
+        let banner = timeout(ctx.timeout, read_banner(&mut stream)).await??;
+        let latency = started.elapsed();
+        stream.write_all(CLIENT_BANNER).await?;
+        let server_kexinit = timeout(ctx.timeout, read_packet(&mut stream)).await??;
+        let kexinit = parse_kexinit(&server_kexinit)
+            .ok_or_else(|| anyhow::anyhow!("malformed SSH KEXINIT"))?;
+
+        let mut info = SshInfo::default();
+        if kexinit
+            .kex
+            .iter()
+            .any(|k| CLIENT_KEX.split(',').any(|c| c == k))
+        {
+            match timeout(ctx.timeout, fetch_host_key(&mut stream)).await {
+                Ok(Ok((key_type, fingerprint))) => {
+                    info.host_key_type = Some(key_type);
+                    info.host_key_fingerprint = Some(fingerprint);
+                }
+                Ok(Err(e)) => tracing::debug!(
+                    "SSH key exchange with {}:{} failed: {}",
+                    ctx.ip,
+                    ctx.probe.port,
+                    e
+                ),
+                Err(_) => tracing::debug!(
+                    "SSH key exchange with {}:{} timed out",
+                    ctx.ip,
+                    ctx.probe.port
+                ),
+            }
+        }
+        info.weak_algorithms = [
+            &kexinit.kex,
+            &kexinit.host_keys,
+            &kexinit.ciphers,
+            &kexinit.macs,
+        ]
+        .into_iter()
+        .flatten()
+        .filter(|name| is_weak_algorithm(name))
+        .cloned()
+        .collect();
+        info.kex_algorithms = kexinit.kex;
+        info.host_key_algorithms = kexinit.host_keys;
+        info.encryption_algorithms = kexinit.ciphers;
+        info.mac_algorithms = kexinit.macs;
+        info.compression_algorithms = kexinit.compression;
+
+        let hit = match_banner_signatures(&format!("{}\r\n", banner))?;
+        let svc = ServiceInfo {
+            name: hit
+                .as_ref()
+                .map(|(service, _)| service.clone())
+                .filter(|s| !s.is_empty())
+                .or_else(|| {
+                    tcp_service_db()
+                        .get_name(ctx.probe.port)
+                        .map(|s| s.to_string())
+                }),
+            cpes: hit.map(|(_, cpes)| cpes).unwrap_or_default(),
+            banner: Some(banner.clone()),
+            raw: Some(ctx.raw_value(banner.as_bytes(), banner.clone())),
+            response_latency_ms: Some(latency.as_millis() as u64),
+            ssh_info: Some(info),
+            ..Default::default()
+        };
+        Ok(PortProbeResult {
+            ip: ctx.ip,
+            hostname: ctx.hostname,
+            port: ctx.probe.port,
+            transport: ctx.probe.transport,
+            probe_id: ctx.probe.probe_id,
+            service_info: svc,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pads_packets_to_block_size() {
+        for len in 0..20 {
+            let p = packet(&vec![1u8; len]);
+            assert_eq!(p.len() % 8, 0);
+            assert!(p[4] >= 4);
+            assert_eq!(
+                u32::from_be_bytes(p[..4].try_into().unwrap()) as usize,
+                p.len() - 4
+            );
+        }
+    }
+
+    #[test]
+    fn parses_kexinit_and_flags_weak_algorithms() {
+        let kexinit = parse_kexinit(&client_kexinit()).unwrap();
+        assert_eq!(
+            kexinit.kex,
+            ["curve25519-sha256", "curve25519-sha256@libssh.org"]
+        );
+        assert_eq!(kexinit.ciphers.len(), 5);
+        assert_eq!(kexinit.compression, ["none"]);
+        assert!(kexinit.host_keys.iter().any(|k| is_weak_algorithm(k)));
+        assert!(is_weak_algorithm("aes128-cbc"));
+        assert!(!is_weak_algorithm("aes128-ctr"));
+        assert!(parse_kexinit(&[SSH_MSG_KEXINIT, 0, 1]).is_none());
+    }
+}
@@ -0,0 +1,288 @@
+use anyhow::{bail, Result};
+use rustls::pki_types::ServerName;
+use std::sync::Arc;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpStream,
+    time::{timeout, Instant},
+};
+use tokio_rustls::TlsConnector;
+
+use crate::probe::service::db::service::tcp_service_db;
+use crate::probe::service::models::ServiceInfo;
+use crate::probe::service::probe::null::match_null_signatures;
+use crate::probe::service::probe::tls::{client_config, extract_tls_info};
+use crate::probe::service::probe::{PortProbeResult, ProbeContext};
+
+const MAX_LINE_LEN: usize = 1024;
+/// Lines of one reply we read before giving up, e.g. EHLO extensions
+const MAX_REPLY_LINES: usize = 64;
+const IMAP_TAG: &str = "np1";
+
+/// Plaintext protocols upgraded to TLS in-band
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StartTlsProtocol {
+    Smtp,
+    Imap,
+    Pop3,
+    Ftp,
+}
+
+impl StartTlsProtocol {
+    /// Protocol spoken on a well-known port
+    pub fn for_port(port: u16) -> Option<Self> {
+        match port {
+            25 | 587 | 2525 => Some(Self::Smtp),
+            143 => Some(Self::Imap),
+            110 => Some(Self::Pop3),
+            21 => Some(Self::Ftp),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Smtp => "smtp",
+            Self::Imap => "imap",
+            Self::Pop3 => "pop3",
+            Self::Ftp => "ftp",
+        }
+    }
+}
+
+/// Read one CRLF (or LF) terminated line
+async fn read_line(stream: &mut TcpStream) -> Result<String> {
+    let mut line = Vec::new();
+    loop {
+        let b = stream.read_u8().await?;
+        if b == b'\n' {
+            break;
+        }
+        if line.len() >= MAX_LINE_LEN {
+            bail!("line too long");
+        }
+        line.push(b);
+    }
+    Ok(String::from_utf8_lossy(&line)
+        .trim_end_matches('\r')
+        .to_string())
+}
+
+/// Read an SMTP/FTP reply: "code-text" lines up to the final "code text".
+/// Returns the code and the lines as received.
+async fn read_reply(stream: &mut TcpStream) -> Result<(u16, Vec<String>)> {
+    let mut lines = Vec::new();
+    for _ in 0..MAX_REPLY_LINES {
+        let line = read_line(stream).await?;
+        let code = match line.get(..3).and_then(|c| c.parse::<u16>().ok()) {
+            Some(code) => code,
+            None => bail!("invalid reply line: {}", line),
+        };
+        let last = line.as_bytes().get(3) != Some(&b'-');
+        lines.push(line);
+        if last {
+            return Ok((code, lines));
+        }
+    }
+    bail!("reply longer than {} lines", MAX_REPLY_LINES)
+}
+
+async fn send(stream: &mut TcpStream, command: &str) -> Result<()> {
+    stream.write_all(command.as_bytes()).await?;
+    stream.write_all(b"\r\n").await?;
+    Ok(())
+}
+
+/// Read the greeting and ask for TLS. Returns the greeting, and whether the
+/// server agreed to start TLS; the stream is then ready for the handshake.
+async fn negotiate(stream: &mut TcpStream, protocol: StartTlsProtocol) -> Result<(String, bool)> {
+    match protocol {
+        StartTlsProtocol::Smtp => {
+            let (code, greeting) = read_reply(stream).await?;
+            if code != 220 {
+                bail!("SMTP greeting {}", code);
+            }
+            send(stream, "EHLO netpulse").await?;
+            let (code, extensions) = read_reply(stream).await?;
+            let offered = code == 250
+                && extensions.iter().any(|e| {
+                    e.get(4..)
+                        .unwrap_or_default()
+                        .trim()
+                        .eq_ignore_ascii_case("STARTTLS")
+                });
+            if !offered {
+                return Ok((greeting.join("\n"), false));
+            }
+            send(stream, "STARTTLS").await?;
+            let (code, _) = read_reply(stream).await?;
+            Ok((greeting.join("\n"), code == 220))
+        }
+        StartTlsProtocol::Ftp => {
+            let (code, greeting) = read_reply(stream).await?;
+            if code != 220 {
+                bail!("FTP greeting {}", code);
+            }
+            send(stream, "AUTH TLS").await?;
+            let (code, _) = read_reply(stream).await?;
+            Ok((greeting.join("\n"), code == 234))
+        }
+        StartTlsProtocol::Imap => {
+            let greeting = read_line(stream).await?;
+            if !greeting.starts_with("* OK") {
+                bail!("IMAP greeting: {}", greeting);
+            }
+            send(stream, &format!("{} STARTTLS", IMAP_TAG)).await?;
+            // Untagged responses may come before the tagged one
+            for _ in 0..MAX_REPLY_LINES {
+                let line = read_line(stream).await?;
+                if let Some(status) = line.strip_prefix(IMAP_TAG) {
+                    return Ok((greeting, status.trim_start().starts_with("OK")));
+                }
+            }
+            bail!("no tagged IMAP response")
+        }
+        StartTlsProtocol::Pop3 => {
+            let greeting = read_line(stream).await?;
+            if !greeting.starts_with("+OK") {
+                bail!("POP3 greeting: {}", greeting);
+            }
+            send(stream, "STLS").await?;
+            let status = read_line(stream).await?;
+            Ok((greeting, status.starts_with("+OK")))
+        }
+    }
+}
+
+/// Probe implementation for tcp:starttls.
+/// Upgrades SMTP, IMAP, POP3 and FTP connections to TLS and reports the
+/// handshake like the TLS probe. The greeting is kept as the banner, also
+/// when the server does not offer STARTTLS.
+pub struct StartTlsProbe;
+
+impl StartTlsProbe {
+    pub async fn run(ctx: ProbeContext) -> Result<PortProbeResult> {
+        let protocol = match StartTlsProtocol::for_port(ctx.probe.port) {
+            Some(protocol) => protocol,
+            None => bail!("No STARTTLS protocol for port {}", ctx.probe.port),
+        };
+        let mut stream = ctx.connect().await?;
+        let started = Instant::now();
+        let (greeting, upgraded) = timeout(ctx.timeout, negotiate(&mut stream, protocol)).await??;
+        let greeting_time = started.elapsed();
+
+        let mut svc = ServiceInfo::default();
+        svc.name = tcp_service_db()
+            .get_name(ctx.probe.port)
+            .map(|s| s.to_string());
+        if let Some((_service, cpes)) = match_null_signatures("tcp:NULL", &greeting)? {
+            svc.cpes = cpes;
+        }
+        svc.banner = greeting.lines().next().map(str::to_string);
+        svc.raw = Some(ctx.raw_value(greeting.as_bytes(), greeting.clone()));
+        svc.response_latency_ms = Some(greeting_time.as_millis() as u64);
+
+        if upgraded {
+            let connector = TlsConnector::from(Arc::new(client_config(&ctx)?));
+            let server_name = ServerName::try_from(ctx.server_name())?;
+            let started = Instant::now();
+            let tls_stream = timeout(ctx.timeout, connector.connect(server_name, stream)).await??;
+            svc.tls_info = extract_tls_info(&ctx, tls_stream.get_ref().1).map(|mut tls_info| {
+                tls_info.handshake_ms = Some(started.elapsed().as_millis() as u64);
+                tls_info.starttls = Some(protocol.as_str().to_string());
+                tls_info
+            });
+        } else {
+            tracing::debug!(
+                "{}:{} - STARTTLS not offered ({})",
+                ctx.ip,
+                ctx.probe.port,
+                protocol.as_str()
+            );
+        }
+
+        Ok(PortProbeResult {
+            ip: ctx.ip,
+            hostname: ctx.hostname,
+            port: ctx.probe.port,
+            transport: ctx.probe.transport,
+            probe_id: ctx.probe.probe_id,
+            service_info: svc,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+
+    /// Server that writes `script` lines, each after reading one client line
+    /// (the first one right away), and returns what the client sent
+    async fn scripted_server(
+        script: &'static [&'static str],
+    ) -> (u16, tokio::task::JoinHandle<Vec<String>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut received = Vec::new();
+            for (i, reply) in script.iter().enumerate() {
+                if i > 0 {
+                    received.push(read_line(&mut stream).await.unwrap());
+                }
+                stream.write_all(reply.as_bytes()).await.unwrap();
+            }
+            received
+        });
+        (port, server)
+    }
+
+    #[tokio::test]
+    async fn smtp_starttls_after_ehlo() {
+        let (port, server) = scripted_server(&[
+            "220-mail.example.test ESMTP\r\n220 ready\r\n",
+            "250-mail.example.test\r\n250-PIPELINING\r\n250 STARTTLS\r\n",
+            "220 2.0.0 Ready to start TLS\r\n",
+        ])
+        .await;
+        let mut stream = TcpStream::connect(("127.0.0.1", port)).await.unwrap();
+        let (greeting, upgraded) = negotiate(&mut stream, StartTlsProtocol::Smtp)
+            .await
+            .unwrap();
+        assert!(upgraded);
+        assert_eq!(greeting, "220-mail.example.test ESMTP\n220 ready");
+        assert_eq!(server.await.unwrap(), ["EHLO netpulse", "STARTTLS"]);
+    }
+
+    #[tokio::test]
+    async fn smtp_without_starttls_keeps_greeting() {
+        let (port, server) = scripted_server(&[
+            "220 mail.example.test ESMTP\r\n",
+            "250-mail.example.test\r\n250 8BITMIME\r\n",
+        ])
+        .await;
+        let mut stream = TcpStream::connect(("127.0.0.1", port)).await.unwrap();
+        let (greeting, upgraded) = negotiate(&mut stream, StartTlsProtocol::Smtp)
+            .await
+            .unwrap();
+        assert!(!upgraded);
+        assert_eq!(greeting, "220 mail.example.test ESMTP");
+        assert_eq!(server.await.unwrap(), ["EHLO netpulse"]);
+    }
+
+    #[tokio::test]
+    async fn imap_waits_for_tagged_response() {
+        let (port, server) = scripted_server(&[
+            "* OK IMAP4rev1 ready\r\n",
+            "* BYE not really\r\nnp1 OK Begin TLS negotiation now\r\n",
+        ])
+        .await;
+        let mut stream = TcpStream::connect(("127.0.0.1", port)).await.unwrap();
+        let (_, upgraded) = negotiate(&mut stream, StartTlsProtocol::Imap)
+            .await
+            .unwrap();
+        assert!(upgraded);
+        assert_eq!(server.await.unwrap(), ["np1 STARTTLS"]);
+    }
+}
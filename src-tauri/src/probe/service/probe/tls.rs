@@ -1,14 +1,23 @@
 use crate::probe::service::db;
-use crate::probe::service::models::{ServiceInfo, TlsInfo};
-use crate::probe::service::probe::{PortProbeResult, ProbeContext};
+use crate::probe::service::models::{ServiceInfo, TlsInfo, TlsResumption};
+use crate::probe::service::probe::{tlsenum, PortProbeResult, ProbeContext};
 use anyhow::Result;
 use rustls::client::danger::ServerCertVerifier;
+use rustls::client::{
+    ClientSessionMemoryCache, ClientSessionStore, Resumption, Tls12ClientSessionValue,
+    Tls13ClientSessionValue,
+};
 use rustls::pki_types::{CertificateDer, ServerName, UnixTime};
-use rustls::ClientConnection;
-use std::net::SocketAddr;
+use rustls::{ClientConnection, HandshakeKind, NamedGroup};
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
 use std::sync::Arc;
-use tokio::{net::TcpStream, time::timeout};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::sync::Notify;
+use tokio::time::timeout;
 use tokio_rustls::{
+    client::TlsStream,
     rustls::{ClientConfig, RootCertStore},
     TlsConnector,
 };
@@ -70,6 +79,137 @@ impl ServerCertVerifier for SkipServerVerification {
     }
 }
 
+/// Longest wait for the session ticket after the first handshake.
+/// TLS 1.3 servers send tickets right after it, TLS 1.2 servers within it.
+const TICKET_WAIT: Duration = Duration::from_millis(500);
+
+/// Session store that records whether the server offered a session to resume
+#[derive(Debug)]
+struct TicketRecorder {
+    cache: ClientSessionMemoryCache,
+    offered: AtomicBool,
+    max_early_data: AtomicU32,
+    notify: Notify,
+}
+
+impl TicketRecorder {
+    fn new() -> Arc<Self> {
+        Arc::new(Self {
+            // Sized for several servers' worth of tickets; a smaller cache
+            // evicts the only server on insert
+            cache: ClientSessionMemoryCache::new(32),
+            offered: AtomicBool::new(false),
+            max_early_data: AtomicU32::new(0),
+            notify: Notify::new(),
+        })
+    }
+
+    fn record(&self, max_early_data: u32) {
+        self.max_early_data
+            .fetch_max(max_early_data, Ordering::Relaxed);
+        self.offered.store(true, Ordering::Release);
+        self.notify.notify_one();
+    }
+
+    fn offered(&self) -> bool {
+        self.offered.load(Ordering::Acquire)
+    }
+
+    /// Wait until a ticket or session is recorded
+    async fn wait(&self) {
+        if !self.offered() {
+            self.notify.notified().await;
+        }
+    }
+}
+
+impl ClientSessionStore for TicketRecorder {
+    fn set_kx_hint(&self, server_name: ServerName<'static>, group: NamedGroup) {
+        self.cache.set_kx_hint(server_name, group)
+    }
+
+    fn kx_hint(&self, server_name: &ServerName<'_>) -> Option<NamedGroup> {
+        self.cache.kx_hint(server_name)
+    }
+
+    fn set_tls12_session(&self, server_name: ServerName<'static>, value: Tls12ClientSessionValue) {
+        self.cache.set_tls12_session(server_name, value);
+        self.record(0);
+    }
+
+    fn tls12_session(&self, server_name: &ServerName<'_>) -> Option<Tls12ClientSessionValue> {
+        self.cache.tls12_session(server_name)
+    }
+
+    fn remove_tls12_session(&self, server_name: &ServerName<'static>) {
+        self.cache.remove_tls12_session(server_name)
+    }
+
+    fn insert_tls13_ticket(
+        &self,
+        server_name: ServerName<'static>,
+        value: Tls13ClientSessionValue,
+    ) {
+        let max_early_data = value.max_early_data_size();
+        self.cache.insert_tls13_ticket(server_name, value);
+        self.record(max_early_data);
+    }
+
+    fn take_tls13_ticket(
+        &self,
+        server_name: &ServerName<'static>,
+    ) -> Option<Tls13ClientSessionValue> {
+        self.cache.take_tls13_ticket(server_name)
+    }
+}
+
+/// Close the first connection once the server offered a session, then
+/// connect again with it and time the second handshake.
+/// 0-RTT data is not sent; `max_early_data` tells whether the server allows it.
+async fn resume_session(
+    ctx: &ProbeContext,
+    connector: &TlsConnector,
+    server_name: ServerName<'static>,
+    mut first: TlsStream<TcpStream>,
+    recorder: &TicketRecorder,
+) -> TlsResumption {
+    // Tickets arrive as post-handshake messages, processed while reading
+    let mut buf = [0u8; 512];
+    let _ = timeout(ctx.timeout.min(TICKET_WAIT), async {
+        tokio::select! {
+            _ = recorder.wait() => {}
+            _ = async { while matches!(first.read(&mut buf).await, Ok(n) if n > 0) {} } => {}
+        }
+    })
+    .await;
+    let _ = first.shutdown().await;
+
+    let mut resumption = TlsResumption {
+        offered: recorder.offered(),
+        max_early_data: recorder.max_early_data.load(Ordering::Relaxed),
+        ..Default::default()
+    };
+    if !resumption.offered {
+        return resumption;
+    }
+    let second = async {
+        let tcp_stream = ctx.connect().await?;
+        let started = Instant::now();
+        let tls_stream = timeout(ctx.timeout, connector.connect(server_name, tcp_stream)).await??;
+        anyhow::Ok((tls_stream, started.elapsed()))
+    };
+    match second.await {
+        Ok((mut tls_stream, handshake_time)) => {
+            resumption.resumed =
+                tls_stream.get_ref().1.handshake_kind() == Some(HandshakeKind::Resumed);
+            resumption.handshake_ms = Some(handshake_time.as_millis() as u64);
+            let _ = tls_stream.shutdown().await;
+        }
+        Err(e) => resumption.error = Some(e.to_string()),
+    }
+    resumption
+}
+
 /// Extract TLS info from a ClientConnection
 pub fn extract_tls_info(
     probe_ctx: &ProbeContext,
@@ -95,7 +235,7 @@ pub fn extract_tls_info(
         .and_then(|v| v.first())
         .cloned()
     {
-        tls_info.sni = probe_ctx.hostname.clone();
+        tls_info.sni = probe_ctx.sni_name();
         match X509Certificate::from_der(&cert) {
             Ok((_, x509)) => {
                 // Subject
@@ -129,6 +269,9 @@ pub fn extract_tls_info(
                 tls_info.san_list = san_list;
                 tls_info.not_before = Some(x509.validity().not_before.to_string());
                 tls_info.not_after = Some(x509.validity().not_after.to_string());
+                tls_info.not_before_unix = Some(x509.validity().not_before.timestamp());
+                tls_info.not_after_unix = Some(x509.validity().not_after.timestamp());
+                tls_info.self_signed = Some(x509.subject() == x509.issuer());
                 tls_info.serial_hex = Some(x509.raw_serial_as_string());
                 let sig_alg_name =
                     db::tls::oid_sig_name(x509.signature_algorithm.oid().to_id_string().as_str());
@@ -146,39 +289,45 @@ pub fn extract_tls_info(
     Some(tls_info)
 }
 
+/// Client config trusting the native roots, or any certificate when
+/// `skip_cert_verify` is set
+pub fn client_config(ctx: &ProbeContext) -> Result<ClientConfig> {
+    let mut roots = RootCertStore::empty();
+    for cert in rustls_native_certs::load_native_certs()? {
+        let _ = roots.add(cert);
+    }
+    let mut config = ClientConfig::builder()
+        .with_root_certificates(roots)
+        .with_no_client_auth();
+    if ctx.skip_cert_verify {
+        config
+            .dangerous()
+            .set_certificate_verifier(SkipServerVerification::new());
+    }
+    Ok(config)
+}
+
 /// Probe implementation for tcp:tls
 pub struct TlsProbe;
 
 impl TlsProbe {
     /// Run the TLS probe with the given context.
     pub async fn run(ctx: ProbeContext) -> Result<PortProbeResult> {
-        let addr: SocketAddr = SocketAddr::new(ctx.ip, ctx.probe.port);
-        let hostname = ctx.hostname.clone().unwrap_or_else(|| ctx.ip.to_string());
-        let tcp_stream = timeout(ctx.timeout, TcpStream::connect(addr)).await??;
-
-        // rustls config
-        let mut roots = RootCertStore::empty();
-        for cert in rustls_native_certs::load_native_certs()? {
-            let _ = roots.add(cert);
-        }
-        let mut config = ClientConfig::builder()
-            .with_root_certificates(roots)
-            .with_no_client_auth();
-
-        if ctx.skip_cert_verify {
-            config
-                .dangerous()
-                .set_certificate_verifier(SkipServerVerification::new());
+        let tcp_stream = ctx.connect().await?;
+
+        let mut config = client_config(&ctx)?;
+        let recorder = TicketRecorder::new();
+        if ctx.tls_resumption {
+            config.resumption = Resumption::store(recorder.clone());
         }
 
         let connector = TlsConnector::from(Arc::new(config));
-        let sni_name = if ctx.sni {
-            ServerName::try_from(hostname)?
-        } else {
-            ServerName::try_from("localhost")?
-        };
+        let sni_name = ServerName::try_from(ctx.server_name())?;
 
-        let tls_stream = timeout(ctx.timeout, connector.connect(sni_name, tcp_stream)).await??;
+        let started = Instant::now();
+        let tls_stream =
+            timeout(ctx.timeout, connector.connect(sni_name.clone(), tcp_stream)).await??;
+        let handshake_time = started.elapsed();
         let conn = tls_stream.get_ref().1; // server connection
 
         let mut svc = ServiceInfo::default();
@@ -186,6 +335,19 @@ impl TlsProbe {
         svc.name = tcp_svc_db.get_name(ctx.probe.port).map(|s| s.to_string());
 
         svc.tls_info = extract_tls_info(&ctx, &conn);
+        svc.response_latency_ms = Some(handshake_time.as_millis() as u64);
+        if let Some(tls_info) = svc.tls_info.as_mut() {
+            tls_info.handshake_ms = svc.response_latency_ms;
+            if ctx.tls_resumption {
+                tls_info.resumption =
+                    Some(resume_session(&ctx, &connector, sni_name, tls_stream, &recorder).await);
+            }
+            if ctx.tls_enum {
+                let accepted = tlsenum::enumerate(&ctx).await;
+                tls_info.supported_versions = accepted.versions;
+                tls_info.supported_ciphers = accepted.ciphers;
+            }
+        }
 
         let probe_result: PortProbeResult = PortProbeResult {
             ip: ctx.ip,
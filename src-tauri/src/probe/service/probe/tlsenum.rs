@@ -0,0 +1,419 @@
+//! Enumeration of the TLS versions and cipher suites a server accepts.
+//!
+//! rustls only speaks TLS 1.2 and 1.3 with modern suites, so each attempt is
+//! a hand-built ClientHello. The ServerHello (or alert) tells whether the
+//! offer is accepted, and the handshake is dropped there.
+
+use anyhow::{bail, Result};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpStream,
+    time::timeout,
+};
+
+use crate::probe::service::probe::ProbeContext;
+
+/// Versions tried, oldest first, named as in `TlsInfo.version`
+const TLS_VERSIONS: [(u16, &str); 4] = [
+    (0x0301, "TLSv1_0"),
+    (0x0302, "TLSv1_1"),
+    (0x0303, "TLSv1_2"),
+    (0x0304, "TLSv1_3"),
+];
+
+/// Upper bound of handshakes per port. One per version comes first, the
+/// rest goes to cipher suites in version order.
+const MAX_HANDSHAKES: usize = 48;
+
+/// Suites offered up to TLS 1.2, strongest first. Includes the weak ones
+/// auditors look for: CBC with SHA-1, 3DES, RC4, DES, export, anon and NULL.
+const LEGACY_CIPHER_SUITES: [(u16, &str); 40] = [
+    (0xc02c, "TLS_ECDHE_ECDSA_WITH_AES_256_GCM_SHA384"),
+    (0xc02b, "TLS_ECDHE_ECDSA_WITH_AES_128_GCM_SHA256"),
+    (0xc030, "TLS_ECDHE_RSA_WITH_AES_256_GCM_SHA384"),
+    (0xc02f, "TLS_ECDHE_RSA_WITH_AES_128_GCM_SHA256"),
+    (0xcca9, "TLS_ECDHE_ECDSA_WITH_CHACHA20_POLY1305_SHA256"),
+    (0xcca8, "TLS_ECDHE_RSA_WITH_CHACHA20_POLY1305_SHA256"),
+    (0x009f, "TLS_DHE_RSA_WITH_AES_256_GCM_SHA384"),
+    (0x009e, "TLS_DHE_RSA_WITH_AES_128_GCM_SHA256"),
+    (0xc024, "TLS_ECDHE_ECDSA_WITH_AES_256_CBC_SHA384"),
+    (0xc023, "TLS_ECDHE_ECDSA_WITH_AES_128_CBC_SHA256"),
+    (0xc028, "TLS_ECDHE_RSA_WITH_AES_256_CBC_SHA384"),
+    (0xc027, "TLS_ECDHE_RSA_WITH_AES_128_CBC_SHA256"),
+    (0xc00a, "TLS_ECDHE_ECDSA_WITH_AES_256_CBC_SHA"),
+    (0xc009, "TLS_ECDHE_ECDSA_WITH_AES_128_CBC_SHA"),
+    (0xc014, "TLS_ECDHE_RSA_WITH_AES_256_CBC_SHA"),
+    (0xc013, "TLS_ECDHE_RSA_WITH_AES_128_CBC_SHA"),
+    (0x0039, "TLS_DHE_RSA_WITH_AES_256_CBC_SHA"),
+    (0x0033, "TLS_DHE_RSA_WITH_AES_128_CBC_SHA"),
+    (0x009d, "TLS_RSA_WITH_AES_256_GCM_SHA384"),
+    (0x009c, "TLS_RSA_WITH_AES_128_GCM_SHA256"),
+    (0x003d, "TLS_RSA_WITH_AES_256_CBC_SHA256"),
+    (0x003c, "TLS_RSA_WITH_AES_128_CBC_SHA256"),
+    (0x0035, "TLS_RSA_WITH_AES_256_CBC_SHA"),
+    (0x002f, "TLS_RSA_WITH_AES_128_CBC_SHA"),
+    (0xc012, "TLS_ECDHE_RSA_WITH_3DES_EDE_CBC_SHA"),
+    (0x0016, "TLS_DHE_RSA_WITH_3DES_EDE_CBC_SHA"),
+    (0x000a, "TLS_RSA_WITH_3DES_EDE_CBC_SHA"),
+    (0xc011, "TLS_ECDHE_RSA_WITH_RC4_128_SHA"),
+    (0xc007, "TLS_ECDHE_ECDSA_WITH_RC4_128_SHA"),
+    (0x0005, "TLS_RSA_WITH_RC4_128_SHA"),
+    (0x0004, "TLS_RSA_WITH_RC4_128_MD5"),
+    (0x0009, "TLS_RSA_WITH_DES_CBC_SHA"),
+    (0x0008, "TLS_RSA_EXPORT_WITH_DES40_CBC_SHA"),
+    (0x0003, "TLS_RSA_EXPORT_WITH_RC4_40_MD5"),
+    (0x0034, "TLS_DH_anon_WITH_AES_128_CBC_SHA"),
+    (0x0018, "TLS_DH_anon_WITH_RC4_128_MD5"),
+    (0xc010, "TLS_ECDHE_RSA_WITH_NULL_SHA"),
+    (0x003b, "TLS_RSA_WITH_NULL_SHA256"),
+    (0x0002, "TLS_RSA_WITH_NULL_SHA"),
+    (0x0001, "TLS_RSA_WITH_NULL_MD5"),
+];
+
+/// TLS 1.3 suites, named as rustls names them in `TlsInfo.cipher_suite`
+const TLS13_CIPHER_SUITES: [(u16, &str); 5] = [
+    (0x1302, "TLS13_AES_256_GCM_SHA384"),
+    (0x1301, "TLS13_AES_128_GCM_SHA256"),
+    (0x1303, "TLS13_CHACHA20_POLY1305_SHA256"),
+    (0x1304, "TLS13_AES_128_CCM_SHA256"),
+    (0x1305, "TLS13_AES_128_CCM_8_SHA256"),
+];
+
+const CONTENT_ALERT: u8 = 21;
+const CONTENT_HANDSHAKE: u8 = 22;
+const HANDSHAKE_CLIENT_HELLO: u8 = 1;
+const HANDSHAKE_SERVER_HELLO: u8 = 2;
+/// Largest record we accept (RFC 8446 5.2, plus expansion)
+const MAX_RECORD_LEN: usize = 16384 + 256;
+
+const EXT_SERVER_NAME: u16 = 0x0000;
+const EXT_SUPPORTED_GROUPS: u16 = 0x000a;
+const EXT_EC_POINT_FORMATS: u16 = 0x000b;
+const EXT_SIGNATURE_ALGORITHMS: u16 = 0x000d;
+const EXT_SUPPORTED_VERSIONS: u16 = 0x002b;
+const EXT_KEY_SHARE: u16 = 0x0033;
+
+/// x25519 first: a random 32 byte string is a valid key share for it, and
+/// the handshake is dropped before any key is derived. Servers preferring
+/// another group answer with a HelloRetryRequest, which still names the suite.
+const GROUP_X25519: u16 = 0x001d;
+const SUPPORTED_GROUPS: [u16; 4] = [GROUP_X25519, 0x0017, 0x0018, 0x0019];
+/// ECDSA, RSA-PSS, Ed25519 and RSA PKCS#1 schemes, SHA-1 last
+const SIGNATURE_ALGORITHMS: [u16; 12] = [
+    0x0403, 0x0503, 0x0603, 0x0804, 0x0805, 0x0806, 0x0807, 0x0401, 0x0501, 0x0601, 0x0203, 0x0201,
+];
+
+/// Versions and cipher suites a server accepted
+#[derive(Debug, Default)]
+pub struct TlsEnumeration {
+    pub versions: Vec<String>,
+    pub ciphers: Vec<String>,
+}
+
+/// Version and suite picked in a ServerHello or HelloRetryRequest
+#[derive(Debug, PartialEq, Eq)]
+struct ServerHello {
+    version: u16,
+    cipher: u16,
+}
+
+fn cipher_suites(version: u16) -> &'static [(u16, &'static str)] {
+    if version >= 0x0304 {
+        &TLS13_CIPHER_SUITES
+    } else {
+        &LEGACY_CIPHER_SUITES
+    }
+}
+
+fn cipher_name(version: u16, id: u16) -> Option<&'static str> {
+    cipher_suites(version)
+        .iter()
+        .find(|(suite, _)| *suite == id)
+        .map(|(_, name)| *name)
+}
+
+fn put_u16(buf: &mut Vec<u8>, v: u16) {
+    buf.extend_from_slice(&v.to_be_bytes());
+}
+
+/// `data` prefixed with its length as a u16
+fn put_vec16(buf: &mut Vec<u8>, data: &[u8]) {
+    put_u16(buf, data.len() as u16);
+    buf.extend_from_slice(data);
+}
+
+fn put_extension(buf: &mut Vec<u8>, ext_type: u16, data: &[u8]) {
+    put_u16(buf, ext_type);
+    put_vec16(buf, data);
+}
+
+fn u16_list(values: &[u16]) -> Vec<u8> {
+    values.iter().flat_map(|v| v.to_be_bytes()).collect()
+}
+
+/// ClientHello record offering only `version` and `ciphers`
+fn client_hello(version: u16, ciphers: &[u16], sni: Option<&str>) -> Vec<u8> {
+    let mut exts = Vec::new();
+    if let Some(name) = sni {
+        let mut entry = vec![0u8];
+        put_vec16(&mut entry, name.as_bytes());
+        let mut list = Vec::new();
+        put_vec16(&mut list, &entry);
+        put_extension(&mut exts, EXT_SERVER_NAME, &list);
+    }
+    let mut groups = Vec::new();
+    put_vec16(&mut groups, &u16_list(&SUPPORTED_GROUPS));
+    put_extension(&mut exts, EXT_SUPPORTED_GROUPS, &groups);
+    // Uncompressed points only
+    put_extension(&mut exts, EXT_EC_POINT_FORMATS, &[1, 0]);
+    let mut sig_algs = Vec::new();
+    put_vec16(&mut sig_algs, &u16_list(&SIGNATURE_ALGORITHMS));
+    put_extension(&mut exts, EXT_SIGNATURE_ALGORITHMS, &sig_algs);
+    if version >= 0x0304 {
+        let mut versions = vec![2];
+        put_u16(&mut versions, version);
+        put_extension(&mut exts, EXT_SUPPORTED_VERSIONS, &versions);
+        let key: [u8; 32] = std::array::from_fn(|_| fastrand::u8(..));
+        let mut share = Vec::new();
+        put_u16(&mut share, GROUP_X25519);
+        put_vec16(&mut share, &key);
+        let mut shares = Vec::new();
+        put_vec16(&mut shares, &share);
+        put_extension(&mut exts, EXT_KEY_SHARE, &shares);
+    }
+
+    let mut body = Vec::new();
+    // TLS 1.3 is negotiated in supported_versions; the legacy field stays 1.2
+    put_u16(&mut body, version.min(0x0303));
+    let random: [u8; 32] = std::array::from_fn(|_| fastrand::u8(..));
+    body.extend_from_slice(&random);
+    // Empty session ID
+    body.push(0);
+    put_vec16(&mut body, &u16_list(ciphers));
+    // Null compression only
+    body.extend_from_slice(&[1, 0]);
+    put_vec16(&mut body, &exts);
+
+    let mut handshake = vec![HANDSHAKE_CLIENT_HELLO];
+    handshake.extend_from_slice(&(body.len() as u32).to_be_bytes()[1..]);
+    handshake.extend_from_slice(&body);
+    // Record version 1.0, which every server accepts in a ClientHello
+    let mut record = vec![CONTENT_HANDSHAKE];
+    put_u16(&mut record, 0x0301);
+    put_vec16(&mut record, &handshake);
+    record
+}
+
+/// Reader of big-endian TLS fields
+struct Fields<'a>(&'a [u8]);
+
+impl<'a> Fields<'a> {
+    fn bytes(&mut self, n: usize) -> Option<&'a [u8]> {
+        let (head, rest) = (self.0.get(..n)?, self.0.get(n..)?);
+        self.0 = rest;
+        Some(head)
+    }
+
+    fn u8(&mut self) -> Option<u8> {
+        Some(self.bytes(1)?[0])
+    }
+
+    fn u16(&mut self) -> Option<u16> {
+        Some(u16::from_be_bytes(self.bytes(2)?.try_into().ok()?))
+    }
+
+    fn vec16(&mut self) -> Option<&'a [u8]> {
+        let len = self.u16()? as usize;
+        self.bytes(len)
+    }
+}
+
+/// Version and suite of a ServerHello handshake message, header included.
+/// The version is the one in supported_versions when present (TLS 1.3).
+fn parse_server_hello(msg: &[u8]) -> Option<ServerHello> {
+    let mut f = Fields(msg);
+    if f.u8()? != HANDSHAKE_SERVER_HELLO {
+        return None;
+    }
+    // Length
+    f.bytes(3)?;
+    let mut version = f.u16()?;
+    // Random
+    f.bytes(32)?;
+    let session_id_len = f.u8()? as usize;
+    f.bytes(session_id_len)?;
+    let cipher = f.u16()?;
+    // Compression
+    f.u8()?;
+    // Extensions are optional before TLS 1.3
+    let mut exts = Fields(f.vec16().unwrap_or_default());
+    while !exts.0.is_empty() {
+        let ext_type = exts.u16()?;
+        let data = exts.vec16()?;
+        if ext_type == EXT_SUPPORTED_VERSIONS {
+            version = Fields(data).u16()?;
+        }
+    }
+    Some(ServerHello { version, cipher })
+}
+
+/// Read the reply to a ClientHello. `None` when the server refuses it with
+/// an alert or by closing the connection.
+async fn read_server_hello(stream: &mut TcpStream) -> Result<Option<ServerHello>> {
+    let mut header = [0u8; 5];
+    if stream.read_exact(&mut header).await.is_err() {
+        return Ok(None);
+    }
+    let len = u16::from_be_bytes([header[3], header[4]]) as usize;
+    match header[0] {
+        CONTENT_HANDSHAKE if len <= MAX_RECORD_LEN => {}
+        CONTENT_ALERT => return Ok(None),
+        other => bail!("unexpected TLS record type {} ({} bytes)", other, len),
+    }
+    let mut body = vec![0u8; len];
+    stream.read_exact(&mut body).await?;
+    match parse_server_hello(&body) {
+        Some(hello) => Ok(Some(hello)),
+        None => bail!("invalid ServerHello"),
+    }
+}
+
+/// Send one ClientHello on a new connection and read the reply
+async fn handshake(
+    ctx: &ProbeContext,
+    version: u16,
+    ciphers: &[u16],
+    sni: Option<&str>,
+) -> Result<Option<ServerHello>> {
+    let mut stream = ctx.connect().await?;
+    stream
+        .write_all(&client_hello(version, ciphers, sni))
+        .await?;
+    let reply = timeout(ctx.timeout, read_server_hello(&mut stream)).await?;
+    let _ = stream.shutdown().await;
+    reply
+}
+
+/// Handshake for `version` offering `remaining`, and drop the suite the
+/// server picked from it. `None` when the offer is refused.
+async fn accept_one(
+    ctx: &ProbeContext,
+    version: u16,
+    remaining: &mut Vec<u16>,
+    sni: Option<&str>,
+) -> Option<&'static str> {
+    match handshake(ctx, version, remaining, sni).await {
+        Ok(Some(hello)) if hello.version == version && remaining.contains(&hello.cipher) => {
+            remaining.retain(|c| *c != hello.cipher);
+            cipher_name(version, hello.cipher)
+        }
+        Ok(_) => None,
+        Err(e) => {
+            tracing::debug!(
+                "{}:{} - TLS enumeration handshake failed: {}",
+                ctx.ip,
+                ctx.probe.port,
+                e
+            );
+            None
+        }
+    }
+}
+
+/// Try each TLS version with every known suite, then ask again without
+/// the picked suite until the server refuses, in at most `MAX_HANDSHAKES`.
+pub async fn enumerate(ctx: &ProbeContext) -> TlsEnumeration {
+    let sni = ctx.sni_name();
+    let mut result = TlsEnumeration::default();
+    let mut budget = MAX_HANDSHAKES;
+    let mut accepted = Vec::new();
+    for (version, name) in TLS_VERSIONS {
+        let mut remaining: Vec<u16> = cipher_suites(version).iter().map(|(id, _)| *id).collect();
+        budget -= 1;
+        if let Some(cipher) = accept_one(ctx, version, &mut remaining, sni.as_deref()).await {
+            result.versions.push(name.to_string());
+            result.ciphers.push(cipher.to_string());
+            accepted.push((version, remaining));
+        }
+    }
+    for (version, mut remaining) in accepted {
+        while budget > 0 && !remaining.is_empty() {
+            budget -= 1;
+            match accept_one(ctx, version, &mut remaining, sni.as_deref()).await {
+                Some(cipher) => {
+                    if !result.ciphers.iter().any(|c| c == cipher) {
+                        result.ciphers.push(cipher.to_string());
+                    }
+                }
+                None => break,
+            }
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn client_hello_offers_tls13_in_supported_versions() {
+        let record = client_hello(0x0304, &[0x1301, 0x1302], Some("example.test"));
+        assert_eq!(record[0], CONTENT_HANDSHAKE);
+        assert_eq!(
+            u16::from_be_bytes([record[3], record[4]]) as usize,
+            record.len() - 5
+        );
+        let mut f = Fields(&record[5..]);
+        assert_eq!(f.u8(), Some(HANDSHAKE_CLIENT_HELLO));
+        f.bytes(3).unwrap();
+        assert_eq!(f.u16(), Some(0x0303));
+        f.bytes(32).unwrap();
+        assert_eq!(f.u8(), Some(0));
+        assert_eq!(f.vec16(), Some(&[0x13, 0x01, 0x13, 0x02][..]));
+        f.bytes(2).unwrap();
+        let mut exts = Fields(f.vec16().unwrap());
+        let mut types = Vec::new();
+        while !exts.0.is_empty() {
+            let ext_type = exts.u16().unwrap();
+            let data = exts.vec16().unwrap();
+            if ext_type == EXT_SUPPORTED_VERSIONS {
+                assert_eq!(data, [2, 0x03, 0x04]);
+            }
+            types.push(ext_type);
+        }
+        assert!(types.contains(&EXT_SERVER_NAME));
+        assert!(types.contains(&EXT_KEY_SHARE));
+    }
+
+    #[test]
+    fn server_hello_version_comes_from_supported_versions() {
+        let mut msg = vec![HANDSHAKE_SERVER_HELLO, 0, 0, 0];
+        put_u16(&mut msg, 0x0303);
+        msg.extend_from_slice(&[7u8; 32]);
+        msg.push(0);
+        put_u16(&mut msg, 0x1302);
+        msg.push(0);
+        let mut exts = Vec::new();
+        put_extension(&mut exts, EXT_SUPPORTED_VERSIONS, &[0x03, 0x04]);
+        put_vec16(&mut msg, &exts);
+        assert_eq!(
+            parse_server_hello(&msg),
+            Some(ServerHello {
+                version: 0x0304,
+                cipher: 0x1302
+            })
+        );
+        // TLS 1.0 reply without extensions
+        let mut legacy = msg[..4 + 2 + 32 + 1 + 2 + 1].to_vec();
+        legacy[4..6].copy_from_slice(&[0x03, 0x01]);
+        legacy[39..41].copy_from_slice(&[0x00, 0x2f]);
+        assert_eq!(
+            parse_server_hello(&legacy),
+            Some(ServerHello {
+                version: 0x0301,
+                cipher: 0x002f
+            })
+        );
+    }
+}
@@ -0,0 +1,244 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+use crate::model::scan::PortScanSample;
+use crate::probe::service::models::{Severity, VulnSummary};
+
+/// User-provided CVE index, looked up in the app dir
+pub const CVE_INDEX_FILE_NAME: &str = "netpulse-cve-index.json";
+/// CVE ids listed in a summary, highest score first
+const MAX_LISTED_CVES: usize = 10;
+
+/// Known vulnerability of a CPE
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct CveRecord {
+    pub id: String,
+    /// CVSS base score
+    #[serde(default)]
+    pub cvss: Option<f32>,
+    /// Taken from `cvss` when missing
+    #[serde(default)]
+    pub severity: Option<Severity>,
+}
+
+impl CveRecord {
+    fn severity(&self) -> Option<Severity> {
+        self.severity.or_else(|| self.cvss.map(Severity::from_cvss))
+    }
+}
+
+/// CVEs of one CPE. A CPE without a version applies to every version.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct CveIndexEntry {
+    pub cpe: String,
+    pub cves: Vec<CveRecord>,
+}
+
+/// On-disk format of `netpulse-cve-index.json`
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct CveIndexFile {
+    pub entries: Vec<CveIndexEntry>,
+}
+
+/// CVE records keyed by normalized CPE (`part:vendor:product[:version...]`)
+#[derive(Debug, Default)]
+pub struct CveIndex {
+    by_cpe: HashMap<String, Vec<CveRecord>>,
+}
+
+/// Normalize CPE 2.2 URIs (`cpe:/a:apache:http_server:2.4.49`, as produced
+/// by the signatures) and CPE 2.3 strings (`cpe:2.3:a:apache:...:*:*`) to
+/// `a:apache:http_server:2.4.49`. Wildcard and empty trailing fields are dropped.
+fn normalize_cpe(cpe: &str) -> Option<String> {
+    let cpe = cpe.trim().to_ascii_lowercase();
+    let body = if let Some(rest) = cpe.strip_prefix("cpe:/") {
+        // Signature CPEs may carry a trailing `/` and flags
+        rest.split('/').next().unwrap_or("").to_string()
+    } else if let Some(rest) = cpe.strip_prefix("cpe:2.3:") {
+        rest.to_string()
+    } else {
+        return None;
+    };
+    let mut parts: Vec<&str> = body.split(':').collect();
+    while matches!(parts.last(), Some(&"") | Some(&"*") | Some(&"-")) {
+        parts.pop();
+    }
+    // part, vendor and product are required
+    if parts.len() < 3 {
+        return None;
+    }
+    Some(parts.join(":"))
+}
+
+impl CveIndex {
+    pub fn from_file(file: CveIndexFile) -> Self {
+        let mut by_cpe: HashMap<String, Vec<CveRecord>> = HashMap::new();
+        for entry in file.entries {
+            match normalize_cpe(&entry.cpe) {
+                Some(key) => by_cpe.entry(key).or_default().extend(entry.cves),
+                None => tracing::warn!("Ignoring invalid CPE in CVE index: {}", entry.cpe),
+            }
+        }
+        CveIndex { by_cpe }
+    }
+
+    /// Load `netpulse-cve-index.json` from the app dir.
+    /// `None` when the file is missing or invalid; there is no bundled index.
+    pub fn load() -> Option<Self> {
+        let path = crate::fs::get_user_file_path(CVE_INDEX_FILE_NAME)?;
+        if !path.exists() {
+            return None;
+        }
+        let content = match std::fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(e) => {
+                tracing::error!("{:?}", e);
+                return None;
+            }
+        };
+        match serde_json::from_str::<CveIndexFile>(&content) {
+            Ok(file) => Some(Self::from_file(file)),
+            Err(e) => {
+                tracing::error!("Invalid {}: {:?}", path.display(), e);
+                None
+            }
+        }
+    }
+
+    /// Summarize the CVEs matching any of `cpes`: the exact CPE and its
+    /// version-less prefixes. `None` if nothing matches.
+    pub fn summarize(&self, cpes: &[String]) -> Option<VulnSummary> {
+        let mut seen = HashSet::new();
+        let mut matched: Vec<&CveRecord> = Vec::new();
+        for cpe in cpes {
+            let Some(key) = normalize_cpe(cpe) else {
+                continue;
+            };
+            let parts: Vec<&str> = key.split(':').collect();
+            for n in (3..=parts.len()).rev() {
+                if let Some(records) = self.by_cpe.get(&parts[..n].join(":")) {
+                    for r in records {
+                        if seen.insert(r.id.as_str()) {
+                            matched.push(r);
+                        }
+                    }
+                }
+            }
+        }
+        if matched.is_empty() {
+            return None;
+        }
+
+        let mut summary = VulnSummary {
+            total: matched.len(),
+            ..Default::default()
+        };
+        for r in &matched {
+            match r.severity() {
+                Some(Severity::Critical) => summary.critical += 1,
+                Some(Severity::High) => summary.high += 1,
+                Some(Severity::Medium) => summary.medium += 1,
+                Some(Severity::Low) => summary.low += 1,
+                None => summary.unknown += 1,
+            }
+            if let Some(score) = r.cvss {
+                summary.max_cvss = Some(summary.max_cvss.map_or(score, |m| m.max(score)));
+            }
+        }
+        matched.sort_by(|a, b| {
+            b.cvss
+                .unwrap_or(-1.0)
+                .total_cmp(&a.cvss.unwrap_or(-1.0))
+                .then_with(|| a.id.cmp(&b.id))
+        });
+        summary.cve_ids = matched
+            .iter()
+            .take(MAX_LISTED_CVES)
+            .map(|r| r.id.clone())
+            .collect();
+        Some(summary)
+    }
+}
+
+/// Attach a vulnerability summary to every sample with detected CPEs.
+/// Does nothing when no CVE index is installed.
+pub fn annotate_samples(samples: &mut [PortScanSample]) {
+    let Some(index) = CveIndex::load() else {
+        tracing::debug!("No CVE index, skipping vulnerability lookup");
+        return;
+    };
+    for sample in samples {
+        if let Some(info) = sample.service_info.as_mut() {
+            info.vuln_summary = index.summarize(&info.cpes);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn index() -> CveIndex {
+        serde_json::from_str::<CveIndexFile>(
+            r#"{"entries": [
+                {"cpe": "cpe:2.3:a:apache:http_server:2.4.49:*:*:*:*:*:*:*", "cves": [
+                    {"id": "CVE-2021-41773", "cvss": 7.5},
+                    {"id": "CVE-2021-42013", "cvss": 9.8}
+                ]},
+                {"cpe": "cpe:/a:apache:http_server", "cves": [
+                    {"id": "CVE-2000-0001", "severity": "Low"},
+                    {"id": "CVE-2000-0002"}
+                ]},
+                {"cpe": "not-a-cpe", "cves": [{"id": "CVE-1999-0001"}]}
+            ]}"#,
+        )
+        .map(CveIndex::from_file)
+        .unwrap()
+    }
+
+    #[test]
+    fn normalizes_cpe_notations() {
+        let expected = Some("a:apache:http_server:2.4.49".to_string());
+        assert_eq!(normalize_cpe("cpe:/a:apache:http_server:2.4.49"), expected);
+        assert_eq!(normalize_cpe("CPE:/a:Apache:HTTP_Server:2.4.49/"), expected);
+        assert_eq!(
+            normalize_cpe("cpe:2.3:a:apache:http_server:2.4.49:*:*:*:*:*:*:*"),
+            expected
+        );
+        assert_eq!(normalize_cpe("cpe:/a:apache"), None);
+        assert_eq!(normalize_cpe("a:apache:http_server"), None);
+    }
+
+    #[test]
+    fn summarizes_exact_and_versionless_matches() {
+        let index = index();
+        let summary = index
+            .summarize(&["cpe:/a:apache:http_server:2.4.49".to_string()])
+            .unwrap();
+        assert_eq!(summary.total, 4);
+        assert_eq!(
+            (summary.critical, summary.high, summary.low, summary.unknown),
+            (1, 1, 1, 1)
+        );
+        assert_eq!(summary.max_cvss, Some(9.8));
+        // Highest score first, unscored last by id
+        assert_eq!(
+            summary.cve_ids,
+            [
+                "CVE-2021-42013",
+                "CVE-2021-41773",
+                "CVE-2000-0001",
+                "CVE-2000-0002"
+            ]
+        );
+
+        // Another version matches the version-less entry only
+        let other = index
+            .summarize(&["cpe:/a:apache:http_server:2.4.58".to_string()])
+            .unwrap();
+        assert_eq!(other.total, 2);
+        assert!(index
+            .summarize(&["cpe:/a:nginx:nginx".to_string()])
+            .is_none());
+    }
+}
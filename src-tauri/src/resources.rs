@@ -6,3 +6,13 @@ pub const SERVICE_PROBES_JSON: &str = include_str!("../resources/np-service-prob
 pub const PORT_PROBES_JSON: &str = include_str!("../resources/np-port-probes.json");
 /// TLS OID mappings
 pub const TLS_OID_MAP_JSON: &str = include_str!("../resources/np-tls-oid-map.json");
+/// The 1000 most frequently open TCP ports (Top1000 preset)
+pub const TOP_1000_PORTS_JSON: &str = include_str!("../resources/np-top-1000-ports.json");
+/// Ports ranked by how often they are found open, most common first
+pub const PORT_RANK_JSON: &str = include_str!("../resources/np-port-rank.json");
+/// Curated service ports of the Services preset
+pub const SERVICE_PORTS_JSON: &str = include_str!("../resources/np-service-ports.json");
+/// Ports skipped in safe mode, known to crash fragile printers and OT devices
+pub const FRAGILE_PORTS_JSON: &str = include_str!("../resources/np-fragile-ports.json");
+/// Template of the standalone HTML port scan report
+pub const REPORT_TEMPLATE_HTML: &str = include_str!("../resources/np-report-template.html");
@@ -0,0 +1,217 @@
+//! Append-only JSONL files holding the open-port samples of large scans.
+//!
+//! With `spill_results` set, a port scan writes each open sample here as it
+//! completes instead of keeping it in memory. The report then carries only
+//! the summary and the file path; samples are read back a page at a time.
+
+use anyhow::{bail, Context, Result};
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Lines, Write};
+use std::path::{Path, PathBuf};
+
+use crate::error::ScanError;
+use crate::model::scan::{PortScanSample, PortState};
+use crate::model::stats::PortScanSummary;
+
+/// Directory under the app dir holding one JSONL file per spilled scan
+pub const RESULTS_DIR_NAME: &str = "results";
+/// Samples read back at once, e.g. for service detection
+pub const RESULTS_BATCH: usize = 1024;
+
+fn results_dir() -> Result<PathBuf> {
+    let mut path = crate::fs::get_app_dir_path().context("Failed to resolve app dir")?;
+    path.push(RESULTS_DIR_NAME);
+    if !path.exists() {
+        std::fs::create_dir_all(&path)?;
+    }
+    Ok(path)
+}
+
+/// Final results file of `run_id`
+pub fn results_path(run_id: &str) -> Result<PathBuf> {
+    // Run IDs are UUIDs; reject anything else so it can't escape the results dir
+    if uuid::Uuid::parse_str(run_id).is_err() {
+        bail!(ScanError::invalid_input(format!(
+            "Invalid run ID: {}",
+            run_id
+        )));
+    }
+    let mut path = results_dir()?;
+    path.push(format!("{}.jsonl", run_id));
+    Ok(path)
+}
+
+/// Samples written while the scan is still probing, before service detection
+pub fn partial_path(run_id: &str) -> Result<PathBuf> {
+    Ok(results_path(run_id)?.with_extension("jsonl.part"))
+}
+
+/// Appends one sample per line
+pub struct SampleWriter {
+    path: PathBuf,
+    out: BufWriter<File>,
+    count: usize,
+}
+
+impl SampleWriter {
+    /// Create or truncate the file at `path`
+    pub fn create(path: PathBuf) -> Result<Self> {
+        let file = File::create(&path)
+            .with_context(|| format!("Failed to create results file {}", path.display()))?;
+        Ok(Self {
+            path,
+            out: BufWriter::new(file),
+            count: 0,
+        })
+    }
+
+    pub fn push(&mut self, sample: &PortScanSample) -> Result<()> {
+        serde_json::to_writer(&mut self.out, sample)?;
+        self.out.write_all(b"\n")?;
+        self.count += 1;
+        Ok(())
+    }
+
+    /// Samples written so far
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// Flush and return the path of the file
+    pub fn finish(mut self) -> Result<PathBuf> {
+        self.out.flush()?;
+        Ok(self.path)
+    }
+}
+
+/// Reads the samples of a results file in order
+pub struct SampleReader {
+    lines: Lines<BufReader<File>>,
+}
+
+impl SampleReader {
+    pub fn open(path: &Path) -> Result<Self> {
+        let file = File::open(path)
+            .with_context(|| format!("Results file not found: {}", path.display()))?;
+        Ok(Self {
+            lines: BufReader::new(file).lines(),
+        })
+    }
+
+    /// Up to `max` next samples; empty at the end of the file
+    pub fn next_batch(&mut self, max: usize) -> Result<Vec<PortScanSample>> {
+        self.by_ref().take(max).collect()
+    }
+}
+
+impl Iterator for SampleReader {
+    type Item = Result<PortScanSample>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let line = match self.lines.next()? {
+                Ok(line) => line,
+                Err(e) => return Some(Err(e.into())),
+            };
+            if !line.trim().is_empty() {
+                return Some(serde_json::from_str(&line).map_err(Into::into));
+            }
+        }
+    }
+}
+
+/// Summary of the open ports in a results file, read in one pass
+pub fn summarize(path: &Path) -> Result<PortScanSummary> {
+    let mut open = Vec::new();
+    for sample in SampleReader::open(path)? {
+        let sample = sample?;
+        if sample.state != PortState::Open {
+            continue;
+        }
+        let name = sample
+            .service_info
+            .and_then(|info| info.name)
+            .or(sample.service_name);
+        open.push((name, sample.rtt_ms));
+    }
+    Ok(PortScanSummary::from_open_ports(open))
+}
+
+/// `limit` samples of a results file starting at `offset`
+pub fn read_page(path: &Path, offset: usize, limit: usize) -> Result<Vec<PortScanSample>> {
+    SampleReader::open(path)?.skip(offset).take(limit).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::{IpAddr, Ipv4Addr};
+
+    fn sample(port: u16, service: &str, rtt_ms: u64) -> PortScanSample {
+        PortScanSample {
+            rtt_ms: Some(rtt_ms),
+            service_name: Some(service.to_string()),
+            ..PortScanSample::fixture(IpAddr::V4(Ipv4Addr::LOCALHOST), port, PortState::Open)
+        }
+    }
+
+    #[test]
+    fn writes_and_reads_back_samples() {
+        let path = std::env::temp_dir().join(format!("np-results-{}.jsonl", std::process::id()));
+        let mut writer = SampleWriter::create(path.clone()).unwrap();
+        for (port, service, rtt) in [(22, "ssh", 3), (80, "http", 5), (8080, "http", 7)] {
+            writer.push(&sample(port, service, rtt)).unwrap();
+        }
+        assert_eq!(writer.count(), 3);
+        let path = writer.finish().unwrap();
+
+        let mut reader = SampleReader::open(&path).unwrap();
+        assert_eq!(reader.next_batch(2).unwrap().len(), 2);
+        assert_eq!(reader.next_batch(2).unwrap().len(), 1);
+        assert!(reader.next_batch(2).unwrap().is_empty());
+
+        let page: Vec<u16> = read_page(&path, 1, 5)
+            .unwrap()
+            .iter()
+            .map(|s| s.port)
+            .collect();
+        assert_eq!(page, [80, 8080]);
+
+        let summary = summarize(&path).unwrap();
+        assert_eq!(summary.open, 3);
+        assert_eq!(summary.by_service[0].name, "http");
+        assert_eq!(summary.by_service[0].count, 2);
+        assert_eq!(summary.rtt.unwrap().max_ms, 7);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn spilled_reports_read_samples_back() {
+        use crate::model::endpoint::Endpoint;
+        use crate::model::scan::PortScanReport;
+
+        let path = std::env::temp_dir().join(format!("np-spilled-{}.jsonl", std::process::id()));
+        let mut writer = SampleWriter::create(path.clone()).unwrap();
+        for (port, service, rtt) in [(443, "https", 4), (22, "ssh", 2)] {
+            writer.push(&sample(port, service, rtt)).unwrap();
+        }
+        let path = writer.finish().unwrap();
+        let report = PortScanReport {
+            ports_scanned: Some(2),
+            results_file: Some(path.to_string_lossy().into_owned()),
+            ..PortScanReport::fixture(IpAddr::V4(Ipv4Addr::LOCALHOST), Vec::new())
+        };
+
+        assert_eq!(report.open_ports().unwrap(), [22, 443]);
+        assert_eq!(report.with_samples().unwrap().samples.len(), 2);
+        let mut endpoint = Endpoint::new(report.ip_addr);
+        endpoint.add_report(&report).unwrap();
+        assert_eq!(endpoint.ports.len(), 2);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn rejects_paths_outside_results_dir() {
+        assert!(results_path("../history/x").is_err());
+    }
+}
@@ -1,3 +1,5 @@
+pub mod notify;
+pub mod schedule;
 pub mod task;
 
 use std::{sync::Arc, time::Duration};
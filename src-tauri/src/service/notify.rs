@@ -0,0 +1,132 @@
+use anyhow::{bail, Context, Result};
+use reqwest::Client;
+use serde::Serialize;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use crate::config::NotifyConfig;
+use crate::history::ScanRecord;
+use crate::model::diff::ScanDiff;
+
+/// Bumped only on breaking changes to `WebhookPayload`
+pub const WEBHOOK_SCHEMA_VERSION: u32 = 1;
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+const WEBHOOK_MAX_ATTEMPTS: u32 = 4;
+const WEBHOOK_INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+
+static HTTP_CLIENT: OnceLock<Client> = OnceLock::new();
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookEvent {
+    /// A scheduled scan finished
+    ScanComplete,
+    /// A scheduled scan differs from its previous run
+    ScanChanged,
+}
+
+/// JSON body POSTed to the webhook URL
+#[derive(Serialize, Clone)]
+pub struct WebhookPayload {
+    pub schema_version: u32,
+    pub event: WebhookEvent,
+    pub job_id: String,
+    pub run_id: String,
+    /// Run the diff was computed against
+    pub previous_run_id: Option<String>,
+    /// "PortScan" or "HostScan"
+    pub kind: String,
+    pub target: String,
+    /// Unix timestamp in milliseconds
+    pub finished_at_ms: u64,
+    /// Present for `scan_changed`
+    pub diff: Option<ScanDiff>,
+}
+
+fn http_client() -> Result<&'static Client> {
+    if let Some(client) = HTTP_CLIENT.get() {
+        return Ok(client);
+    }
+    let client = Client::builder()
+        .timeout(WEBHOOK_TIMEOUT)
+        .build()
+        .context("build http client")?;
+    Ok(HTTP_CLIENT.get_or_init(|| client))
+}
+
+/// POST the payload, retrying with exponential backoff on
+/// connection errors and 5xx/429 responses.
+pub async fn post_webhook(url: &str, payload: &WebhookPayload) -> Result<()> {
+    let client = http_client()?;
+    let mut backoff = WEBHOOK_INITIAL_BACKOFF;
+    let mut attempt = 1;
+    loop {
+        let err = match client.post(url).json(payload).send().await {
+            Ok(resp) if resp.status().is_success() => return Ok(()),
+            Ok(resp) => {
+                let status = resp.status();
+                // Other client errors won't succeed on retry
+                if status.is_client_error() && status != reqwest::StatusCode::TOO_MANY_REQUESTS {
+                    bail!("POST {} -> HTTP {}", url, status);
+                }
+                anyhow::anyhow!("POST {} -> HTTP {}", url, status)
+            }
+            Err(e) => anyhow::Error::from(e).context(format!("POST {}", url)),
+        };
+        if attempt >= WEBHOOK_MAX_ATTEMPTS {
+            return Err(err);
+        }
+        tracing::debug!("webhook attempt {} failed: {:#}", attempt, err);
+        tokio::time::sleep(backoff).await;
+        backoff *= 2;
+        attempt += 1;
+    }
+}
+
+/// Send the notifications enabled in `config` for a finished scheduled scan.
+pub async fn notify_scan(
+    config: &NotifyConfig,
+    job_id: &str,
+    record: &ScanRecord,
+    previous: Option<&ScanRecord>,
+    finished_at_ms: u64,
+) {
+    let Some(url) = config.webhook_url.as_deref().filter(|u| !u.is_empty()) else {
+        return;
+    };
+    let kind = match record {
+        ScanRecord::PortScan(_) => "PortScan",
+        ScanRecord::HostScan(_) => "HostScan",
+    };
+    let diff = previous
+        .and_then(|prev| ScanDiff::between(prev, record).ok())
+        .filter(|d| !d.is_empty());
+
+    let mut events = Vec::new();
+    if config.on_complete {
+        events.push(WebhookEvent::ScanComplete);
+    }
+    if config.on_change && diff.is_some() {
+        events.push(WebhookEvent::ScanChanged);
+    }
+    for event in events {
+        let payload = WebhookPayload {
+            schema_version: WEBHOOK_SCHEMA_VERSION,
+            event,
+            job_id: job_id.to_string(),
+            run_id: record.run_id().to_string(),
+            previous_run_id: previous.map(|p| p.run_id().to_string()),
+            kind: kind.to_string(),
+            target: record.target(),
+            finished_at_ms,
+            diff: if event == WebhookEvent::ScanChanged {
+                diff.clone()
+            } else {
+                None
+            },
+        };
+        if let Err(e) = post_webhook(url, &payload).await {
+            tracing::warn!("webhook notification failed: {:#}", e);
+        }
+    }
+}
@@ -0,0 +1,247 @@
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::async_runtime::{self, JoinHandle};
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::sync::Mutex;
+use tokio::time::{interval_at, Instant, MissedTickBehavior};
+
+use crate::command::config::ConfigState;
+use crate::config::ScanTemplateSetting;
+use crate::error::ScanError;
+use crate::history::ScanRecord;
+use crate::probe::scan::sink::JobSink;
+use crate::state::SharedState;
+
+pub const DEFAULT_SCHEDULE_FILE_NAME: &str = "netpulse-schedule.json";
+/// Shortest allowed interval between runs of a job
+pub const MIN_JOB_INTERVAL_SECS: u64 = 60;
+
+/// Recurring scan job
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ScanJob {
+    pub id: String,
+    pub name: Option<String>,
+    pub setting: ScanTemplateSetting,
+    pub interval_secs: u64,
+    /// Last successful run, loaded from history as the baseline of change
+    /// notifications, across restarts too
+    #[serde(default)]
+    pub last_run_id: Option<String>,
+}
+
+/// Payload of `schedule:run_complete`
+#[derive(Serialize, Clone, Debug)]
+pub struct ScheduleRunPayload {
+    pub job_id: String,
+    /// Run ID of the stored report, if the scan succeeded
+    pub run_id: Option<String>,
+    pub error: Option<String>,
+    /// Unix timestamp in milliseconds
+    pub finished_at_ms: u64,
+}
+
+/// Scheduled jobs and their background runners
+#[derive(Debug, Default)]
+pub struct Scheduler {
+    jobs: Mutex<HashMap<String, (ScanJob, JoinHandle<()>)>>,
+}
+
+impl Scheduler {
+    /// Persist a new job, then start its runner.
+    pub async fn add(
+        &self,
+        app: &AppHandle,
+        state: &SharedState,
+        name: Option<String>,
+        setting: ScanTemplateSetting,
+        interval_secs: u64,
+    ) -> Result<ScanJob> {
+        if interval_secs < MIN_JOB_INTERVAL_SECS {
+            bail!(ScanError::invalid_input(format!(
+                "Interval must be at least {} seconds",
+                MIN_JOB_INTERVAL_SECS
+            )));
+        }
+        let job = ScanJob {
+            id: uuid::Uuid::new_v4().to_string(),
+            name,
+            setting,
+            interval_secs,
+            last_run_id: None,
+        };
+        let mut jobs = self.jobs.lock().await;
+        // Persist first, so a job that could not be saved never runs
+        save_jobs(jobs.values().map(|(j, _)| j).chain(std::iter::once(&job)))?;
+        let handle = spawn_runner(app.clone(), state.clone(), job.clone());
+        jobs.insert(job.id.clone(), (job.clone(), handle));
+        Ok(job)
+    }
+
+    /// Stop and remove a job. Returns false if no such job exists.
+    pub async fn remove(&self, job_id: &str) -> Result<bool> {
+        let mut jobs = self.jobs.lock().await;
+        let Some((_, handle)) = jobs.remove(job_id) else {
+            return Ok(false);
+        };
+        handle.abort();
+        save_jobs(jobs.values().map(|(j, _)| j))?;
+        Ok(true)
+    }
+
+    /// Persist the latest successful run of a job as its change baseline.
+    async fn set_last_run(&self, job_id: &str, run_id: &str) -> Result<()> {
+        let mut jobs = self.jobs.lock().await;
+        let Some((job, _)) = jobs.get_mut(job_id) else {
+            // Removed while the run was in progress
+            return Ok(());
+        };
+        job.last_run_id = Some(run_id.to_string());
+        save_jobs(jobs.values().map(|(j, _)| j))
+    }
+
+    pub async fn list(&self) -> Vec<ScanJob> {
+        let jobs = self.jobs.lock().await;
+        let mut list: Vec<ScanJob> = jobs.values().map(|(j, _)| j.clone()).collect();
+        list.sort_by(|a, b| a.id.cmp(&b.id));
+        list
+    }
+
+    /// Start runners for the jobs persisted by a previous session.
+    pub async fn restore(&self, app: &AppHandle, state: &SharedState) {
+        let mut jobs = self.jobs.lock().await;
+        for job in load_jobs() {
+            let handle = spawn_runner(app.clone(), state.clone(), job.clone());
+            jobs.insert(job.id.clone(), (job, handle));
+        }
+    }
+}
+
+fn load_jobs() -> Vec<ScanJob> {
+    let Some(path) = crate::fs::get_user_file_path(DEFAULT_SCHEDULE_FILE_NAME) else {
+        return Vec::new();
+    };
+    match std::fs::read_to_string(&path) {
+        Ok(content) => match serde_json::from_str(&content) {
+            Ok(jobs) => jobs,
+            Err(e) => {
+                tracing::error!("{:?}", e);
+                Vec::new()
+            }
+        },
+        // No schedule saved yet
+        Err(_) => Vec::new(),
+    }
+}
+
+fn save_jobs<'a>(jobs: impl Iterator<Item = &'a ScanJob>) -> Result<()> {
+    let Some(path) = crate::fs::get_user_file_path(DEFAULT_SCHEDULE_FILE_NAME) else {
+        bail!("Failed to resolve schedule file path");
+    };
+    let jobs: Vec<&ScanJob> = jobs.collect();
+    std::fs::write(&path, serde_json::to_string_pretty(&jobs)?)?;
+    Ok(())
+}
+
+/// Report of a previous run from history. `None` if it was evicted or
+/// cannot be read, in which case no change notification is sent.
+async fn load_baseline(run_id: String) -> Option<ScanRecord> {
+    let loaded = tokio::task::spawn_blocking(move || crate::history::load(&run_id)).await;
+    match loaded {
+        Ok(Ok(entry)) => Some(entry.record),
+        Ok(Err(e)) => {
+            tracing::debug!("No baseline for change detection: {}", e);
+            None
+        }
+        Err(e) => {
+            tracing::warn!("Failed to load change baseline: {}", e);
+            None
+        }
+    }
+}
+
+/// Run the job every `interval_secs`, starting one interval from now.
+fn spawn_runner(app: AppHandle, state: SharedState, job: ScanJob) -> JoinHandle<()> {
+    async_runtime::spawn(async move {
+        let period = Duration::from_secs(job.interval_secs);
+        let mut tick = interval_at(Instant::now() + period, period);
+        // A long scan should not trigger a burst of catch-up runs
+        tick.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        // Last successful run, compared against for change notifications
+        let mut last_run_id = job.last_run_id.clone();
+        loop {
+            tick.tick().await;
+            let result = run_job(&app, &state, &job).await;
+            let finished_at_ms = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_millis() as u64)
+                .unwrap_or(0);
+            let payload = match result {
+                Ok(record) => {
+                    let notify_config = app.state::<ConfigState>().0.read().await.notify.clone();
+                    let job_id = job.id.clone();
+                    let run_id = record.run_id().to_string();
+                    let previous_run_id = last_run_id.replace(run_id.clone());
+                    if let Err(e) = state.scheduler.set_last_run(&job.id, &run_id).await {
+                        tracing::warn!("Failed to save last run of job {}: {}", job.id, e);
+                    }
+                    // Webhook retries must not delay the next run
+                    async_runtime::spawn(async move {
+                        let prev = match previous_run_id {
+                            Some(id) => load_baseline(id).await,
+                            None => None,
+                        };
+                        crate::service::notify::notify_scan(
+                            &notify_config,
+                            &job_id,
+                            &record,
+                            prev.as_ref(),
+                            finished_at_ms,
+                        )
+                        .await;
+                    });
+                    ScheduleRunPayload {
+                        job_id: job.id.clone(),
+                        run_id: Some(run_id),
+                        error: None,
+                        finished_at_ms,
+                    }
+                }
+                Err(e) => {
+                    tracing::warn!("scheduled job {} failed: {}", job.id, e);
+                    ScheduleRunPayload {
+                        job_id: job.id.clone(),
+                        run_id: None,
+                        error: Some(e.to_string()),
+                        finished_at_ms,
+                    }
+                }
+            };
+            let _ = app.emit("schedule:run_complete", payload);
+        }
+    })
+}
+
+async fn run_job(
+    app: &AppHandle,
+    state: &SharedState,
+    job: &ScanJob,
+) -> std::result::Result<ScanRecord, ScanError> {
+    // Service detection needs the probe databases
+    crate::command::scan::init_probe_db().await?;
+    // Progress goes out as `schedule:progress`, not as the scan's own events
+    let sink = JobSink::new(app.clone(), &job.id);
+    match &job.setting {
+        ScanTemplateSetting::PortScan(setting) => {
+            crate::command::scan::run_port_scan(&sink, state, setting.clone())
+                .await
+                .map(ScanRecord::PortScan)
+        }
+        ScanTemplateSetting::HostScan(setting) => {
+            crate::command::scan::run_host_scan(&sink, state, setting.clone(), None)
+                .await
+                .map(ScanRecord::HostScan)
+        }
+    }
+}
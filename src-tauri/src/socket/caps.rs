@@ -0,0 +1,96 @@
+use std::io;
+
+use crate::model::{SocketCapability, SocketKind};
+use crate::socket::icmp::{AsyncIcmpSocket, IcmpConfig, IcmpKind};
+use crate::socket::quic::{AsyncQuicSocket, QuicConfig};
+use crate::socket::tcp::AsyncTcpSocket;
+use crate::socket::udp::AsyncUdpSocket;
+use crate::socket::SocketFamily;
+
+/// What usually fixes a failed socket open on this platform.
+fn hint(kind: SocketKind, e: &io::Error) -> Option<String> {
+    let v6 = matches!(
+        kind,
+        SocketKind::IcmpV6 | SocketKind::RawV6 | SocketKind::TcpV6 | SocketKind::UdpV6
+    );
+    let hint = match e.kind() {
+        io::ErrorKind::PermissionDenied => {
+            if cfg!(target_os = "linux") {
+                match kind {
+                    SocketKind::IcmpV4 | SocketKind::IcmpV6 => {
+                        "needs CAP_NET_RAW, or your group in net.ipv4.ping_group_range"
+                    }
+                    _ => "needs CAP_NET_RAW (run as root or grant the capability)",
+                }
+            } else if cfg!(windows) {
+                "needs to run as Administrator"
+            } else {
+                "needs root privileges"
+            }
+        }
+        io::ErrorKind::Unsupported | io::ErrorKind::AddrNotAvailable if v6 => {
+            "IPv6 looks disabled on this system"
+        }
+        _ => return None,
+    };
+    Some(hint.to_string())
+}
+
+fn capability(kind: SocketKind, result: io::Result<Option<String>>) -> SocketCapability {
+    match result {
+        Ok(detail) => SocketCapability {
+            kind,
+            supported: true,
+            detail,
+            error: None,
+            hint: None,
+        },
+        Err(e) => SocketCapability {
+            kind,
+            supported: false,
+            detail: None,
+            hint: hint(kind, &e),
+            error: Some(e.to_string()),
+        },
+    }
+}
+
+async fn open_icmp(kind: IcmpKind) -> io::Result<Option<String>> {
+    let socket = AsyncIcmpSocket::new(&IcmpConfig::new(kind)).await?;
+    let detail = if socket.socket_type().is_dgram() {
+        "DGRAM"
+    } else {
+        "RAW"
+    };
+    Ok(Some(detail.to_string()))
+}
+
+fn open_quic() -> io::Result<Option<String>> {
+    let config = QuicConfig {
+        skip_verify: true,
+        alpn: vec![b"h3".to_vec()],
+        family: SocketFamily::IPV4,
+    };
+    AsyncQuicSocket::from_config(&config)
+        .map(|_| None)
+        .map_err(|e| match e.downcast::<io::Error>() {
+            Ok(e) => e,
+            Err(e) => io::Error::other(e.to_string()),
+        })
+}
+
+/// Open each socket type the scanners use and report which work.
+/// Sockets are closed right away; nothing is sent.
+pub async fn check_capabilities() -> Vec<SocketCapability> {
+    vec![
+        capability(SocketKind::IcmpV4, open_icmp(IcmpKind::V4).await),
+        capability(SocketKind::IcmpV6, open_icmp(IcmpKind::V6).await),
+        capability(SocketKind::RawV4, AsyncTcpSocket::raw_v4().map(|_| None)),
+        capability(SocketKind::RawV6, AsyncTcpSocket::raw_v6().map(|_| None)),
+        capability(SocketKind::TcpV4, AsyncTcpSocket::v4_stream().map(|_| None)),
+        capability(SocketKind::TcpV6, AsyncTcpSocket::v6_stream().map(|_| None)),
+        capability(SocketKind::UdpV4, AsyncUdpSocket::v4_dgram().map(|_| None)),
+        capability(SocketKind::UdpV6, AsyncUdpSocket::v6_dgram().map(|_| None)),
+        capability(SocketKind::Quic, open_quic()),
+    ]
+}
@@ -3,6 +3,7 @@ use socket2::{Domain, Protocol, Socket, Type as SockType};
 use std::io;
 use std::net::{SocketAddr, UdpSocket as StdUdpSocket};
 use std::time::Duration;
+use tokio::io::Interest;
 use tokio::net::UdpSocket;
 
 /// ICMP protocol version.
@@ -69,6 +70,10 @@ pub struct IcmpConfig {
     pub sock_type_hint: IcmpSocketType,
     /// FreeBSD only: optional FIB (Forwarding Information Base) support.
     pub fib: Option<u32>,
+    /// Linux only: optional firewall mark (SO_MARK) for policy routing.
+    pub fwmark: Option<u32>,
+    /// Receive the TTL / hop limit of incoming packets as ancillary data (Unix only).
+    pub recv_ttl: bool,
 }
 
 impl IcmpConfig {
@@ -87,6 +92,8 @@ impl IcmpConfig {
             interface: None,
             sock_type_hint: IcmpSocketType::Dgram,
             fib: None,
+            fwmark: None,
+            recv_ttl: false,
         }
     }
 
@@ -137,6 +144,18 @@ impl IcmpConfig {
         self.fib = Some(fib);
         self
     }
+
+    /// Set the firewall mark for Linux.
+    pub fn with_fwmark(mut self, mark: u32) -> Self {
+        self.fwmark = Some(mark);
+        self
+    }
+
+    /// Enable receiving the TTL / hop limit of incoming packets.
+    pub fn with_recv_ttl(mut self, enable: bool) -> Self {
+        self.recv_ttl = enable;
+        self
+    }
 }
 
 /// Asynchronous ICMP socket built on Tokio.
@@ -188,11 +207,21 @@ impl AsyncIcmpSocket {
         if let Some(fib) = config.fib {
             socket.set_fib(fib)?;
         }
+        // Unix: request TTL / hop limit of received packets as ancillary data
+        #[cfg(unix)]
+        if config.recv_ttl {
+            set_recv_ttl(&socket, config.socket_family)?;
+        }
         // Linux: optional interface name
         #[cfg(any(target_os = "linux", target_os = "android", target_os = "fuchsia"))]
         if let Some(interface) = &config.interface {
             socket.bind_device(Some(interface.as_bytes()))?;
         }
+        // Linux: optional firewall mark
+        #[cfg(any(target_os = "linux", target_os = "android", target_os = "fuchsia"))]
+        if let Some(mark) = config.fwmark {
+            socket.set_mark(mark)?;
+        }
 
         // bind to the specified address if provided
         if let Some(addr) = &config.bind {
@@ -235,11 +264,62 @@ impl AsyncIcmpSocket {
         self.inner.recv_from(buf).await
     }
 
+    /// Receive a packet asynchronously along with the TTL / hop limit of the reply.
+    ///
+    /// The value is read from ancillary data when enabled with `IcmpConfig::with_recv_ttl`,
+    /// or from the IPv4 header on RAW sockets. `None` if the platform does not expose it.
+    pub async fn recv_from_with_ttl(
+        &self,
+        buf: &mut [u8],
+    ) -> io::Result<(usize, SocketAddr, Option<u8>)> {
+        #[cfg(unix)]
+        let (n, addr, ttl) = {
+            let fd = self.as_raw_fd();
+            self.inner
+                .async_io(Interest::READABLE, || recvmsg_with_ttl(fd, buf))
+                .await?
+        };
+        #[cfg(not(unix))]
+        let (n, addr, ttl) = {
+            let (n, addr) = self.inner.recv_from(buf).await?;
+            (n, addr, None)
+        };
+
+        let ttl = ttl.or_else(|| self.ttl_from_ip_header(&buf[..n]));
+        Ok((n, addr, ttl))
+    }
+
+    /// Read the TTL from the IPv4 header included on RAW sockets.
+    fn ttl_from_ip_header(&self, packet: &[u8]) -> Option<u8> {
+        if self.socket_type.is_raw()
+            && self.socket_family == SocketFamily::IPV4
+            && packet.len() >= 20
+            && packet[0] >> 4 == 4
+        {
+            Some(packet[8])
+        } else {
+            None
+        }
+    }
+
     /// Retrieve the local address.
     pub fn local_addr(&self) -> io::Result<SocketAddr> {
         self.inner.local_addr()
     }
 
+    /// Identifier carried on the wire by echo requests sent with `requested` as id.
+    /// Linux ping (DGRAM) sockets replace it with the local port, assigned on the
+    /// first send, and only deliver replies carrying that identifier.
+    pub fn echo_id(&self, requested: u16) -> u16 {
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        if self.socket_type.is_dgram() {
+            if let Ok(addr) = self.local_addr() {
+                return addr.port();
+            }
+        }
+        requested
+    }
+
     /// Return the socket type (DGRAM or RAW).
     pub fn socket_type(&self) -> IcmpSocketType {
         self.socket_type
@@ -272,3 +352,94 @@ impl AsyncIcmpSocket {
         self.inner.as_raw_socket()
     }
 }
+
+/// Enable IP_RECVTTL / IPV6_RECVHOPLIMIT on the socket.
+#[cfg(unix)]
+fn set_recv_ttl(socket: &Socket, family: SocketFamily) -> io::Result<()> {
+    use std::os::fd::AsRawFd;
+
+    let (level, name) = match family {
+        SocketFamily::IPV4 => (libc::IPPROTO_IP, libc::IP_RECVTTL),
+        SocketFamily::IPV6 => (libc::IPPROTO_IPV6, libc::IPV6_RECVHOPLIMIT),
+    };
+    let on: libc::c_int = 1;
+    let ret = unsafe {
+        libc::setsockopt(
+            socket.as_raw_fd(),
+            level,
+            name,
+            (&on as *const libc::c_int).cast(),
+            std::mem::size_of::<libc::c_int>() as libc::socklen_t,
+        )
+    };
+    if ret < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// Receive a datagram with `recvmsg(2)` and extract the TTL / hop limit
+/// from the control messages, if present.
+#[cfg(unix)]
+fn recvmsg_with_ttl(
+    fd: std::os::unix::io::RawFd,
+    buf: &mut [u8],
+) -> io::Result<(usize, SocketAddr, Option<u8>)> {
+    let mut storage: libc::sockaddr_storage = unsafe { std::mem::zeroed() };
+    let mut iov = libc::iovec {
+        iov_base: buf.as_mut_ptr().cast(),
+        iov_len: buf.len(),
+    };
+    // u64 elements keep the control buffer aligned for cmsghdr.
+    let mut control = [0u64; 16];
+    let mut msg: libc::msghdr = unsafe { std::mem::zeroed() };
+    msg.msg_name = (&mut storage as *mut libc::sockaddr_storage).cast();
+    msg.msg_namelen = std::mem::size_of::<libc::sockaddr_storage>() as libc::socklen_t;
+    msg.msg_iov = &mut iov;
+    msg.msg_iovlen = 1;
+    msg.msg_control = control.as_mut_ptr().cast();
+    msg.msg_controllen = std::mem::size_of_val(&control) as _;
+
+    let n = unsafe { libc::recvmsg(fd, &mut msg, 0) };
+    if n < 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    let mut ttl = None;
+    unsafe {
+        let mut cmsg = libc::CMSG_FIRSTHDR(&msg);
+        while !cmsg.is_null() {
+            let hdr = &*cmsg;
+            if let Some(v) = cmsg_ttl(hdr.cmsg_level, hdr.cmsg_type, libc::CMSG_DATA(cmsg)) {
+                ttl = Some(v);
+            }
+            cmsg = libc::CMSG_NXTHDR(&msg, cmsg);
+        }
+    }
+
+    let addr = unsafe { socket2::SockAddr::new(storage, msg.msg_namelen) };
+    let addr = addr
+        .as_socket()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "unsupported address family"))?;
+    Ok((n as usize, addr, ttl))
+}
+
+/// Decode a TTL / hop limit control message.
+#[cfg(unix)]
+unsafe fn cmsg_ttl(level: libc::c_int, ty: libc::c_int, data: *const u8) -> Option<u8> {
+    if level == libc::IPPROTO_IP {
+        // Linux reports IP_TTL as an int, BSD-derived systems report IP_RECVTTL as a byte.
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        if ty == libc::IP_TTL {
+            return Some(std::ptr::read_unaligned(data as *const libc::c_int) as u8);
+        }
+        #[cfg(not(any(target_os = "linux", target_os = "android")))]
+        if ty == libc::IP_RECVTTL {
+            return Some(*data);
+        }
+    }
+    if level == libc::IPPROTO_IPV6 && ty == libc::IPV6_HOPLIMIT {
+        return Some(std::ptr::read_unaligned(data as *const libc::c_int) as u8);
+    }
+    None
+}
@@ -0,0 +1,115 @@
+use socket2::{Domain, Protocol, Socket, Type as SockType};
+use std::io;
+use std::net::{SocketAddr, UdpSocket as StdUdpSocket};
+use tokio::net::UdpSocket;
+
+/// Protocol number of a send-only raw socket (IPPROTO_RAW)
+const IPPROTO_RAW: u8 = 255;
+
+/// Asynchronous raw IPv4 socket for an arbitrary IP protocol number.
+/// The kernel builds the IP header; received packets include it.
+/// Requires administrator privileges.
+#[derive(Debug)]
+pub struct AsyncRawIpSocket {
+    inner: UdpSocket,
+    protocol: u8,
+}
+
+impl AsyncRawIpSocket {
+    /// Create a raw IPv4 socket for the given protocol number.
+    pub fn new_v4(protocol: u8) -> io::Result<Self> {
+        let socket = Socket::new(
+            Domain::IPV4,
+            SockType::RAW,
+            Some(Protocol::from(protocol as i32)),
+        )?;
+        socket.set_nonblocking(true)?;
+
+        #[cfg(windows)]
+        let std_socket = unsafe {
+            use std::os::windows::io::{FromRawSocket, IntoRawSocket};
+            StdUdpSocket::from_raw_socket(socket.into_raw_socket())
+        };
+        #[cfg(unix)]
+        let std_socket = unsafe {
+            use std::os::fd::{FromRawFd, IntoRawFd};
+            StdUdpSocket::from_raw_fd(socket.into_raw_fd())
+        };
+
+        let inner = UdpSocket::from_std(std_socket)?;
+        Ok(Self { inner, protocol })
+    }
+
+    /// Create a send-only raw IPv4 socket (IPPROTO_RAW) with IP_HDRINCL set.
+    /// Packets passed to `send_to` must start with a complete IPv4 header.
+    pub fn new_v4_header_included() -> io::Result<Self> {
+        let socket = Socket::new(
+            Domain::IPV4,
+            SockType::RAW,
+            Some(Protocol::from(IPPROTO_RAW as i32)),
+        )?;
+        socket.set_header_included_v4(true)?;
+        socket.set_nonblocking(true)?;
+
+        #[cfg(windows)]
+        let std_socket = unsafe {
+            use std::os::windows::io::{FromRawSocket, IntoRawSocket};
+            StdUdpSocket::from_raw_socket(socket.into_raw_socket())
+        };
+        #[cfg(unix)]
+        let std_socket = unsafe {
+            use std::os::fd::{FromRawFd, IntoRawFd};
+            StdUdpSocket::from_raw_fd(socket.into_raw_fd())
+        };
+
+        let inner = UdpSocket::from_std(std_socket)?;
+        Ok(Self {
+            inner,
+            protocol: IPPROTO_RAW,
+        })
+    }
+
+    /// Create a send-only raw IPv6 socket (IPPROTO_RAW).
+    /// On Linux this implies a header-included socket, so packets passed to
+    /// `send_to` must start with a complete IPv6 header. Linux only.
+    #[cfg(target_os = "linux")]
+    pub fn new_v6_header_included() -> io::Result<Self> {
+        use std::os::fd::{FromRawFd, IntoRawFd};
+
+        let socket = Socket::new(
+            Domain::IPV6,
+            SockType::RAW,
+            Some(Protocol::from(IPPROTO_RAW as i32)),
+        )?;
+        socket.set_nonblocking(true)?;
+        let std_socket = unsafe { StdUdpSocket::from_raw_fd(socket.into_raw_fd()) };
+        let inner = UdpSocket::from_std(std_socket)?;
+        Ok(Self {
+            inner,
+            protocol: IPPROTO_RAW,
+        })
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    pub fn new_v6_header_included() -> io::Result<Self> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "header-included IPv6 raw sockets are only supported on Linux",
+        ))
+    }
+
+    /// IP protocol number of the socket.
+    pub fn protocol(&self) -> u8 {
+        self.protocol
+    }
+
+    /// Send a payload asynchronously. The port of `target` is ignored.
+    pub async fn send_to(&self, buf: &[u8], target: SocketAddr) -> io::Result<usize> {
+        self.inner.send_to(buf, target).await
+    }
+
+    /// Receive a packet (including the IP header) asynchronously.
+    pub async fn recv_from(&self, buf: &mut [u8]) -> io::Result<(usize, SocketAddr)> {
+        self.inner.recv_from(buf).await
+    }
+}
@@ -1,7 +1,10 @@
 #![allow(dead_code)]
 
+pub mod caps;
 pub mod icmp;
+pub mod ip;
 pub mod os;
+pub mod packet;
 pub mod quic;
 pub mod tcp;
 pub mod tls;
@@ -0,0 +1,117 @@
+use std::io;
+#[cfg(target_os = "linux")]
+use tokio::net::UdpSocket;
+
+/// Asynchronous link-layer capture socket bound to one interface.
+/// Receives every IP packet sent or received on the interface with the
+/// link-layer header removed. Requires administrator privileges.
+/// Only supported on Linux (AF_PACKET).
+#[derive(Debug)]
+pub struct AsyncPacketSocket {
+    #[cfg(target_os = "linux")]
+    inner: UdpSocket,
+    if_index: u32,
+}
+
+impl AsyncPacketSocket {
+    /// Open a capture socket on the interface with the given index.
+    #[cfg(target_os = "linux")]
+    pub fn bind(if_index: u32) -> io::Result<Self> {
+        // SOCK_DGRAM strips the link-layer header, so loopback and
+        // tunnel interfaces yield the same IP packets as Ethernet.
+        Self::open(if_index, socket2::Type::DGRAM, &[])
+    }
+
+    /// Open a socket receiving whole Ethernet frames, including the
+    /// link-layer header, on the interface with the given index.
+    /// Frames sent to the `multicast` MAC addresses are accepted by the
+    /// interface for the lifetime of the socket.
+    #[cfg(target_os = "linux")]
+    pub fn bind_link(if_index: u32, multicast: &[[u8; 6]]) -> io::Result<Self> {
+        Self::open(if_index, socket2::Type::RAW, multicast)
+    }
+
+    #[cfg(target_os = "linux")]
+    fn open(if_index: u32, sock_type: socket2::Type, multicast: &[[u8; 6]]) -> io::Result<Self> {
+        use socket2::{Domain, Protocol, Socket};
+        use std::net::UdpSocket as StdUdpSocket;
+        use std::os::fd::{AsRawFd, FromRawFd, IntoRawFd};
+
+        let eth_p_all = (libc::ETH_P_ALL as u16).to_be();
+        let socket = Socket::new(
+            Domain::PACKET,
+            sock_type,
+            Some(Protocol::from(eth_p_all as i32)),
+        )?;
+
+        let mut sll: libc::sockaddr_ll = unsafe { std::mem::zeroed() };
+        sll.sll_family = libc::AF_PACKET as u16;
+        sll.sll_protocol = eth_p_all;
+        sll.sll_ifindex = if_index as i32;
+        let ret = unsafe {
+            libc::bind(
+                socket.as_raw_fd(),
+                &sll as *const libc::sockaddr_ll as *const libc::sockaddr,
+                std::mem::size_of::<libc::sockaddr_ll>() as libc::socklen_t,
+            )
+        };
+        if ret != 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        for mac in multicast {
+            let mut mreq: libc::packet_mreq = unsafe { std::mem::zeroed() };
+            mreq.mr_ifindex = if_index as i32;
+            mreq.mr_type = libc::PACKET_MR_MULTICAST as u16;
+            mreq.mr_alen = mac.len() as u16;
+            mreq.mr_address[..mac.len()].copy_from_slice(mac);
+            let ret = unsafe {
+                libc::setsockopt(
+                    socket.as_raw_fd(),
+                    libc::SOL_PACKET,
+                    libc::PACKET_ADD_MEMBERSHIP,
+                    &mreq as *const libc::packet_mreq as *const libc::c_void,
+                    std::mem::size_of::<libc::packet_mreq>() as libc::socklen_t,
+                )
+            };
+            if ret != 0 {
+                return Err(io::Error::last_os_error());
+            }
+        }
+        socket.set_nonblocking(true)?;
+
+        let std_socket = unsafe { StdUdpSocket::from_raw_fd(socket.into_raw_fd()) };
+        let inner = UdpSocket::from_std(std_socket)?;
+        Ok(Self { inner, if_index })
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    pub fn bind(_if_index: u32) -> io::Result<Self> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "packet capture is only supported on Linux",
+        ))
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    pub fn bind_link(_if_index: u32, _multicast: &[[u8; 6]]) -> io::Result<Self> {
+        Self::bind(0)
+    }
+
+    /// Index of the interface the socket is bound to.
+    pub fn if_index(&self) -> u32 {
+        self.if_index
+    }
+
+    /// Receive one packet asynchronously. Starts at the IP header, or at the
+    /// Ethernet header for sockets opened with `bind_link`.
+    #[cfg(target_os = "linux")]
+    pub async fn recv(&self, buf: &mut [u8]) -> io::Result<usize> {
+        self.inner.recv(buf).await
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    pub async fn recv(&self, _buf: &mut [u8]) -> io::Result<usize> {
+        Err(io::Error::from(io::ErrorKind::Unsupported))
+    }
+}
@@ -1,9 +1,35 @@
 use super::tls::SkipServerVerification;
 use anyhow::Result;
-use quinn::{ClientConfig, Endpoint as QuinnEndpoint};
+use quinn::udp::{RecvMeta, Transmit};
+use quinn::{AsyncUdpSocket, ClientConfig, Endpoint as QuinnEndpoint, EndpointConfig, UdpPoller};
 use rustls::{ClientConfig as RustlsClientConfig, RootCertStore};
+use std::io::{self, IoSliceMut};
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::task::{Context, Poll};
 use std::{net::SocketAddr, sync::Arc, time::Duration};
 
+/// QUIC version 1 (RFC 9000)
+pub const QUIC_VERSION_1: u32 = 0x0000_0001;
+
+/// Label for a QUIC version in the `ServiceInfo::quic_version` format.
+pub fn quic_version_label(version: u32) -> String {
+    match version {
+        QUIC_VERSION_1 => "1".to_string(),
+        v if v & 0xffff_ff00 == 0xff00_0000 => format!("draft-{}", v & 0xff),
+        v => format!("0x{:08x}", v),
+    }
+}
+
+/// Get the ALPN protocol negotiated on an established connection.
+pub fn negotiated_alpn(conn: &quinn::Connection) -> Option<String> {
+    conn.handshake_data()?
+        .downcast::<quinn::crypto::rustls::HandshakeData>()
+        .ok()?
+        .protocol
+        .map(|p| String::from_utf8_lossy(&p).to_string())
+}
+
 /// Create a QUIC client configuration with optional certificate verification skipping and ALPN protocols.
 pub fn quic_client_config(skip_verify: bool, alpn: &Vec<Vec<u8>>) -> Result<ClientConfig> {
     let mut roots = RootCertStore::empty();
@@ -20,7 +46,85 @@ pub fn quic_client_config(skip_verify: bool, alpn: &Vec<Vec<u8>>) -> Result<Clie
     tls.enable_early_data = true;
     tls.alpn_protocols = alpn.iter().map(|p| p.to_vec()).collect();
     let client_conf = quinn::crypto::rustls::QuicClientConfig::try_from(tls)?;
-    Ok(ClientConfig::new(Arc::new(client_conf)))
+    let mut client_cfg = ClientConfig::new(Arc::new(client_conf));
+    // quinn never downgrades, so this is also the version of every established connection
+    client_cfg.version(QUIC_VERSION_1);
+    Ok(client_cfg)
+}
+
+/// Check whether a datagram starts with a QUIC Retry packet.
+fn is_retry_packet(datagram: &[u8]) -> bool {
+    // Long header form bit
+    if datagram.len() < 5 || datagram[0] & 0x80 == 0 {
+        return false;
+    }
+    let version = u32::from_be_bytes([datagram[1], datagram[2], datagram[3], datagram[4]]);
+    let packet_type = (datagram[0] & 0x30) >> 4;
+    match version {
+        // Version negotiation
+        0 => false,
+        // QUIC v2 (RFC 9369) renumbers the long header types
+        0x6b33_43cf => packet_type == 0,
+        _ => packet_type == 3,
+    }
+}
+
+/// Observations on datagrams received by a QUIC endpoint.
+#[derive(Debug, Default)]
+struct PacketTap {
+    retry: AtomicBool,
+}
+
+/// UDP socket wrapper that records handshake events quinn does not expose.
+#[derive(Debug)]
+struct TapSocket {
+    inner: Arc<dyn AsyncUdpSocket>,
+    tap: Arc<PacketTap>,
+}
+
+impl AsyncUdpSocket for TapSocket {
+    fn create_io_poller(self: Arc<Self>) -> Pin<Box<dyn UdpPoller>> {
+        self.inner.clone().create_io_poller()
+    }
+
+    fn try_send(&self, transmit: &Transmit) -> io::Result<()> {
+        self.inner.try_send(transmit)
+    }
+
+    fn poll_recv(
+        &self,
+        cx: &mut Context,
+        bufs: &mut [IoSliceMut<'_>],
+        meta: &mut [RecvMeta],
+    ) -> Poll<io::Result<usize>> {
+        let res = self.inner.poll_recv(cx, bufs, meta);
+        if let Poll::Ready(Ok(n)) = &res {
+            for (buf, m) in bufs.iter().zip(meta.iter()).take(*n) {
+                // A buffer may hold several datagrams when GRO is in use
+                let stride = m.stride.max(1);
+                if buf[..m.len].chunks(stride).any(is_retry_packet) {
+                    self.tap.retry.store(true, Ordering::Release);
+                }
+            }
+        }
+        res
+    }
+
+    fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.inner.local_addr()
+    }
+
+    fn max_transmit_segments(&self) -> usize {
+        self.inner.max_transmit_segments()
+    }
+
+    fn max_receive_segments(&self) -> usize {
+        self.inner.max_receive_segments()
+    }
+
+    fn may_fragment(&self) -> bool {
+        self.inner.may_fragment()
+    }
 }
 
 /// Configuration options for a QUIC socket.
@@ -35,23 +139,72 @@ pub struct QuicConfig {
 #[derive(Debug)]
 pub struct AsyncQuicSocket {
     inner: QuinnEndpoint,
+    version: u32,
+    tap: Arc<PacketTap>,
 }
 
 impl AsyncQuicSocket {
     /// Create an asynchronous QUIC socket from the given configuration.
     pub fn from_config(config: &QuicConfig) -> Result<Self> {
         let client_cfg = quic_client_config(config.skip_verify, &config.alpn)?;
-        let mut endpoint = QuinnEndpoint::client(
-            (if config.family.is_v6() {
-                "[::]:0"
-            } else {
-                "0.0.0.0:0"
-            })
-            .parse()
-            .unwrap(),
+        let bind_addr: SocketAddr = (if config.family.is_v6() {
+            "[::]:0"
+        } else {
+            "0.0.0.0:0"
+        })
+        .parse()
+        .unwrap();
+        let runtime =
+            quinn::default_runtime().ok_or_else(|| io::Error::other("no async runtime found"))?;
+        let socket = runtime.wrap_udp_socket(std::net::UdpSocket::bind(bind_addr)?)?;
+        let tap = Arc::new(PacketTap::default());
+        let socket = Arc::new(TapSocket {
+            inner: socket,
+            tap: tap.clone(),
+        });
+        let mut endpoint = QuinnEndpoint::new_with_abstract_socket(
+            EndpointConfig::default(),
+            None,
+            socket,
+            runtime,
         )?;
         endpoint.set_default_client_config(client_cfg);
-        Ok(Self { inner: endpoint })
+        Ok(Self {
+            inner: endpoint,
+            version: QUIC_VERSION_1,
+            tap,
+        })
+    }
+
+    /// QUIC version used for outgoing connections.
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+
+    /// Whether the server answered any handshake on this socket with a Retry packet.
+    pub fn retry_received(&self) -> bool {
+        self.tap.retry.load(Ordering::Acquire)
+    }
+
+    /// Reconnect using the session ticket from an earlier connection and report
+    /// whether the server accepted 0-RTT. Returns `None` if no ticket was issued.
+    pub async fn probe_zero_rtt(
+        &self,
+        remote_addr: &SocketAddr,
+        server_name: &str,
+        timeout: Duration,
+    ) -> Result<Option<bool>> {
+        let connecting = self.inner.connect(*remote_addr, server_name)?;
+        match connecting.into_0rtt() {
+            Ok((conn, accepted)) => {
+                let accepted = tokio::time::timeout(timeout, accepted)
+                    .await
+                    .unwrap_or(false);
+                conn.close(0u32.into(), b"done");
+                Ok(Some(accepted))
+            }
+            Err(_) => Ok(None),
+        }
     }
 
     /// Connect to the specified remote address using QUIC.
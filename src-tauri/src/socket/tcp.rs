@@ -45,6 +45,8 @@ pub struct TcpConfig {
     pub nonblocking: bool,
     /// Whether to allow address reuse.
     pub reuseaddr: Option<bool>,
+    /// Whether to allow port reuse (SO_REUSEPORT, Unix only).
+    pub reuseport: Option<bool>,
     /// Whether to disable Nagle's algorithm (TCP_NODELAY).
     pub nodelay: Option<bool>,
     /// Optional linger duration for the socket.
@@ -59,6 +61,8 @@ pub struct TcpConfig {
     pub write_timeout: Option<Duration>,
     /// Optional device to bind the socket to.
     pub bind_device: Option<String>,
+    /// Optional firewall mark (SO_MARK, Linux only).
+    pub fwmark: Option<u32>,
     /// Whether to enable TCP keepalive.
     pub keepalive: Option<bool>,
 }
@@ -72,6 +76,7 @@ impl TcpConfig {
             bind_addr: None,
             nonblocking: false,
             reuseaddr: None,
+            reuseport: None,
             nodelay: None,
             linger: None,
             ttl: None,
@@ -79,6 +84,7 @@ impl TcpConfig {
             read_timeout: None,
             write_timeout: None,
             bind_device: None,
+            fwmark: None,
             keepalive: None,
         }
     }
@@ -127,6 +133,11 @@ impl TcpConfig {
         self
     }
 
+    pub fn with_reuseport(mut self, flag: bool) -> Self {
+        self.reuseport = Some(flag);
+        self
+    }
+
     pub fn with_nodelay(mut self, flag: bool) -> Self {
         self.nodelay = Some(flag);
         self
@@ -137,6 +148,12 @@ impl TcpConfig {
         self
     }
 
+    /// Close with an RST instead of a FIN (SO_LINGER with timeout 0),
+    /// so no TIME_WAIT state is left behind.
+    pub fn with_reset_on_close(self) -> Self {
+        self.with_linger(Duration::ZERO)
+    }
+
     pub fn with_ttl(mut self, ttl: u32) -> Self {
         self.ttl = Some(ttl);
         self
@@ -166,6 +183,11 @@ impl TcpConfig {
         self.bind_device = Some(iface.into());
         self
     }
+
+    pub fn with_fwmark(mut self, mark: u32) -> Self {
+        self.fwmark = Some(mark);
+        self
+    }
 }
 
 /// Asynchronous TCP socket built on top of Tokio.
@@ -189,9 +211,16 @@ impl AsyncTcpSocket {
         if let Some(flag) = config.reuseaddr {
             socket.set_reuse_address(flag)?;
         }
+        #[cfg(all(unix, not(any(target_os = "solaris", target_os = "illumos"))))]
+        if let Some(flag) = config.reuseport {
+            socket.set_reuse_port(flag)?;
+        }
         if let Some(flag) = config.nodelay {
             socket.set_nodelay(flag)?;
         }
+        if let Some(linger) = config.linger {
+            socket.set_linger(Some(linger))?;
+        }
         if let Some(ttl) = config.ttl {
             socket.set_ttl(ttl)?;
         }
@@ -213,6 +242,11 @@ impl AsyncTcpSocket {
         if let Some(iface) = &config.bind_device {
             socket.bind_device(Some(iface.as_bytes()))?;
         }
+        // Linux: optional firewall mark for policy routing
+        #[cfg(any(target_os = "linux", target_os = "android", target_os = "fuchsia"))]
+        if let Some(mark) = config.fwmark {
+            socket.set_mark(mark)?;
+        }
 
         // bind to the specified address if provided
         if let Some(addr) = config.bind_addr {
@@ -7,6 +7,10 @@ use std::{
 use tauri::async_runtime::JoinHandle;
 use tokio::sync::Mutex;
 
+use crate::log::RunLogBuffer;
+use crate::probe::scan::control::ScanRegistry;
+use crate::service::schedule::Scheduler;
+
 #[derive(Debug, Clone)]
 pub struct IfStats {
     // Total bytes received
@@ -31,6 +35,12 @@ pub struct AppState {
     pub last_refresh: Mutex<SystemTime>,
     /// Update task handle
     pub task: Mutex<Option<JoinHandle<()>>>,
+    /// Running scans
+    pub scans: ScanRegistry,
+    /// Log lines captured per scan run
+    pub run_logs: Arc<RunLogBuffer>,
+    /// Recurring scan jobs
+    pub scheduler: Scheduler,
 }
 
 impl Default for AppState {
@@ -40,6 +50,9 @@ impl Default for AppState {
             stats: Mutex::new(HashMap::new()),
             last_refresh: Mutex::new(SystemTime::now()),
             task: Mutex::new(None),
+            scans: ScanRegistry::default(),
+            run_logs: Arc::new(RunLogBuffer::default()),
+            scheduler: Scheduler::default(),
         }
     }
 }
@@ -3,10 +3,19 @@ export interface LoggingConfig {
   file_path?: string | null;
 }
 
+export interface NotifyConfig {
+  webhook_url?: string | null;
+  on_complete: boolean;
+  on_change: boolean;
+}
+
 export interface AppConfig {
   startup: boolean;
   refresh_interval_ms: number;
   theme: "system" | "light" | "dark";
   data_unit: "bits" | "bytes";
   logging: LoggingConfig;
+  notify?: NotifyConfig;
+  // Time running scans get to finalize when the app exits (default 3000)
+  scan_shutdown_timeout_ms?: number | null;
 }
@@ -10,6 +10,7 @@ export type CertRecord = { cert_type: number; key_tag: number; algorithm: number
 
 export type DomainLookupInfo = {
   name: string;
+  ascii_name?: string;
   a: string[];
   aaaa: string[];
   mx: MxRecord[];
@@ -20,3 +21,36 @@ export type DomainLookupInfo = {
   txt: TxtRecord[];
   cert: CertRecord[];
 };
+
+export type DnsServer = { addr: string; protocol: string };
+export type DnsConfig = {
+  nameservers: DnsServer[];
+  domain?: string | null;
+  search: string[];
+  timeout_ms: number;
+  attempts: number;
+};
+export type DnsServerTest = {
+  server: string;
+  query: string;
+  ips: string[];
+  rtts_ms: number[];
+  avg_rtt_ms?: number | null;
+  attempts: number;
+  error?: string | null;
+};
+export type RaceLookup = {
+  name: string;
+  ips: string[];
+  resolver: string;
+  rtt_ms: number;
+  consistent?: boolean | null;
+};
+
+// Hostname target that did not resolve, even after retries
+export type UnresolvedName = {
+  name: string;
+  // NXDOMAIN / no records, as opposed to a resolver failure
+  not_found: boolean;
+  error: string;
+};
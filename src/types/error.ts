@@ -0,0 +1,18 @@
+export type ScanErrorKind =
+  | "PermissionDenied"
+  | "ResolutionFailed"
+  | "NoInterface"
+  | "SocketError"
+  | "Timeout"
+  | "Cancelled"
+  | "Unsupported"
+  | "InvalidInput"
+  | "NotFound"
+  | "Io"
+  | "Internal";
+
+/** Error returned by every command */
+export type ScanError = {
+  kind: ScanErrorKind;
+  message: string;
+};
@@ -19,6 +19,17 @@ export function cidr(addr: string, prefix_len: number): string {
 export interface Host {
   ip: IpAddr;
   hostname: string;
+  // Punycode form of an IDN hostname
+  ascii_hostname?: string | null;
+  // Forward-confirmed reverse DNS result; null when not checked
+  fcrdns?: boolean | null;
+}
+
+export interface LocalSubnet {
+  iface_name: string;
+  addr: IpAddr;
+  network: string;
+  host_count?: number | null;
 }
 
 export interface NetworkDevice {
@@ -129,3 +140,80 @@ export function toDate(ts: unknown): Date {
 
   return new Date(NaN);
 }
+
+export interface CapturedPacket {
+  index: number;
+  timestamp_ms: number;
+  src: string;
+  dst: string;
+  protocol: string;
+  src_port?: number | null;
+  dst_port?: number | null;
+  length: number;
+}
+
+export interface CaptureReport {
+  capture_id: string;
+  iface: string;
+  captured: number;
+  seen: number;
+  stopped: boolean;
+}
+
+// One answer to the DHCPDISCOVER of discover_dhcp
+export interface DhcpOffer {
+  server_ip: string;
+  // Differs from server_ip when the offer came through a relay
+  source_ip: IpAddr;
+  relay_ip?: string | null;
+  offered_ip: string;
+  subnet_mask?: string | null;
+  routers: string[];
+  dns_servers: string[];
+  domain_name?: string | null;
+  lease_time_secs?: number | null;
+  renewal_time_secs?: number | null;
+  rebinding_time_secs?: number | null;
+  broadcast_addr?: string | null;
+  ntp_servers: string[];
+  option_codes: number[];
+  rtt_ms: number;
+}
+
+export interface DhcpDiscoveryReport {
+  iface: string;
+  mac_addr: MacAddr;
+  xid: number;
+  offers: DhcpOffer[];
+  // More than one server answered: possible rogue DHCP server
+  multiple_servers: boolean;
+  elapsed_ms: number;
+}
+
+export type DiscoveryProtocol = "Lldp" | "Cdp";
+
+// Switch announcing itself via LLDP or CDP (listen_lldp)
+export interface LinkNeighbor {
+  protocol: DiscoveryProtocol;
+  source_mac: MacAddr;
+  chassis_id?: string | null;
+  port_id?: string | null;
+  port_description?: string | null;
+  system_name?: string | null;
+  // LLDP system description or CDP software version
+  system_description?: string | null;
+  platform?: string | null;
+  vlan_id?: number | null;
+  capabilities: string[];
+  management_addrs: IpAddr[];
+  ttl_secs?: number | null;
+  last_seen_ms: number;
+}
+
+export interface LinkDiscoveryReport {
+  // ID to stop the listen with (stop_lldp)
+  listen_id: string;
+  iface: string;
+  neighbors: LinkNeighbor[];
+  elapsed_ms: number;
+}
@@ -1,4 +1,5 @@
 import { Host } from "./net";
+import { UnresolvedName } from "./dns";
 
 export type ProbeStatusKind = "Done" | "Error" | "Timeout";
 export type PingProtocol = "Icmp" | "Tcp" | "Udp" | "Quic" | "Http";
@@ -31,6 +32,43 @@ export interface PingStat {
   max?: number | null;
 }
 
+export type IcmpQueryKind = "Timestamp" | "AddressMask";
+
+export interface IcmpQueryResult {
+  ip_addr: string;
+  kind: IcmpQueryKind;
+  responded: boolean;
+  rtt_ms?: number | null;
+  remote_receive_ms?: number | null;
+  remote_transmit_ms?: number | null;
+  clock_offset_ms?: number | null;
+  netmask?: string | null;
+  message?: string | null;
+}
+
+export type Ipv6ExtHeader = "HopByHop" | "DestinationOptions" | "Fragment";
+
+export type Ipv6ExtProbeOutcome =
+  | "EchoReply"
+  | "ParameterProblem"
+  | "IcmpError"
+  | "NoResponse";
+
+export interface Ipv6ExtProbeResult {
+  ext_headers: Ipv6ExtHeader[];
+  outcome: Ipv6ExtProbeOutcome;
+  rtt_ms?: number | null;
+  responder?: string | null;
+  icmp_type?: number | null;
+  icmp_code?: number | null;
+  pointer?: number | null;
+}
+
+export interface Ipv6ExtProbeReport {
+  ip_addr: string;
+  results: Ipv6ExtProbeResult[];
+}
+
 export interface PingSetting {
   hostname?: string | null;
   ip_addr: string;         
@@ -43,9 +81,16 @@ export interface PingSetting {
 }
 
 export type PortScanProtocol = "Tcp" | "Quic";
-export type TargetPortsPreset = "Common" | "WellKnown" | "Full" | "Top1000" | "Custom";
+// Services: curated service ports, detection always on
+export type TargetPortsPreset = "Common" | "WellKnown" | "Full" | "Top1000" | "Custom" | "Services";
 
-export type PortState = "Open" | "Closed" | "Filtered";
+export type PortState =
+  | "Open"
+  | "Closed"
+  | "Filtered"
+  | "OpenFiltered"
+  | "Unknown"
+  | "Blocked";
 
 export interface TlsInfo {
   version?: string | null;
@@ -56,10 +101,34 @@ export interface TlsInfo {
   issuer?: string | null;
   not_before?: string | null;
   not_after?: string | null;
+  // Unix seconds
+  not_before_unix?: number | null;
+  not_after_unix?: number | null;
+  self_signed?: boolean | null;
   san_list: string[];
   serial_hex?: string | null;
   sig_algorithm?: string | null;
   pubkey_algorithm?: string | null;
+  // Full handshake time
+  handshake_ms?: number | null;
+  // Set when the scan asked for resumption testing
+  resumption?: TlsResumption | null;
+  // Set when the scan asked for enumeration, e.g. ["TLSv1_2", "TLSv1_3"]
+  supported_versions?: string[];
+  supported_ciphers?: string[];
+  // "smtp", "imap", "pop3" or "ftp" when upgraded with STARTTLS
+  starttls?: string | null;
+}
+
+export interface TlsResumption {
+  // Server issued a session ticket or session ID
+  offered: boolean;
+  // Second handshake resumed the session
+  resumed: boolean;
+  handshake_ms?: number | null;
+  // 0-RTT data the ticket allows, in bytes; 0 when not offered
+  max_early_data: number;
+  error?: string | null;
 }
 
 export interface ServiceInfo {
@@ -71,8 +140,67 @@ export interface ServiceInfo {
   raw?: string | null;
   cpes: string[];
   tls_info?: TlsInfo | null;
+  response?: ServiceResponse;
+  // Probe sent -> first response byte (TLS: handshake time)
+  response_latency_ms?: number | null;
+  vuln_summary?: VulnSummary | null;
+  smb_info?: SmbInfo | null;
+  rdp_info?: RdpInfo | null;
+  ssh_info?: SshInfo | null;
+}
+
+// From NetBIOS node status and an SMB2 negotiate on 139/445
+export interface SmbInfo {
+  dialect?: string | null;
+  signing_required?: boolean | null;
+  computer_name?: string | null;
+  domain?: string | null;
+  dns_computer_name?: string | null;
+  dns_domain?: string | null;
+  os_version?: string | null;
+  netbios_name?: string | null;
+  workgroup?: string | null;
+}
+
+// RDP security negotiation (X.224 connection request)
+export interface RdpInfo {
+  security_protocol?: string | null;
+  negotiation_failure?: string | null;
+  nla_required?: boolean | null;
+  rdp_security_allowed?: boolean | null;
+  tls_allowed?: boolean | null;
+}
+
+// SSH KEXINIT algorithms and host key
+export interface SshInfo {
+  kex_algorithms: string[];
+  host_key_algorithms: string[];
+  encryption_algorithms: string[];
+  mac_algorithms: string[];
+  compression_algorithms: string[];
+  host_key_type?: string | null;
+  // "SHA256:..." as printed by OpenSSH
+  host_key_fingerprint?: string | null;
+  weak_algorithms: string[];
 }
 
+export type Severity = "Critical" | "High" | "Medium" | "Low";
+
+// Known CVEs of the service CPEs, from the offline netpulse-cve-index.json
+export interface VulnSummary {
+  total: number;
+  critical: number;
+  high: number;
+  medium: number;
+  low: number;
+  unknown: number;
+  max_cvss?: number | null;
+  cve_ids: string[];
+}
+
+// "NoResponse" = accepted the connection but sent nothing (open|no-response)
+export type ServiceResponse = "Unknown" | "Responded" | "NoResponse";
+
 export interface PortScanSample {
   ip_addr: string;
   port: number;
@@ -81,16 +209,102 @@ export interface PortScanSample {
   message?: string | null;
   service_name?: string | null;
   service_info?: ServiceInfo | null;
+  reply_ttl?: number | null;
+  quic_handshake?: QuicHandshakeInfo | null;
   done?: number;
   total?: number;
 }
 
+export interface QuicHandshakeInfo {
+  retry: boolean;
+  address_validation: boolean;
+  new_token: boolean;
+  zero_rtt_accepted?: boolean | null;
+}
+
 export interface PortScanReport {
   run_id: string;
   ip_addr: string;
   hostname?: string | null;
   protocol: PortScanProtocol;
   samples: PortScanSample[];
+  // Likely tarpit or honeypot
+  suspicious?: boolean;
+  suspicious_reason?: string | null;
+  summary?: PortScanSummary;
+  // Stopped early at max_duration_ms
+  deadline_reached?: boolean;
+  elapsed_ms?: number | null;
+  ports_preset?: TargetPortsPreset | null;
+  ports_scanned?: number | null;
+  // Ports probed, as inclusive [first, last] ranges
+  scanned_ports?: [number, number][];
+  // Samples are in this JSONL file instead; page through read_scan_results
+  results_file?: string | null;
+}
+
+// RTTs in [lower_ms, upper_ms); upper_ms is null for the last bucket
+export interface RttBucket {
+  lower_ms: number;
+  upper_ms?: number | null;
+  count: number;
+}
+
+export interface RttStats {
+  min_ms: number;
+  avg_ms: number;
+  max_ms: number;
+  histogram: RttBucket[];
+}
+
+export interface ServiceCount {
+  name: string;
+  count: number;
+}
+
+export interface PortScanSummary {
+  open: number;
+  by_service: ServiceCount[];
+  rtt?: RttStats | null;
+}
+
+export interface HostScanSummary {
+  alive: number;
+  total: number;
+  rtt?: RttStats | null;
+}
+
+export type TransportProtocol = "tcp" | "udp" | "quic";
+
+// Port with its transport: endpoint ports and ports_for_service results
+export interface ServicePort {
+  number: number;
+  transport: TransportProtocol;
+}
+
+export interface PortService {
+  port: ServicePort;
+  rtt_ms?: number | null;
+  service_name?: string | null;
+  service_info?: ServiceInfo | null;
+}
+
+// Open ports of one host merged across protocols (scan_endpoint)
+export interface Endpoint {
+  ip: string;
+  hostname?: string | null;
+  mac_addr?: string | null;
+  tags: string[];
+  ports: ServicePort[];
+  services: PortService[];
+}
+
+// Registered service of a port (lookup_service)
+export interface RegisteredService {
+  port: number;
+  protocol: TransportProtocol;
+  name: string;
+  description?: string | null;
 }
 
 export interface PortScanSetting {
@@ -100,10 +314,61 @@ export interface PortScanSetting {
   user_ports: number[];
   protocol: PortScanProtocol;
   timeout_ms: number;
-  ordered: boolean;
+  order: ScanOrder;
   service_detection: boolean;
+  detection_intensity?: DetectionIntensity;
+  verbose?: boolean;
+  source_port?: number | null;
+  // TCP: connect from random local ports in [first, last] instead of the OS range
+  source_port_range?: [number, number] | null;
+  reset_on_close?: boolean;
+  routing?: ScanRouting;
+  responsive_only?: boolean;
+  // Cap on simultaneous probes to the target, shared across running scans of it
+  max_per_host?: number | null;
+  // Skip fragile printer/OT ports and use read-only service probes only
+  safe_mode?: boolean;
+  exclude_ports?: number[];
+  // Report firewall rejections (ICMP admin prohibited, local filter) as Blocked
+  detect_blocked?: boolean;
+  // Keep service responses as a hex+ASCII dump in ServiceInfo.raw
+  raw_hex_dump?: boolean;
+  raw_hex_max_bytes?: number | null;
+  // Count known CVEs per service from netpulse-cve-index.json in the app dir
+  vuln_lookup?: boolean;
+  // Skip service probes marked intrusive in the probe database
+  skip_intrusive?: boolean;
+  // Scan over one IP family only; the hostname is re-resolved if needed
+  family?: AddressFamily | null;
+  // Stop issuing probes after this long and report what was collected
+  max_duration_ms?: number | null;
+  // Write open ports to a results file instead of memory (huge TCP scans)
+  spill_results?: boolean;
+  // Hand each open connection to service detection instead of reconnecting
+  reuse_connection?: boolean;
+  // Time a second, resuming TLS handshake (TlsInfo.resumption)
+  tls_resumption?: boolean;
+  // List accepted TLS versions and cipher suites (extra handshakes per port)
+  tls_enum?: boolean;
+}
+
+export type AddressFamily = "V4" | "V6" | "Both";
+
+// Linux only: bind scan sockets to a VRF device and/or set a fwmark.
+export interface ScanRouting {
+  bind_device?: string | null;
+  fwmark?: number | null;
 }
 
+// Legacy boolean `ordered` is still accepted by the backend.
+export type ScanOrder =
+  | "Sequential"
+  | "Random"
+  | "ByFrequency"
+  | { Random: number | null };
+
+export type DetectionIntensity = "Light" | "Normal" | "Aggressive";
+
 export type HostState = "Alive" | "Unreachable";
 
 export interface HostScanProgress {
@@ -111,15 +376,81 @@ export interface HostScanProgress {
   state: HostState;
   rtt_ms?: number | null;
   message?: string | null;
+  reply_ttl?: number | null;
   done: number;
   total: number;
 }
 
+// Payload of "hostscan:unresolved", emitted before probing starts
+export interface HostScanUnresolvedPayload {
+  run_id: string;
+  unresolved: UnresolvedName[];
+}
+
+// Payload of "portscan:deadline_reached" and "hostscan:deadline_reached"
+export interface DeadlineReachedPayload {
+  run_id: string;
+  max_duration_ms: number;
+}
+
 export interface HostScanReport {
   run_id: string;
   alive: [Host, number][];
   unreachable: Host[];
   total: number;
+  summary?: HostScanSummary;
+  // Hostname targets that were not scanned
+  unresolved?: UnresolvedName[];
+  // Stopped early at max_duration_ms
+  deadline_reached?: boolean;
+  // IP -> every probe in seq order (verbose scans only)
+  samples?: Record<string, EchoSample[]>;
+}
+
+// One echo request of a verbose host scan ("hostscan:sample")
+export interface EchoSample {
+  ip_addr: string;
+  seq: number;
+  // null if the probe was lost
+  rtt_ms?: number | null;
+  reply_ttl?: number | null;
+  message?: string | null;
+}
+
+// One host scan per up interface (host_scan_all_interfaces)
+export interface InterfaceHostScan {
+  iface_name: string;
+  report?: HostScanReport | null;
+  error?: string | null;
+}
+
+export interface MultiInterfaceHostScanReport {
+  scans: InterfaceHostScan[];
+  // Alive host IP -> interfaces it answered through
+  reachable_via: Record<string, string[]>;
+}
+
+export type ScanPhase = "Discovery" | "Ports";
+
+// Payload of "scan:progress": overall progress of host_port_scan
+export interface ScanProgressPayload {
+  run_id: string;
+  phase: ScanPhase;
+  done: number;
+  total: number;
+}
+
+// Port scan of one alive host (host_port_scan)
+export interface HostPortScan {
+  host: Host;
+  report?: PortScanReport | null;
+  error?: string | null;
+}
+
+export interface HostPortScanReport {
+  run_id: string;
+  discovery: HostScanReport;
+  scans: HostPortScan[];
 }
 
 export interface HostScanRequest {
@@ -128,8 +459,93 @@ export interface HostScanRequest {
   timeout_ms: number;
   count: number;
   payload?: string | null;
-  ordered: boolean;
+  order: ScanOrder;
   concurrency?: number | null;
+  // IPv6 probes in flight; defaults to concurrency
+  concurrency_v6?: number | null;
+  max_alive?: number | null;
+  routing?: ScanRouting;
+  // IPs, CIDRs or ranges (a.b.c.d-e) never probed
+  exclude_targets?: string[];
+  resolve_timeout_ms?: number | null;
+  resolve_concurrency?: number | null;
+  resolve_strategy?: ResolveStrategy;
+  family?: AddressFamily | null;
+  // Send and receive on this interface instead of the default one
+  iface_name?: string | null;
+  // Stop issuing probes after this long and report what was collected
+  max_duration_ms?: number | null;
+  // Send every probe and emit "hostscan:sample" for each
+  verbose?: boolean;
+  // Time between probes to the same host (back-to-back when unset)
+  interval_ms?: number | null;
+}
+
+// System resolver, or all nameservers in parallel (verified flags disagreement)
+export type ResolveStrategy = "System" | "Race" | "RaceVerified";
+
+export interface MaybeHost {
+  ip?: string | null;
+  hostname?: string | null;
+}
+
+export interface HostScanSetting {
+  targets: MaybeHost[];
+  hop_limit: number;
+  timeout_ms: number;
+  count: number;
+  payload?: string | null;
+  order: ScanOrder;
+  concurrency?: number | null;
+  // IPv6 probes in flight; defaults to concurrency
+  concurrency_v6?: number | null;
+  max_alive?: number | null;
+  routing?: ScanRouting;
+  // IPs, CIDRs or ranges (a.b.c.d-e) never probed
+  exclude_targets?: string[];
+  resolve_timeout_ms?: number | null;
+  resolve_concurrency?: number | null;
+  resolve_strategy?: ResolveStrategy;
+  family?: AddressFamily | null;
+  // Send and receive on this interface instead of the default one
+  iface_name?: string | null;
+  // Stop issuing probes after this long and report what was collected
+  max_duration_ms?: number | null;
+  // Send every probe and emit "hostscan:sample" for each
+  verbose?: boolean;
+  // Time between probes to the same host (back-to-back when unset)
+  interval_ms?: number | null;
+}
+
+export type ScanTemplateSetting =
+  | { kind: "PortScan"; setting: PortScanSetting }
+  | { kind: "HostScan"; setting: HostScanSetting };
+
+// Argument of estimate_scan_cost
+export type ScanCostSetting =
+  | { kind: "PortScan"; setting: PortScanSetting }
+  | { kind: "HostScan"; setting: HostScanRequest };
+
+// Expected cost of a scan, assuming no probe is answered
+export interface ScanCostEstimate {
+  targets: number;
+  probes: number;
+  // Packets sent, SYN retransmissions included
+  packets: number;
+  // Bytes sent, IP headers included
+  bytes: number;
+  concurrency: number;
+  duration_ms: number;
+  // Average upstream bandwidth in bits per second
+  bandwidth_bps: number;
+  // max_duration_ms stops the scan before every probe is sent
+  truncated: boolean;
+}
+
+export interface ScanTemplate {
+  name: string;
+  builtin: boolean;
+  setting: ScanTemplateSetting;
 }
 
 export type NeighborHost = {
@@ -140,10 +556,16 @@ export type NeighborHost = {
   tags: string[];
 };
 
+// Tcp = unprivileged fallback of quick_lan_scan
+export type NeighborDiscoveryMethod = "Icmp" | "Tcp";
+
 export type NeighborScanReport = {
   run_id: string;
   neighbors: NeighborHost[];
   total: number;
+  method?: NeighborDiscoveryMethod;
+  // Hosts the TCP sweep could not check because local connects failed
+  unknown?: string[];
 };
 
 export type TraceProtocol = "Icmp" | "Udp";
@@ -172,3 +594,205 @@ export interface TraceDonePayload {
   hostname?: string | null;
   protocol: TraceProtocol;
 }
+
+export interface RunLogLine {
+  run_id: string;
+  timestamp_ms: number;
+  level: string;
+  message: string;
+}
+
+export type IpProtocolState = "Open" | "Closed" | "Filtered" | "OpenFiltered";
+
+export interface IpProtocolScanSetting {
+  ip_addr: string;
+  hostname?: string | null;
+  protocols?: number[];
+  timeout_ms: number;
+  fragment_size?: number | null;
+  decoys?: string[];
+  source_port?: number | null;
+}
+
+export interface IpProtocolScanSample {
+  protocol: number;
+  name: string;
+  state: IpProtocolState;
+  rtt_ms?: number | null;
+  message?: string | null;
+  done: number;
+  total: number;
+}
+
+export interface IpProtocolScanReport {
+  run_id: string;
+  ip_addr: string;
+  hostname?: string | null;
+  samples: IpProtocolScanSample[];
+}
+
+export interface ScanHistorySummary {
+  run_id: string;
+  saved_at_ms: number;
+  kind: "PortScan" | "HostScan";
+  target: string;
+  // Cancelled, shut down or cut off at its deadline before finishing
+  partial: boolean;
+}
+
+// Interrupted scan run that resume_interrupted_scan can continue
+export interface ScanCheckpointSummary {
+  run_id: string;
+  saved_at_ms: number;
+  kind: "PortScan" | "HostScan";
+  // Ports or hosts already completed
+  completed: number;
+}
+
+// Result of resume_interrupted_scan; input of the report exports
+export type ScanRecord =
+  | { kind: "PortScan"; report: PortScanReport }
+  | { kind: "HostScan"; report: HostScanReport };
+
+// Grepable = nmap -oG style, one line per host
+export type ExportFormat = "Json" | "Csv" | "Grepable" | "Table";
+
+// Sarif = SARIF 2.1.0
+export type FindingsFormat = "Json" | "Sarif";
+
+export type FindingSeverity = "info" | "low" | "medium" | "high" | "critical";
+
+export interface FindingTarget {
+  ip_addr: string;
+  hostname?: string | null;
+  port?: number | null;
+  protocol?: string | null;
+}
+
+export interface Finding {
+  // Stable rule id, e.g. "NP-TLS-EXPIRED"
+  rule: string;
+  severity: FindingSeverity;
+  title: string;
+  target: FindingTarget;
+  evidence: string;
+}
+
+// Result of export_findings with "Json"
+export interface FindingsReport {
+  schema: "netpulse-findings";
+  schema_version: string;
+  run_id: string;
+  generated_at_ms: number;
+  findings: Finding[];
+}
+
+export interface ServiceChange {
+  port: number;
+  before?: ServiceInfo | null;
+  after?: ServiceInfo | null;
+}
+
+export interface PortScanDiff {
+  ip_addr: string;
+  opened: PortScanSample[];
+  closed: PortScanSample[];
+  changed: ServiceChange[];
+}
+
+export interface HostScanDiff {
+  up: Host[];
+  down: Host[];
+}
+
+export type ScanDiff =
+  | { kind: "PortScan"; diff: PortScanDiff }
+  | { kind: "HostScan"; diff: HostScanDiff };
+
+export interface ScanJob {
+  id: string;
+  name?: string | null;
+  setting: ScanTemplateSetting;
+  interval_secs: number;
+}
+
+export interface ScheduleRunPayload {
+  job_id: string;
+  run_id?: string | null;
+  error?: string | null;
+  finished_at_ms: number;
+}
+
+// One port of a knock sequence (port_knock); quic is sent as udp
+export interface KnockStep {
+  port: number;
+  protocol: TransportProtocol;
+}
+
+export interface KnockResult {
+  port: number;
+  protocol: TransportProtocol;
+  sent: boolean;
+  error?: string | null;
+}
+
+// TCP connect attempt made after the sequence
+export interface KnockVerify {
+  port: number;
+  open: boolean;
+  rtt_ms?: number | null;
+  message?: string | null;
+}
+
+export interface PortKnockReport {
+  ip_addr: string;
+  knocks: KnockResult[];
+  verify?: KnockVerify | null;
+}
+
+// Expected-ports policy for check_against_policy
+export interface PolicyRule {
+  // IP address or CIDR
+  target: string;
+  required_ports?: number[];
+  allowed_ports?: number[];
+  description?: string | null;
+}
+
+export interface PortPolicy {
+  rules: PolicyRule[];
+  default_deny?: boolean;
+}
+
+export type PolicyViolationKind = "UnexpectedOpen" | "ExpectedClosed" | "NotChecked";
+
+export interface PolicyViolation {
+  ip_addr: string;
+  port: number;
+  kind: PolicyViolationKind;
+  rule?: string | null;
+  rule_description?: string | null;
+}
+
+export interface PolicyCheckResult {
+  run_id: string;
+  compliant: boolean;
+  unmatched_hosts: string[];
+  violations: PolicyViolation[];
+  // Required ports the scan did not probe
+  not_checked?: PolicyViolation[];
+}
+
+// Message on the Channel passed to port_scan / host_scan.
+// `event` is the name of the matching global event, e.g. "portscan:progress".
+export interface ScanEvent<T = unknown> {
+  event: string;
+  payload: T;
+}
+
+// Global "schedule:progress" event: a scan event of a scheduled job's run.
+export interface JobScanEvent<T = unknown> {
+  job_id: string;
+  event: string;
+  payload: T;
+}
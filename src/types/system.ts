@@ -16,3 +16,23 @@ export type SysInfo = {
   architecture: string;  // "x86_64" | "aarch64" | ...
   proxy: ProxyEnv;
 };
+
+export type SocketKind =
+  | "IcmpV4"
+  | "IcmpV6"
+  | "RawV4"
+  | "RawV6"
+  | "TcpV4"
+  | "TcpV6"
+  | "UdpV4"
+  | "UdpV6"
+  | "Quic";
+
+// Result of capabilities_check for one socket type
+export type SocketCapability = {
+  kind: SocketKind;
+  supported: boolean;
+  detail?: string | null; // "DGRAM" | "RAW" for ICMP
+  error?: string | null;
+  hint?: string | null;   // e.g. "needs CAP_NET_RAW ..."
+};